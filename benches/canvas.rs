@@ -0,0 +1,63 @@
+//! Drawing on the Braille canvas, and turning it into text, at the
+//! sizes of a small chart, a full terminal and a large `--live` window.
+//!
+//! Run with `cargo bench --bench canvas`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use nu_plugin_plot::color_plot::drawille::{Canvas, PixelColor};
+
+const SIZES: [(u32, u32); 3] = [(120, 40), (500, 200), (2000, 800)];
+
+/// A canvas with a wave across it and a diagonal line, drawn in
+/// `colors` in turn, or plain if there are none.
+fn drawn(width: u32, height: u32, colors: &[PixelColor]) -> Canvas {
+    let mut canvas = Canvas::new(width, height);
+    for x in 0..width {
+        let y = ((height as f64 / 2.0 * (1.0 + (x as f64 / 20.0).sin())) as u32).min(height - 1);
+        match colors {
+            [] => canvas.set(x, y),
+            colors => canvas.set_colored(x, y, colors[x as usize / 8 % colors.len()]),
+        }
+    }
+    canvas.line(0, 0, width - 1, height - 1);
+    canvas
+}
+
+fn lines(c: &mut Criterion) {
+    let mut group = c.benchmark_group("line");
+    for (width, height) in SIZES {
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{}x{}", width, height)), &(width, height), |b, &(width, height)| {
+            b.iter(|| {
+                let mut canvas = Canvas::new(width, height);
+                // a fan of lines from the bottom left corner
+                for i in 0..64 {
+                    canvas.line_colored(0, height - 1, width * i / 63, 0, PixelColor::Red);
+                    canvas.line(0, height - 1, width - 1, height * i / 64);
+                }
+                black_box(canvas)
+            })
+        });
+    }
+    group.finish();
+}
+
+fn frames(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frame");
+    for (width, height) in SIZES {
+        let canvas = drawn(width, height, &[PixelColor::Red]);
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{}x{}", width, height)), &canvas, |b, canvas| {
+            b.iter(|| black_box(canvas.frame()))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("frame 500x200");
+    let colors = [PixelColor::Red, PixelColor::Green, PixelColor::Blue];
+    for (name, canvas) in [("plain", drawn(500, 200, &[])), ("colored", drawn(500, 200, &colors))] {
+        group.bench_function(name, |b| b.iter(|| black_box(canvas.frame())));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, lines, frames);
+criterion_main!(benches);
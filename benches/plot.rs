@@ -0,0 +1,68 @@
+//! The plugin's work on a list of values: converting them to points,
+//! binning them for a histogram, and drawing a whole chart of them.
+//!
+//! Run with `cargo bench --bench plot`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use nu_plugin_plot::bins::{BinRule, Bins};
+use nu_plugin_plot::color_plot::textplots::utils::histogram_edges;
+use nu_plugin_plot::render_benchmark_fixture;
+use nu_protocol::{Span, Value};
+
+const COUNTS: [usize; 3] = [1_000, 100_000, 1_000_000];
+
+/// `n` values of a noisy wave.
+fn wave(n: usize) -> Vec<f64> {
+    (0..n).map(|i| (i as f64 / 50.0).sin() + ((i * 7919) % 101) as f64 / 500.0).collect()
+}
+
+/// `n` values of a noisy wave, as nushell hands them to the plugin.
+fn values(n: usize) -> Vec<Value> {
+    wave(n).into_iter().map(|y| Value::float(y, Span::unknown())).collect()
+}
+
+fn convert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("convert");
+    group.sample_size(10);
+    for n in COUNTS {
+        let values = values(n);
+        group.bench_with_input(BenchmarkId::from_parameter(n), &values, |b, values| {
+            b.iter_batched(|| values.clone(), |values| black_box(render_benchmark_fixture(values, 200, 50).unwrap()), BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+fn hist(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hist");
+    group.sample_size(10);
+    for n in COUNTS {
+        let data = wave(n);
+        group.bench_with_input(BenchmarkId::from_parameter(n), &data, |b, data| {
+            b.iter(|| {
+                let edges = Bins::Rule(BinRule::Auto).edges(data, -1.0, 1.2);
+                let points: Vec<(f64, f64)> = data.iter().map(|y| (0.0, *y)).collect();
+                black_box(histogram_edges(&points, &edges))
+            })
+        });
+    }
+    group.finish();
+}
+
+fn render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+    let values = values(10_000);
+    for (width, height) in [(120, 40), (500, 200)] {
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{}x{}", width, height)), &values, |b, values| {
+            b.iter_batched(
+                || render_benchmark_fixture(values.clone(), width, height).unwrap(),
+                |mut chart| black_box(chart.to_string()),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, convert, hist, render);
+criterion_main!(benches);
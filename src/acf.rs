@@ -0,0 +1,161 @@
+//! The `acf` command.
+//!
+//! Draws the autocorrelation function of a numeric list, a time series:
+//! how closely each value follows the one a number of steps, a lag,
+//! before it. Each lag is a stem up or down from zero, between dashed
+//! bounds outside which an autocorrelation is significant, at the 5%
+//! level, for a series of independent values.
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::{Category, LabeledError, PipelineData, Signature, SyntaxShape, Value};
+
+use crate::color_plot::drawille::PixelColor;
+use crate::color_plot::textplots::{Annotation, Shape};
+use crate::stats::acf;
+use crate::{common_chart_flags, finish_chart, label_formats, legend_entry, new_chart, numeric_input_types, parse_cli_opts, series_names, Meta, Plotter, PluginPlot, Unit};
+
+/// The color of the significance bounds, as of other reference lines.
+const BOUNDS: PixelColor = PixelColor::BrightBlack;
+
+/// The normal quantile of a two-sided test at the 5% level.
+const Z_95: f64 = 1.959964;
+
+pub struct CommandAcf;
+
+impl Plotter for CommandAcf {
+    fn plot(
+        &self,
+        call: &EvaluatedCall,
+        input: Vec<f64>,
+        _meta: &mut Meta,
+    ) -> Result<Value, LabeledError> {
+        let opts = parse_cli_opts(call)?;
+        let n = input.iter().filter(|v| v.is_finite()).count();
+        if n < 2 {
+            return Err(LabeledError::new("An autocorrelation needs at least two finite values.").with_label("Too few values.", call.head));
+        }
+        // as R does, 10 log10(n) lags unless told otherwise, and none
+        // longer than the series
+        let lags = match call.get_flag::<i64>("lags")? {
+            None => ((10.0 * (n as f64).log10()) as usize).min(input.len() - 1),
+            Some(lags) if lags > 0 => (lags as usize).min(input.len() - 1),
+            Some(_) => {
+                return Err(LabeledError::new("The number of lags must be a positive integer.").with_label("Lags error.", call.get_flag_span("lags").unwrap_or(call.head)))
+            }
+        };
+
+        let correlations = acf(&input, lags);
+        if correlations.iter().any(|r| r.is_nan()) {
+            return Err(LabeledError::new("Can't find the autocorrelation of values which are all the same.").with_label("No spread.", call.head));
+        }
+        let stems: Vec<(f64, f64)> = correlations
+            .iter()
+            .enumerate()
+            .flat_map(|(lag, r)| [(lag as f64, 0.0), (lag as f64, *r), (f64::NAN, f64::NAN)])
+            .collect();
+
+        let max_x = opts.width_op.unwrap_or(200);
+        let max_y = opts.height_op.unwrap_or(50);
+        let labels = label_formats(opts.label_format, (Unit::Number, Unit::Number));
+        let bound = Z_95 / (n as f64).sqrt();
+        let mut annotations = opts.annotations.clone();
+        annotations.extend([Annotation::HLine(bound, None), Annotation::HLine(-bound, None)]);
+
+        let names = match &opts.names {
+            Some(_) => series_names(call, opts.names.as_deref(), 1)?,
+            None => vec!["ACF".to_string()],
+        };
+        let mut entries = vec![];
+        if opts.legend {
+            entries.push(opts.palette.entry(&names[0], 0, 1, None, labels.1));
+            entries.push(legend_entry("95% bounds", BOUNDS, None, None, labels.1));
+        }
+
+        // room either side for the stems of the first and last lags
+        let mut chart = new_chart(opts.format, opts.charset, labels, max_x, max_y, -0.5, lags as f64 + 0.5);
+        opts.palette.plot(&mut chart, Shape::Lines(stems));
+        Ok(finish_chart(&mut chart, opts.format, &names, &annotations, &opts.titles, &entries, opts.legend_pos, &opts.pad, opts.object, call.head))
+    }
+
+    fn plot_nested(
+        &self,
+        call: &EvaluatedCall,
+        _input: Vec<Vec<f64>>,
+        _meta: &mut Meta,
+    ) -> Result<Value, LabeledError> {
+        Err(LabeledError::new("acf finds the autocorrelation of a single list, not a nested list.").with_label("Nested list error.", call.head))
+    }
+}
+
+impl PluginCommand for CommandAcf {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "acf"
+    }
+
+    fn signature(&self) -> Signature {
+        common_chart_flags(Signature::build("acf").description("Render an ASCII autocorrelation function of a list of values, with its 95% significance bounds."))
+            .named(
+                "lags",
+                SyntaxShape::Int,
+                "The most lags to find the autocorrelation at, 10 log10(n) by default.",
+                None,
+            )
+            .switch("square", "Draw the chart as tall as it is wide, as many dots across as up.", None)
+            .named(
+                "names",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "A name for the series in the legend, rather than ACF.",
+                None,
+            )
+            .named(
+                "label-format",
+                SyntaxShape::String,
+                "How to write the numbers of the axes and labels: %.3f, %.2e, scientific, si, percent, thousands, bytes, duration or date, all but date with :N decimal places.",
+                None,
+            )
+            .switch("skip-nulls", "Leave out null values.", None)
+            .named(
+                "fill-nulls",
+                SyntaxShape::String,
+                "Replace null values using linear, previous or zero.",
+                None,
+            )
+            .switch("grid", "Draw dim grid lines across and down the chart, behind the series.", None)
+            .named("bg", SyntaxShape::String, "The color behind a text chart, such as black, or bright-white on a dark terminal.", None)
+            .named(
+                "hline",
+                SyntaxShape::Any,
+                "Draw horizontal reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .named(
+                "vline",
+                SyntaxShape::Any,
+                "Draw vertical reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .switch("meta", "Return a record of the chart and facts about the input.", None)
+            .input_output_types(numeric_input_types())
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render an ASCII autocorrelation function of a list of values, a time series, as a stem at each lag up to --lags, with dashed bounds at ±1.96/√n outside which an autocorrelation is significant at the 5% level."
+    }
+
+    fn search_terms(&self) -> Vec<&str> {
+        vec!["autocorrelation", "correlogram", "time series", "lag"]
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        self.plot_input(engine, call, input)
+    }
+}
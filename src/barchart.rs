@@ -0,0 +1,226 @@
+//! The `barchart` command.
+//!
+//! Renders categorical data as horizontal bars, one row per label,
+//! with the labels in a left hand gutter. Unlike the Braille `--bars`
+//! shape this is drawn directly with block characters.
+//!
+//! `hist` uses the same rendering for lists of strings, counting how
+//! often each distinct value appears.
+
+use std::collections::HashMap;
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::{Category, Example, LabeledError, PipelineData, Signature, Type, Value};
+use owo_colors::OwoColorize;
+
+use crate::{common_chart_flags, output_chart, parse_cli_opts, with_config, Charset, CliOpts, Format, Meta, PluginPlot, DOTS};
+
+/// Partial blocks, in eighths of a cell.
+const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// A full block.
+const FULL: char = '█';
+
+pub struct CommandBarchart;
+
+/// Collect `(label, value)` pairs from either a record of
+/// name -> number, or a (possibly streamed) table of `{label, value}` records.
+fn labelled_values(call: &EvaluatedCall, input: PipelineData) -> Result<Vec<(String, f64)>, LabeledError> {
+    let as_number = |v: &Value| -> Result<f64, LabeledError> {
+        match v {
+            Value::Int { val, .. } => Ok(*val as f64),
+            Value::Float { val, .. } => Ok(*val),
+            e => Err(LabeledError::new(format!("Got {}, need integer or float.", e.get_type())).with_label("Incorrect type supplied.", call.head)),
+        }
+    };
+
+    match input {
+        PipelineData::Value(Value::Record { val, .. }, ..) => val.iter().map(|(k, v)| Ok((k.clone(), as_number(v)?))).collect(),
+        PipelineData::ListStream(..) | PipelineData::Value(Value::List { .. }, ..) => input
+            .into_iter()
+            .map(|row| {
+                let record = row
+                    .as_record()
+                    .map_err(|_| LabeledError::new("barchart needs a table of {label, value} records.").with_label("Incorrect input type.", call.head))?;
+                let label = record.get("label").ok_or_else(|| LabeledError::new("Missing `label` column.").with_label("Incorrect table columns.", call.head))?;
+                let value = record.get("value").ok_or_else(|| LabeledError::new("Missing `value` column.").with_label("Incorrect table columns.", call.head))?;
+                Ok((label.coerce_string()?, as_number(value)?))
+            })
+            .collect(),
+        e => Err(LabeledError::new(format!("Input type should be a record or a table, got {}.", e.get_type())).with_label("Incorrect input type.", call.head)),
+    }
+}
+
+/// Draw a bar of `len` cells, where `len` can be fractional, in eighths
+/// of a cell with blocks, or in whole cells of `#` with `Charset::Ascii`.
+fn bar(len: f64, charset: Charset) -> String {
+    if charset == Charset::Ascii {
+        return "#".repeat(len.round() as usize);
+    }
+    let eighths = (len * 8.0).round() as usize;
+    let mut bar = FULL.to_string().repeat(eighths / 8);
+    let partial = eighths % 8;
+    if partial != 0 {
+        bar.push(EIGHTHS[partial]);
+    }
+    bar
+}
+
+/// Render the bars, fitting the whole chart into `width` characters,
+/// with values shown to `decimals` decimal places, each line after the
+/// `--pad`, and the bars in the color of `--palette`, if one is given.
+fn render(data: &[(String, f64)], width: usize, decimals: usize, opts: &CliOpts) -> String {
+    let label_width = data.iter().map(|(l, _)| l.chars().count()).max().unwrap_or(0);
+    let values: Vec<String> = data.iter().map(|(_, v)| format!("{:.*}", decimals, v)).collect();
+    let value_width = values.iter().map(|v| v.len()).max().unwrap_or(0);
+
+    // label, " │", bar, " ", value
+    let bar_width = width.saturating_sub(label_width + value_width + 3).max(1);
+
+    let max = data.iter().map(|(_, v)| *v).fold(0.0, f64::max);
+
+    data.iter()
+        .zip(values)
+        .map(|((label, v), value)| {
+            let len = if max > 0.0 { v / max * bar_width as f64 } else { 0.0 };
+            let bar = match opts.palette.single() {
+                Some(color) => bar(len, opts.charset).color(color).to_string(),
+                None => bar(len, opts.charset),
+            };
+            format!("{}{label:>label_width$} │{bar} {value}", opts.pad)
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+impl PluginCommand for CommandBarchart {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "barchart"
+    }
+
+    fn signature(&self) -> Signature {
+        common_chart_flags(Signature::build("barchart").description("Render a horizontal bar chart from labelled values."))
+            .switch("meta", "Return a record of the chart and facts about the input.", None)
+            .input_output_types(vec![(Type::record(), Type::Any), (Type::table(), Type::Any)])
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render a horizontal bar chart from labelled values."
+    }
+
+    fn examples(&self) -> Vec<Example<'_>> {
+        vec![
+            Example {
+                example: "{apples: 12, pears: 7, plums: 3.5} | barchart --width 64",
+                description: "Draw a bar for each fruit, 64 dots, or 32 characters, wide.",
+                result: Some(Value::test_string(
+                    [
+                        "    apples │███████████████████ 12.0",
+                        "     pears │███████████▏ 7.0",
+                        "     plums │█████▌ 3.5",
+                    ]
+                    .join("\n"),
+                )),
+            },
+            Example {
+                example: "[[label value]; [mon 3] [tue 5]] | barchart --charset ascii -t Visits",
+                description: "Draw the bars of a table in plain ASCII, under a title.",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        let call = &with_config(engine, call)?;
+        let opts = parse_cli_opts(call)?;
+        if opts.format != Format::Text {
+            return Err(LabeledError::new("A bar chart can only be drawn as text.").with_label("Format error.", call.get_flag_span("format").or(call.get_flag_span("output")).unwrap_or(call.head)));
+        }
+        if opts.object {
+            return Err(LabeledError::new("A bar chart can't be returned as a chart value.").with_label("Format error.", call.get_flag_span("object").unwrap_or(call.head)));
+        }
+        let data = labelled_values(call, input)?;
+        if data.is_empty() {
+            return Err(LabeledError::new("Can't plot a zero element list.").with_label("No elements in the input.", call.head));
+        }
+        if data.iter().any(|(_, v)| *v < 0.0) {
+            return Err(LabeledError::new("barchart can't draw negative values.").with_label("Negative value supplied.", call.head));
+        }
+
+        let meta = Meta {
+            series: 1,
+            points: data.len(),
+            ..Meta::default()
+        };
+        output_chart(engine, call, Value::string(titled_chart(&opts, &data, 1), call.head), meta)
+    }
+}
+
+/// Render the bars as wide as `--width`, in dots, two to a character,
+/// or the terminal, and add the `--title`.
+fn titled_chart(opts: &CliOpts, data: &[(String, f64)], decimals: usize) -> String {
+    let width = (opts.width_op.unwrap_or(200) / DOTS.0) as usize;
+    opts.titles.around(&render(data, width, decimals, opts), &opts.pad)
+}
+
+/// A frequency bar chart of the distinct strings in `values`, most
+/// common first, keeping the `--top` N if given.
+pub(crate) fn categorical_hist(
+    engine: &nu_plugin::EngineInterface,
+    call: &EvaluatedCall,
+    values: impl Iterator<Item = Value>,
+) -> Result<PipelineData, LabeledError> {
+    let top = match call.get_flag::<i64>("top")? {
+        None => None,
+        Some(n) if n > 0 => Some(n as usize),
+        Some(_) => {
+            return Err(LabeledError::new("--top must be a positive integer.").with_label("Top error.", call.get_flag_span("top").unwrap_or(call.head)));
+        }
+    };
+    if call.get_flag::<String>("format")?.is_some_and(|f| f != "text") || call.get_flag::<String>("output")?.is_some() {
+        return Err(LabeledError::new("A list of strings can only be drawn as text, and not saved.").with_label("Format error.", call.head));
+    }
+    if call.has_flag("object")? {
+        return Err(LabeledError::new("A frequency chart of strings can't be returned as a chart value.").with_label("Format error.", call.get_flag_span("object").unwrap_or(call.head)));
+    }
+
+    let mut meta = Meta::default();
+    let mut counts: Vec<(String, f64)> = vec![];
+    let mut index = HashMap::new();
+    for val in values {
+        meta.points += 1;
+        let label = match val {
+            Value::String { val, .. } => val,
+            Value::Nothing { .. } if call.has_flag("skip-nulls")? => {
+                meta.nulls += 1;
+                continue;
+            }
+            _ => return Err(LabeledError::new("Can't plot a list of multiple types.").with_label("Type differences.", call.head)),
+        };
+        let i = *index.entry(label.clone()).or_insert_with(|| {
+            counts.push((label, 0.0));
+            counts.len() - 1
+        });
+        counts[i].1 += 1.0;
+    }
+    if counts.is_empty() {
+        return Err(LabeledError::new("Can't plot a zero element list.").with_label("No elements in the list.", call.head));
+    }
+
+    // most common first, ties in order of appearance
+    counts.sort_by(|a, b| b.1.total_cmp(&a.1));
+    counts.truncate(top.unwrap_or(usize::MAX));
+    meta.series = 1;
+    meta.bins = Some(counts.len());
+
+    output_chart(engine, call, Value::string(titled_chart(&parse_cli_opts(call)?, &counts, 0), call.head), meta)
+}
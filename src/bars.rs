@@ -0,0 +1,75 @@
+//! Bars of several series at the same x values, stacked on each other
+//! or side by side.
+//!
+//! `Shape::Bars` draws a bar between each pair of points, down to the
+//! bottom of the chart. So a bar of its own is two points at the height
+//! of its top, its left and right edges, followed by a gap, and each
+//! series is arranged as such bars around its x values, taking up most
+//! of the room to the next.
+
+/// How much of the room between neighbouring x values their bars fill.
+const FILL: f64 = 0.8;
+
+/// How the bars of several series at the same x value are laid out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BarLayout {
+    /// Each on top of the one after it, so the first series is the top
+    /// of the stack.
+    Stacked,
+    /// Side by side, in the order of the series.
+    Dodged,
+}
+
+/// The bars of `series`, laid out by `layout`, each series as points to
+/// draw as `Shape::Bars`. Stacked bars reach to the top of their part
+/// of the stack, and as each covers the bottom of those drawn before it,
+/// drawing the series in order leaves every part in its own color.
+/// Values above and below zero are stacked apart.
+///
+/// ```
+/// use nu_plugin_plot::bars::{arrange, BarLayout};
+///
+/// let series = vec![vec![(0.0, 1.0), (1.0, 2.0)], vec![(0.0, 3.0), (1.0, 1.0)]];
+/// let stacked = arrange(&series, BarLayout::Stacked);
+/// assert_eq!(stacked[0][..2], [(-0.4, 4.0), (0.4, 4.0)]);
+/// assert_eq!(stacked[1][..2], [(-0.4, 3.0), (0.4, 3.0)]);
+/// assert!(stacked[0][2].0.is_nan());
+/// let dodged = arrange(&series, BarLayout::Dodged);
+/// assert_eq!(dodged[0][..2], [(-0.4, 1.0), (0.0, 1.0)]);
+/// assert_eq!(dodged[1][..2], [(0.0, 3.0), (0.4, 3.0)]);
+/// ```
+pub fn arrange(series: &[Vec<(f64, f64)>], layout: BarLayout) -> Vec<Vec<(f64, f64)>> {
+    let mut xs: Vec<f64> = series.iter().flatten().map(|(x, _)| *x).filter(|x| x.is_finite()).collect();
+    xs.sort_by(f64::total_cmp);
+    xs.dedup();
+    let step = xs.windows(2).map(|pair| pair[1] - pair[0]).fold(f64::INFINITY, f64::min);
+    let width = if step.is_finite() { step } else { 1.0 } * FILL;
+    let slot = width / series.len() as f64;
+
+    // the tops of the stacks above and below zero at each x value
+    let mut above = vec![0.0; xs.len()];
+    let mut below = vec![0.0; xs.len()];
+    let mut bars = vec![vec![]; series.len()];
+    let order: Vec<usize> = match layout {
+        BarLayout::Stacked => (0..series.len()).rev().collect(),
+        BarLayout::Dodged => (0..series.len()).collect(),
+    };
+    for i in order {
+        for &(x, y) in series[i].iter().filter(|(x, y)| x.is_finite() && y.is_finite()) {
+            let (left, right, top) = match layout {
+                BarLayout::Stacked => {
+                    let k = xs.binary_search_by(|v| v.total_cmp(&x)).unwrap_or_default();
+                    let stack = if y < 0.0 { &mut below[k] } else { &mut above[k] };
+                    *stack += y;
+                    (x - width / 2.0, x + width / 2.0, *stack)
+                }
+                BarLayout::Dodged => {
+                    let left = x - width / 2.0 + i as f64 * slot;
+                    (left, left + slot, y)
+                }
+            };
+            bars[i].extend([(left, top), (right, top), (f64::NAN, f64::NAN)]);
+        }
+    }
+    bars
+}
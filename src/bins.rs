@@ -0,0 +1,205 @@
+//! Choosing the bins of a histogram, and scaling their counts.
+
+/// A rule for choosing the number of bins from the data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinRule {
+    /// The larger of Sturges and Freedman–Diaconis.
+    Auto,
+    /// `log2(n) + 1` bins, fine for small, roughly normal data.
+    Sturges,
+    /// Bin width from the interquartile range, robust to outliers.
+    FreedmanDiaconis,
+    /// Bin width from the standard deviation.
+    Scott,
+}
+
+/// The bins of a histogram, or how to choose them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Bins {
+    /// A fixed number of bins.
+    Count(usize),
+    /// Choose the number of bins from the data.
+    Rule(BinRule),
+    /// Bins of a fixed width, aligned to multiples of the width.
+    Width(f64),
+    /// Bins between these increasing edges.
+    Edges(Vec<f64>),
+}
+
+impl BinRule {
+    /// The rule with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(BinRule::Auto),
+            "sturges" => Some(BinRule::Sturges),
+            "fd" | "freedman-diaconis" => Some(BinRule::FreedmanDiaconis),
+            "scott" => Some(BinRule::Scott),
+            _ => None,
+        }
+    }
+
+    /// The name of the rule, as given on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            BinRule::Auto => "auto",
+            BinRule::Sturges => "sturges",
+            BinRule::FreedmanDiaconis => "fd",
+            BinRule::Scott => "scott",
+        }
+    }
+
+    /// The number of bins this rule gives for `data`.
+    ///
+    /// Values which are not finite are ignored. Rules based on a bin
+    /// width fall back to Sturges if the data has no spread.
+    ///
+    /// ```
+    /// # use nu_plugin_plot::bins::BinRule;
+    /// let data: Vec<f64> = (0..1000).map(|e| e as f64).collect();
+    /// assert_eq!(BinRule::Sturges.bins(&data), 11);
+    /// assert_eq!(BinRule::FreedmanDiaconis.bins(&data), 10);
+    /// assert_eq!(BinRule::Auto.bins(&data), 11);
+    /// ```
+    pub fn bins(self, data: &[f64]) -> usize {
+        let mut sorted: Vec<f64> = data.iter().copied().filter(|e| e.is_finite()).collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        let n = sorted.len();
+        if n < 2 {
+            return 1;
+        }
+        let sturges = (n as f64).log2().ceil() as usize + 1;
+        let range = sorted[n - 1] - sorted[0];
+
+        let from_width = |width: f64| {
+            if width > 0.0 && range > 0.0 {
+                (range / width).ceil() as usize
+            } else {
+                sturges
+            }
+        };
+        let fd = || {
+            let iqr = quantile(&sorted, 0.75) - quantile(&sorted, 0.25);
+            from_width(2.0 * iqr / (n as f64).cbrt())
+        };
+
+        match self {
+            BinRule::Auto => sturges.max(fd()),
+            BinRule::Sturges => sturges,
+            BinRule::FreedmanDiaconis => fd(),
+            BinRule::Scott => {
+                let mean = sorted.iter().sum::<f64>() / n as f64;
+                let var = sorted.iter().map(|e| (e - mean).powi(2)).sum::<f64>() / n as f64;
+                from_width(3.49 * var.sqrt() / (n as f64).cbrt())
+            }
+        }
+        .max(1)
+    }
+}
+
+impl Bins {
+    /// The edges of the bins for `data`, which lies between `min` and `max`.
+    ///
+    /// ```
+    /// # use nu_plugin_plot::bins::Bins;
+    /// assert_eq!(Bins::Count(2).edges(&[], 0.0, 10.0), vec![0.0, 5.0, 10.0]);
+    /// assert_eq!(Bins::Width(2.0).edges(&[], 0.5, 5.0), vec![0.0, 2.0, 4.0, 6.0]);
+    /// ```
+    pub fn edges(&self, data: &[f64], min: f64, max: f64) -> Vec<f64> {
+        let even = |n: usize| {
+            let step = (max - min) / n as f64;
+            (0..=n).map(|i| min + i as f64 * step).collect()
+        };
+        match self {
+            Bins::Count(n) => even(*n),
+            Bins::Rule(rule) => even(rule.bins(data)),
+            Bins::Width(width) => {
+                let first = (min / width).floor() as i64;
+                let last = ((max / width).ceil() as i64).max(first + 1);
+                (first..=last).map(|i| i as f64 * width).collect()
+            }
+            Bins::Edges(edges) => edges.clone(),
+        }
+    }
+}
+
+/// The `q`th quantile of already sorted data, interpolating linearly
+/// between the closest ranks.
+///
+/// ```
+/// # use nu_plugin_plot::bins::quantile;
+/// assert_eq!(quantile(&[1.0, 2.0, 3.0, 4.0], 0.5), 2.5);
+/// ```
+pub fn quantile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let rank = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - lo as f64)
+}
+
+/// Turn the counts of a histogram, as given by
+/// [`histogram_edges`](crate::color_plot::textplots::utils::histogram_edges),
+/// into running totals and/or densities, in place.
+///
+/// A density histogram divides each count by the total count and the
+/// bin width, so the area of the bars sums to 1. A cumulative density
+/// histogram instead ends at 1.
+///
+/// ```
+/// # use nu_plugin_plot::bins::normalize;
+/// let mut hist = vec![(0.0, 1.0), (1.0, 3.0)];
+/// normalize(&mut hist, &[0.0, 1.0, 3.0], true, false);
+/// assert_eq!(hist, vec![(0.0, 1.0), (1.0, 4.0)]);
+/// normalize(&mut hist, &[0.0, 1.0, 3.0], false, true);
+/// assert_eq!(hist, vec![(0.0, 0.2), (1.0, 0.4)]);
+/// ```
+pub fn normalize(hist: &mut [(f64, f64)], edges: &[f64], cumulative: bool, density: bool) {
+    let total: f64 = hist.iter().map(|(_, count)| count).sum();
+    if density && total > 0.0 {
+        for (i, (_, count)) in hist.iter_mut().enumerate() {
+            *count /= total;
+            if !cumulative {
+                *count /= edges[i + 1] - edges[i];
+            }
+        }
+    }
+    if cumulative {
+        let mut sum = 0.0;
+        for (_, count) in hist.iter_mut() {
+            sum += *count;
+            *count = sum;
+        }
+    }
+}
+
+/// The number of `points` in each cell of the grid between `x_edges` and
+/// `y_edges`, a row at a time from the bottom left, as a two dimensional
+/// histogram. As in a histogram, each cell holds its low edges, and the
+/// last column and row their high edges too. Points outside the grid,
+/// or which are not finite, are left out.
+///
+/// ```
+/// # use nu_plugin_plot::bins::grid_counts;
+/// let points = [(0.5, 0.5), (1.5, 0.5), (2.0, 2.0), (1.9, 1.9), (5.0, 1.0)];
+/// assert_eq!(grid_counts(&points, &[0.0, 1.0, 2.0], &[0.0, 1.0, 2.0]), vec![1.0, 1.0, 0.0, 2.0]);
+/// ```
+pub fn grid_counts(points: &[(f64, f64)], x_edges: &[f64], y_edges: &[f64]) -> Vec<f64> {
+    let (columns, rows) = (x_edges.len().saturating_sub(1), y_edges.len().saturating_sub(1));
+    let mut counts = vec![0.0; columns * rows];
+    if counts.is_empty() {
+        return counts;
+    }
+    // the number of edges at or below v, less one, is the bin
+    let bin = |edges: &[f64], v: f64| {
+        let bins = edges.len() - 1;
+        (v.is_finite() && v >= edges[0] && v <= edges[bins]).then(|| (edges.partition_point(|e| *e <= v) - 1).min(bins - 1))
+    };
+    for &(x, y) in points {
+        if let (Some(column), Some(row)) = (bin(x_edges, x), bin(y_edges, y)) {
+            counts[row * columns + column] += 1.0;
+        }
+    }
+    counts
+}
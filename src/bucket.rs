@@ -0,0 +1,57 @@
+//! Time buckets: the points of a series grouped into intervals of x a
+//! fixed width apart, such as every five minutes, and each group
+//! combined into one point, so an irregular stream of events becomes a
+//! regular series.
+//!
+//! Dates and durations are counted in nanoseconds, as nushell counts
+//! them, so buckets of dates are as wide as a duration. Buckets start at
+//! whole multiples of their width from zero, which for dates is the
+//! start of 1970 in UTC, so daily buckets run from midnight to midnight
+//! UTC.
+
+use crate::resample::{aggregate, Aggregate};
+
+/// The most buckets a series is split into, past which a bucket is too
+/// narrow to be drawn as more than a sliver.
+pub const MAX_BUCKETS: f64 = 1e6;
+
+/// The start of the bucket `width` wide that `x` falls in.
+///
+/// ```
+/// # use nu_plugin_plot::bucket::start;
+/// assert_eq!(start(7.0, 5.0), 5.0);
+/// assert_eq!(start(-1.0, 5.0), -5.0);
+/// ```
+pub fn start(x: f64, width: f64) -> f64 {
+    (x / width).floor() * width
+}
+
+/// `data` grouped into buckets `width` wide by x, each combined as
+/// `how` says into a point at the start of its bucket, in order. A gap
+/// is left for the empty buckets between two others, which the chart
+/// joins across like any other, or breaks the line at with
+/// `--break-gaps`.
+///
+/// ```
+/// # use nu_plugin_plot::bucket::bucket;
+/// # use nu_plugin_plot::resample::Aggregate;
+/// let data = [(0.0, 1.0), (3.0, 3.0), (6.0, 5.0), (21.0, 2.0)];
+/// let buckets = bucket(&data, 5.0, Aggregate::Mean);
+/// assert_eq!(buckets[..2], [(0.0, 2.0), (5.0, 5.0)]);
+/// assert!(buckets[2].0 == 10.0 && buckets[2].1.is_nan());
+/// assert_eq!(buckets[3], (20.0, 2.0));
+/// ```
+pub fn bucket(data: &[(f64, f64)], width: f64, how: Aggregate) -> Vec<(f64, f64)> {
+    let starts: Vec<(f64, f64)> = data.iter().map(|&(x, y)| (start(x, width), y)).collect();
+    let mut buckets: Vec<(f64, f64)> = Vec::with_capacity(starts.len());
+    for (x, y) in aggregate(&starts, how) {
+        if let Some(&(last, _)) = buckets.last() {
+            // half a width over, as the starts are only as exact as floats
+            if x - last > 1.5 * width {
+                buckets.push((last + width, f64::NAN));
+            }
+        }
+        buckets.push((x, y));
+    }
+    buckets
+}
@@ -0,0 +1,284 @@
+//! The `candle` command.
+//!
+//! Draws a table of open, high, low and close prices as candlesticks,
+//! one for each row: a wick from the low to the high, and a solid body
+//! from the open to the close, green where the price rose and red where
+//! it fell. With a `date` column the candles are drawn in date order,
+//! evenly spaced, so days without trading leave no gaps.
+//!
+//! Bollinger bands, from `--bollinger N,K`, shade K standard deviations
+//! either side of the moving average of the last N closes, drawn as a
+//! line through the band.
+//!
+//! `--volume` draws the `volume` column as bars in a shorter panel under
+//! the candles, colored as they are, sharing their x axis.
+
+use std::cmp::Ordering;
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::{Category, LabeledError, PipelineData, Signature, SyntaxShape, Type, Value};
+
+use crate::color_plot::drawille::PixelColor;
+use crate::bars::{arrange, BarLayout};
+use crate::color_plot::textplots::{Annotation, Candle, ColorPlot, Shape};
+use crate::stats::rolling;
+use crate::{finish_panels, label_formats, legend_entry, new_chart, output_chart, panel_heights, parse_cli_opts, with_config, Format, Meta, PluginPlot, Unit};
+
+/// The color of a candle whose price rose, or held.
+const UP: PixelColor = PixelColor::Green;
+
+/// The color of a candle whose price fell.
+const DOWN: PixelColor = PixelColor::Red;
+
+/// The color of the moving average of Bollinger bands, and of the band
+/// shaded about it.
+const BAND: PixelColor = PixelColor::Blue;
+
+pub struct CommandCandle;
+
+/// The candles of `input`, a table with open, high, low and close
+/// columns, at 1, 2, 3 and so on, in the order of the rows or of their
+/// dates, and the volume of each if `volume` is set, or none.
+fn candles(call: &EvaluatedCall, input: PipelineData, volume: bool) -> Result<(Vec<Candle>, Vec<f64>), LabeledError> {
+    let not_table = |span| {
+        LabeledError::new("candle needs a table of {open, high, low, close} records.").with_label("Incorrect input type.", span)
+    };
+    let rows: Vec<Value> = match input {
+        PipelineData::ListStream(..) | PipelineData::Value(Value::List { .. }, ..) => input.into_iter().collect(),
+        _ => return Err(not_table(call.head)),
+    };
+    let mut prices = rows
+        .iter()
+        .map(|row| {
+            let record = row.as_record().map_err(|_| not_table(row.span()))?;
+            let price = |column: &str| match record.get(column) {
+                Some(Value::Int { val, .. }) => Ok(*val as f64),
+                Some(Value::Float { val, .. }) => Ok(*val),
+                Some(v) => Err(LabeledError::new(format!("Got {} for {}, need integer or float.", v.get_type(), column)).with_label("Incorrect type supplied.", v.span())),
+                None => Err(LabeledError::new(format!("Missing `{}` column.", column)).with_label("Incorrect table columns.", row.span())),
+            };
+            let volume = if volume { Some(price("volume")?) } else { None };
+            Ok((record.get("date").cloned(), [price("open")?, price("high")?, price("low")?, price("close")?], volume))
+        })
+        .collect::<Result<Vec<_>, LabeledError>>()?;
+    // rows without dates keep their order
+    prices.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+    let volumes = prices.iter().filter_map(|(_, _, volume)| *volume).collect();
+    let candles = prices
+        .into_iter()
+        .enumerate()
+        .map(|(i, (_, [open, high, low, close], _))| Candle { x: (i + 1) as f64, open, high, low, close })
+        .collect();
+    Ok((candles, volumes))
+}
+
+/// The window and the number of standard deviations of `--bollinger`,
+/// given as `N,K`, or `N` for 2 standard deviations.
+fn bollinger(call: &EvaluatedCall) -> Result<Option<(usize, f64)>, LabeledError> {
+    let Some(spec) = call.get_flag::<String>("bollinger")? else {
+        return Ok(None);
+    };
+    let (window, k) = spec.split_once(',').unwrap_or((&spec, "2"));
+    match (window.trim().parse::<usize>(), k.trim().parse::<f64>()) {
+        (Ok(window), Ok(k)) if window >= 2 && k.is_finite() && k > 0.0 => Ok(Some((window, k))),
+        _ => Err(LabeledError::new(format!("Got {} for --bollinger, need N,K: a window of 2 or more candles and a number of standard deviations, such as 20,2.", spec))
+            .with_label("Bollinger band error.", call.get_flag_span("bollinger").unwrap_or(call.head))),
+    }
+}
+
+impl PluginCommand for CommandCandle {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "candle"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("candle")
+            .description("Render candlesticks from a table of open, high, low and close prices.")
+            .named(
+                "width",
+                SyntaxShape::OneOf(vec![SyntaxShape::Number, SyntaxShape::String]),
+                "The maximum width of the plot, in dots, or as a percentage of the terminal such as 80%.",
+                None,
+            )
+            .named(
+                "height",
+                SyntaxShape::OneOf(vec![SyntaxShape::Number, SyntaxShape::String]),
+                "The maximum height of the plot, in dots, or as a percentage of the terminal such as 80%.",
+                None,
+            )
+            .switch("square", "Draw the chart as tall as it is wide, as many dots across as up.", None)
+            .named(
+                "max-lines",
+                SyntaxShape::Int,
+                "The most lines of output, with the title, labels and legend, shrinking the chart to fit.",
+                None,
+            )
+            .named(
+                "title",
+                SyntaxShape::String,
+                "Provide a title to the plot.",
+                Some('t'),
+            )
+            .named("subtitle", SyntaxShape::String, "A line under the title.", None)
+            .named("caption", SyntaxShape::String, "A line under the chart, such as where its data is from.", None)
+            .named("title-align", SyntaxShape::String, "Line the title, subtitle and caption up across the chart: left (default), center or right.", None)
+            .named("pad", SyntaxShape::Int, "Indent each line of a text chart by this many spaces (default 4), or not at all with 0.", None)
+            .switch("legend", "Plot a tiny, maybe useful legend.", Some('l'))
+            .named(
+                "legend-pos",
+                SyntaxShape::String,
+                "Where to put the legend: top, bottom (default), right or inside.",
+                None,
+            )
+            .named(
+                "format",
+                SyntaxShape::String,
+                "Draw the chart as text (default), svg, png, or vega for a Vega-Lite spec.",
+                None,
+            )
+            .named(
+                "charset",
+                SyntaxShape::String,
+                "The characters to draw a text chart with: braille (default), ascii or blocks.",
+                None,
+            )
+            .named(
+                "label-format",
+                SyntaxShape::String,
+                "How to write the numbers of the axes and labels: %.3f, %.2e, scientific, si, percent, thousands, bytes, duration or date, all but date with :N decimal places.",
+                None,
+            )
+            .named(
+                "color",
+                SyntaxShape::String,
+                "Color a text chart always, never, or auto (default) when NO_COLOR and CLICOLOR=0 are unset and the output is a terminal.",
+                None,
+            )
+            .switch("raw", "Return plain text without color or any other escape codes, whatever --color says.", None)
+            .switch("print", "Print the chart as soon as it is drawn rather than returning it, so each chart of a loop or script is shown.", None)
+            .switch("object", "Return the chart as a chart value, shown as the chart, with its data, ranges and facts as cells such as ymax.", None)
+            .named(
+                "output",
+                SyntaxShape::Filepath,
+                "Save the chart to this file rather than returning it, as SVG, PNG or a Vega-Lite spec if it ends in .svg, .png or .json.",
+                Some('o'),
+            )
+            .named(
+                "bollinger",
+                SyntaxShape::String,
+                "Overlay Bollinger bands as N,K: the moving average of the last N closes, shaded K standard deviations either side.",
+                None,
+            )
+            .switch("volume", "Draw the volume column as bars in a shorter panel under the candles, sharing their x axis.", None)
+            .switch("grid", "Draw dim grid lines across and down the chart, behind the series.", None)
+            .named("bg", SyntaxShape::String, "The color behind a text chart, such as black, or bright-white on a dark terminal.", None)
+            .named(
+                "hline",
+                SyntaxShape::Any,
+                "Draw horizontal reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .named(
+                "shade-above",
+                SyntaxShape::Number,
+                "Shade the region above this y value.",
+                None,
+            )
+            .named(
+                "shade-below",
+                SyntaxShape::Number,
+                "Shade the region below this y value.",
+                None,
+            )
+            .switch("meta", "Return a record of the chart and facts about the input.", None)
+            .input_output_type(Type::table(), Type::Any)
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render candlesticks from a table with open, high, low and close columns, one for each row, green where the price rose and red where it fell, in date order if it has a date column."
+    }
+
+    fn search_terms(&self) -> Vec<&str> {
+        vec!["ohlc", "stock", "price", "finance"]
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        let call = &with_config(engine, call)?;
+        let mut opts = parse_cli_opts(call)?;
+        let bollinger = bollinger(call)?;
+        let volume = call.has_flag("volume")?;
+        if volume && opts.format != Format::Text {
+            return Err(LabeledError::new("A volume panel can only be drawn as text.").with_label("Format error.", call.get_flag_span("format").unwrap_or(call.head)));
+        }
+        let (candles, volumes) = candles(call, input, volume)?;
+        if candles.is_empty() {
+            return Err(LabeledError::new("Can't plot a zero element table.").with_label("No rows in the table.", call.head));
+        }
+
+        let max_x = opts.width_op.unwrap_or(200);
+        let max_y = opts.height_op.unwrap_or(50);
+        let (max_y, volume_y) = if volume { panel_heights(max_y) } else { (max_y, 0) };
+        let labels = label_formats(opts.label_format, (Unit::Number, Unit::Number));
+        let mut chart = new_chart(opts.format, opts.charset, labels, max_x, max_y, 0.0, candles.len() as f64 + 1.0);
+
+        // an empty shape would widen the y range to zero
+        let (up, down): (Vec<Candle>, Vec<Candle>) = candles.iter().partition(|c| c.close >= c.open);
+        let mut names = vec![];
+        let mut entries = vec![];
+        for (name, shape, color) in [("up", up, UP), ("down", down, DOWN)] {
+            if !shape.is_empty() {
+                chart.linecolorplot(Shape::Candles(shape), color);
+                names.push(name.to_string());
+                entries.push(legend_entry(name, color, None, None, labels.1));
+            }
+        }
+        if let Some((window, k)) = bollinger {
+            let closes: Vec<f64> = candles.iter().map(|c| c.close).collect();
+            // each average is of the window ending at its candle
+            let xs = candles.iter().skip(window - 1).map(|c| c.x);
+            let band: Vec<(f64, f64, f64)> = xs.zip(rolling(&closes, window)).map(|(x, (mean, sd))| (x, mean - k * sd, mean + k * sd)).collect();
+            if !band.is_empty() {
+                let (low, high) = band.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), p| (low.min(p.1), high.max(p.2)));
+                chart.linecolorplot(Shape::Lines(band.iter().map(|&(x, low, high)| (x, (low + high) / 2.0)).collect()), BAND);
+                chart.widen_y_range(low, high);
+                opts.annotations.push(Annotation::Area(band, Some(BAND)));
+                names.push("bollinger".to_string());
+                entries.push(legend_entry(&format!("mean({}) ± {}sd", window, k), BAND, None, None, labels.1));
+            }
+        }
+        if !opts.legend {
+            entries.clear();
+        }
+
+        let meta = Meta {
+            series: 1,
+            points: candles.len(),
+            ..Meta::default()
+        };
+        let mut panel = volume.then(|| {
+            let mut panel = new_chart(opts.format, opts.charset, labels, max_x, volume_y, 0.0, candles.len() as f64 + 1.0);
+            // each bar colored as its candle, in one layout so they are
+            // all as wide
+            let (up, down): (Vec<_>, Vec<_>) = candles.iter().zip(&volumes).partition(|(c, _)| c.close >= c.open);
+            let points = |bars: Vec<(&Candle, &f64)>| bars.into_iter().map(|(c, &v)| (c.x, v)).collect::<Vec<_>>();
+            for (bars, color) in arrange(&[points(up), points(down)], BarLayout::Stacked).into_iter().zip([UP, DOWN]) {
+                if !bars.is_empty() {
+                    panel.linecolorplot(Shape::Bars(bars), color);
+                }
+            }
+            panel.widen_y_range(0.0, volumes.iter().copied().fold(0.0, f64::max));
+            panel
+        });
+        let chart = finish_panels(&mut chart, panel.as_mut(), opts.format, &names, &opts.annotations, &opts.titles, &entries, opts.legend_pos, &opts.pad, opts.object, call.head);
+        output_chart(engine, call, chart, meta)
+    }
+}
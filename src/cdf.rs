@@ -0,0 +1,34 @@
+//! Empirical cumulative distribution functions.
+
+/// The empirical CDF of `data`, as the points of a step plot.
+///
+/// There is one point per distinct value, paired with the proportion of
+/// the data *below* it, and a last point at the largest value and 1.
+/// This is the form [`Shape::Steps`](crate::color_plot::textplots::Shape::Steps)
+/// draws, rising at each point to the proportion of the next. Values
+/// which are not finite are ignored.
+///
+/// ```
+/// # use nu_plugin_plot::cdf::ecdf;
+/// assert_eq!(
+///     ecdf(&[3.0, 1.0, 3.0, 2.0]),
+///     vec![(1.0, 0.0), (2.0, 0.25), (3.0, 0.5), (3.0, 1.0)]
+/// );
+/// ```
+pub fn ecdf(data: &[f64]) -> Vec<(f64, f64)> {
+    let mut sorted: Vec<f64> = data.iter().copied().filter(|e| e.is_finite()).collect();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let n = sorted.len() as f64;
+    let mut points = Vec::new();
+    for (i, x) in sorted.iter().enumerate() {
+        // only the first of a run of ties starts a step
+        if i == 0 || sorted[i - 1] != *x {
+            points.push((*x, i as f64 / n));
+        }
+    }
+    if let Some(last) = sorted.last() {
+        points.push((*last, 1.0));
+    }
+    points
+}
@@ -0,0 +1,139 @@
+//! Charts as values of their own.
+//!
+//! With `--object` a chart is returned as a `chart` value rather than
+//! as text. It's shown as the chart, but keeps the points it was drawn
+//! from, its ranges and the facts `--meta` gives, as cells, so
+//! `$chart.ymax` or `$chart | get data` read them back, and `plot merge`
+//! draws the series of several charts together.
+
+use nu_protocol::{record, CustomValue, Record, ShellError, Span, Value};
+use serde::{Deserialize, Serialize};
+
+use crate::color_plot::textplots::{Chart, Shape, StepMode};
+
+/// A series of a chart value.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct ChartSeries {
+    pub(crate) name: String,
+    /// `lines`, `points`, `steps` or `bars`, as a spec names them, or
+    /// `candles`, as the closes of candlesticks.
+    pub(crate) shape: String,
+    pub(crate) points: Vec<(f64, f64)>,
+}
+
+/// A chart drawn as text, with what it was drawn from.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct ChartValue {
+    /// The chart as it's shown.
+    pub(crate) frame: String,
+    /// The title, if it has one.
+    pub(crate) title: Option<String>,
+    /// Each series, in the order drawn.
+    pub(crate) series: Vec<ChartSeries>,
+    /// The x range of the chart.
+    x_range: (f64, f64),
+    /// The y range of the chart.
+    y_range: (f64, f64),
+    /// Facts about the input, as `--meta` gives them.
+    pub(crate) facts: Record,
+}
+
+impl ChartValue {
+    /// `chart`, drawn as `frame`, with its shapes called `names`.
+    pub(crate) fn new(chart: &Chart, frame: String, names: &[String], title: Option<&str>) -> Self {
+        let (width, _) = chart.size();
+        let series = chart
+            .shapes()
+            .iter()
+            .enumerate()
+            .map(|(i, (shape, _))| ChartSeries {
+                name: names.get(i).cloned().unwrap_or_else(|| format!("Series {}", i + 1)),
+                shape: match shape {
+                    Shape::Points(_) => "points",
+                    Shape::Steps(_, StepMode::Pre) => "steps",
+                    Shape::Steps(_, StepMode::Post) => "steps:post",
+                    Shape::Steps(_, StepMode::Mid) => "steps:mid",
+                    Shape::Bars(_) => "bars",
+                    Shape::Stems(_) => "stems",
+                    Shape::Candles(_) => "candles",
+                    Shape::Heatmap(_) => "heatmap",
+                    Shape::Continuous(_) | Shape::Lines(_) => "lines",
+                }
+                .to_string(),
+                points: shape.points(chart.x_range(), width),
+            })
+            .collect();
+        ChartValue {
+            frame,
+            title: title.map(str::to_string),
+            series,
+            x_range: chart.x_range(),
+            y_range: chart.y_range(),
+            facts: Record::new(),
+        }
+    }
+
+    /// The cell called `column`, if there is one: the chart, its title,
+    /// the names of its series, their points as `data`, its ranges, or
+    /// one of the facts.
+    fn cell(&self, column: &str, span: Span) -> Option<Value> {
+        let num = |v: f64| if v.is_finite() { Value::float(v, span) } else { Value::nothing(span) };
+        Some(match column {
+            "chart" => Value::string(&self.frame, span),
+            "title" => self.title.as_ref().map_or(Value::nothing(span), |t| Value::string(t, span)),
+            "names" => Value::list(self.series.iter().map(|s| Value::string(&s.name, span)).collect(), span),
+            "data" => Value::list(
+                self.series
+                    .iter()
+                    .flat_map(|s| {
+                        s.points.iter().map(move |(x, y)| Value::record(record! { "series" => Value::string(&s.name, span), "x" => num(*x), "y" => num(*y) }, span))
+                    })
+                    .collect(),
+                span,
+            ),
+            "xmin" => num(self.x_range.0),
+            "xmax" => num(self.x_range.1),
+            "ymin" => num(self.y_range.0),
+            "ymax" => num(self.y_range.1),
+            column => self.facts.get(column)?.clone(),
+        })
+    }
+}
+
+#[typetag::serde]
+impl CustomValue for ChartValue {
+    fn clone_value(&self, span: Span) -> Value {
+        Value::custom(Box::new(self.clone()), span)
+    }
+
+    fn type_name(&self) -> String {
+        "chart".into()
+    }
+
+    fn to_base_value(&self, span: Span) -> Result<Value, ShellError> {
+        Ok(Value::string(&self.frame, span))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn follow_path_string(&self, self_span: Span, column_name: String, path_span: Span) -> Result<Value, ShellError> {
+        self.cell(&column_name, path_span).ok_or(ShellError::CantFindColumn { col_name: column_name, span: Some(path_span), src_span: self_span })
+    }
+}
+
+/// `name`, of a series of the `i`th of several charts, told apart from
+/// the `names` of those before it by the title of its chart, if it has
+/// one, or else by its number.
+pub(crate) fn distinct_name(name: String, names: &[String], i: usize, title: Option<&str>) -> String {
+    match (names.contains(&name), title) {
+        (false, _) => name,
+        (true, Some(title)) => format!("{}: {}", title, name),
+        (true, None) => format!("Chart {}: {}", i + 1, name),
+    }
+}
@@ -0,0 +1,287 @@
+//! A canvas of block characters, for terminals where Braille renders
+//! poorly or colors need to stand out.
+//!
+//! Each cell covers the same two by four points as a Braille character,
+//! drawn as quadrant blocks, so a chart has the same layout either way.
+//! Unlike Braille, every quadrant keeps its own color, and a cell can show
+//! two of them, one in the foreground and one in the background. Shaded
+//! regions are drawn with the shade characters, darker where they overlap.
+//!
+//! ```
+//! use nu_plugin_plot::color_plot::blocks::BlockCanvas;
+//! use nu_plugin_plot::color_plot::textplots::{Chart, Plot, Shape};
+//!
+//! let points = vec![(0.0, 0.0), (1.0, 1.0)];
+//! let chart = Chart::with_backend(Box::new(BlockCanvas::new(32, 32)), 32, 32, 0.0, 1.0)
+//!     .lineplot(Shape::Lines(points))
+//!     .to_string();
+//! assert!(chart.contains('▞'));
+//! assert!(!chart.chars().any(|c| ('\u{2800}'..='\u{28ff}').contains(&c)));
+//! ```
+
+use std::cmp;
+
+use fnv::{FnvHashMap, FnvHashSet};
+use owo_colors::OwoColorize;
+
+use super::drawille::{contrast, Blend, PixelColor};
+use super::textplots::backend::{framed, Backend};
+use super::textplots::label::LabelFormat;
+
+/// The quadrant blocks, indexed by the quadrants they fill: a bit each
+/// for the top left, top right, bottom left and bottom right.
+const QUADRANTS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+/// Shades for a cell in one, two, or three or more shaded regions.
+const SHADES: [char; 3] = ['░', '▒', '▓'];
+
+/// The quadrant block filling the quadrants set in `mask`, a bit each for
+/// the top left, top right, bottom left and bottom right.
+///
+/// ```
+/// use nu_plugin_plot::color_plot::blocks::quadrant;
+///
+/// assert_eq!(quadrant(0b0011), '▀');
+/// assert_eq!(quadrant(0b1111), '█');
+/// ```
+pub fn quadrant(mask: u8) -> char {
+    QUADRANTS[(mask & 0xf) as usize]
+}
+
+/// One character cell of the canvas.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Cell {
+    /// The quadrants drawn in, a bit each as for [`quadrant`].
+    mask: u8,
+    /// The color of each quadrant, `None` for the default color.
+    colors: [Option<PixelColor>; 4],
+    /// The quadrants drawn behind everything else.
+    behind: u8,
+    /// The color of the quadrants drawn behind.
+    behind_color: Option<PixelColor>,
+    /// The number of shaded regions the cell is in, and the color of the last.
+    shade: (usize, Option<PixelColor>),
+    /// A character of text, which replaces anything drawn in the cell.
+    text: Option<(char, Option<PixelColor>)>,
+}
+
+/// `c` in `color` on `background`, or as it is for the default colors.
+/// On a background, the default color is one which shows up on it.
+fn paint(c: char, color: Option<PixelColor>, background: Option<PixelColor>) -> String {
+    match (color.or(background.map(contrast)), background) {
+        (Some(color), Some(background)) => c.color(color).on_color(background).to_string(),
+        (Some(color), None) => c.color(color).to_string(),
+        (None, _) => c.to_string(),
+    }
+}
+
+impl Cell {
+    /// The cell as a character, with its colors, on `background`.
+    fn render(&self, background: Option<PixelColor>) -> String {
+        if let Some((c, color)) = self.text {
+            return paint(c, color, background);
+        }
+        if self.mask != 0 {
+            return self.render_quadrants(background);
+        }
+        if self.behind != 0 {
+            return paint(quadrant(self.behind), self.behind_color, background);
+        }
+        match self.shade {
+            (0, _) => paint(' ', None, background),
+            (n, color) => paint(SHADES[cmp::min(n, SHADES.len()) - 1], color, background),
+        }
+    }
+
+    /// The quadrants drawn in, in the color covering most of them. When
+    /// every quadrant is drawn, the ones in another color are left to the
+    /// background, in that color, so two series can share the cell.
+    fn render_quadrants(&self, background: Option<PixelColor>) -> String {
+        let drawn: Vec<usize> = (0..4).filter(|q| self.mask & 1 << q != 0).collect();
+        let count = |color: Option<PixelColor>| drawn.iter().filter(|&&q| self.colors[q] == color).count();
+        let fg = drawn.iter().map(|&q| self.colors[q]).max_by_key(|&c| count(c)).flatten();
+        let bg = drawn.iter().map(|&q| self.colors[q]).find(|&c| c != fg).flatten();
+        match (fg, bg) {
+            (Some(fg), Some(bg)) if self.mask == 0xf => {
+                let mask = drawn.iter().filter(|&&q| self.colors[q] == Some(fg)).fold(0, |m, q| m | 1 << q);
+                quadrant(mask).color(fg).on_color(bg).to_string()
+            }
+            (fg, _) => paint(quadrant(self.mask), fg, background),
+        }
+    }
+}
+
+/// A canvas drawing with quadrant and shade block characters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockCanvas {
+    cells: FnvHashMap<(u16, u16), Cell>,
+    width: u16,
+    height: u16,
+    /// How a colored quadrant is colored where it lands on another color.
+    blend: Blend,
+    /// The color behind every cell.
+    background: Option<PixelColor>,
+}
+
+impl BlockCanvas {
+    /// Creates a new `BlockCanvas` with the given width and height in
+    /// points, two to a cell across and four down.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            cells: FnvHashMap::default(),
+            width: (width / 2) as u16,
+            height: (height / 4) as u16,
+            blend: Blend::Last,
+            background: None,
+        }
+    }
+
+    /// The cell holding the point `(x, y)`.
+    fn cell(&mut self, x: u32, y: u32) -> &mut Cell {
+        self.cells.entry(((x / 2) as u16, (y / 4) as u16)).or_default()
+    }
+
+    /// The quadrant of its cell the point `(x, y)` falls in.
+    fn quadrant_of(x: u32, y: u32) -> usize {
+        (x % 2 + 2 * (y % 4 / 2)) as usize
+    }
+
+    /// Sets a character of text at the point `(x, y)`.
+    fn set_char(&mut self, x: u32, y: u32, c: char, color: Option<PixelColor>) {
+        *self.cell(x, y) = Cell {
+            text: Some((c, color)),
+            ..Cell::default()
+        };
+    }
+
+    /// The rows of the canvas.
+    fn rows(&self) -> Vec<String> {
+        let (mut width, mut height) = (self.width, self.height);
+        for &(c, r) in self.cells.keys() {
+            width = cmp::max(width, c);
+            height = cmp::max(height, r);
+        }
+        let blank = Cell::default();
+        (0..=height)
+            .map(|r| (0..=width).map(|c| self.cells.get(&(c, r)).unwrap_or(&blank).render(self.background)).collect())
+            .collect()
+    }
+}
+
+impl Backend for BlockCanvas {
+    fn set(&mut self, x: u32, y: u32, color: Option<PixelColor>) {
+        let (q, blend) = (Self::quadrant_of(x, y), self.blend);
+        let cell = self.cell(x, y);
+        cell.colors[q] = match (cell.colors[q], color) {
+            (Some(under), Some(over)) if cell.mask & 1 << q != 0 => Some(blend.color(under, over)),
+            _ => color,
+        };
+        cell.mask |= 1 << q;
+        cell.text = None;
+    }
+
+    fn set_blend(&mut self, blend: Blend) {
+        self.blend = blend;
+    }
+
+    fn set_background(&mut self, color: PixelColor) {
+        self.background = Some(color);
+    }
+
+    fn set_behind(&mut self, x: u32, y: u32, color: PixelColor) {
+        let q = Self::quadrant_of(x, y);
+        let cell = self.cell(x, y);
+        if cell.text.is_none() {
+            cell.behind |= 1 << q;
+            cell.behind_color = Some(color);
+        }
+    }
+
+    fn line(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: Option<PixelColor>) {
+        let xdiff = cmp::max(x1, x2) - cmp::min(x1, x2);
+        let ydiff = cmp::max(y1, y2) - cmp::min(y1, y2);
+        let xdir = if x1 <= x2 { 1 } else { -1 };
+        let ydir = if y1 <= y2 { 1 } else { -1 };
+
+        let r = cmp::max(xdiff, ydiff);
+
+        for i in 0..=r {
+            let mut x = x1 as i32;
+            let mut y = y1 as i32;
+
+            if ydiff != 0 {
+                y += ((i * ydiff) / r) as i32 * ydir;
+            }
+            if xdiff != 0 {
+                x += ((i * xdiff) / r) as i32 * xdir;
+            }
+
+            Backend::set(self, x as u32, y as u32, color);
+        }
+    }
+
+    /// Shades every cell the rectangle touches, once.
+    fn fill(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: PixelColor) {
+        for c in cmp::min(x1, x2) / 2..=cmp::max(x1, x2) / 2 {
+            for r in cmp::min(y1, y2) / 4..=cmp::max(y1, y2) / 4 {
+                let cell = self.cell(2 * c, 4 * r);
+                cell.shade = (cell.shade.0 + 1, Some(color));
+            }
+        }
+    }
+
+    /// Shades every cell between the lines once, however many of the
+    /// columns fall in it.
+    fn fill_area(&mut self, columns: &[(u32, u32, u32)], color: PixelColor) {
+        let cells: FnvHashSet<(u32, u32)> = columns
+            .iter()
+            .flat_map(|&(x, top, bottom)| (cmp::min(top, bottom) / 4..=cmp::max(top, bottom) / 4).map(move |r| (x / 2, r)))
+            .collect();
+        for (c, r) in cells {
+            let cell = self.cell(2 * c, 4 * r);
+            cell.shade = (cell.shade.0 + 1, Some(color));
+        }
+    }
+
+    fn text(&mut self, x: u32, y: u32, max_width: u32, text: &str, color: Option<PixelColor>) {
+        for (i, c) in text.chars().enumerate() {
+            let w = i as u32 * 2;
+            if w > max_width {
+                return;
+            }
+            self.set_char(x + w, y, c, color);
+        }
+    }
+
+    fn text_fits(&self, x: u32, y: u32, len: u32) -> bool {
+        let (col, row) = ((x / 2) as u16, (y / 4) as u16);
+        (col..col + len as u16).all(|c| self.cells.get(&(c, row)).is_none_or(|cell| cell.text.is_none()))
+    }
+
+    /// Box drawing characters around blank cells.
+    fn panel(&mut self, x1: u32, y1: u32, x2: u32, y2: u32) {
+        let (left, right) = (x1.min(x2) / 2, x1.max(x2) / 2);
+        let (top, bottom) = (y1.min(y2) / 4, y1.max(y2) / 4);
+        for r in top..=bottom {
+            for c in left..=right {
+                let edge = match (r, c) {
+                    (r, c) if r == top && c == left => '┌',
+                    (r, c) if r == top && c == right => '┐',
+                    (r, c) if r == bottom && c == left => '└',
+                    (r, c) if r == bottom && c == right => '┘',
+                    (r, _) if r == top || r == bottom => '─',
+                    (_, c) if c == left || c == right => '│',
+                    _ => ' ',
+                };
+                self.set_char(2 * c, 4 * r, edge, None);
+            }
+        }
+    }
+
+    /// The rows of the canvas, framed as on the Braille canvas.
+    fn render(&self, width: u32, height: u32, x_range: (f64, f64), y_range: (f64, f64), _title: Option<&str>, labels: (LabelFormat, LabelFormat)) -> Vec<u8> {
+        framed(self.rows().join("\n"), width, height, x_range, y_range, labels).into_bytes()
+    }
+}
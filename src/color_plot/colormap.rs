@@ -0,0 +1,129 @@
+//! Colormaps, for coloring a series by its values.
+//!
+//! A terminal may have only sixteen colors, so a colormap is a short run
+//! of them from low values to high, and each value takes the color of
+//! the stretch of the run it falls in. Where a terminal has more, a
+//! smooth colormap blends between stops given as red, green and blue
+//! instead.
+
+use super::drawille::PixelColor;
+
+/// A run of colors to pick from by value, from low to high.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Colormap {
+    /// The terminal colors, from low to high.
+    colors: &'static [PixelColor],
+    /// The stops blended between when smooth, from low to high.
+    stops: &'static [(u8, u8, u8)],
+    /// Whether to blend between the stops.
+    smooth: bool,
+}
+
+impl Colormap {
+    /// Green for low values, through yellow, to red for high ones.
+    pub const GREEN_RED: Colormap = Colormap {
+        colors: &[
+            PixelColor::Green,
+            PixelColor::BrightGreen,
+            PixelColor::BrightYellow,
+            PixelColor::Yellow,
+            PixelColor::BrightRed,
+            PixelColor::Red,
+        ],
+        stops: &[(26, 152, 80), (145, 207, 96), (217, 239, 139), (254, 224, 139), (252, 141, 89), (215, 48, 39)],
+        smooth: false,
+    };
+
+    /// Blue for low values, through cyan and white, to red for high ones.
+    pub const BLUE_RED: Colormap = Colormap {
+        colors: &[
+            PixelColor::Blue,
+            PixelColor::BrightBlue,
+            PixelColor::BrightCyan,
+            PixelColor::BrightWhite,
+            PixelColor::BrightRed,
+            PixelColor::Red,
+        ],
+        stops: &[(33, 102, 172), (103, 169, 207), (209, 229, 240), (247, 247, 247), (253, 219, 199), (239, 138, 98), (178, 24, 43)],
+        smooth: false,
+    };
+
+    /// The viridis scale, from purple through blue and green to yellow.
+    pub const VIRIDIS: Colormap = Colormap {
+        colors: &[
+            PixelColor::Magenta,
+            PixelColor::Blue,
+            PixelColor::Cyan,
+            PixelColor::Green,
+            PixelColor::BrightYellow,
+        ],
+        stops: &[(68, 1, 84), (59, 82, 139), (33, 145, 140), (94, 201, 98), (253, 231, 37)],
+        smooth: false,
+    };
+
+    /// The colormap called `name`: `green-red`, `blue-red` or `viridis`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "green-red" => Some(Self::GREEN_RED),
+            "blue-red" => Some(Self::BLUE_RED),
+            "viridis" => Some(Self::VIRIDIS),
+            _ => None,
+        }
+    }
+
+    /// The colormap blending smoothly between its stops, for terminals
+    /// and images with more than sixteen colors.
+    ///
+    /// ```
+    /// use nu_plugin_plot::color_plot::colormap::Colormap;
+    /// use nu_plugin_plot::color_plot::drawille::PixelColor;
+    ///
+    /// let colormap = Colormap::VIRIDIS.smooth();
+    /// assert_eq!(colormap.color(0.0), PixelColor::Rgb(68, 1, 84));
+    /// assert_eq!(colormap.color(0.125), PixelColor::Rgb(64, 42, 112));
+    /// assert_eq!(colormap.color(1.0), PixelColor::Rgb(253, 231, 37));
+    /// ```
+    pub fn smooth(self) -> Self {
+        Self { smooth: true, ..self }
+    }
+
+    /// The terminal colors, from low values to high.
+    pub fn colors(&self) -> &'static [PixelColor] {
+        self.colors
+    }
+
+    /// The colors to show the colormap with, from low values to high:
+    /// the stops if it is smooth, or else the terminal colors.
+    pub fn swatches(&self) -> Vec<PixelColor> {
+        match self.smooth {
+            true => self.stops.iter().map(|&(r, g, b)| PixelColor::Rgb(r, g, b)).collect(),
+            false => self.colors.to_vec(),
+        }
+    }
+
+    /// The color for `t`, from 0 for the lowest values to 1 for the
+    /// highest. Anything outside is taken as the nearest end, and values
+    /// which are not finite as the lowest.
+    ///
+    /// ```
+    /// use nu_plugin_plot::color_plot::colormap::Colormap;
+    /// use nu_plugin_plot::color_plot::drawille::PixelColor;
+    ///
+    /// let colormap = Colormap::GREEN_RED;
+    /// assert_eq!(colormap.color(0.0), PixelColor::Green);
+    /// assert_eq!(colormap.color(1.0), PixelColor::Red);
+    /// assert_eq!(colormap.color(2.0), PixelColor::Red);
+    /// ```
+    pub fn color(&self, t: f64) -> PixelColor {
+        let t = if t.is_finite() { t.clamp(0.0, 1.0) } else { 0.0 };
+        if self.smooth {
+            let along = t * (self.stops.len() - 1) as f64;
+            let i = (along as usize).min(self.stops.len() - 2);
+            let ((r1, g1, b1), (r2, g2, b2)) = (self.stops[i], self.stops[i + 1]);
+            let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * (along - i as f64)).round() as u8;
+            return PixelColor::Rgb(mix(r1, r2), mix(g1, g2), mix(b1, b2));
+        }
+        let i = (t * self.colors.len() as f64) as usize;
+        self.colors[i.min(self.colors.len() - 1)]
+    }
+}
@@ -10,9 +10,7 @@
 //! # Example
 //!
 //! ```
-//! extern crate drawille;
-//!
-//! use drawille::Canvas;
+//! use nu_plugin_plot::color_plot::drawille::Canvas;
 //!
 //! fn main() {
 //!     let mut canvas = Canvas::new(10, 10);
@@ -24,25 +22,563 @@
 //! "    ⠁ "].join("\n"));
 //! }
 //! ```
+use std::borrow::Cow;
 use std::char;
 use std::cmp;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use owo_colors::{AnsiColors, DynColor, DynColors};
+
+/// The color of a pixel: one of the sixteen terminal colors, the
+/// terminal's default, or any other as its red, green and blue.
+///
+/// ```
+/// use nu_plugin_plot::color_plot::drawille::PixelColor;
+///
+/// assert_eq!(PixelColor::from_hex("#ff8800"), Some(PixelColor::Rgb(255, 136, 0)));
+/// assert_eq!(PixelColor::Rgb(250, 10, 5).nearest(), PixelColor::BrightRed);
+/// assert_eq!(PixelColor::Rgb(255, 136, 0).xterm(), 208);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PixelColor {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Default,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+    Rgb(u8, u8, u8),
+}
+
+/// The sixteen terminal colors, in the order of their codes.
+const NAMED: [PixelColor; 16] = [
+    PixelColor::Black,
+    PixelColor::Red,
+    PixelColor::Green,
+    PixelColor::Yellow,
+    PixelColor::Blue,
+    PixelColor::Magenta,
+    PixelColor::Cyan,
+    PixelColor::White,
+    PixelColor::BrightBlack,
+    PixelColor::BrightRed,
+    PixelColor::BrightGreen,
+    PixelColor::BrightYellow,
+    PixelColor::BrightBlue,
+    PixelColor::BrightMagenta,
+    PixelColor::BrightCyan,
+    PixelColor::BrightWhite,
+];
+
+/// The levels of red, green and blue in the six by six by six cube of
+/// colors from 16 to 231 of a 256 color terminal.
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl PixelColor {
+    /// The color written `#rrggbb`, as in CSS.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').filter(|h| h.len() == 6 && h.is_ascii())?;
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some(PixelColor::Rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
 
-use fnv::FnvHashMap;
-pub use owo_colors::AnsiColors as PixelColor;
-use owo_colors::OwoColorize;
+    /// The red, green and blue of the color, the terminal colors as
+    /// xterm shows them, and the default as white.
+    pub fn rgb(self) -> (u8, u8, u8) {
+        match self {
+            PixelColor::Black => (0, 0, 0),
+            PixelColor::Red => (205, 0, 0),
+            PixelColor::Green => (0, 205, 0),
+            PixelColor::Yellow => (205, 205, 0),
+            PixelColor::Blue => (0, 0, 238),
+            PixelColor::Magenta => (205, 0, 205),
+            PixelColor::Cyan => (0, 205, 205),
+            PixelColor::White | PixelColor::Default => (229, 229, 229),
+            PixelColor::BrightBlack => (127, 127, 127),
+            PixelColor::BrightRed => (255, 0, 0),
+            PixelColor::BrightGreen => (0, 255, 0),
+            PixelColor::BrightYellow => (255, 255, 0),
+            PixelColor::BrightBlue => (92, 92, 255),
+            PixelColor::BrightMagenta => (255, 0, 255),
+            PixelColor::BrightCyan => (0, 255, 255),
+            PixelColor::BrightWhite => (255, 255, 255),
+            PixelColor::Rgb(r, g, b) => (r, g, b),
+        }
+    }
+
+    /// The terminal color closest to this one, for terminals with only
+    /// sixteen.
+    pub fn nearest(self) -> Self {
+        match self {
+            PixelColor::Rgb(..) => *NAMED.iter().min_by_key(|named| distance(named.rgb(), self.rgb())).unwrap_or(&PixelColor::Default),
+            named => named,
+        }
+    }
 
-// extern crate colored;
-// pub use colored::Color as PixelColor;
-// use colored::Colorize;
+    /// The number of the color closest to this one of the 256 of an
+    /// xterm: from the cube of colors, or the run of greys after it.
+    pub fn xterm(self) -> u8 {
+        let (r, g, b) = self.rgb();
+        let level = |c: u8| CUBE.iter().enumerate().min_by_key(|(_, &l)| l.abs_diff(c)).map_or(0, |(i, _)| i);
+        let (i, j, k) = (level(r), level(g), level(b));
+        let cube = (CUBE[i], CUBE[j], CUBE[k]);
+        let grey = ((r as u32 + g as u32 + b as u32) / 3).saturating_sub(3).min(230) as u8 / 10;
+        let level = 8 + 10 * grey;
+        if distance((level, level, level), (r, g, b)) < distance(cube, (r, g, b)) {
+            232 + grey
+        } else {
+            16 + 36 * i as u8 + 6 * j as u8 + k as u8
+        }
+    }
+
+    /// The color as owo-colors has it.
+    fn dyn_colors(self) -> DynColors {
+        let ansi = match self {
+            PixelColor::Rgb(r, g, b) => return DynColors::Rgb(r, g, b),
+            PixelColor::Black => AnsiColors::Black,
+            PixelColor::Red => AnsiColors::Red,
+            PixelColor::Green => AnsiColors::Green,
+            PixelColor::Yellow => AnsiColors::Yellow,
+            PixelColor::Blue => AnsiColors::Blue,
+            PixelColor::Magenta => AnsiColors::Magenta,
+            PixelColor::Cyan => AnsiColors::Cyan,
+            PixelColor::White => AnsiColors::White,
+            PixelColor::Default => AnsiColors::Default,
+            PixelColor::BrightBlack => AnsiColors::BrightBlack,
+            PixelColor::BrightRed => AnsiColors::BrightRed,
+            PixelColor::BrightGreen => AnsiColors::BrightGreen,
+            PixelColor::BrightYellow => AnsiColors::BrightYellow,
+            PixelColor::BrightBlue => AnsiColors::BrightBlue,
+            PixelColor::BrightMagenta => AnsiColors::BrightMagenta,
+            PixelColor::BrightCyan => AnsiColors::BrightCyan,
+            PixelColor::BrightWhite => AnsiColors::BrightWhite,
+        };
+        DynColors::Ansi(ansi)
+    }
+}
+
+/// The squared distance between two colors, as red, green and blue.
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    [(r1, r2), (g1, g2), (b1, b2)].iter().map(|&(a, b)| (a.abs_diff(b) as u32).pow(2)).sum()
+}
+
+impl DynColor for PixelColor {
+    fn fmt_ansi_fg(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.dyn_colors().fmt_ansi_fg(f)
+    }
+
+    fn fmt_ansi_bg(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.dyn_colors().fmt_ansi_bg(f)
+    }
+
+    fn fmt_raw_ansi_fg(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.dyn_colors().fmt_raw_ansi_fg(f)
+    }
+
+    fn fmt_raw_ansi_bg(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.dyn_colors().fmt_raw_ansi_bg(f)
+    }
+
+    fn get_dyncolors_fg(&self) -> DynColors {
+        self.dyn_colors()
+    }
+
+    fn get_dyncolors_bg(&self) -> DynColors {
+        self.dyn_colors()
+    }
+}
+
+/// How many colors a terminal can show: the sixteen terminal colors,
+/// the 256 of an xterm, or any at all, as 24-bit true color.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorDepth {
+    #[default]
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// `frame` with the colors its escape sequences give as red, green
+    /// and blue turned into the closest a terminal this deep has, and
+    /// everything else as it was.
+    ///
+    /// ```
+    /// use nu_plugin_plot::color_plot::drawille::ColorDepth;
+    ///
+    /// let frame = "\x1b[38;2;255;136;0m⣿\x1b[39m \x1b[48;2;255;136;0m \x1b[49m";
+    /// assert_eq!(ColorDepth::TrueColor.downgrade(frame), frame);
+    /// assert_eq!(ColorDepth::Ansi256.downgrade(frame), "\x1b[38;5;208m⣿\x1b[39m \x1b[48;5;208m \x1b[49m");
+    /// assert_eq!(ColorDepth::Ansi16.downgrade(frame), "\x1b[33m⣿\x1b[39m \x1b[43m \x1b[49m");
+    /// ```
+    pub fn downgrade(self, frame: &str) -> String {
+        if self == ColorDepth::TrueColor {
+            return frame.to_string();
+        }
+        let mut out = String::with_capacity(frame.len());
+        let mut rest = frame;
+        while let Some(start) = rest.find("\x1b[") {
+            out.push_str(&rest[..start]);
+            let sequence = &rest[start + 2..];
+            // a sequence runs up to and including its final byte
+            let Some(end) = sequence.find(|c: char| ('@'..='~').contains(&c)) else {
+                out.push_str(&rest[start..]);
+                return out;
+            };
+            out.push_str("\x1b[");
+            if sequence[end..].starts_with('m') {
+                out.push_str(&self.parameters(&sequence[..end]));
+            } else {
+                out.push_str(&sequence[..end]);
+            }
+            out.push_str(&sequence[end..end + 1]);
+            rest = &sequence[end + 1..];
+        }
+        out.push_str(rest);
+        out
+    }
+
+    /// The parameters of an escape sequence setting colors, with those
+    /// given as red, green and blue turned into the closest there are.
+    fn parameters(self, parameters: &str) -> String {
+        let parts: Vec<&str> = parameters.split(';').collect();
+        let mut out: Vec<String> = Vec::with_capacity(parts.len());
+        let mut i = 0;
+        while i < parts.len() {
+            let rgb = parts.get(i + 2..i + 5).filter(|_| parts[i + 1] == "2" && (parts[i] == "38" || parts[i] == "48"));
+            match rgb.and_then(|rgb| Some(PixelColor::Rgb(rgb[0].parse().ok()?, rgb[1].parse().ok()?, rgb[2].parse().ok()?))) {
+                Some(color) => {
+                    let background = parts[i] == "48";
+                    out.push(match self {
+                        ColorDepth::Ansi256 => format!("{};5;{}", parts[i], color.xterm()),
+                        _ if background => bg_code(color.nearest()),
+                        _ => fg_code(color.nearest()).into_owned(),
+                    });
+                    i += 5;
+                }
+                None => {
+                    out.push(parts[i].to_string());
+                    i += 1;
+                }
+            }
+        }
+        out.join(";")
+    }
+}
 
 static PIXEL_MAP: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
 
+/// Bytes to set aside for each cell of a row: a Braille character
+/// takes three, and escape sequences need a little more.
+const ROW_BYTES: usize = 4;
+
+/// The escape sequence returning text to the default color.
+const RESET: &str = "\x1b[39m";
+
+/// The parameter of the escape sequence setting text to `color`.
+fn fg_code(color: PixelColor) -> Cow<'static, str> {
+    Cow::Borrowed(match color {
+        PixelColor::Black => "30",
+        PixelColor::Red => "31",
+        PixelColor::Green => "32",
+        PixelColor::Yellow => "33",
+        PixelColor::Blue => "34",
+        PixelColor::Magenta => "35",
+        PixelColor::Cyan => "36",
+        PixelColor::White => "37",
+        PixelColor::Default => "39",
+        PixelColor::BrightBlack => "90",
+        PixelColor::BrightRed => "91",
+        PixelColor::BrightGreen => "92",
+        PixelColor::BrightYellow => "93",
+        PixelColor::BrightBlue => "94",
+        PixelColor::BrightMagenta => "95",
+        PixelColor::BrightCyan => "96",
+        PixelColor::BrightWhite => "97",
+        PixelColor::Rgb(r, g, b) => return format!("38;2;{};{};{}", r, g, b).into(),
+    })
+}
+
+/// The escape sequence returning the background to the default color.
+const RESET_BACKGROUND: &str = "\x1b[49m";
+
+/// The parameter of the escape sequence setting the background to
+/// `color`, ten on from that setting text to it.
+fn bg_code(color: PixelColor) -> String {
+    match color {
+        PixelColor::Rgb(r, g, b) => format!("48;2;{};{};{}", r, g, b),
+        _ => fg_code(color).parse::<u8>().map_or(49, |code| code + 10).to_string(),
+    }
+}
+
+/// A color which shows up on `background`, for what is drawn in the
+/// default color over it: black on the light colors, and bright white
+/// on the rest.
+///
+/// ```
+/// use nu_plugin_plot::color_plot::drawille::{contrast, PixelColor};
+///
+/// assert_eq!(contrast(PixelColor::BrightWhite), PixelColor::Black);
+/// assert_eq!(contrast(PixelColor::Blue), PixelColor::BrightWhite);
+/// ```
+pub fn contrast(background: PixelColor) -> PixelColor {
+    match background {
+        PixelColor::Rgb(r, g, b) if 299 * r as u32 + 587 * g as u32 + 114 * b as u32 > 128_000 => PixelColor::Black,
+        PixelColor::White | PixelColor::BrightWhite | PixelColor::Yellow | PixelColor::BrightYellow | PixelColor::Cyan | PixelColor::BrightCyan | PixelColor::BrightGreen => PixelColor::Black,
+        _ => PixelColor::BrightWhite,
+    }
+}
+
+/// The color two series cross in, with `Blend::Highlight`.
+pub const HIGHLIGHT: PixelColor = PixelColor::BrightWhite;
+
+/// How a pixel drawn in one color is colored where it lands on another
+/// color already drawn: in its own (`Last`), as if drawn last, in a mix of
+/// the two (`Mix`), or in the [`HIGHLIGHT`] color (`Highlight`), so where
+/// series cross shows.
+///
+/// ```
+/// use nu_plugin_plot::color_plot::drawille::{Blend, PixelColor, HIGHLIGHT};
+///
+/// assert_eq!(Blend::Mix.color(PixelColor::Red, PixelColor::Blue), PixelColor::Magenta);
+/// assert_eq!(Blend::Mix.color(PixelColor::BrightGreen, PixelColor::Red), PixelColor::BrightYellow);
+/// assert_eq!(Blend::Highlight.color(PixelColor::Red, PixelColor::Blue), HIGHLIGHT);
+/// assert_eq!(Blend::default().color(PixelColor::Red, PixelColor::Blue), PixelColor::Blue);
+/// assert_eq!(Blend::from_name("mix"), Some(Blend::Mix));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Blend {
+    #[default]
+    Last,
+    Mix,
+    Highlight,
+}
+
+impl Blend {
+    /// The blend called `name`: `last`, `mix` or `highlight`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "last" => Some(Blend::Last),
+            "mix" => Some(Blend::Mix),
+            "highlight" => Some(Blend::Highlight),
+            _ => None,
+        }
+    }
+
+    /// The color of a pixel drawn in `over` where `under` is drawn. A mix
+    /// adds the red, green and blue of the two terminal colors, and is
+    /// bright if either is, or takes the brighter of each of the red,
+    /// green and blue of two colors where either is not a terminal one.
+    pub fn color(self, under: PixelColor, over: PixelColor) -> PixelColor {
+        if under == over {
+            return over;
+        }
+        match self {
+            Blend::Last => over,
+            Blend::Highlight => HIGHLIGHT,
+            Blend::Mix if matches!(under, PixelColor::Rgb(..)) || matches!(over, PixelColor::Rgb(..)) => {
+                let ((r1, g1, b1), (r2, g2, b2)) = (under.rgb(), over.rgb());
+                PixelColor::Rgb(r1.max(r2), g1.max(g2), b1.max(b2))
+            }
+            Blend::Mix => {
+                let ((a, bright_a), (b, bright_b)) = (channels(under), channels(over));
+                from_channels(a | b, bright_a || bright_b)
+            }
+        }
+    }
+}
+
+/// The red, green and blue of `color`, a bit each, and whether it is bright.
+fn channels(color: PixelColor) -> (u8, bool) {
+    match color.nearest() {
+        PixelColor::Black => (0b000, false),
+        PixelColor::Red => (0b100, false),
+        PixelColor::Green => (0b010, false),
+        PixelColor::Yellow => (0b110, false),
+        PixelColor::Blue => (0b001, false),
+        PixelColor::Magenta => (0b101, false),
+        PixelColor::Cyan => (0b011, false),
+        PixelColor::White | PixelColor::Default => (0b111, false),
+        PixelColor::BrightBlack => (0b000, true),
+        PixelColor::BrightRed => (0b100, true),
+        PixelColor::BrightGreen => (0b010, true),
+        PixelColor::BrightYellow => (0b110, true),
+        PixelColor::BrightBlue => (0b001, true),
+        PixelColor::BrightMagenta => (0b101, true),
+        PixelColor::BrightCyan => (0b011, true),
+        PixelColor::BrightWhite | PixelColor::Rgb(..) => (0b111, true),
+    }
+}
+
+/// The terminal color of the red, green and blue bits `rgb`, bright or not.
+fn from_channels(rgb: u8, bright: bool) -> PixelColor {
+    const PLAIN: [PixelColor; 8] = [
+        PixelColor::Black,
+        PixelColor::Blue,
+        PixelColor::Green,
+        PixelColor::Cyan,
+        PixelColor::Red,
+        PixelColor::Magenta,
+        PixelColor::Yellow,
+        PixelColor::White,
+    ];
+    const BRIGHT: [PixelColor; 8] = [
+        PixelColor::BrightBlack,
+        PixelColor::BrightBlue,
+        PixelColor::BrightGreen,
+        PixelColor::BrightCyan,
+        PixelColor::BrightRed,
+        PixelColor::BrightMagenta,
+        PixelColor::BrightYellow,
+        PixelColor::BrightWhite,
+    ];
+    if bright {
+        BRIGHT[rgb as usize & 7]
+    } else {
+        PLAIN[rgb as usize & 7]
+    }
+}
+
+/// The characters a `Canvas` draws its cells of two by four pixels with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Charset {
+    /// A Braille character for each cell, with a dot for each pixel.
+    #[default]
+    Braille,
+    /// Plain ASCII, `.`, `*`, `|`, `-` and `+`, roughly following the
+    /// shape of the pixels, for terminals and fonts where Braille
+    /// renders poorly.
+    Ascii,
+    /// Quadrant blocks, a quarter of the cell for each two pixels. A
+    /// [`BlockCanvas`](super::blocks::BlockCanvas) draws these with a
+    /// color for each quadrant.
+    Blocks,
+}
+
+impl Charset {
+    /// The character for a cell with the pixels set in `dots`, one bit
+    /// per pixel as in a Braille character.
+    ///
+    /// ```
+    /// use nu_plugin_plot::color_plot::drawille::Charset;
+    ///
+    /// assert_eq!(Charset::Braille.glyph(0x47), '⡇');
+    /// assert_eq!(Charset::Ascii.glyph(0x47), '|');
+    /// assert_eq!(Charset::Ascii.glyph(0x12), '-');
+    /// assert_eq!(Charset::Ascii.glyph(0x01), '.');
+    /// assert_eq!(Charset::Blocks.glyph(0x47), '▌');
+    /// ```
+    pub fn glyph(self, dots: u8) -> char {
+        match self {
+            Charset::Braille => char::from_u32(0x2800 + dots as u32).unwrap(),
+            Charset::Ascii => {
+                let set = |row: usize, col: usize| dots & PIXEL_MAP[row][col] != 0;
+                let mut rows = (0..4).filter(|&r| set(r, 0) || set(r, 1));
+                let tall = match (rows.next(), rows.next_back()) {
+                    (Some(first), Some(last)) => last - first >= 2,
+                    _ => false,
+                };
+                let crossed = (0..4).any(|r| set(r, 0) && set(r, 1));
+                let both_columns = (0..4).any(|r| set(r, 0)) && (0..4).any(|r| set(r, 1));
+                match dots.count_ones() {
+                    0 => ' ',
+                    1 => '.',
+                    _ if tall && crossed => '+',
+                    _ if tall => '|',
+                    _ if both_columns => '-',
+                    _ => '*',
+                }
+            }
+            Charset::Blocks => {
+                let quadrants = [0x01 | 0x02, 0x08 | 0x10, 0x04 | 0x40, 0x20 | 0x80];
+                let mask = (0..4).filter(|&q| dots & quadrants[q] != 0).fold(0, |m, q| m | 1 << q);
+                super::blocks::quadrant(mask)
+            }
+        }
+    }
+}
+
+/// The points of a line from `from` to `to`, as [`Canvas::line`] draws
+/// it, `width` points across, and in dashes if `dash` is set, as
+/// [`Canvas::line_dashed`] draws them from `phase` points into the
+/// pattern. The points beside each are above and below it on a line
+/// more across than up, and either side of it otherwise, those past the
+/// first on the far side. With `smooth` set, the corner of each
+/// diagonal step is filled too, so the line runs on from one point to
+/// the next along an edge rather than touching at a corner.
+///
+/// ```
+/// use nu_plugin_plot::color_plot::drawille::stroke;
+///
+/// assert_eq!(stroke((0, 0), (2, 0), None, 0, (2, false)), [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0), (2, 1)]);
+/// assert_eq!(stroke((0, 0), (2, 2), None, 0, (1, true)), [(0, 0), (1, 1), (1, 0), (2, 2), (2, 1)]);
+/// assert_eq!(stroke((0, 0), (0, 3), Some((1, 1)), 0, (1, false)), [(0, 0), (0, 2)]);
+/// ```
+pub fn stroke(from: (u32, u32), to: (u32, u32), dash: Option<(u32, u32)>, phase: u32, (width, smooth): (u32, bool)) -> Vec<(u32, u32)> {
+    let (xdiff, ydiff) = (from.0.abs_diff(to.0), from.1.abs_diff(to.1));
+    let steps = xdiff.max(ydiff);
+    let along = |a: u32, b: u32, i: u32| (a as i64 + (b as i64 - a as i64) * i as i64 / steps.max(1) as i64) as u32;
+    let across: (i64, i64) = if xdiff >= ydiff { (0, 1) } else { (1, 0) };
+
+    let mut points = vec![];
+    let mut last: Option<(u32, u32)> = None;
+    for i in 0..=steps {
+        let point = (along(from.0, to.0, i), along(from.1, to.1, i));
+        let corner = last.filter(|&(x, y)| smooth && x != point.0 && y != point.1).map(|(_, y)| (point.0, y));
+        last = Some(point);
+        if dash.is_some_and(|(on, off)| (phase + i) % (on + off) >= on) {
+            continue;
+        }
+        for (x, y) in std::iter::once(point).chain(corner) {
+            for k in 0..width as i64 {
+                let offset = k - (width as i64 - 1) / 2;
+                let (x, y) = (x as i64 + offset * across.0, y as i64 + offset * across.1);
+                if x >= 0 && y >= 0 {
+                    points.push((x as u32, y as u32));
+                }
+            }
+        }
+    }
+    points
+}
+
+/// A cell of the canvas: its dots, a letter drawn in place of them,
+/// whether it is colored, and its color.
+type Cell = (u8, char, bool, PixelColor);
+
+/// A cell with nothing drawn in it.
+const BLANK: Cell = (0, ' ', false, PixelColor::White);
+
 /// A canvas object that can be used to draw to the terminal using Braille characters.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Canvas {
-    chars: FnvHashMap<(u16, u16), (u8, char, bool, PixelColor)>,
+    /// The cells, a line of `cols` at a time from the top.
+    chars: Vec<Cell>,
+    /// Number of cells in a line of the grid.
+    cols: usize,
+    /// Number of lines of the grid.
+    lines: usize,
     width: u16,
     height: u16,
+    charset: Charset,
+    /// How a colored pixel is colored where it lands on another color.
+    blend: Blend,
+    /// The color behind every cell without one of its own.
+    background: Option<PixelColor>,
+    /// The colors behind cells, by the cell across and down.
+    backgrounds: BTreeMap<(u16, u16), PixelColor>,
 }
 
 impl Canvas {
@@ -51,25 +587,67 @@ impl Canvas {
     /// Note that the `Canvas` can still draw outside the given dimensions (expanding the canvas)
     /// if a pixel is set outside the dimensions.
     pub fn new(width: u32, height: u32) -> Canvas {
+        Canvas::with_charset(width, height, Charset::Braille)
+    }
+
+    /// Creates a new `Canvas` with the given width and height, drawn
+    /// with the characters of `charset`.
+    pub fn with_charset(width: u32, height: u32, charset: Charset) -> Canvas {
+        let (width, height) = ((width / 2) as u16, (height / 4) as u16);
+        let (cols, lines) = (width as usize + 1, height as usize + 1);
         Canvas {
-            chars: FnvHashMap::default(),
-            width: (width / 2) as u16,
-            height: (height / 4) as u16,
+            chars: vec![BLANK; cols * lines],
+            cols,
+            lines,
+            width,
+            height,
+            charset,
+            blend: Blend::Last,
+            background: None,
+            backgrounds: BTreeMap::new(),
         }
     }
 
-    /// Clears the canvas.
+    /// The cell at `(row, col)`, if it is on the grid.
+    fn cell(&self, row: u16, col: u16) -> Option<&Cell> {
+        let (row, col) = (row as usize, col as usize);
+        (row < self.cols && col < self.lines).then(|| &self.chars[col * self.cols + row])
+    }
+
+    /// The cell at `(row, col)`, growing the grid to take it in.
+    fn cell_mut(&mut self, row: u16, col: u16) -> &mut Cell {
+        let (row, col) = (row as usize, col as usize);
+        if row >= self.cols || col >= self.lines {
+            let cols = self.cols.max(row + 1);
+            let lines = self.lines.max(col + 1);
+            let mut chars = vec![BLANK; cols * lines];
+            for (line, cells) in self.chars.chunks(self.cols).enumerate() {
+                chars[line * cols..line * cols + self.cols].copy_from_slice(cells);
+            }
+            self.chars = chars;
+            self.cols = cols;
+            self.lines = lines;
+        }
+        &mut self.chars[col * self.cols + row]
+    }
+
+    /// The characters the canvas is drawn with.
+    pub fn charset(&self) -> Charset {
+        self.charset
+    }
+
+    /// Clears the canvas, shrinking it back to its given dimensions.
     pub fn clear(&mut self) {
-        self.chars.clear();
+        self.cols = self.width as usize + 1;
+        self.lines = self.height as usize + 1;
+        self.chars = vec![BLANK; self.cols * self.lines];
+        self.backgrounds.clear();
     }
 
     /// Sets a pixel at the specified coordinates.
     pub fn set(&mut self, x: u32, y: u32) {
         let (row, col) = ((x / 2) as u16, (y / 4) as u16);
-        let a = self
-            .chars
-            .entry((row, col))
-            .or_insert((0, ' ', false, PixelColor::White));
+        let a = self.cell_mut(row, col);
         a.0 |= PIXEL_MAP[y as usize % 4][x as usize % 2];
         a.1 = ' ';
         a.2 = false;
@@ -78,25 +656,89 @@ impl Canvas {
 
     /// Sets a pixel at the specified coordinates.
     /// specifying the color of the braille char
+    ///
+    /// A cell shows one color, so where the cell already has dots in
+    /// another, the two are blended as [`Canvas::set_blend`] says.
     pub fn set_colored(&mut self, x: u32, y: u32, color: PixelColor) {
         let (row, col) = ((x / 2) as u16, (y / 4) as u16);
-        let a = self
-            .chars
-            .entry((row, col))
-            .or_insert((0, ' ', false, PixelColor::White));
+        let blend = self.blend;
+        let a = self.cell_mut(row, col);
+        let color = if a.2 && a.0 != 0 { blend.color(a.3, color) } else { color };
         a.0 |= PIXEL_MAP[y as usize % 4][x as usize % 2];
         a.1 = ' ';
         a.2 = true;
         a.3 = color;
     }
 
+    /// Sets how pixels are colored where they land on another color.
+    pub fn set_blend(&mut self, blend: Blend) {
+        self.blend = blend;
+    }
+
+    /// Sets the color behind the whole canvas, or the terminal's own
+    /// with `None`. Dots and letters drawn in the default color are drawn
+    /// in a color which shows up on it, as [`contrast`] picks.
+    pub fn set_background(&mut self, color: Option<PixelColor>) {
+        self.background = color;
+    }
+
+    /// Sets the color behind the cell holding the pixel at the specified
+    /// coordinates, over the background of the canvas, so a cell can be
+    /// filled whole whatever is drawn in it.
+    ///
+    /// ```
+    /// use nu_plugin_plot::color_plot::drawille::{Canvas, PixelColor};
+    ///
+    /// let mut canvas = Canvas::new(4, 4);
+    /// canvas.set_cell_background(2, 0, PixelColor::Blue);
+    /// assert!(canvas.frame().starts_with(" \x1b[44m\x1b[97m \x1b[49m"));
+    /// ```
+    pub fn set_cell_background(&mut self, x: u32, y: u32, color: PixelColor) {
+        self.backgrounds.insert(((x / 2) as u16, (y / 4) as u16), color);
+    }
+
+    /// Sets a pixel at the specified coordinates in the given color,
+    /// unless something is already drawn in its cell. The pixel then
+    /// joins the cell without changing its color, so whatever is drawn
+    /// this way stays behind the rest of the canvas.
+    pub fn set_behind(&mut self, x: u32, y: u32, color: PixelColor) {
+        let (row, col) = ((x / 2) as u16, (y / 4) as u16);
+        let a = self.cell_mut(row, col);
+        if *a == BLANK {
+            *a = (0, ' ', true, color);
+        }
+        if a.0 == 0 && a.1 != ' ' {
+            return;
+        }
+        a.0 |= PIXEL_MAP[y as usize % 4][x as usize % 2];
+    }
+
+    /// Fills the rectangle between `(x1, y1)` and `(x2, y2)` with a sparse
+    /// pattern of pixels in the given color, behind anything already drawn.
+    pub fn fill(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: PixelColor) {
+        for x in cmp::min(x1, x2)..=cmp::max(x1, x2) {
+            for y in cmp::min(y1, y2)..=cmp::max(y1, y2) {
+                if (x % 4 == 0 && y % 4 == 0) || (x % 4 == 2 && y % 4 == 2) {
+                    self.set_behind(x, y, color);
+                }
+            }
+        }
+    }
+
+    /// Detects whether `len` letters of text at the specified coordinates
+    /// would overlap any text already drawn.
+    pub fn text_fits(&self, x: u32, y: u32, len: u32) -> bool {
+        let (row, col) = ((x / 2) as u16, (y / 4) as u16);
+        (row..row + len as u16).all(|r| {
+            self.cell(r, col)
+                .is_none_or(|a| a.0 != 0 || a.1 == ' ')
+        })
+    }
+
     /// Sets a letter at the specified coordinates.
     pub fn set_char(&mut self, x: u32, y: u32, c: char) {
         let (row, col) = ((x / 2) as u16, (y / 4) as u16);
-        let a = self
-            .chars
-            .entry((row, col))
-            .or_insert((0, ' ', false, PixelColor::White));
+        let a = self.cell_mut(row, col);
         a.0 = 0;
         a.1 = c;
         a.2 = false;
@@ -114,30 +756,45 @@ impl Canvas {
         }
     }
 
+    /// Sets a letter at the specified coordinates, in the given color.
+    pub fn set_char_colored(&mut self, x: u32, y: u32, c: char, color: PixelColor) {
+        self.set_char(x, y, c);
+        let (row, col) = ((x / 2) as u16, (y / 4) as u16);
+        let a = self.cell_mut(row, col);
+        a.2 = true;
+        a.3 = color;
+    }
+
+    /// Draws text in the given color at the specified coordinates (top-left of the text)
+    /// up to max_width length
+    pub fn text_colored(&mut self, x: u32, y: u32, max_width: u32, text: &str, color: PixelColor) {
+        for (i, c) in text.chars().enumerate() {
+            let w = i as u32 * 2;
+            if w > max_width {
+                return;
+            }
+            self.set_char_colored(x + w, y, c, color);
+        }
+    }
+
     /// Deletes a pixel at the specified coordinates.
     pub fn unset(&mut self, x: u32, y: u32) {
         let (row, col) = ((x / 2) as u16, (y / 4) as u16);
-        let a = self
-            .chars
-            .entry((row, col))
-            .or_insert((0, ' ', false, PixelColor::White));
+        let a = self.cell_mut(row, col);
         a.0 &= !PIXEL_MAP[y as usize % 4][x as usize % 2];
     }
 
     /// Toggles a pixel at the specified coordinates.
     pub fn toggle(&mut self, x: u32, y: u32) {
         let (row, col) = ((x / 2) as u16, (y / 4) as u16);
-        let a = self
-            .chars
-            .entry((row, col))
-            .or_insert((0, ' ', false, PixelColor::White));
+        let a = self.cell_mut(row, col);
         a.0 ^= PIXEL_MAP[y as usize % 4][x as usize % 2];
     }
 
     /// Detects whether the pixel at the given coordinates is set.
     pub fn get(&self, x: u32, y: u32) -> bool {
         let (row, col) = ((x / 2) as u16, (y / 4) as u16);
-        self.chars.get(&(row, col)).map_or(false, |a| {
+        self.cell(row, col).is_some_and(|a| {
             let dot_index = PIXEL_MAP[y as usize % 4][x as usize % 2];
             a.0 & dot_index != 0
         })
@@ -148,47 +805,83 @@ impl Canvas {
     /// Note that each row is actually four pixels high due to the fact that a single Braille
     /// character spans two by four pixels.
     pub fn rows(&self) -> Vec<String> {
-        let mut maxrow = self.width;
-        let mut maxcol = self.height;
-        for &(x, y) in self.chars.keys() {
-            if x > maxrow {
-                maxrow = x;
-            }
-            if y > maxcol {
-                maxcol = y;
-            }
-        }
-
-        let mut result = Vec::with_capacity(maxcol as usize + 1);
-        for y in 0..=maxcol {
-            let mut row = String::with_capacity(maxrow as usize + 1);
-            for x in 0..=maxrow {
-                let cell =
-                    self.chars
-                        .get(&(x, y))
-                        .cloned()
-                        .unwrap_or((0, ' ', false, PixelColor::White));
-                match cell {
-                    (0, _, _, _) => row.push(cell.1),
-                    (_, _, false, _) => row.push(char::from_u32(0x2800 + cell.0 as u32).unwrap()),
-                    (_, _, true, _) => {
-                        row = format!(
-                            "{0}{1}",
-                            row,
-                            String::from(char::from_u32(0x2800 + cell.0 as u32).unwrap())
-                                .color(cell.3)
-                        )
-                    }
-                };
-            }
-            result.push(row);
-        }
-        result
+        self.chars
+            .chunks(self.cols)
+            .enumerate()
+            .map(|(line, cells)| {
+                let mut row = String::with_capacity(ROW_BYTES * self.cols);
+                self.write_row(line, cells, &mut row);
+                row
+            })
+            .collect()
     }
 
     /// Draws the canvas to a `String` and returns it.
+    ///
+    /// ```
+    /// use nu_plugin_plot::color_plot::drawille::{Canvas, PixelColor};
+    ///
+    /// let mut canvas = Canvas::new(4, 4);
+    /// canvas.set_colored(0, 0, PixelColor::Red);
+    /// canvas.set_colored(2, 0, PixelColor::Red);
+    /// assert!(canvas.frame().starts_with("\x1b[31m⠁⠁\x1b[39m \n"));
+    /// ```
     pub fn frame(&self) -> String {
-        self.rows().join("\n")
+        let mut frame = String::with_capacity((ROW_BYTES * self.cols + 1) * self.lines);
+        for (i, cells) in self.chars.chunks(self.cols).enumerate() {
+            if i > 0 {
+                frame.push('\n');
+            }
+            self.write_row(i, cells, &mut frame);
+        }
+        frame
+    }
+
+    /// Writes the `line`th row of cells onto the end of `out`. A run of
+    /// cells of the same color, or on the same background, shares one
+    /// escape sequence, and the colors are reset before the end of the
+    /// row.
+    fn write_row(&self, line: usize, cells: &[Cell], out: &mut String) {
+        let (mut current, mut paper) = (None, None);
+        for (i, &cell) in cells.iter().enumerate() {
+            let (c, color) = match cell {
+                (0, c, true, color) if c != ' ' => (c, Some(color)),
+                (0, c, _, _) => (c, None),
+                (dots, _, false, _) => (self.charset.glyph(dots), None),
+                (dots, _, true, color) => (self.charset.glyph(dots), Some(color)),
+            };
+            let background = self.backgrounds.get(&(i as u16, line as u16)).copied().or(self.background);
+            if background != paper {
+                match background {
+                    Some(background) => {
+                        out.push_str("\x1b[");
+                        out.push_str(&bg_code(background));
+                        out.push('m');
+                    }
+                    None => out.push_str(RESET_BACKGROUND),
+                }
+                paper = background;
+            }
+            let color = color.or(background.map(contrast));
+            if color != current {
+                match color {
+                    Some(color) => {
+                        out.push_str("\x1b[");
+                        out.push_str(&fg_code(color));
+                        out.push('m');
+                    }
+                    None => out.push_str(RESET),
+                }
+                current = color;
+            }
+            out.push(c);
+        }
+        if current.is_some() {
+            out.push_str(RESET);
+        }
+        if paper.is_some() {
+            out.push_str(RESET_BACKGROUND);
+        }
     }
 
     /// Draws a line from `(x1, y1)` to `(x2, y2)` onto the `Canvas`.
@@ -239,4 +932,280 @@ impl Canvas {
             self.set_colored(x as u32, y as u32, color);
         }
     }
+
+    /// Draws a line from `(x1, y1)` to `(x2, y2)` onto the `Canvas` as
+    /// [`Canvas::line`] does, in `color` or the default color, but skips
+    /// the points the `dash` pattern leaves out: so many on, then so many
+    /// off, starting `phase` points into it. Returns the phase at the end
+    /// of the line, for the next line of a path to carry on from.
+    #[allow(clippy::too_many_arguments)]
+    pub fn line_dashed(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: Option<PixelColor>, (on, off): (u32, u32), phase: u32) -> u32 {
+        let xdiff = cmp::max(x1, x2) - cmp::min(x1, x2);
+        let ydiff = cmp::max(y1, y2) - cmp::min(y1, y2);
+        let xdir = if x1 <= x2 { 1 } else { -1 };
+        let ydir = if y1 <= y2 { 1 } else { -1 };
+
+        let r = cmp::max(xdiff, ydiff);
+
+        for i in 0..=r {
+            if (phase + i) % (on + off) >= on {
+                continue;
+            }
+            let mut x = x1 as i32;
+            let mut y = y1 as i32;
+
+            if ydiff != 0 {
+                y += ((i * ydiff) / r) as i32 * ydir;
+            }
+            if xdiff != 0 {
+                x += ((i * xdiff) / r) as i32 * xdir;
+            }
+
+            match color {
+                Some(color) => self.set_colored(x as u32, y as u32, color),
+                None => self.set(x as u32, y as u32),
+            }
+        }
+        phase + r
+    }
+
+    /// Draws a line from `(x1, y1)` to `(x2, y2)` onto the `Canvas` as
+    /// [`stroke`] lays it out, `width` points across and smoothed if
+    /// `smooth` is set, in `color` or the default color, and in dashes
+    /// if `dash` is set. Points beside the line which are off the canvas
+    /// are left out, rather than growing it. Returns the phase at the end
+    /// of the line, as [`Canvas::line_dashed`] does.
+    #[allow(clippy::too_many_arguments)]
+    pub fn line_stroked(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: Option<PixelColor>, dash: Option<(u32, u32)>, phase: u32, (width, smooth): (u32, bool)) -> u32 {
+        let (right, bottom) = (2 * self.width as u32 + 1, 4 * self.height as u32 + 3);
+        for (x, y) in stroke((x1, y1), (x2, y2), dash, phase, (width, smooth)) {
+            if x > right.max(x1).max(x2) || y > bottom.max(y1).max(y2) {
+                continue;
+            }
+            match color {
+                Some(color) => self.set_colored(x, y, color),
+                None => self.set(x, y),
+            }
+        }
+        phase + x1.abs_diff(x2).max(y1.abs_diff(y2))
+    }
+
+    /// Sets a pixel in `color`, or the default color, unless it is left
+    /// or above the canvas, as points of a shape round another can be.
+    fn set_point(&mut self, x: i64, y: i64, color: Option<PixelColor>) {
+        if x < 0 || y < 0 {
+            return;
+        }
+        match color {
+            Some(color) => self.set_colored(x as u32, y as u32, color),
+            None => self.set(x as u32, y as u32),
+        }
+    }
+
+    /// Draws the outline of a circle of radius `r` around `(x, y)` onto
+    /// the `Canvas`, leaving out the points left of or above it.
+    ///
+    /// ```
+    /// use nu_plugin_plot::color_plot::drawille::Canvas;
+    ///
+    /// let mut canvas = Canvas::new(8, 8);
+    /// canvas.circle(3, 3, 2);
+    /// assert_eq!(canvas.frame(), [
+    /// "⢠⠒⢢  ",
+    /// "⠈⠒⠊  ",
+    /// "     "].join("\n"));
+    /// ```
+    pub fn circle(&mut self, x: u32, y: u32, r: u32) {
+        self.circle_points(x, y, r, None);
+    }
+
+    /// Draws the outline of a circle as [`Canvas::circle`] does, in the
+    /// given color.
+    pub fn circle_colored(&mut self, x: u32, y: u32, r: u32, color: PixelColor) {
+        self.circle_points(x, y, r, Some(color));
+    }
+
+    /// The points of a circle, an eighth at a time by the midpoint
+    /// method, each mirrored round the rest.
+    fn circle_points(&mut self, x: u32, y: u32, r: u32, color: Option<PixelColor>) {
+        let (cx, cy) = (x as i64, y as i64);
+        let (mut dx, mut dy, mut err) = (r as i64, 0i64, 1 - r as i64);
+        while dx >= dy {
+            for (i, j) in [(dx, dy), (dy, dx), (-dy, dx), (-dx, dy), (-dx, -dy), (-dy, -dx), (dy, -dx), (dx, -dy)] {
+                self.set_point(cx + i, cy + j, color);
+            }
+            dy += 1;
+            if err < 0 {
+                err += 2 * dy + 1;
+            } else {
+                dx -= 1;
+                err += 2 * (dy - dx) + 1;
+            }
+        }
+    }
+
+    /// Draws the outline of the rectangle between `(x1, y1)` and
+    /// `(x2, y2)` onto the `Canvas`.
+    pub fn rect(&mut self, x1: u32, y1: u32, x2: u32, y2: u32) {
+        self.polygon(&[(x1, y1), (x2, y1), (x2, y2), (x1, y2)]);
+    }
+
+    /// Draws the outline of a rectangle as [`Canvas::rect`] does, in the
+    /// given color.
+    pub fn rect_colored(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: PixelColor) {
+        self.polygon_colored(&[(x1, y1), (x2, y1), (x2, y2), (x1, y2)], color);
+    }
+
+    /// Draws the outline of the polygon with the corners `points` onto
+    /// the `Canvas`, a line from each to the next and from the last back
+    /// to the first.
+    pub fn polygon(&mut self, points: &[(u32, u32)]) {
+        for (&(x1, y1), &(x2, y2)) in points.iter().zip(points.iter().cycle().skip(1)) {
+            self.line(x1, y1, x2, y2);
+        }
+    }
+
+    /// Draws the outline of a polygon as [`Canvas::polygon`] does, in the
+    /// given color.
+    pub fn polygon_colored(&mut self, points: &[(u32, u32)], color: PixelColor) {
+        for (&(x1, y1), &(x2, y2)) in points.iter().zip(points.iter().cycle().skip(1)) {
+            self.line_colored(x1, y1, x2, y2, color);
+        }
+    }
+
+    /// Draws the polygon with the corners `points` onto the `Canvas`,
+    /// filled solid, with its outline. A point is inside if a line from
+    /// it crosses the edges an odd number of times, so where the edges
+    /// cross themselves the parts they overlap twice are left empty.
+    ///
+    /// ```
+    /// use nu_plugin_plot::color_plot::drawille::Canvas;
+    ///
+    /// let mut canvas = Canvas::new(8, 8);
+    /// canvas.fill_polygon(&[(0, 0), (7, 0), (0, 7)]);
+    /// assert_eq!(canvas.frame(), [
+    /// "⣿⣿⡿⠋ ",
+    /// "⡿⠋   ",
+    /// "     "].join("\n"));
+    /// ```
+    pub fn fill_polygon(&mut self, points: &[(u32, u32)]) {
+        self.fill_polygon_points(points, None);
+        self.polygon(points);
+    }
+
+    /// Draws a filled polygon as [`Canvas::fill_polygon`] does, in the
+    /// given color.
+    pub fn fill_polygon_colored(&mut self, points: &[(u32, u32)], color: PixelColor) {
+        self.fill_polygon_points(points, Some(color));
+        self.polygon_colored(points, color);
+    }
+
+    /// The points inside a polygon, a row at a time, between each pair of
+    /// the edges crossing the middle of the row, left to right.
+    fn fill_polygon_points(&mut self, points: &[(u32, u32)], color: Option<PixelColor>) {
+        let (Some(top), Some(bottom)) = (points.iter().map(|p| p.1).min(), points.iter().map(|p| p.1).max()) else {
+            return;
+        };
+        let edges: Vec<((f64, f64), (f64, f64))> = points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .map(|(&(x1, y1), &(x2, y2))| ((x1 as f64, y1 as f64), (x2 as f64, y2 as f64)))
+            .collect();
+        for y in top..=bottom {
+            let row = y as f64 + 0.5;
+            let mut crossings: Vec<f64> = edges
+                .iter()
+                .filter(|((_, y1), (_, y2))| (*y1 <= row) != (*y2 <= row))
+                .map(|((x1, y1), (x2, y2))| x1 + (row - y1) / (y2 - y1) * (x2 - x1))
+                .collect();
+            crossings.sort_by(f64::total_cmp);
+            for pair in crossings.chunks_exact(2) {
+                for x in pair[0].round() as i64..=pair[1].round() as i64 {
+                    self.set_point(x, y as i64, color);
+                }
+            }
+        }
+    }
+    /// The pixels and letters drawn on the canvas, each at its position,
+    /// a letter at the top left of its cell, with its color if it has one.
+    /// A pixel has no letter.
+    fn drawn(&self) -> impl Iterator<Item = ((u32, u32), Option<char>, Option<PixelColor>)> + '_ {
+        self.chars.iter().enumerate().flat_map(move |(i, &(dots, c, colored, color))| {
+            let (x, y) = (2 * (i % self.cols) as u32, 4 * (i / self.cols) as u32);
+            let color = colored.then_some(color);
+            let letter = (c != ' ').then_some(((x, y), Some(c), color));
+            let pixels = (0..8).filter(move |k| dots & PIXEL_MAP[k / 2][k % 2] != 0).map(move |k| ((x + (k % 2) as u32, y + (k / 2) as u32), None, color));
+            letter.into_iter().chain(pixels)
+        })
+    }
+
+    /// Sets a pixel, or a letter if there is one, at `(x, y)`, in `color`
+    /// or the default color.
+    fn put(&mut self, x: u32, y: u32, letter: Option<char>, color: Option<PixelColor>) {
+        match (letter, color) {
+            (Some(c), Some(color)) => self.set_char_colored(x, y, c, color),
+            (Some(c), None) => self.set_char(x, y, c),
+            (None, Some(color)) => self.set_colored(x, y, color),
+            (None, None) => self.set(x, y),
+        }
+    }
+
+    /// Draws `other` onto the `Canvas` with its top left at `(x, y)`, in
+    /// pixels, each of its pixels and letters in its own color, over
+    /// what is already drawn and blended with it as
+    /// [`Canvas::set_blend`] says. A letter lands in the cell its top left
+    /// does, so letters stay where they were only if `x` is a whole
+    /// number of cells, two pixels, across and `y` four down.
+    ///
+    /// ```
+    /// use nu_plugin_plot::color_plot::drawille::Canvas;
+    ///
+    /// let mut left = Canvas::new(4, 4);
+    /// left.line(0, 0, 3, 3);
+    /// let mut canvas = Canvas::new(8, 4);
+    /// canvas.merge(&left, 0, 0);
+    /// canvas.merge(&left, 4, 0);
+    /// assert_eq!(canvas.frame(), "⠑⢄⠑⢄ \n     ");
+    /// ```
+    pub fn merge(&mut self, other: &Canvas, x: u32, y: u32) {
+        for ((i, j), letter, color) in other.drawn() {
+            self.put(x + i, y + j, letter, color);
+        }
+        for (&(i, j), &color) in &other.backgrounds {
+            self.set_cell_background(x + 2 * i as u32, y + 4 * j as u32, color);
+        }
+    }
+
+    /// The part of the `Canvas` between `(x1, y1)` and `(x2, y2)`, in
+    /// pixels, as a canvas of its own with its top left at `(x1, y1)`,
+    /// drawn with the same characters. Letters whose cells start in it
+    /// are kept.
+    ///
+    /// ```
+    /// use nu_plugin_plot::color_plot::drawille::Canvas;
+    ///
+    /// let mut canvas = Canvas::new(8, 4);
+    /// canvas.line(0, 0, 7, 3);
+    /// let right = canvas.crop(4, 0, 7, 3);
+    /// assert_eq!(right.frame(), "⠢⢄ \n   ");
+    /// ```
+    pub fn crop(&self, x1: u32, y1: u32, x2: u32, y2: u32) -> Canvas {
+        let ((left, right), (top, bottom)) = ((x1.min(x2), x1.max(x2)), (y1.min(y2), y1.max(y2)));
+        let mut part = Canvas::with_charset(right - left + 1, bottom - top + 1, self.charset);
+        part.blend = self.blend;
+        for ((i, j), letter, color) in self.drawn() {
+            if !(left..=right).contains(&i) || !(top..=bottom).contains(&j) {
+                continue;
+            }
+            part.put(i - left, j - top, letter, color);
+        }
+        for (&(i, j), &color) in &self.backgrounds {
+            let (i, j) = (2 * i as u32, 4 * j as u32);
+            if (left..=right).contains(&i) && (top..=bottom).contains(&j) {
+                part.set_cell_background(i - left, j - top, color);
+            }
+        }
+        part.background = self.background;
+        part
+    }
 }
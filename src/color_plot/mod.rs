@@ -1,5 +1,9 @@
 //! Forks of some nice open source plotting
 //! and drawing Rust libraries so we can get
 //! ANSI color support.
+pub mod blocks;
+pub mod colormap;
 pub mod drawille;
+pub mod png;
+pub mod svg;
 pub mod textplots;
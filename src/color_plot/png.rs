@@ -0,0 +1,387 @@
+//! A raster canvas, so charts can be saved as PNG images.
+//!
+//! Everything is drawn where the Braille canvas would put it, in the
+//! colors of the [SVG canvas](super::svg::SvgCanvas), but into pixels,
+//! with text in a small bitmap font.
+//!
+//! ```
+//! use nu_plugin_plot::color_plot::png::PngCanvas;
+//! use nu_plugin_plot::color_plot::textplots::{Chart, Plot, Shape};
+//!
+//! let points = vec![(0.0, 1.0), (1.0, 3.0), (2.0, 2.0)];
+//! let png = Chart::with_backend(Box::new(PngCanvas::new(64, 32)), 64, 32, 0.0, 2.0)
+//!     .lineplot(Shape::Lines(points))
+//!     .to_bytes();
+//! assert!(png.starts_with(b"\x89PNG"));
+//! ```
+
+use std::collections::HashSet;
+
+use super::drawille::PixelColor;
+use super::svg::css;
+use super::textplots::backend::Backend;
+use super::textplots::label::LabelFormat;
+
+/// Pixels per point.
+const SCALE: u32 = 6;
+
+/// Room around the plot for the axis labels and title, in pixels.
+const MARGIN: u32 = 48;
+
+/// The size of a character cell in pixels, two points wide and four high.
+const CELL: (u32, u32) = (2 * SCALE, 4 * SCALE);
+
+/// Pixels per pixel of the font.
+const FONT_SCALE: u32 = 2;
+
+const WHITE: [u8; 3] = [255, 255, 255];
+
+/// A 5x7 font for the printable ASCII characters, from space to `~`,
+/// with an eighth row for descenders. Each glyph is five columns, with
+/// the top row in the lowest bit.
+const FONT: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x5f, 0x00, 0x00],
+    [0x00, 0x07, 0x00, 0x07, 0x00],
+    [0x14, 0x7f, 0x14, 0x7f, 0x14],
+    [0x24, 0x2a, 0x7f, 0x2a, 0x12],
+    [0x23, 0x13, 0x08, 0x64, 0x62],
+    [0x36, 0x49, 0x55, 0x22, 0x50],
+    [0x00, 0x05, 0x03, 0x00, 0x00],
+    [0x00, 0x1c, 0x22, 0x41, 0x00],
+    [0x00, 0x41, 0x22, 0x1c, 0x00],
+    [0x08, 0x2a, 0x1c, 0x2a, 0x08],
+    [0x08, 0x08, 0x3e, 0x08, 0x08],
+    [0x00, 0x50, 0x30, 0x00, 0x00],
+    [0x08, 0x08, 0x08, 0x08, 0x08],
+    [0x00, 0x60, 0x60, 0x00, 0x00],
+    [0x20, 0x10, 0x08, 0x04, 0x02],
+    [0x3e, 0x51, 0x49, 0x45, 0x3e],
+    [0x00, 0x42, 0x7f, 0x40, 0x00],
+    [0x42, 0x61, 0x51, 0x49, 0x46],
+    [0x21, 0x41, 0x45, 0x4b, 0x31],
+    [0x18, 0x14, 0x12, 0x7f, 0x10],
+    [0x27, 0x45, 0x45, 0x45, 0x39],
+    [0x3c, 0x4a, 0x49, 0x49, 0x30],
+    [0x01, 0x71, 0x09, 0x05, 0x03],
+    [0x36, 0x49, 0x49, 0x49, 0x36],
+    [0x06, 0x49, 0x49, 0x29, 0x1e],
+    [0x00, 0x36, 0x36, 0x00, 0x00],
+    [0x00, 0x56, 0x36, 0x00, 0x00],
+    [0x08, 0x14, 0x22, 0x41, 0x00],
+    [0x14, 0x14, 0x14, 0x14, 0x14],
+    [0x00, 0x41, 0x22, 0x14, 0x08],
+    [0x02, 0x01, 0x51, 0x09, 0x06],
+    [0x32, 0x49, 0x79, 0x41, 0x3e],
+    [0x7e, 0x11, 0x11, 0x11, 0x7e],
+    [0x7f, 0x49, 0x49, 0x49, 0x36],
+    [0x3e, 0x41, 0x41, 0x41, 0x22],
+    [0x7f, 0x41, 0x41, 0x22, 0x1c],
+    [0x7f, 0x49, 0x49, 0x49, 0x41],
+    [0x7f, 0x09, 0x09, 0x09, 0x01],
+    [0x3e, 0x41, 0x49, 0x49, 0x7a],
+    [0x7f, 0x08, 0x08, 0x08, 0x7f],
+    [0x00, 0x41, 0x7f, 0x41, 0x00],
+    [0x20, 0x40, 0x41, 0x3f, 0x01],
+    [0x7f, 0x08, 0x14, 0x22, 0x41],
+    [0x7f, 0x40, 0x40, 0x40, 0x40],
+    [0x7f, 0x02, 0x0c, 0x02, 0x7f],
+    [0x7f, 0x04, 0x08, 0x10, 0x7f],
+    [0x3e, 0x41, 0x41, 0x41, 0x3e],
+    [0x7f, 0x09, 0x09, 0x09, 0x06],
+    [0x3e, 0x41, 0x51, 0x21, 0x5e],
+    [0x7f, 0x09, 0x19, 0x29, 0x46],
+    [0x46, 0x49, 0x49, 0x49, 0x31],
+    [0x01, 0x01, 0x7f, 0x01, 0x01],
+    [0x3f, 0x40, 0x40, 0x40, 0x3f],
+    [0x1f, 0x20, 0x40, 0x20, 0x1f],
+    [0x3f, 0x40, 0x38, 0x40, 0x3f],
+    [0x63, 0x14, 0x08, 0x14, 0x63],
+    [0x07, 0x08, 0x70, 0x08, 0x07],
+    [0x61, 0x51, 0x49, 0x45, 0x43],
+    [0x00, 0x7f, 0x41, 0x41, 0x00],
+    [0x02, 0x04, 0x08, 0x10, 0x20],
+    [0x00, 0x41, 0x41, 0x7f, 0x00],
+    [0x04, 0x02, 0x01, 0x02, 0x04],
+    [0x40, 0x40, 0x40, 0x40, 0x40],
+    [0x00, 0x01, 0x02, 0x04, 0x00],
+    [0x20, 0x54, 0x54, 0x54, 0x78],
+    [0x7f, 0x48, 0x44, 0x44, 0x38],
+    [0x38, 0x44, 0x44, 0x44, 0x20],
+    [0x38, 0x44, 0x44, 0x48, 0x7f],
+    [0x38, 0x54, 0x54, 0x54, 0x18],
+    [0x08, 0x7e, 0x09, 0x01, 0x02],
+    [0x18, 0xa4, 0xa4, 0xa4, 0x7c],
+    [0x7f, 0x08, 0x04, 0x04, 0x78],
+    [0x00, 0x44, 0x7d, 0x40, 0x00],
+    [0x40, 0x80, 0x84, 0x7d, 0x00],
+    [0x7f, 0x10, 0x28, 0x44, 0x00],
+    [0x00, 0x41, 0x7f, 0x40, 0x00],
+    [0x7c, 0x04, 0x18, 0x04, 0x78],
+    [0x7c, 0x08, 0x04, 0x04, 0x78],
+    [0x38, 0x44, 0x44, 0x44, 0x38],
+    [0xfc, 0x24, 0x24, 0x24, 0x18],
+    [0x18, 0x24, 0x24, 0x24, 0xfc],
+    [0x7c, 0x08, 0x04, 0x04, 0x08],
+    [0x48, 0x54, 0x54, 0x54, 0x20],
+    [0x04, 0x3f, 0x44, 0x40, 0x20],
+    [0x3c, 0x40, 0x40, 0x20, 0x7c],
+    [0x1c, 0x20, 0x40, 0x20, 0x1c],
+    [0x3c, 0x40, 0x30, 0x40, 0x3c],
+    [0x44, 0x28, 0x10, 0x28, 0x44],
+    [0x1c, 0xa0, 0xa0, 0xa0, 0x7c],
+    [0x44, 0x64, 0x54, 0x4c, 0x44],
+    [0x00, 0x08, 0x36, 0x41, 0x00],
+    [0x00, 0x00, 0x7f, 0x00, 0x00],
+    [0x00, 0x41, 0x36, 0x08, 0x00],
+    [0x08, 0x04, 0x08, 0x10, 0x08],
+];
+
+/// The glyph for `c`, or an empty box for characters the font lacks.
+fn glyph(c: char) -> [u8; 5] {
+    match c {
+        ' '..='~' => FONT[c as usize - ' ' as usize],
+        '²' => [0x00, 0x09, 0x0d, 0x0a, 0x00],
+        _ => [0x7f, 0x41, 0x41, 0x41, 0x7f],
+    }
+}
+
+/// The RGB color for a terminal color, matching the SVG canvas.
+fn rgb(color: Option<PixelColor>) -> [u8; 3] {
+    let hex = u32::from_str_radix(&css(color)[1..], 16).unwrap_or(0);
+    [(hex >> 16) as u8, (hex >> 8) as u8, hex as u8]
+}
+
+/// An RGB image, white where nothing has been drawn.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<[u8; 3]>,
+}
+
+impl Image {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![WHITE; (width * height) as usize],
+        }
+    }
+
+    /// The pixel at `(x, y)`, if it is inside the image.
+    fn pixel(&mut self, x: i64, y: i64) -> Option<&mut [u8; 3]> {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return None;
+        }
+        Some(&mut self.pixels[(y * self.width as i64 + x) as usize])
+    }
+
+    /// Paints the pixel at `(x, y)`, only if nothing is drawn there yet
+    /// when `behind` is set.
+    fn paint(&mut self, x: i64, y: i64, color: [u8; 3], behind: bool) {
+        if let Some(p) = self.pixel(x, y) {
+            if !behind || *p == WHITE {
+                *p = color;
+            }
+        }
+    }
+
+    /// Paints a disc of radius `r` around `(x, y)`.
+    fn disc(&mut self, x: i64, y: i64, r: i64, color: [u8; 3], behind: bool) {
+        for dy in -r..=r {
+            for dx in -r..=r {
+                if dx * dx + dy * dy <= r * r {
+                    self.paint(x + dx, y + dy, color, behind);
+                }
+            }
+        }
+    }
+
+    /// Paints the rectangle with corners `(x1, y1)` and `(x2, y2)`.
+    fn rect(&mut self, (x1, y1): (i64, i64), (x2, y2): (i64, i64), color: [u8; 3], behind: bool) {
+        for y in y1.min(y2)..=y1.max(y2) {
+            for x in x1.min(x2)..=x1.max(x2) {
+                self.paint(x, y, color, behind);
+            }
+        }
+    }
+
+    /// Paints the one pixel border of the rectangle with corners
+    /// `(x1, y1)` and `(x2, y2)`.
+    fn outline(&mut self, (x1, y1): (i64, i64), (x2, y2): (i64, i64), color: [u8; 3], behind: bool) {
+        self.rect((x1, y1), (x2, y1), color, behind);
+        self.rect((x1, y2), (x2, y2), color, behind);
+        self.rect((x1, y1), (x1, y2), color, behind);
+        self.rect((x2, y1), (x2, y2), color, behind);
+    }
+
+    /// Draws `text` with its top left at `(x, y)`, a character to each
+    /// cell, clearing the cells first.
+    fn text(&mut self, x: i64, y: i64, text: &str, color: [u8; 3]) {
+        let (w, h) = (CELL.0 as i64, CELL.1 as i64);
+        let scale = FONT_SCALE as i64;
+        // the glyph in the middle of the cell
+        let (left, top) = ((w - 5 * scale) / 2, (h - 8 * scale) / 2);
+        for (i, c) in text.chars().enumerate() {
+            let cx = x + i as i64 * w;
+            self.rect((cx, y), (cx + w - 1, y + h - 1), WHITE, false);
+            for (col, bits) in glyph(c).iter().enumerate() {
+                for row in 0..8 {
+                    if bits >> row & 1 == 1 {
+                        let (px, py) = (cx + left + col as i64 * scale, y + top + row * scale);
+                        self.rect((px, py), (px + scale - 1, py + scale - 1), color, false);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Copies `other` into this image, with its top left at `(x, y)`.
+    fn blit(&mut self, other: &Image, x: i64, y: i64) {
+        for (i, p) in other.pixels.iter().enumerate() {
+            let (ox, oy) = (i as i64 % other.width as i64, i as i64 / other.width as i64);
+            self.paint(x + ox, y + oy, *p, false);
+        }
+    }
+
+    /// The image encoded as a PNG.
+    fn encode(&self) -> Vec<u8> {
+        let mut out = vec![];
+        let mut encoder = ::png::Encoder::new(&mut out, self.width, self.height);
+        encoder.set_color(::png::ColorType::Rgb);
+        encoder.set_depth(::png::BitDepth::Eight);
+        // writing to memory only fails on a bad header, which this isn't
+        let mut writer = encoder.write_header().expect("valid PNG header");
+        writer.write_image_data(&self.pixels.concat()).expect("image data of the right size");
+        writer.finish().expect("PNG written to memory");
+        out
+    }
+}
+
+/// Draws a chart as a PNG image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PngCanvas {
+    /// The plot, without the margins around it.
+    plot: Image,
+    /// The character cells holding text, for finding collisions.
+    text_cells: HashSet<(u32, u32)>,
+}
+
+impl PngCanvas {
+    /// Creates a new, blank `PngCanvas` for a chart `width` by `height`
+    /// points.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            plot: Image::new(width * SCALE + 1, height * SCALE + 1),
+            text_cells: HashSet::new(),
+        }
+    }
+}
+
+/// The pixel for the point `(x, y)`.
+fn px(x: u32, y: u32) -> (i64, i64) {
+    ((x * SCALE) as i64, (y * SCALE) as i64)
+}
+
+impl Backend for PngCanvas {
+    fn set(&mut self, x: u32, y: u32, color: Option<PixelColor>) {
+        let (x, y) = px(x, y);
+        self.plot.disc(x, y, (SCALE / 2) as i64, rgb(color), false);
+    }
+
+    fn set_behind(&mut self, x: u32, y: u32, color: PixelColor) {
+        let (x, y) = px(x, y);
+        self.plot.disc(x, y, (SCALE / 4) as i64, rgb(Some(color)), true);
+    }
+
+    fn line(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: Option<PixelColor>) {
+        let ((x1, y1), (x2, y2)) = (px(x1, y1), px(x2, y2));
+        let steps = (x2 - x1).abs().max((y2 - y1).abs()).max(1);
+        for i in 0..=steps {
+            let x = x1 as f64 + (x2 - x1) as f64 * i as f64 / steps as f64;
+            let y = y1 as f64 + (y2 - y1) as f64 * i as f64 / steps as f64;
+            self.plot.disc(x.round() as i64, y.round() as i64, (SCALE / 4) as i64, rgb(color), false);
+        }
+    }
+
+    fn fill(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: PixelColor) {
+        // a fifth of the color over the white background
+        let tint = rgb(Some(color)).map(|c| ((c as u32 + 4 * 255) / 5) as u8);
+        self.plot.rect(px(x1, y1), px(x2, y2), tint, true);
+    }
+
+    /// Each column as wide as a point, so they meet.
+    fn fill_area(&mut self, columns: &[(u32, u32, u32)], color: PixelColor) {
+        let tint = rgb(Some(color)).map(|c| ((c as u32 + 4 * 255) / 5) as u8);
+        for &(x, top, bottom) in columns {
+            let ((left, top), (_, bottom)) = (px(x, top), px(x, bottom));
+            self.plot.rect((left, top), (left + SCALE as i64 - 1, bottom), tint, true);
+        }
+    }
+
+    /// A rect of the color mixed with the white background as the cell
+    /// is dense.
+    fn fill_cell(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, density: f64, color: Option<PixelColor>) {
+        if density <= 0.0 || x2 <= x1 || y2 <= y1 {
+            return;
+        }
+        let mix = rgb(color).map(|c| (c as f64 * density + 255.0 * (1.0 - density)).round() as u8);
+        let ((left, top), (right, bottom)) = (px(x1, y1), px(x2, y2));
+        self.plot.rect((left, top), (right - 1, bottom - 1), mix, false);
+    }
+
+    fn text(&mut self, x: u32, y: u32, max_width: u32, text: &str, color: Option<PixelColor>) {
+        // as many characters as the Braille canvas would draw
+        let text: String = text.chars().take((max_width / 2 + 1) as usize).collect();
+        for i in 0..text.chars().count() as u32 {
+            self.text_cells.insert((x / 2 + i, y / 4));
+        }
+        let (left, top) = px(x / 2 * 2, y / 4 * 4);
+        self.plot.text(left, top, &text, rgb(color));
+    }
+
+    fn text_fits(&self, x: u32, y: u32, len: u32) -> bool {
+        (0..len).all(|i| !self.text_cells.contains(&(x / 2 + i, y / 4)))
+    }
+
+    fn panel(&mut self, x1: u32, y1: u32, x2: u32, y2: u32) {
+        // around whole character cells, as on the Braille canvas
+        let top_left = px(x1.min(x2) / 2 * 2, y1.min(y2) / 4 * 4);
+        let bottom_right = px(x1.max(x2) / 2 * 2 + 2, y1.max(y2) / 4 * 4 + 4);
+        self.plot.rect(top_left, bottom_right, WHITE, false);
+        self.plot.outline(top_left, bottom_right, rgb(Some(PixelColor::BrightBlack)), false);
+    }
+
+    /// A PNG image with the plot inside a frame, the y range at the
+    /// right of its top and bottom, the x range underneath, and the title
+    /// above.
+    fn render(&self, _width: u32, _height: u32, (xmin, xmax): (f64, f64), (ymin, ymax): (f64, f64), title: Option<&str>, (x_labels, y_labels): (LabelFormat, LabelFormat)) -> Vec<u8> {
+        let (x_step, y_step) = (xmax - xmin, ymax - ymin);
+        let (ymin, ymax) = (y_labels.axis(ymin, y_step), y_labels.axis(ymax, y_step));
+        let (xmin, xmax) = (x_labels.axis(xmin, x_step), x_labels.axis(xmax, x_step));
+        let cells = |s: &str| (s.chars().count() as u32 * CELL.0) as i64;
+        let (w, h) = (self.plot.width as i64, self.plot.height as i64);
+        let margin = MARGIN as i64;
+
+        let right = margin.max(SCALE as i64 + cells(&ymin).max(cells(&ymax)) + SCALE as i64);
+        let title_width = title.map_or(0, cells);
+        let mut image = Image::new(
+            (margin + w + right).max(2 * margin + title_width) as u32,
+            (2 * margin + h) as u32,
+        );
+        image.blit(&self.plot, margin, margin);
+        image.outline((margin, margin), (margin + w - 1, margin + h - 1), rgb(Some(PixelColor::BrightBlack)), true);
+
+        let ink = rgb(None);
+        let cell_h = CELL.1 as i64;
+        if let Some(title) = title {
+            image.text(margin, (margin - cell_h) / 2, title, ink);
+        }
+        image.text(margin + w + SCALE as i64, margin, &ymax, ink);
+        image.text(margin + w + SCALE as i64, margin + h - cell_h, &ymin, ink);
+        image.text(margin, margin + h, &xmin, ink);
+        image.text(margin + w - cells(&xmax), margin + h, &xmax, ink);
+        image.encode()
+    }
+}
@@ -0,0 +1,277 @@
+//! An SVG canvas, so charts can be saved as vector images.
+//!
+//! Lines, shading and text are drawn as real SVG elements, at the
+//! positions the Braille canvas would put them, so an SVG chart has the
+//! same layout as the one in the terminal.
+//!
+//! ```
+//! use nu_plugin_plot::color_plot::svg::SvgCanvas;
+//! use nu_plugin_plot::color_plot::textplots::{Chart, Plot, Shape};
+//!
+//! let points = vec![(0.0, 1.0), (1.0, 3.0), (2.0, 2.0)];
+//! let svg = Chart::with_backend(Box::new(SvgCanvas::new()), 64, 32, 0.0, 2.0)
+//!     .lineplot(Shape::Lines(points))
+//!     .to_string();
+//! assert!(svg.starts_with("<svg") && svg.contains("<line"));
+//! ```
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use super::drawille::PixelColor;
+use super::textplots::backend::{ticks, Backend, TICK_ROWS};
+use super::textplots::label::LabelFormat;
+
+/// Pixels per point.
+const SCALE: f64 = 4.0;
+
+/// Room around the plot for the axis labels and title, in pixels.
+const MARGIN: f64 = 48.0;
+
+/// The default color of lines, points and text.
+const INK: &str = "#222222";
+
+/// Draws a chart as an SVG document.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SvgCanvas {
+    /// Elements drawn behind everything else.
+    behind: Vec<String>,
+    /// Everything else, in the order it was drawn.
+    elements: Vec<String>,
+    /// The character cells holding text, for finding collisions.
+    text_cells: HashSet<(u32, u32)>,
+}
+
+impl SvgCanvas {
+    /// Creates a new, empty `SvgCanvas`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// The CSS color for a terminal color, on a white background, or for
+/// any other as it is.
+pub(crate) fn css(color: Option<PixelColor>) -> Cow<'static, str> {
+    Cow::Borrowed(match color {
+        None | Some(PixelColor::White | PixelColor::BrightWhite | PixelColor::Default) => INK,
+        Some(PixelColor::Black) => "#000000",
+        Some(PixelColor::Red) => "#c0392b",
+        Some(PixelColor::Green) => "#27ae60",
+        Some(PixelColor::Yellow) => "#b7950b",
+        Some(PixelColor::Blue) => "#2e59a8",
+        Some(PixelColor::Magenta) => "#8e44ad",
+        Some(PixelColor::Cyan) => "#17a2b8",
+        Some(PixelColor::BrightBlack) => "#999999",
+        Some(PixelColor::BrightRed) => "#e74c3c",
+        Some(PixelColor::BrightGreen) => "#2ecc71",
+        Some(PixelColor::BrightYellow) => "#f1c40f",
+        Some(PixelColor::BrightBlue) => "#3498db",
+        Some(PixelColor::BrightMagenta) => "#c39bd3",
+        Some(PixelColor::BrightCyan) => "#48c9b0",
+        Some(PixelColor::Rgb(r, g, b)) => return format!("#{:02x}{:02x}{:02x}", r, g, b).into(),
+    })
+}
+
+/// `text` with the characters XML treats specially escaped.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// A text element with its baseline at `(x, y)` pixels.
+fn text_element(x: f64, y: f64, anchor: &str, text: &str, color: &str) -> String {
+    format!(
+        r#"<text x="{:.1}" y="{:.1}" text-anchor="{}" fill="{}">{}</text>"#,
+        x,
+        y,
+        anchor,
+        color,
+        escape(text)
+    )
+}
+
+impl Backend for SvgCanvas {
+    fn set(&mut self, x: u32, y: u32, color: Option<PixelColor>) {
+        self.elements.push(format!(
+            r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+            x as f64 * SCALE,
+            y as f64 * SCALE,
+            SCALE / 2.0,
+            css(color)
+        ));
+    }
+
+    fn set_behind(&mut self, x: u32, y: u32, color: PixelColor) {
+        self.behind.push(format!(
+            r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+            x as f64 * SCALE,
+            y as f64 * SCALE,
+            SCALE / 4.0,
+            css(Some(color))
+        ));
+    }
+
+    fn line(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: Option<PixelColor>) {
+        self.elements.push(format!(
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}"/>"#,
+            x1 as f64 * SCALE,
+            y1 as f64 * SCALE,
+            x2 as f64 * SCALE,
+            y2 as f64 * SCALE,
+            css(color)
+        ));
+    }
+
+    /// One line, dashed by the stroke, rather than a point for each
+    /// point of the dashes.
+    fn dashed_line(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: Option<PixelColor>, (on, off): (u32, u32), phase: u32) -> u32 {
+        self.elements.push(format!(
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-dasharray="{} {}" stroke-dashoffset="{}"/>"#,
+            x1 as f64 * SCALE,
+            y1 as f64 * SCALE,
+            x2 as f64 * SCALE,
+            y2 as f64 * SCALE,
+            css(color),
+            on as f64 * SCALE,
+            off as f64 * SCALE,
+            (phase % (on + off)) as f64 * SCALE
+        ));
+        phase + x1.abs_diff(x2).max(y1.abs_diff(y2))
+    }
+
+    fn fill(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: PixelColor) {
+        self.behind.push(format!(
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" fill-opacity="0.2"/>"#,
+            x1.min(x2) as f64 * SCALE,
+            y1.min(y2) as f64 * SCALE,
+            x1.abs_diff(x2) as f64 * SCALE,
+            y1.abs_diff(y2) as f64 * SCALE,
+            css(Some(color))
+        ));
+    }
+
+    /// One polygon, along the top line and back along the bottom one.
+    fn fill_area(&mut self, columns: &[(u32, u32, u32)], color: PixelColor) {
+        if columns.is_empty() {
+            return;
+        }
+        let corners: Vec<String> = columns
+            .iter()
+            .map(|&(x, top, _)| (x, top))
+            .chain(columns.iter().rev().map(|&(x, _, bottom)| (x, bottom)))
+            .map(|(x, y)| format!("{},{}", x as f64 * SCALE, y as f64 * SCALE))
+            .collect();
+        self.behind.push(format!(r#"<polygon points="{}" fill="{}" fill-opacity="0.2"/>"#, corners.join(" "), css(Some(color))));
+    }
+
+    /// A rect as opaque as the cell is dense.
+    fn fill_cell(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, density: f64, color: Option<PixelColor>) {
+        if density <= 0.0 {
+            return;
+        }
+        self.elements.push(format!(
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" fill-opacity="{}"/>"#,
+            x1 as f64 * SCALE,
+            y1 as f64 * SCALE,
+            x2.saturating_sub(x1) as f64 * SCALE,
+            y2.saturating_sub(y1) as f64 * SCALE,
+            css(color),
+            density
+        ));
+    }
+
+    fn text(&mut self, x: u32, y: u32, max_width: u32, text: &str, color: Option<PixelColor>) {
+        // as many characters as the Braille canvas would draw
+        let text: String = text.chars().take((max_width / 2 + 1) as usize).collect();
+        for i in 0..text.chars().count() as u32 {
+            self.text_cells.insert((x / 2 + i, y / 4));
+        }
+        // characters fill their cells, with the baseline near the bottom
+        let (left, top) = ((x / 2 * 2) as f64 * SCALE, (y / 4 * 4) as f64 * SCALE);
+        self.elements.push(format!(
+            r#"<text x="{:.1}" y="{:.1}" textLength="{:.1}" fill="{}" stroke="white" stroke-width="{}" paint-order="stroke">{}</text>"#,
+            left,
+            top + 3.0 * SCALE,
+            text.chars().count() as f64 * 2.0 * SCALE,
+            css(color),
+            SCALE,
+            escape(&text)
+        ));
+    }
+
+    fn text_fits(&self, x: u32, y: u32, len: u32) -> bool {
+        (0..len).all(|i| !self.text_cells.contains(&(x / 2 + i, y / 4)))
+    }
+
+    fn panel(&mut self, x1: u32, y1: u32, x2: u32, y2: u32) {
+        // around whole character cells, as on the Braille canvas
+        let (left, right) = ((x1.min(x2) / 2 * 2) as f64, (x1.max(x2) / 2 * 2 + 2) as f64);
+        let (top, bottom) = ((y1.min(y2) / 4 * 4) as f64, (y1.max(y2) / 4 * 4 + 4) as f64);
+        self.elements.push(format!(
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="white" stroke="{}"/>"#,
+            left * SCALE,
+            top * SCALE,
+            (right - left) * SCALE,
+            (bottom - top) * SCALE,
+            css(Some(PixelColor::BrightBlack))
+        ));
+    }
+
+    /// An SVG document with the plot inside a frame, y labels in a
+    /// gutter on its left on every few rows, as the text chart has them,
+    /// the x range underneath, and the title above.
+    fn render(&self, width: u32, height: u32, (xmin, xmax): (f64, f64), (ymin, ymax): (f64, f64), title: Option<&str>, (x_labels, y_labels): (LabelFormat, LabelFormat)) -> Vec<u8> {
+        let (w, h) = (width as f64 * SCALE, height as f64 * SCALE);
+
+        // each label at the top point of its row, and the last at the bottom
+        let last = (height / 4) as usize;
+        let mut rows = ticks(last, TICK_ROWS);
+        rows.dedup();
+        let y_step = (ymax - ymin) / (rows.len() - 1).max(1) as f64;
+        let labels: Vec<(f64, String)> = rows
+            .iter()
+            .map(|&r| {
+                let point = if r == last { height } else { 4 * r as u32 };
+                let y = ymax - (ymax - ymin) * point as f64 / height.max(1) as f64;
+                (point as f64 * SCALE, y_labels.axis(y, y_step))
+            })
+            .collect();
+        // characters as wide as the text of the plot has them
+        let gutter = labels.iter().map(|(_, l)| l.chars().count()).max().unwrap_or(0) as f64 * 2.0 * SCALE + 2.0 * SCALE;
+        let left = MARGIN.max(gutter);
+
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}" font-family="monospace" font-size="{2}">"#,
+            left + w + MARGIN,
+            h + 2.0 * MARGIN,
+            3.0 * SCALE
+        );
+        svg.push('\n');
+        svg += r#"<rect width="100%" height="100%" fill="white"/>"#;
+        svg.push('\n');
+        if let Some(title) = title {
+            svg += &text_element(left, MARGIN / 2.0, "start", title, INK);
+            svg.push('\n');
+        }
+
+        svg += &format!(r#"<g transform="translate({},{})" stroke-width="{}">"#, left, MARGIN, SCALE / 2.0);
+        svg.push('\n');
+        svg += &format!(r#"<rect width="{}" height="{}" fill="none" stroke="{}"/>"#, w, h, css(Some(PixelColor::BrightBlack)));
+        svg.push('\n');
+        for element in self.behind.iter().chain(&self.elements) {
+            svg += element;
+            svg.push('\n');
+        }
+        for (y, label) in &labels {
+            // the baseline a little below the point, so the label is centred on it
+            svg += &text_element(-SCALE, y + SCALE, "end", label, INK);
+            svg.push('\n');
+        }
+        let x_step = xmax - xmin;
+        svg += &text_element(0.0, h + 5.0 * SCALE, "start", &x_labels.axis(xmin, x_step), INK);
+        svg.push('\n');
+        svg += &text_element(w, h + 5.0 * SCALE, "end", &x_labels.axis(xmax, x_step), INK);
+        svg.push('\n');
+        svg += "</g>\n</svg>\n";
+        svg.into_bytes()
+    }
+}
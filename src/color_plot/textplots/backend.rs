@@ -0,0 +1,248 @@
+//! Where a chart is drawn.
+//!
+//! A [`Chart`](super::Chart) works out what goes where in points, and
+//! draws it through a [`Backend`]. The Braille canvas draws text for the
+//! terminal, the [block canvas](crate::color_plot::blocks::BlockCanvas)
+//! text in block characters, the [SVG canvas](crate::color_plot::svg::SvgCanvas) a
+//! vector image, and the [PNG canvas](crate::color_plot::png::PngCanvas)
+//! a raster one.
+
+use super::label::LabelFormat;
+use crate::color_plot::drawille::{stroke, Blend, Canvas as BrailleCanvas, Charset, PixelColor};
+
+/// The order points of a 4 by 4 block are set in as it is shaded more
+/// densely, spread out so a block part shaded is evenly so.
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Something a chart can be drawn on.
+///
+/// Coordinates are in points from the top left, and text is laid out
+/// as on the Braille canvas, each character two points wide and four
+/// high.
+pub trait Backend {
+    /// Sets a point, in `color` or the default color.
+    fn set(&mut self, x: u32, y: u32, color: Option<PixelColor>);
+
+    /// Sets a point in `color`, behind anything already drawn there.
+    fn set_behind(&mut self, x: u32, y: u32, color: PixelColor);
+
+    /// Draws a line from `(x1, y1)` to `(x2, y2)`, in `color` or the default color.
+    fn line(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: Option<PixelColor>);
+
+    /// Draws a line from `(x1, y1)` to `(x2, y2)` in dashes, in `color` or
+    /// the default color: `dash` points on, then so many off, starting
+    /// `phase` points into the pattern. Returns the phase at the end of
+    /// the line, so the lines of a path carry the pattern on between them.
+    #[allow(clippy::too_many_arguments)]
+    fn dashed_line(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: Option<PixelColor>, (on, off): (u32, u32), phase: u32) -> u32 {
+        let steps = x1.abs_diff(x2).max(y1.abs_diff(y2));
+        let along = |a: u32, b: u32, i: u32| (a as i64 + (b as i64 - a as i64) * i as i64 / steps.max(1) as i64) as u32;
+        for i in (0..=steps).filter(|i| (phase + i) % (on + off) < on) {
+            self.set(along(x1, x2, i), along(y1, y2, i), color);
+        }
+        phase + steps
+    }
+
+    /// Draws a line from `(x1, y1)` to `(x2, y2)` as [`stroke`] lays it
+    /// out, `width` points across and smoothed if `smooth` is set, in
+    /// `color` or the default color, and in dashes if `dash` is set.
+    /// Returns the phase at the end of the line, as
+    /// [`Backend::dashed_line`] does.
+    #[allow(clippy::too_many_arguments)]
+    fn stroked_line(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: Option<PixelColor>, dash: Option<(u32, u32)>, phase: u32, (width, smooth): (u32, bool)) -> u32 {
+        for (x, y) in stroke((x1, y1), (x2, y2), dash, phase, (width, smooth)) {
+            self.set(x, y, color);
+        }
+        phase + x1.abs_diff(x2).max(y1.abs_diff(y2))
+    }
+
+    /// Sets how a colored point is colored where it lands on a point of
+    /// another color. Backends which draw every point apart, rather than
+    /// sharing a cell between them, leave the later one on top.
+    fn set_blend(&mut self, _blend: Blend) {}
+
+    /// Sets the color behind the whole plot, drawing what is in the
+    /// default color in one which shows up on it. Backends which are
+    /// images, on a white page of their own, leave it white.
+    fn set_background(&mut self, _color: PixelColor) {}
+
+    /// Shades the rectangle between `(x1, y1)` and `(x2, y2)` in `color`,
+    /// behind anything already drawn.
+    fn fill(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: PixelColor);
+
+    /// Shades `columns`, each a column and the rows of the two lines it
+    /// runs between, top then bottom, in `color`, behind anything already
+    /// drawn. A column may come more than once, but is shaded as if it
+    /// came once.
+    fn fill_area(&mut self, columns: &[(u32, u32, u32)], color: PixelColor) {
+        for &(x, top, bottom) in columns {
+            self.fill(x, top, x, bottom, color);
+        }
+    }
+
+    /// Shades the cell from `(x1, y1)` up to but not including `(x2, y2)`
+    /// `density` of the way from empty to solid, in `color` or the default
+    /// color. Points are set in an ordered dither, so neighbouring cells
+    /// of the same density make an even pattern.
+    fn fill_cell(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, density: f64, color: Option<PixelColor>) {
+        for y in y1..y2 {
+            for x in x1..x2 {
+                if (BAYER[y as usize % 4][x as usize % 4] as f64) / 16.0 < density {
+                    self.set(x, y, color);
+                }
+            }
+        }
+    }
+
+    /// Draws text with its top left at `(x, y)`, up to `max_width` points
+    /// long, in `color` or the default color.
+    fn text(&mut self, x: u32, y: u32, max_width: u32, text: &str, color: Option<PixelColor>);
+
+    /// Whether `len` characters of text at `(x, y)` would overlap any
+    /// text already drawn.
+    fn text_fits(&self, x: u32, y: u32, len: u32) -> bool;
+
+    /// Clears the rectangle between `(x1, y1)` and `(x2, y2)`, and draws
+    /// a border around it, to hold text such as a legend.
+    fn panel(&mut self, x1: u32, y1: u32, x2: u32, y2: u32);
+
+    /// The finished drawing of a `width` by `height` chart, with its x
+    /// and y ranges marked, written as the x and y `labels` have them, and
+    /// its title, if the backend draws titles. Text and SVG are returned as UTF-8, and
+    /// images in their file format.
+    fn render(&self, width: u32, height: u32, x_range: (f64, f64), y_range: (f64, f64), title: Option<&str>, labels: (LabelFormat, LabelFormat)) -> Vec<u8>;
+}
+
+impl Backend for BrailleCanvas {
+    fn set(&mut self, x: u32, y: u32, color: Option<PixelColor>) {
+        match color {
+            Some(color) => self.set_colored(x, y, color),
+            None => BrailleCanvas::set(self, x, y),
+        }
+    }
+
+    fn set_behind(&mut self, x: u32, y: u32, color: PixelColor) {
+        BrailleCanvas::set_behind(self, x, y, color);
+    }
+
+    fn line(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: Option<PixelColor>) {
+        match color {
+            Some(color) => self.line_colored(x1, y1, x2, y2, color),
+            None => BrailleCanvas::line(self, x1, y1, x2, y2),
+        }
+    }
+
+    fn dashed_line(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: Option<PixelColor>, dash: (u32, u32), phase: u32) -> u32 {
+        self.line_dashed(x1, y1, x2, y2, color, dash, phase)
+    }
+
+    fn stroked_line(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: Option<PixelColor>, dash: Option<(u32, u32)>, phase: u32, stroke: (u32, bool)) -> u32 {
+        self.line_stroked(x1, y1, x2, y2, color, dash, phase, stroke)
+    }
+
+    fn set_blend(&mut self, blend: Blend) {
+        BrailleCanvas::set_blend(self, blend);
+    }
+
+    fn set_background(&mut self, color: PixelColor) {
+        BrailleCanvas::set_background(self, Some(color));
+    }
+
+    fn fill(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: PixelColor) {
+        BrailleCanvas::fill(self, x1, y1, x2, y2, color);
+    }
+
+    fn text(&mut self, x: u32, y: u32, max_width: u32, text: &str, color: Option<PixelColor>) {
+        match color {
+            Some(color) => self.text_colored(x, y, max_width, text, color),
+            None => BrailleCanvas::text(self, x, y, max_width, text),
+        }
+    }
+
+    fn text_fits(&self, x: u32, y: u32, len: u32) -> bool {
+        BrailleCanvas::text_fits(self, x, y, len)
+    }
+
+    /// Box drawing characters around blank cells, or `+`, `-` and `|`
+    /// on an ASCII canvas.
+    fn panel(&mut self, x1: u32, y1: u32, x2: u32, y2: u32) {
+        let (left, right) = (x1.min(x2) / 2, x1.max(x2) / 2);
+        let (top, bottom) = (y1.min(y2) / 4, y1.max(y2) / 4);
+        let [top_left, top_right, bottom_left, bottom_right, horizontal, vertical] = match self.charset() {
+            Charset::Braille | Charset::Blocks => ['┌', '┐', '└', '┘', '─', '│'],
+            Charset::Ascii => ['+', '+', '+', '+', '-', '|'],
+        };
+        for r in top..=bottom {
+            for c in left..=right {
+                let edge = match (r, c) {
+                    (r, c) if r == top && c == left => top_left,
+                    (r, c) if r == top && c == right => top_right,
+                    (r, c) if r == bottom && c == left => bottom_left,
+                    (r, c) if r == bottom && c == right => bottom_right,
+                    (r, _) if r == top || r == bottom => horizontal,
+                    (_, c) if c == left || c == right => vertical,
+                    _ => ' ',
+                };
+                self.set_char(2 * c, 4 * r, edge);
+            }
+        }
+    }
+
+    /// The rows of the canvas, framed. Titles are left to whoever prints
+    /// the chart.
+    fn render(&self, width: u32, height: u32, x_range: (f64, f64), y_range: (f64, f64), _title: Option<&str>, labels: (LabelFormat, LabelFormat)) -> Vec<u8> {
+        framed(self.frame(), width, height, x_range, y_range, labels).into_bytes()
+    }
+}
+
+/// Rows of the canvas between two y labels, roughly.
+pub(crate) const TICK_ROWS: usize = 4;
+
+/// Columns of the canvas between two vertical grid lines, roughly.
+pub(crate) const TICK_COLUMNS: usize = 10;
+
+/// The cells from 0 to `last` that ticks fall on, about `per` cells
+/// apart, the first and the last among them.
+pub(crate) fn ticks(last: usize, per: usize) -> Vec<usize> {
+    let intervals = ((last as f64 / per as f64).round() as usize).max(1);
+    (0..=intervals).map(|i| (i * last + intervals / 2) / intervals).collect()
+}
+
+/// The rows of a text canvas `width` by `height` points, after a gutter
+/// of y labels, right aligned, on every few rows from the top to the
+/// bottom of the y range, with the x range underneath, each end of it
+/// under its column. The numbers are written as the x and y `formats`
+/// have them.
+pub(crate) fn framed(frame: String, width: u32, height: u32, (xmin, xmax): (f64, f64), (ymin, ymax): (f64, f64), (x_format, y_format): (LabelFormat, LabelFormat)) -> String {
+    let rows: Vec<&str> = frame.lines().collect();
+    // the row of the bottom of the y range, which text may have drawn past
+    let last = (height as usize / 4).min(rows.len().saturating_sub(1));
+    if last == 0 {
+        return frame;
+    }
+
+    let ticks = ticks(last, TICK_ROWS);
+    let y_step = (ymax - ymin) / (ticks.len() - 1) as f64;
+    let labels: Vec<Option<String>> = (0..rows.len())
+        .map(|r| {
+            let tick = ticks.contains(&r);
+            // a row covers four points, labelled with the value of its top one
+            let point = if r == last { height } else { 4 * r as u32 };
+            let y = ymax - (ymax - ymin) * point as f64 / height as f64;
+            tick.then(|| y_format.axis(y, y_step))
+        })
+        .collect();
+    let gutter = labels.iter().flatten().map(|l| l.chars().count()).max().unwrap_or(0) + 1;
+
+    let mut out = String::with_capacity(frame.len() + rows.len() * gutter + width as usize);
+    for (row, label) in rows.iter().zip(&labels) {
+        out += &format!("{:>w$} {}\n", label.as_deref().unwrap_or(""), row, w = gutter - 1);
+    }
+
+    // xmin starts under the first column, and xmax ends under the last
+    let (left, right) = (x_format.axis(xmin, xmax - xmin), x_format.axis(xmax, xmax - xmin));
+    let end = width as usize / 2 + 1;
+    let start = end.saturating_sub(right.chars().count()).max(left.chars().count() + 1);
+    out += &format!("{}{:<w$}{}\n", " ".repeat(gutter), left, right, w = start);
+    out
+}
@@ -0,0 +1,264 @@
+//! How numbers are written in axis labels, and in labels of values.
+
+use crate::resample::Rescale;
+
+/// SI prefixes from nano to exa, a thousand times apart, with none in
+/// the middle.
+const SI_PREFIXES: [&str; 10] = ["n", "µ", "m", "", "k", "M", "G", "T", "P", "E"];
+
+/// Where no prefix is in [`SI_PREFIXES`].
+const SI_UNPREFIXED: i32 = 3;
+
+/// Units of bytes, 1024 times apart.
+const BYTE_UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+/// Units of time, as nushell writes them, and their length in
+/// nanoseconds.
+const DURATION_UNITS: [(&str, f64); 7] = [
+    ("ns", 1.0),
+    ("µs", 1e3),
+    ("ms", 1e6),
+    ("sec", 1e9),
+    ("min", 6e10),
+    ("hr", 3.6e12),
+    ("day", 8.64e13),
+];
+
+/// Nanoseconds in a day.
+const DAY: f64 = 8.64e13;
+
+/// How to write a number as a label. Each way takes a number of decimal
+/// places, or as many as tell the labels of an axis apart.
+///
+/// ```
+/// use nu_plugin_plot::color_plot::textplots::label::LabelFormat;
+/// use nu_plugin_plot::resample::Rescale;
+///
+/// let si = LabelFormat::from_spec("si").unwrap();
+/// assert_eq!(si.value(1073741824.0), "1.07G");
+/// assert_eq!(si.axis(1879048192.0, 1e9), "1.9G");
+/// let thousands = LabelFormat::from_spec("thousands").unwrap();
+/// assert_eq!(thousands.value(1073741824.0), "1,073,741,824");
+/// let fixed = LabelFormat::from_spec("%.3f").unwrap();
+/// assert_eq!(fixed.axis(0.5, 0.25), "0.500");
+/// assert_eq!(LabelFormat::from_spec("percent").unwrap().axis(0.25, 0.25), "25%");
+/// assert_eq!(LabelFormat::Bytes(None).value(1572864.0), "1.50MiB");
+/// assert_eq!(LabelFormat::Duration(None).axis(2.5e6, 1e6), "2.5ms");
+/// assert_eq!(LabelFormat::Hidden.axis(2.5, 1.0), "");
+/// let date = LabelFormat::from_spec("date").unwrap();
+/// assert_eq!(date.axis(1.7e18, 8.64e13), "2023-11-14");
+/// assert_eq!(date.axis(1.7e18, 3.6e12), "11-14 22:13");
+/// assert_eq!(date.value(1.7e18), "2023-11-14 22:13:20");
+/// let log = LabelFormat::Rescaled(Some(Rescale::Log), 0.0);
+/// assert_eq!(log.axis(2.0, 0.5), "100");
+/// assert_eq!(log.value(0.5), "3.16");
+/// assert_eq!(LabelFormat::Rescaled(Some(Rescale::Log1p), 0.0).value(100f64.ln_1p()), "100");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LabelFormat {
+    /// Plain decimals, at least one.
+    #[default]
+    Auto,
+    /// Plain decimals, to this many places.
+    Fixed(usize),
+    /// A mantissa and a power of ten, like `1.07e9`.
+    Scientific(Option<usize>),
+    /// Scaled to an SI prefix, like `1.2k` or `3.4M`.
+    Si(Option<usize>),
+    /// Times a hundred, with a percent sign.
+    Percent(Option<usize>),
+    /// Plain decimals, with commas between the thousands.
+    Thousands(Option<usize>),
+    /// A number of bytes, in a unit like `KiB` or `MiB`.
+    Bytes(Option<usize>),
+    /// A number of nanoseconds, in a unit like `ms` or `sec`.
+    Duration(Option<usize>),
+    /// A number of nanoseconds since 1970 began, as a date and time of
+    /// day, to as fine a unit as tells the labels apart.
+    Date,
+    /// Nothing at all, for an axis whose numbers mean nothing to a
+    /// reader, such as one of named rows drawn on the chart.
+    Hidden,
+    /// Plain decimals of the values which were shifted by an offset and
+    /// then, if at all, rescaled before they were plotted, so an axis of
+    /// their logs, say, is labelled with the values themselves.
+    Rescaled(Option<Rescale>, f64),
+}
+
+impl LabelFormat {
+    /// The format called `spec`: `%.3f` or `%.2e` printf style, or one of
+    /// `auto`, `scientific`, `si`, `percent`, `thousands`, `bytes`,
+    /// `duration` or `date`, all but `auto` and `date` with `:N` for N
+    /// decimal places.
+    pub fn from_spec(spec: &str) -> Option<Self> {
+        if let Some(printf) = spec.strip_prefix('%') {
+            let (places, kind) = match printf.strip_prefix('.') {
+                Some(rest) if rest.len() > 1 => (Some(rest[..rest.len() - 1].parse().ok()?), &rest[rest.len() - 1..]),
+                Some(_) => return None,
+                None => (None, printf),
+            };
+            return match (kind, places) {
+                ("f", Some(places)) => Some(LabelFormat::Fixed(places)),
+                ("f", None) => Some(LabelFormat::Auto),
+                ("e", places) => Some(LabelFormat::Scientific(places)),
+                _ => None,
+            };
+        }
+        let (name, places) = match spec.split_once(':') {
+            Some((name, places)) => (name, Some(places.parse().ok()?)),
+            None => (spec, None),
+        };
+        Some(match (name, places) {
+            ("auto", None) => LabelFormat::Auto,
+            ("date", None) => LabelFormat::Date,
+            ("scientific", places) => LabelFormat::Scientific(places),
+            ("si", places) => LabelFormat::Si(places),
+            ("percent", places) => LabelFormat::Percent(places),
+            ("thousands", places) => LabelFormat::Thousands(places),
+            ("bytes", places) => LabelFormat::Bytes(places),
+            ("duration", places) => LabelFormat::Duration(places),
+            _ => return None,
+        })
+    }
+
+    /// `value` as one of the labels of an axis, `step` apart.
+    pub fn axis(&self, value: f64, step: f64) -> String {
+        match *self {
+            LabelFormat::Auto => format!("{:.*}", decimals(step, 1), value),
+            LabelFormat::Fixed(places) => format!("{:.*}", places, value),
+            LabelFormat::Scientific(places) => format!("{:.*e}", places.unwrap_or(2), value),
+            LabelFormat::Si(places) => {
+                let power = if value != 0.0 && value.is_finite() {
+                    ((value.abs().log10() / 3.0).floor() as i32).clamp(-SI_UNPREFIXED, SI_PREFIXES.len() as i32 - 1 - SI_UNPREFIXED)
+                } else {
+                    0
+                };
+                scaled(value, step, places, 1000f64.powi(power), SI_PREFIXES[(power + SI_UNPREFIXED) as usize])
+            }
+            LabelFormat::Percent(places) => format!("{:.*}%", places.unwrap_or_else(|| decimals(step * 100.0, 0)), value * 100.0),
+            LabelFormat::Thousands(places) => thousands(&format!("{:.*}", places.unwrap_or_else(|| decimals(step, 0)), value)),
+            LabelFormat::Bytes(places) => {
+                let power = if value.abs() >= 1.0 && value.is_finite() {
+                    ((value.abs().log2() / 10.0).floor() as usize).min(BYTE_UNITS.len() - 1)
+                } else {
+                    0
+                };
+                scaled(value, step, places, 1024f64.powi(power as i32), BYTE_UNITS[power])
+            }
+            LabelFormat::Duration(places) => {
+                let (unit, length) = DURATION_UNITS.iter().rev().find(|(_, length)| value.abs() >= *length).unwrap_or(&DURATION_UNITS[0]);
+                scaled(value, step, places, *length, unit)
+            }
+            LabelFormat::Date => date(value, step),
+            LabelFormat::Hidden => String::new(),
+            LabelFormat::Rescaled(rescale, offset) => {
+                let original = |value: f64| unscaled(rescale, offset, value);
+                // labels are unevenly far apart once rescaled, so as many
+                // places as tell this one from the nearer of its neighbours
+                let apart = (original(value + step) - original(value)).abs().min((original(value) - original(value - step)).abs());
+                format!("{:.*}", decimals(apart, 0), original(value))
+            }
+        }
+    }
+
+    /// `value` as a label of its own: by default, a whole number as it
+    /// is and anything else to two decimal places, and otherwise to a
+    /// few significant figures. A date has its time of day, to the
+    /// second, unless it is midnight.
+    pub fn value(&self, value: f64) -> String {
+        match self {
+            LabelFormat::Auto if value.fract() == 0.0 && value.abs() < 1e15 => format!("{}", value),
+            LabelFormat::Auto => format!("{:.2}", value),
+            LabelFormat::Date if (value / 1e9).round() % 86400.0 == 0.0 => date(value, DAY),
+            LabelFormat::Date => format!("{} {}", date(value, DAY), date(value, 0.0)),
+            LabelFormat::Rescaled(rescale, offset) => LabelFormat::Auto.value(unscaled(*rescale, *offset, value)),
+            format => format.axis(value, value.abs() / 10.0),
+        }
+    }
+}
+
+/// The value `value` was shifted by `offset` and rescaled from, to ten
+/// significant figures, so a whole number comes back whole rather than
+/// a rounding error off it.
+fn unscaled(rescale: Option<Rescale>, offset: f64, value: f64) -> f64 {
+    let original = rescale.map_or(value, |r| r.invert(value)) - offset;
+    if original == 0.0 || !original.is_finite() {
+        return original;
+    }
+    let scale = 10f64.powi(9 - original.abs().log10().floor() as i32);
+    (original * scale).round() / scale
+}
+
+/// `value` in units `scale` long, called `unit`, to `places` decimal
+/// places, or by default to a place more than values `step` apart need,
+/// as the whole part is short.
+fn scaled(value: f64, step: f64, places: Option<usize>, scale: f64, unit: &str) -> String {
+    let places = places.unwrap_or_else(|| decimals(step / scale / 10.0, 0));
+    format!("{:.*}{}", places, value / scale, unit)
+}
+
+/// Decimal places enough to tell apart values `step` apart, at least
+/// `least`.
+fn decimals(step: f64, least: usize) -> usize {
+    if step.is_finite() && step > 0.0 {
+        (-step.log10().floor()).clamp(least as f64, 6.0) as usize
+    } else {
+        least
+    }
+}
+
+/// `value`, nanoseconds since 1970 began, as a date, to the year, month
+/// or day, or the time of day, to the minute with its date or to the
+/// second alone, as labels `step` apart need.
+fn date(value: f64, step: f64) -> String {
+    if !value.is_finite() {
+        return format!("{}", value);
+    }
+    let seconds = (value / 1e9).round() as i64;
+    let (year, month, day) = civil(seconds.div_euclid(86400));
+    let time = seconds.rem_euclid(86400);
+    let (hour, minute, second) = (time / 3600, time / 60 % 60, time % 60);
+    match step {
+        step if step >= 365.0 * DAY => format!("{}", year),
+        step if step >= 28.0 * DAY => format!("{}-{:02}", year, month),
+        step if step >= DAY => format!("{}-{:02}-{:02}", year, month, day),
+        step if step >= 6e10 => format!("{:02}-{:02} {:02}:{:02}", month, day, hour, minute),
+        _ => format!("{:02}:{:02}:{:02}", hour, minute, second),
+    }
+}
+
+/// The year, month and day of the date `days` after 1970 began, by the
+/// proleptic Gregorian calendar, as Howard Hinnant's `civil_from_days`.
+fn civil(days: i64) -> (i64, i64, i64) {
+    // counted from a 400 year era beginning on the 1st of March, 0 AD
+    let z = days + 719468;
+    let (era, day_of_era) = (z.div_euclid(146097), z.rem_euclid(146097));
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+/// A number written with decimals, with commas between the thousands
+/// of its whole part.
+fn thousands(number: &str) -> String {
+    let (sign, unsigned) = match number.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", number),
+    };
+    let (whole, fraction) = match unsigned.find('.') {
+        Some(i) => unsigned.split_at(i),
+        None => (unsigned, ""),
+    };
+    let mut out = sign.to_string();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out + fraction
+}
@@ -19,12 +19,12 @@
 //! ```
 //!
 //! ```rust
-//! use textplots::{Chart, Plot, Shape};
+//! use nu_plugin_plot::color_plot::textplots::{Chart, Plot, Shape};
 //!
 //! println!("y = sin(x) / x");
 //!
 //! Chart::default()
-//!     .lineplot(&Shape::Continuous(Box::new(|x| x.sin() / x)))
+//!     .lineplot(Shape::Continuous(Box::new(|x| x.sin() / x)))
 //!     .display();
 //! ```
 //!
@@ -36,13 +36,13 @@
 //! You can override the defaults calling `new`.
 //!
 //! ```rust
-//! use textplots::{Chart, Plot, Shape};
+//! use nu_plugin_plot::color_plot::textplots::{Chart, Plot, Shape};
 //!
 //! println!("y = cos(x), y = sin(x) / 2");
 //!
 //! Chart::new(180, 60, -5.0, 5.0)
-//!     .lineplot(&Shape::Continuous(Box::new(|x| x.cos())))
-//!     .lineplot(&Shape::Continuous(Box::new(|x| x.sin() / 2.0)))
+//!     .lineplot(Shape::Continuous(Box::new(|x| x.cos())))
+//!     .lineplot(Shape::Continuous(Box::new(|x| x.sin() / 2.0)))
 //!     .display();
 //! ```
 //!
@@ -52,15 +52,20 @@
 //!
 //! <img src="https://github.com/loony-bean/textplots-rs/blob/master/doc/demo3.png?raw=true"/>
 
+pub mod backend;
+pub mod label;
 pub mod scale;
 pub mod utils;
 
 use super::drawille::Canvas as BrailleCanvas;
-use super::drawille::PixelColor;
+use backend::{ticks, Backend, TICK_COLUMNS, TICK_ROWS};
+use label::LabelFormat;
+use super::colormap::Colormap;
+use super::drawille::{Blend, PixelColor};
 use scale::Scale;
 use std::cmp;
 use std::default::Default;
-use std::f32;
+use std::f64;
 
 /// How the chart will do the ranging on axes
 #[derive(PartialEq)]
@@ -71,67 +76,369 @@ enum ChartRangeMethod {
     FixedRange,
 }
 
-/// Controls the drawing.
-pub struct Chart<'a> {
+/// Controls the drawing. A chart owns the shapes plotted on it, so it
+/// can be built up a shape at a time, kept, and returned.
+pub struct Chart {
     /// Canvas width in points.
     width: u32,
     /// Canvas height in points.
     height: u32,
     /// X-axis start value.
-    xmin: f32,
+    xmin: f64,
     /// X-axis end value.
-    xmax: f32,
+    xmax: f64,
     /// Y-axis start value (potentially calculated automatically).
-    ymin: f32,
+    ymin: f64,
     /// Y-axis end value (potentially calculated automatically).
-    ymax: f32,
+    ymax: f64,
     /// The type of y axis ranging we'll do
     y_ranging: ChartRangeMethod,
     /// Collection of shapes to be presented on the canvas.
-    shapes: Vec<(&'a Shape<'a>, Option<PixelColor>)>,
+    shapes: Vec<(Shape, Option<PixelColor>)>,
+    /// How the lines of each shape are drawn, in the order of `shapes`.
+    styles: Vec<LineStyle>,
+    /// How many points across the lines of each shape are, in the order
+    /// of `shapes`.
+    widths: Vec<u32>,
+    /// Fill the corners of the diagonal steps of lines.
+    smooth: bool,
+    /// How shapes are colored where they cross one another.
+    blend: Blend,
+    /// Reference lines, shading and labels.
+    annotations: Vec<Annotation>,
+    /// Legend entries boxed in the top right corner.
+    legend: Vec<LegendEntry>,
+    /// A title, for backends which draw their own.
+    title: Option<String>,
+    /// Colors shapes without their own color by height, if set.
+    gradient: Option<Colormap>,
+    /// How to write the numbers of the x and y axes.
+    label_formats: (LabelFormat, LabelFormat),
     /// Underlying canvas object.
-    canvas: BrailleCanvas,
+    canvas: Box<dyn Backend>,
 }
 
 /// Specifies different kinds of plotted data.
-pub enum Shape<'a> {
+pub enum Shape {
     /// Real value function.
-    Continuous(Box<dyn Fn(f32) -> f32 + 'a>),
+    Continuous(Box<dyn Fn(f64) -> f64>),
     /// Points of a scatter plot.
-    Points(&'a [(f32, f32)]),
+    Points(Vec<(f64, f64)>),
     /// Points connected with lines.
-    Lines(&'a [(f32, f32)]),
-    /// Points connected in step fashion.
-    Steps(&'a [(f32, f32)]),
+    Lines(Vec<(f64, f64)>),
+    /// Points connected in step fashion, stepping where `StepMode` says.
+    Steps(Vec<(f64, f64)>, StepMode),
     /// Points represented with bars.
-    Bars(&'a [(f32, f32)]),
+    Bars(Vec<(f64, f64)>),
+    /// Points on stems, each a line up or down to it from zero with a
+    /// dot on the end, as for sparse or discrete data.
+    Stems(Vec<(f64, f64)>),
+    /// Candlesticks, a wick from the low to the high of each, and a
+    /// solid body from its open to its close.
+    Candles(Vec<Candle>),
+    /// A grid of cells, each shaded as densely as its value.
+    Heatmap(Heatmap),
 }
 
+/// The open, high, low and close of a candlestick at an x value.
+///
+/// ```
+/// use nu_plugin_plot::color_plot::textplots::{Candle, Chart, Plot, Shape};
+///
+/// let candle = Candle { x: 1.0, open: 1.0, high: 4.0, low: 0.0, close: 3.0 };
+/// let mut chart = Chart::new(32, 32, 0.0, 2.0);
+/// let chart = chart.lineplot(Shape::Candles(vec![candle]));
+/// assert_eq!(chart.y_range(), (0.0, 4.0));
+/// assert!(chart.to_string().contains('⣿'));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Candle {
+    pub x: f64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+/// How much of the room between neighbouring candles their bodies fill.
+const CANDLE_FILL: f64 = 0.6;
+
+/// Where a step plot steps between two points: at the first, so each
+/// value holds back to the point before it (`Pre`), at the second, so
+/// each holds until the next point (`Post`), as a counter does, or
+/// halfway between them (`Mid`).
+///
+/// ```
+/// use nu_plugin_plot::color_plot::textplots::StepMode;
+///
+/// assert_eq!(StepMode::from_name("post"), Some(StepMode::Post));
+/// assert_eq!(StepMode::default().name(), "pre");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StepMode {
+    #[default]
+    Pre,
+    Post,
+    Mid,
+}
+
+impl StepMode {
+    /// The step mode called `name`: `pre`, `post` or `mid`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "pre" => Some(StepMode::Pre),
+            "post" => Some(StepMode::Post),
+            "mid" => Some(StepMode::Mid),
+            _ => None,
+        }
+    }
+
+    /// The name of the step mode, as given on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            StepMode::Pre => "pre",
+            StepMode::Post => "post",
+            StepMode::Mid => "mid",
+        }
+    }
+}
+
+/// How a line is drawn: unbroken (`Solid`), in dashes (`Dashed`) or in
+/// dots (`Dotted`), so series which overlap can be told apart without
+/// color.
+///
+/// ```
+/// use nu_plugin_plot::color_plot::textplots::LineStyle;
+///
+/// assert_eq!(LineStyle::from_name("dotted"), Some(LineStyle::Dotted));
+/// assert_eq!(LineStyle::default().dash(), None);
+/// assert_eq!(LineStyle::Dashed.dash(), Some((4, 2)));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl LineStyle {
+    /// The line style called `name`: `solid`, `dashed` or `dotted`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "solid" => Some(LineStyle::Solid),
+            "dashed" => Some(LineStyle::Dashed),
+            "dotted" => Some(LineStyle::Dotted),
+            _ => None,
+        }
+    }
+
+    /// The name of the line style, as given on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            LineStyle::Solid => "solid",
+            LineStyle::Dashed => "dashed",
+            LineStyle::Dotted => "dotted",
+        }
+    }
+
+    /// The points on, then the points off, the line is drawn in, or
+    /// `None` for an unbroken line.
+    pub fn dash(self) -> Option<(u32, u32)> {
+        match self {
+            LineStyle::Solid => None,
+            LineStyle::Dashed => Some((4, 2)),
+            LineStyle::Dotted => Some((1, 2)),
+        }
+    }
+}
+
+/// A grid of cells between the edges of its columns and rows, each with
+/// a value. A cell is shaded empty at the low end of `range`, solid at
+/// the high end and in proportion between them, and left out if its
+/// value is not finite. With a `midpoint`, as for a diverging colormap,
+/// a cell is shaded empty there instead, and more densely the further
+/// its value is from it, either way.
+///
+/// ```
+/// use nu_plugin_plot::color_plot::textplots::{Chart, Heatmap, Plot, Shape};
+///
+/// let heatmap = Heatmap { x: vec![0.0, 1.0, 2.0], y: vec![0.0, 1.0], values: vec![0.0, 4.0], range: (0.0, 4.0), midpoint: None };
+/// assert_eq!(heatmap.density(2.0), 0.5);
+/// let diverging = Heatmap { range: (-1.0, 1.0), midpoint: Some(0.0), ..heatmap.clone() };
+/// assert_eq!((diverging.density(-0.5), diverging.position(-0.5)), (0.5, 0.25));
+/// let mut chart = Chart::new(32, 32, 0.0, 2.0);
+/// let chart = chart.lineplot(Shape::Heatmap(heatmap));
+/// assert_eq!(chart.y_range(), (0.0, 1.0));
+/// assert!(chart.to_string().contains('⣿'));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heatmap {
+    /// The edges of the columns, from the left, one more than there are columns.
+    pub x: Vec<f64>,
+    /// The edges of the rows, from the bottom, one more than there are rows.
+    pub y: Vec<f64>,
+    /// The value of each cell, a row at a time from the bottom left.
+    pub values: Vec<f64>,
+    /// The values shaded as empty and as solid.
+    pub range: (f64, f64),
+    /// The value shaded as empty, if not the low end of the range.
+    pub midpoint: Option<f64>,
+}
+
+impl Heatmap {
+    /// Where `value` is along the range, from 0 at its low end to 1 at
+    /// its high end, which picks the color of its cell from a colormap.
+    pub fn position(&self, value: f64) -> f64 {
+        let (low, high) = self.range;
+        if high > low {
+            ((value - low) / (high - low)).clamp(0.0, 1.0)
+        } else {
+            1.0
+        }
+    }
+
+    /// How densely to shade a cell of `value`, from 0 to 1.
+    pub fn density(&self, value: f64) -> f64 {
+        let (low, high) = self.range;
+        match self.midpoint {
+            Some(mid) => {
+                let reach = (high - mid).abs().max((mid - low).abs());
+                if reach > 0.0 {
+                    ((value - mid).abs() / reach).clamp(0.0, 1.0)
+                } else {
+                    1.0
+                }
+            }
+            None => self.position(value),
+        }
+    }
+
+    /// Each cell, as its left and right edges, its bottom and top edges,
+    /// and its value.
+    pub fn cells(&self) -> impl Iterator<Item = ((f64, f64), (f64, f64), f64)> + '_ {
+        self.y
+            .windows(2)
+            .flat_map(move |y| self.x.windows(2).map(move |x| ((x[0], x[1]), (y[0], y[1]))))
+            .zip(&self.values)
+            .map(|((x, y), value)| (x, y, *value))
+    }
+}
+
+impl Shape {
+    /// The points of the shape, a function's at `steps` evenly spaced
+    /// x values from `xmin` to `xmax`, and its ends, and a candlestick's
+    /// at its close, and a heatmap's at the middle of each cell with a
+    /// value.
+    pub fn points(&self, (xmin, xmax): (f64, f64), steps: u32) -> Vec<(f64, f64)> {
+        match self {
+            Shape::Continuous(f) => (0..=steps)
+                .map(|i| {
+                    let x = xmin + (xmax - xmin) * i as f64 / steps as f64;
+                    (x, f(x))
+                })
+                .collect(),
+            Shape::Points(dt) | Shape::Lines(dt) | Shape::Steps(dt, _) | Shape::Bars(dt) | Shape::Stems(dt) => dt.to_vec(),
+            Shape::Candles(candles) => candles.iter().map(|c| (c.x, c.close)).collect(),
+            Shape::Heatmap(heatmap) => heatmap
+                .cells()
+                .filter(|(_, _, value)| value.is_finite())
+                .map(|((x1, x2), (y1, y2), _)| ((x1 + x2) / 2.0, (y1 + y2) / 2.0))
+                .collect(),
+        }
+    }
+}
+
+/// A reference line or shaded region, drawn dimly behind the shapes, or
+/// a label drawn over them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Annotation {
+    /// A horizontal line at this y value, with an optional label.
+    HLine(f64, Option<String>),
+    /// A vertical line at this x value, with an optional label.
+    VLine(f64, Option<String>),
+    /// Shade everything above this y value.
+    ShadeAbove(f64),
+    /// Shade everything below this y value.
+    ShadeBelow(f64),
+    /// Shade everything between these x values.
+    ShadeX(f64, f64),
+    /// Shade between two lines, through points of an x value and a low
+    /// and a high y value, in a color or the default one for annotations.
+    /// A point which isn't finite breaks the shading.
+    Area(Vec<(f64, f64, f64)>, Option<PixelColor>),
+    /// A dashed line through these points in turn, such as a ring or a
+    /// spoke of the grid of a radar chart. A point which isn't finite
+    /// breaks the line.
+    Path(Vec<(f64, f64)>),
+    /// A text label next to the point at these x and y values.
+    Label(f64, f64, String),
+    /// A text label just above the point at these x and y values, left
+    /// out if it would run into another label.
+    Value(f64, f64, String),
+    /// Text with its top left at the point at these x and y values, as
+    /// it is, such as the name of a row.
+    Text(f64, f64, String),
+    /// A marker glyph on the point at these x and y values, in a color or
+    /// the default one, so a series can be told apart without color.
+    Marker(f64, f64, char, Option<PixelColor>),
+    /// An error bar at this x value, from this low to this high y value,
+    /// with a cap at either end, in a color, behind the shapes.
+    ErrorBar(f64, f64, f64, PixelColor),
+    /// Dim grid lines across at the rows the y axis is labelled on, and
+    /// down at columns as far apart, behind everything else.
+    Grid,
+    /// The color behind the plot, for text charts, with what is drawn in
+    /// the default color drawn in one which shows up on it.
+    Background(PixelColor),
+}
+
+/// A legend entry, as pieces of text each with an optional color.
+pub type LegendEntry = Vec<(String, Option<PixelColor>)>;
+
+/// The color of reference lines, shading and labels.
+const ANNOTATION_COLOR: PixelColor = PixelColor::BrightBlack;
+
+/// The color of grid lines.
+const GRID_COLOR: PixelColor = PixelColor::BrightBlack;
+
+/// Points either side of an error bar its caps reach.
+const ERROR_CAP: u32 = 1;
+
 /// Provides an interface for drawing plots.
-pub trait Plot<'a> {
+pub trait Plot {
     /// Draws a [line chart](https://en.wikipedia.org/wiki/Line_chart) of points connected by straight line segments.
-    fn lineplot(&'a mut self, shape: &'a Shape) -> &'a mut Chart;
+    fn lineplot(&mut self, shape: Shape) -> &mut Chart;
 }
 
 /// Provides an interface for drawing colored plots.
-pub trait ColorPlot<'a> {
+pub trait ColorPlot {
     /// Draws a [line chart](https://en.wikipedia.org/wiki/Line_chart) of points connected by straight line segments using the specified color
-    fn linecolorplot(&'a mut self, shape: &'a Shape, color: PixelColor) -> &'a mut Chart;
+    fn linecolorplot(&mut self, shape: Shape, color: PixelColor) -> &mut Chart;
 }
 
-impl<'a> Default for Chart<'a> {
+impl Default for Chart {
     fn default() -> Self {
         Self::new(120, 60, -10.0, 10.0)
     }
 }
 
-impl<'a> Chart<'a> {
+impl Chart {
     /// Creates a new `Chart` object.
     ///
     /// # Panics
     ///
     /// Panics if `width` or `height` is less than 32.
-    pub fn new(width: u32, height: u32, xmin: f32, xmax: f32) -> Self {
+    pub fn new(width: u32, height: u32, xmin: f64, xmax: f64) -> Self {
+        Self::with_backend(Box::new(BrailleCanvas::new(width, height)), width, height, xmin, xmax)
+    }
+
+    /// Creates a new `Chart` object drawn on `backend`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `height` is less than 32.
+    pub fn with_backend(backend: Box<dyn Backend>, width: u32, height: u32, xmin: f64, xmax: f64) -> Self {
         if width < 32 {
             panic!("width should be more then 32, {} is provided", width);
         }
@@ -143,13 +450,22 @@ impl<'a> Chart<'a> {
         Self {
             xmin,
             xmax,
-            ymin: f32::INFINITY,
-            ymax: f32::NEG_INFINITY,
+            ymin: f64::INFINITY,
+            ymax: f64::NEG_INFINITY,
             y_ranging: ChartRangeMethod::AutoRange,
             width,
             height,
             shapes: Vec::new(),
-            canvas: BrailleCanvas::new(width, height),
+            styles: Vec::new(),
+            widths: Vec::new(),
+            smooth: false,
+            blend: Blend::Last,
+            annotations: Vec::new(),
+            legend: Vec::new(),
+            title: None,
+            gradient: None,
+            label_formats: Default::default(),
+            canvas: backend,
         }
     }
 
@@ -161,10 +477,10 @@ impl<'a> Chart<'a> {
     pub fn new_with_y_range(
         width: u32,
         height: u32,
-        xmin: f32,
-        xmax: f32,
-        ymin: f32,
-        ymax: f32,
+        xmin: f64,
+        xmax: f64,
+        ymin: f64,
+        ymax: f64,
     ) -> Self {
         if width < 32 {
             panic!("width should be more then 32, {} is provided", width);
@@ -183,7 +499,16 @@ impl<'a> Chart<'a> {
             width,
             height,
             shapes: Vec::new(),
-            canvas: BrailleCanvas::new(width, height),
+            styles: Vec::new(),
+            widths: Vec::new(),
+            smooth: false,
+            blend: Blend::Last,
+            annotations: Vec::new(),
+            legend: Vec::new(),
+            title: None,
+            gradient: None,
+            label_formats: Default::default(),
+            canvas: Box::new(BrailleCanvas::new(width, height)),
         }
     }
 
@@ -198,45 +523,190 @@ impl<'a> Chart<'a> {
         self.hline(h);
     }
 
-    /// Draws vertical line.
+    /// Draws a dotted vertical line, every third point set.
     fn vline(&mut self, i: u32) {
         if i <= self.width {
-            for j in 0..=self.height {
-                if j % 3 == 0 {
-                    self.canvas.set(i, j);
-                }
-            }
+            self.canvas.dashed_line(i, 0, i, self.height, None, (1, 2), 0);
         }
     }
 
-    /// Draws horizontal line.
+    /// Draws a dotted horizontal line, every third point set.
     fn hline(&mut self, j: u32) {
         if j <= self.height {
-            for i in 0..=self.width {
-                if i % 3 == 0 {
-                    self.canvas.set(i, self.height - j);
-                }
+            self.canvas.dashed_line(0, self.height - j, self.width, self.height - j, None, (1, 2), 0);
+        }
+    }
+
+    /// Adds a reference line, shaded region or label. A horizontal line or the edge
+    /// of a region shaded above or below widens an automatic y range to
+    /// include it, and an area or an error bar to include its ends.
+    pub fn annotate(&mut self, annotation: Annotation) -> &mut Self {
+        if let Annotation::Area(points, _) = &annotation {
+            for &(_, low, high) in points {
+                self.widen_y_range(low.min(high), low.max(high));
+            }
+        }
+        if let Annotation::ErrorBar(_, low, high, _) = annotation {
+            self.widen_y_range(low.min(high), low.max(high));
+        }
+        if let Annotation::HLine(y, _) | Annotation::ShadeAbove(y) | Annotation::ShadeBelow(y) = annotation {
+            if self.y_ranging == ChartRangeMethod::AutoRange && y.is_finite() {
+                self.ymin = f64::min(self.ymin, y);
+                self.ymax = f64::max(self.ymax, y);
             }
         }
+        self.annotations.push(annotation);
+        self
+    }
+
+    /// Widens an automatic y range to take in `ymin` to `ymax`, so that
+    /// charts of different data can be drawn to the same scale.
+    pub fn widen_y_range(&mut self, ymin: f64, ymax: f64) -> &mut Self {
+        if self.y_ranging == ChartRangeMethod::AutoRange && ymin.is_finite() && ymax.is_finite() {
+            self.ymin = f64::min(self.ymin, ymin);
+            self.ymax = f64::max(self.ymax, ymax);
+        }
+        self
+    }
+
+    /// Fixes the y range to `ymin` to `ymax`, whatever is plotted.
+    pub fn fix_y_range(&mut self, ymin: f64, ymax: f64) -> &mut Self {
+        self.ymin = ymin;
+        self.ymax = ymax;
+        self.y_ranging = ChartRangeMethod::FixedRange;
+        self
+    }
+
+    /// Sets the legend, drawn in a box in the top right corner.
+    pub fn legend(&mut self, entries: &[LegendEntry]) -> &mut Self {
+        self.legend = entries.to_vec();
+        self
+    }
+
+    /// Sets the title, for backends which draw their own.
+    pub fn title(&mut self, title: &str) -> &mut Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    /// Colors the shapes drawn without a color of their own along
+    /// `colormap`, each piece by its height on the chart.
+    pub fn gradient(&mut self, colormap: Colormap) -> &mut Self {
+        self.gradient = Some(colormap);
+        self
+    }
+
+    /// Sets how the numbers of the x and y axes are written.
+    pub fn label_format(&mut self, x: LabelFormat, y: LabelFormat) -> &mut Self {
+        self.label_formats = (x, y);
+        self
+    }
+
+    /// The colormap shapes without a color of their own are drawn along.
+    pub fn colormap(&self) -> Option<Colormap> {
+        self.gradient
+    }
+
+    /// The shapes plotted so far, with their colors.
+    pub fn shapes(&self) -> &[(Shape, Option<PixelColor>)] {
+        &self.shapes
+    }
+
+    /// Colors the shapes where they cross one another as `blend` says,
+    /// rather than in the color of the one drawn last.
+    pub fn blend(&mut self, blend: Blend) -> &mut Self {
+        self.blend = blend;
+        self
+    }
+
+    /// Draws the lines of the shape plotted last in `style`.
+    pub fn line_style(&mut self, style: LineStyle) -> &mut Self {
+        if let Some(last) = self.styles.last_mut() {
+            *last = style;
+        }
+        self
+    }
+
+    /// How the lines of each shape plotted so far are drawn.
+    pub fn line_styles(&self) -> &[LineStyle] {
+        &self.styles
+    }
+
+    /// Draws the lines of the shape plotted last `width` points across,
+    /// rather than one, so it stands out from the rest.
+    pub fn line_width(&mut self, width: u32) -> &mut Self {
+        if let Some(last) = self.widths.last_mut() {
+            *last = width.max(1);
+        }
+        self
+    }
+
+    /// How many points across the lines of each shape plotted so far are.
+    pub fn line_widths(&self) -> &[u32] {
+        &self.widths
+    }
+
+    /// Fills the corner of each diagonal step of the lines of every
+    /// shape, so they run on unbroken rather than touching at corners.
+    pub fn smooth(&mut self, smooth: bool) -> &mut Self {
+        self.smooth = smooth;
+        self
     }
 
+    /// The width and height of the chart in points.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// The x range of the chart.
+    pub fn x_range(&self) -> (f64, f64) {
+        (self.xmin, self.xmax)
+    }
+
+    /// The y range of the chart, fitted to its shapes so far unless it
+    /// was fixed.
+    pub fn y_range(&self) -> (f64, f64) {
+        (self.ymin, self.ymax)
+    }
+
+    /// Draws the chart, as text for the text backends and the SVG canvas.
+    ///
+    /// As text, the y range is labelled down the left of the chart, and
+    /// the x range underneath it.
+    ///
+    /// ```
+    /// use nu_plugin_plot::color_plot::textplots::{Chart, Plot, Shape};
+    ///
+    /// let chart = Chart::new(32, 32, 0.0, 10.0)
+    ///     .lineplot(Shape::Lines(vec![(0.0, -4.0), (10.0, 4.0)]))
+    ///     .to_string();
+    /// let rows: Vec<&str> = chart.lines().collect();
+    /// assert!(rows[0].starts_with(" 4.0 "));
+    /// assert!(rows[4].starts_with(" 0.0 "));
+    /// assert!(rows[8].starts_with("-4.0 "));
+    /// assert_eq!(rows[9], "     0.0          10.0");
+    /// ```
     #[allow(clippy::inherent_to_string)]
     pub fn to_string(&mut self) -> String {
+        String::from_utf8_lossy(&self.to_bytes()).into_owned()
+    }
+
+    /// Draws the chart, as the bytes of the backend's output.
+    pub fn to_bytes(&mut self) -> Vec<u8> {
         self.figures();
+        self.annotations();
         self.axis();
+        self.labels();
+        self.legend_box();
 
-        let mut frame = self.canvas.frame();
-        if let Some(idx) = frame.find('\n') {
-            frame.insert_str(idx, &format!(" {0:.1}", self.ymax));
-            frame.push_str(&format!(
-                " {0:.1}\n{1: <width$.1}{2:.1}\n",
-                self.ymin,
-                self.xmin,
-                self.xmax,
-                width = (self.width as usize) / 2 - 3
-            ));
-        }
-        frame
+        self.canvas.render(
+            self.width,
+            self.height,
+            (self.xmin, self.xmax),
+            (self.ymin, self.ymax),
+            self.title.as_deref(),
+            self.label_formats,
+        )
     }
 
     /// Prints canvas content.
@@ -252,8 +722,8 @@ impl<'a> Chart<'a> {
 
     /// Show axis.
     pub fn axis(&mut self) {
-        let x_scale = Scale::new(self.xmin..self.xmax, 0.0..self.width as f32);
-        let y_scale = Scale::new(self.ymin..self.ymax, 0.0..self.height as f32);
+        let x_scale = Scale::new(self.xmin..self.xmax, 0.0..self.width as f64);
+        let y_scale = Scale::new(self.ymin..self.ymax, 0.0..self.height as f64);
 
         if self.xmin <= 0.0 && self.xmax >= 0.0 {
             self.vline(x_scale.linear(0.0) as u32);
@@ -265,123 +735,441 @@ impl<'a> Chart<'a> {
 
     // Show figures.
     pub fn figures(&mut self) {
-        for (shape, color) in &self.shapes {
-            let x_scale = Scale::new(self.xmin..self.xmax, 0.0..self.width as f32);
-            let y_scale = Scale::new(self.ymin..self.ymax, 0.0..self.height as f32);
+        // a gradient colors a shape against itself, so is never blended
+        if self.gradient.is_none() {
+            self.canvas.set_blend(self.blend);
+        }
+        for (((shape, color), style), &width) in self.shapes.iter().zip(&self.styles).zip(&self.widths) {
+            let x_scale = Scale::new(self.xmin..self.xmax, 0.0..self.width as f64);
+            let y_scale = Scale::new(self.ymin..self.ymax, 0.0..self.height as f64);
 
-            // translate (x, y) points into screen coordinates
+            // translate (x, y) points into screen coordinates, points that
+            // are not finite become gaps (`None`) which break the line
             let points: Vec<_> = match shape {
                 Shape::Continuous(f) => (0..self.width)
-                    .filter_map(|i| {
-                        let x = x_scale.inv_linear(i as f32);
-                        let y = f(x);
-                        if y.is_normal() {
-                            let j = y_scale.linear(y).round();
+                    .map(|i| {
+                        let x = x_scale.inv_linear(i as f64);
+                        let j = y_scale.linear(f(x)).round();
+                        // off the chart, the curve breaks until it comes back
+                        if j >= 0.0 && j <= self.height as f64 {
                             Some((i, self.height - j as u32))
                         } else {
                             None
                         }
                     })
                     .collect(),
-                Shape::Points(dt) | Shape::Lines(dt) | Shape::Steps(dt) | Shape::Bars(dt) => dt
+                Shape::Points(dt) | Shape::Lines(dt) | Shape::Steps(dt, _) | Shape::Bars(dt) | Shape::Stems(dt) => dt
                     .iter()
                     .filter_map(|(x, y)| {
+                        if !x.is_finite() || !y.is_finite() {
+                            return Some(None);
+                        }
                         let i = x_scale.linear(*x).round() as u32;
                         let j = y_scale.linear(*y).round() as u32;
                         if i <= self.width && j <= self.height {
-                            Some((i, self.height - j))
+                            Some(Some((i, self.height - j)))
                         } else {
                             None
                         }
                     })
                     .collect(),
+                // drawn a column or a cell at a time, below
+                Shape::Candles(_) | Shape::Heatmap(_) => vec![],
+            };
+            let segments = points.windows(2).filter_map(|pair| match pair {
+                [Some(a), Some(b)] => Some((*a, *b)),
+                _ => None,
+            });
+            // a point with a gap or the end either side is in no segment,
+            // so is drawn on its own
+            let lone: Vec<(u32, u32)> = (0..points.len())
+                .filter(|&i| i.checked_sub(1).and_then(|j| points[j]).is_none() && points.get(i + 1).copied().flatten().is_none())
+                .filter_map(|i| points[i])
+                .collect();
+
+            // the color of a piece of the shape, whose rows run from `y1` to
+            // `y2`: its own, or from the gradient by the height of its middle
+            let height = self.height;
+            let paint = |y1: u32, y2: u32| match (color, self.gradient) {
+                (None, Some(colormap)) => Some(colormap.color(1.0 - (y1 + y2) as f64 / (2 * height) as f64)),
+                _ => *color,
+            };
+
+            // a line of the shape, in dashes carried on from where the
+            // last left off if its style has them, as wide as it is and
+            // smoothed if the chart is
+            let mut phase = 0;
+            let canvas = &mut self.canvas;
+            let smooth = self.smooth;
+            let mut line = |x1: u32, y1: u32, x2: u32, y2: u32, color: Option<PixelColor>| match style.dash() {
+                _ if width > 1 || smooth => phase = canvas.stroked_line(x1, y1, x2, y2, color, style.dash(), phase, (width, smooth)),
+                Some(dash) => phase = canvas.dashed_line(x1, y1, x2, y2, color, dash, phase),
+                None => canvas.line(x1, y1, x2, y2, color),
             };
 
             // display segments
             match shape {
                 Shape::Continuous(_) | Shape::Lines(_) => {
-                    for pair in points.windows(2) {
-                        let (x1, y1) = pair[0];
-                        let (x2, y2) = pair[1];
-                        if let Some(color) = color {
-                            self.canvas.line_colored(x1, y1, x2, y2, *color);
-                        } else {
-                            self.canvas.line(x1, y1, x2, y2);
-                        }
+                    for ((x1, y1), (x2, y2)) in segments {
+                        line(x1, y1, x2, y2, paint(y1, y2));
+                    }
+                    for (x, y) in lone {
+                        self.canvas.set(x, y, paint(y, y));
                     }
                 }
                 Shape::Points(_) => {
-                    for (x, y) in points {
-                        self.canvas.set(x, y);
+                    for (x, y) in points.into_iter().flatten() {
+                        let color = if self.gradient.is_some() { paint(y, y) } else { None };
+                        self.canvas.set(x, y, color);
                     }
                 }
-                Shape::Steps(_) => {
-                    for pair in points.windows(2) {
-                        let (x1, y1) = pair[0];
-                        let (x2, y2) = pair[1];
-
-                        if let Some(color) = color {
-                            self.canvas.line_colored(x1, y2, x2, y2, *color);
-                            self.canvas.line_colored(x1, y1, x1, y2, *color);
-                        } else {
-                            self.canvas.line(x1, y2, x2, y2);
-                            self.canvas.line(x1, y1, x1, y2);
+                Shape::Steps(_, mode) => {
+                    for ((x1, y1), (x2, y2)) in segments {
+                        match mode {
+                            StepMode::Pre => {
+                                line(x1, y2, x2, y2, paint(y2, y2));
+                                line(x1, y1, x1, y2, paint(y1, y2));
+                            }
+                            StepMode::Post => {
+                                line(x1, y1, x2, y1, paint(y1, y1));
+                                line(x2, y1, x2, y2, paint(y1, y2));
+                            }
+                            StepMode::Mid => {
+                                let mid = (x1 + x2) / 2;
+                                line(x1, y1, mid, y1, paint(y1, y1));
+                                line(mid, y1, mid, y2, paint(y1, y2));
+                                line(mid, y2, x2, y2, paint(y2, y2));
+                            }
                         }
                     }
+                    for (x, y) in lone {
+                        self.canvas.set(x, y, paint(y, y));
+                    }
                 }
                 Shape::Bars(_) => {
+                    for ((x1, y1), (x2, y2)) in segments {
+                        self.canvas.line(x1, y2, x2, y2, paint(y2, y2));
+                        self.canvas.line(x1, y1, x1, y2, paint(y1, y2));
+                        self.canvas.line(x1, self.height, x1, y1, paint(y1, y1));
+                        self.canvas.line(x2, self.height, x2, y2, paint(y2, y2));
+                    }
+                    for (x, y) in lone {
+                        self.canvas.line(x, self.height, x, y, paint(y, y));
+                    }
+                }
+                // from the row of zero, or the edge it is past, with a
+                // dot of a point's neighbours on the end
+                Shape::Stems(_) => {
+                    let base = height - y_scale.linear(0.0).round().clamp(0.0, height as f64) as u32;
+                    for (x, y) in points.into_iter().flatten() {
+                        self.canvas.line(x, base, x, y, paint(base, y));
+                        let head = [(x, y), (x.saturating_sub(1), y), ((x + 1).min(self.width), y), (x, y.saturating_sub(1)), (x, (y + 1).min(height))];
+                        for (i, j) in head {
+                            self.canvas.set(i, j, paint(y, y));
+                        }
+                    }
+                }
+                Shape::Candles(candles) => {
+                    let col = |x: f64| x_scale.linear(x).round().clamp(0.0, self.width as f64) as u32;
+                    let row = |y: f64| height - y_scale.linear(y).round().clamp(0.0, height as f64) as u32;
+                    let mut cols: Vec<f64> = candles.iter().map(|c| x_scale.linear(c.x)).filter(|i| i.is_finite()).collect();
+                    cols.sort_by(f64::total_cmp);
+                    let gap = cols.windows(2).map(|pair| pair[1] - pair[0]).fold(self.width as f64, f64::min);
+                    let half = (gap * CANDLE_FILL / 2.0).floor() as u32;
+                    for c in candles {
+                        if ![c.x, c.open, c.high, c.low, c.close].iter().all(|v| v.is_finite()) || !(self.xmin..=self.xmax).contains(&c.x) {
+                            continue;
+                        }
+                        let i = col(c.x);
+                        let (top, bottom) = (row(c.open.max(c.close)), row(c.open.min(c.close)));
+                        self.canvas.line(i, row(c.high), i, row(c.low), paint(top, bottom));
+                        for x in i.saturating_sub(half)..=(i + half).min(self.width) {
+                            self.canvas.line(x, top, x, bottom, paint(top, bottom));
+                        }
+                    }
+                }
+                // each cell its own color, or from the gradient by its value
+                Shape::Heatmap(heatmap) => {
+                    let col = |x: f64| x_scale.linear(x).round().clamp(0.0, self.width as f64) as u32;
+                    let row = |y: f64| height - y_scale.linear(y).round().clamp(0.0, height as f64) as u32;
+                    for ((x1, x2), (y1, y2), value) in heatmap.cells() {
+                        if !value.is_finite() {
+                            continue;
+                        }
+                        let density = heatmap.density(value);
+                        let color = match (color, self.gradient) {
+                            (None, Some(colormap)) => Some(colormap.color(heatmap.position(value))),
+                            _ => *color,
+                        };
+                        self.canvas.fill_cell(col(x1), row(y2), col(x2), row(y1), density, color);
+                    }
+                }
+            }
+        }
+        // what's drawn over the shapes covers them
+        self.canvas.set_blend(Blend::Last);
+    }
+
+    /// Show grid lines across and down the chart, between its edges, a
+    /// point in every four, so they are lighter than reference lines.
+    fn grid(&mut self) {
+        let rows = ticks((self.height / 4) as usize, TICK_ROWS);
+        let columns = ticks((self.width / 2) as usize, TICK_COLUMNS);
+        for &r in &rows[1..rows.len() - 1] {
+            for i in (0..=self.width).step_by(4) {
+                self.canvas.set_behind(i, 4 * r as u32, GRID_COLOR);
+            }
+        }
+        for &c in &columns[1..columns.len() - 1] {
+            for j in (0..=self.height).step_by(4) {
+                self.canvas.set_behind(2 * c as u32, j, GRID_COLOR);
+            }
+        }
+    }
+
+    /// Show the background and the grid, then shaded regions, then
+    /// reference lines and paths, dashed, the lines with their labels.
+    fn annotations(&mut self) {
+        if let Some(color) = self.annotations.iter().find_map(|a| match a {
+            Annotation::Background(color) => Some(*color),
+            _ => None,
+        }) {
+            self.canvas.set_background(color);
+        }
+        // under everything else drawn behind the shapes
+        if self.annotations.iter().any(|a| matches!(a, Annotation::Grid)) {
+            self.grid();
+        }
+
+        let x_scale = Scale::new(self.xmin..self.xmax, 0.0..self.width as f64);
+        let y_scale = Scale::new(self.ymin..self.ymax, 0.0..self.height as f64);
+        // screen coordinates, clamped to the canvas
+        let col = |x: f64| x_scale.linear(x).round().clamp(0.0, self.width as f64) as u32;
+        let row = |y: f64| self.height - y_scale.linear(y).round().clamp(0.0, self.height as f64) as u32;
+
+        let (shades, lines): (Vec<&Annotation>, Vec<&Annotation>) = self
+            .annotations
+            .iter()
+            .partition(|a| matches!(a, Annotation::ShadeAbove(_) | Annotation::ShadeBelow(_) | Annotation::ShadeX(..) | Annotation::Area(..)));
+
+        for annotation in shades.into_iter().chain(lines) {
+            match annotation {
+                Annotation::ShadeAbove(y) => {
+                    self.canvas.fill(0, 0, self.width, row(*y), ANNOTATION_COLOR);
+                }
+                Annotation::ShadeBelow(y) => {
+                    self.canvas.fill(0, row(*y), self.width, self.height, ANNOTATION_COLOR);
+                }
+                Annotation::ShadeX(from, to) => {
+                    if from.max(*to) < self.xmin || from.min(*to) > self.xmax {
+                        continue;
+                    }
+                    self.canvas.fill(col(*from), 0, col(*to), self.height, ANNOTATION_COLOR);
+                }
+                Annotation::Area(points, color) => {
+                    // each run of the two lines, as their rows in every column they cross
+                    for run in points.split(|(x, low, high)| !x.is_finite() || !low.is_finite() || !high.is_finite()) {
+                        let mut columns: Vec<(u32, u32, u32)> = run.iter().take(1).map(|(x, low, high)| (col(*x), row(*high), row(*low))).collect();
+                        for pair in run.windows(2) {
+                            let (c1, c2) = (col(pair[0].0), col(pair[1].0));
+                            let between = |y1: f64, y2: f64, t: f64| (row(y1) as f64 + t * (row(y2) as f64 - row(y1) as f64)).round() as u32;
+                            for c in c1.min(c2)..=c1.max(c2) {
+                                let t = if c1 == c2 { 1.0 } else { (c as f64 - c1 as f64) / (c2 as f64 - c1 as f64) };
+                                columns.push((c, between(pair[0].2, pair[1].2, t), between(pair[0].1, pair[1].1, t)));
+                            }
+                        }
+                        self.canvas.fill_area(&columns, color.unwrap_or(ANNOTATION_COLOR));
+                    }
+                }
+                Annotation::HLine(y, label) => {
+                    if !(self.ymin..=self.ymax).contains(y) {
+                        continue;
+                    }
+                    let j = self.height - y_scale.linear(*y).round() as u32;
+                    for i in (0..=self.width).step_by(2) {
+                        self.canvas.set_behind(i, j, ANNOTATION_COLOR);
+                    }
+                    if let Some(label) = label {
+                        // right aligned, in the row above the line if there is one
+                        let len = 2 * label.chars().count() as u32;
+                        let row = if j >= 4 { j - 4 } else { j + 4 };
+                        self.canvas.text(self.width.saturating_sub(len), row, self.width, label, Some(ANNOTATION_COLOR));
+                    }
+                }
+                Annotation::VLine(x, label) => {
+                    if !(self.xmin..=self.xmax).contains(x) {
+                        continue;
+                    }
+                    let i = x_scale.linear(*x).round() as u32;
+                    for j in (0..=self.height).step_by(2) {
+                        self.canvas.set_behind(i, j, ANNOTATION_COLOR);
+                    }
+                    if let Some(label) = label {
+                        // along the top, to the right of the line if it fits
+                        let len = 2 * label.chars().count() as u32;
+                        let col = if i + 2 + len <= self.width { i + 2 } else { i.saturating_sub(len + 1) };
+                        self.canvas.text(col, 0, self.width, label, Some(ANNOTATION_COLOR));
+                    }
+                }
+                Annotation::Path(points) => {
                     for pair in points.windows(2) {
-                        let (x1, y1) = pair[0];
-                        let (x2, y2) = pair[1];
-
-                        if let Some(color) = color {
-                            self.canvas.line_colored(x1, y2, x2, y2, *color);
-                            self.canvas.line_colored(x1, y1, x1, y2, *color);
-                            self.canvas.line_colored(x1, self.height, x1, y1, *color);
-                            self.canvas.line_colored(x2, self.height, x2, y2, *color);
-                        } else {
-                            self.canvas.line(x1, y2, x2, y2);
-                            self.canvas.line(x1, y1, x1, y2);
-                            self.canvas.line(x1, self.height, x1, y1);
-                            self.canvas.line(x2, self.height, x2, y2);
+                        let [(x1, y1), (x2, y2)] = [pair[0], pair[1]];
+                        if ![x1, y1, x2, y2].iter().all(|v| v.is_finite()) {
+                            continue;
+                        }
+                        // every other point along it, as many as it is long
+                        let steps = col(x1).abs_diff(col(x2)).max(row(y1).abs_diff(row(y2)));
+                        for step in (0..=steps).step_by(2) {
+                            let t = if steps == 0 { 0.0 } else { step as f64 / steps as f64 };
+                            self.canvas.set_behind(col(x1 + t * (x2 - x1)), row(y1 + t * (y2 - y1)), ANNOTATION_COLOR);
                         }
                     }
                 }
+                Annotation::ErrorBar(x, low, high, color) => {
+                    if !(self.xmin..=self.xmax).contains(x) || !low.is_finite() || !high.is_finite() {
+                        continue;
+                    }
+                    let i = x_scale.linear(*x).round() as u32;
+                    let (top, bottom) = (row(low.max(*high)), row(low.min(*high)));
+                    for j in top..=bottom {
+                        self.canvas.set_behind(i, j, *color);
+                    }
+                    for j in [top, bottom] {
+                        for cap in i.saturating_sub(ERROR_CAP)..=(i + ERROR_CAP).min(self.width) {
+                            self.canvas.set_behind(cap, j, *color);
+                        }
+                    }
+                }
+                // drawn over the axes, by `labels`, or above, first
+                Annotation::Label(..) | Annotation::Value(..) | Annotation::Text(..) | Annotation::Marker(..) | Annotation::Grid | Annotation::Background(..) => {}
             }
         }
     }
 
-    /// Return the frame.
-    pub fn frame(&self) -> String {
-        self.canvas.frame()
+    /// Show markers on their points, then text where it is put, then
+    /// labelled points, in the row above or below each point, then the
+    /// values of points, above them, where there is room.
+    fn labels(&mut self) {
+        let x_scale = Scale::new(self.xmin..self.xmax, 0.0..self.width as f64);
+        let y_scale = Scale::new(self.ymin..self.ymax, 0.0..self.height as f64);
+        // screen coordinates, clamped to the canvas
+        let col = |x: f64| x_scale.linear(x).round().clamp(0.0, self.width as f64) as u32;
+        let row = |y: f64| self.height - y_scale.linear(y).round().clamp(0.0, self.height as f64) as u32;
+
+        for annotation in &self.annotations {
+            if let Annotation::Marker(x, y, marker, color) = annotation {
+                if (self.xmin..=self.xmax).contains(x) && (self.ymin..=self.ymax).contains(y) {
+                    self.canvas.text(col(*x), row(*y), 0, &marker.to_string(), *color);
+                }
+            }
+        }
+
+        for annotation in &self.annotations {
+            if let Annotation::Text(x, y, text) = annotation {
+                let col = col(*x);
+                self.canvas.text(col, row(*y), self.width.saturating_sub(col), text, None);
+            }
+        }
+
+        for annotation in &self.annotations {
+            if let Annotation::Label(x, y, label) = annotation {
+                // points just outside the range, such as extremes dropped
+                // by downsampling, are labelled at the edge
+                let (i, j) = (col(*x), row(*y));
+                let len = 2 * label.chars().count() as u32;
+                // away from the middle of the chart if there is room
+                let away = if j < self.height / 2 { j.checked_sub(4) } else { Some(j + 4).filter(|r| *r <= self.height) };
+                let row = away.unwrap_or(if j < self.height / 2 { j + 4 } else { j - 4 });
+                let col = if i + len <= self.width { i } else { self.width.saturating_sub(len) };
+                self.canvas.text(col, row, self.width - col, label, None);
+            }
+        }
+
+        for annotation in &self.annotations {
+            if let Annotation::Value(x, y, label) = annotation {
+                if !(self.xmin..=self.xmax).contains(x) || !(self.ymin..=self.ymax).contains(y) {
+                    continue;
+                }
+                let (i, j) = (col(*x), row(*y));
+                let chars = label.chars().count() as u32;
+                // centred over the point, or under it in the top row
+                let row = if j >= 4 { j - 4 } else { j + 4 };
+                let col = i.saturating_sub(chars).min(self.width.saturating_sub(2 * chars));
+                // keep a blank cell either side, so neighbours stay readable
+                if self.canvas.text_fits(col.saturating_sub(2), row, chars + 2) {
+                    self.canvas.text(col, row, self.width - col, label, None);
+                }
+            }
+        }
+    }
+
+    /// Show the legend, if there is one, in a box in the top right corner.
+    fn legend_box(&mut self) {
+        if self.legend.is_empty() {
+            return;
+        }
+        // in cells, with a border and a space either side of the entries
+        let inner = self
+            .legend
+            .iter()
+            .map(|entry| entry.iter().map(|(text, _)| text.chars().count()).sum::<usize>())
+            .max()
+            .unwrap_or(0) as u32;
+        let cells = inner + 4;
+        let left = (self.width / 2 + 1).saturating_sub(cells);
+        let bottom = self.legend.len() as u32 + 1;
+
+        self.canvas.panel(2 * left, 0, 2 * (left + cells - 1), 4 * bottom);
+        for (r, entry) in self.legend.iter().enumerate() {
+            let mut col = left + 2;
+            for (text, color) in entry {
+                let (x, y) = (2 * col, 4 * (r as u32 + 1));
+                self.canvas.text(x, y, self.width, text, *color);
+                col += text.chars().count() as u32;
+            }
+        }
     }
 
     fn rescale(&mut self, shape: &Shape) {
         // rescale ymin and ymax
-        let x_scale = Scale::new(self.xmin..self.xmax, 0.0..self.width as f32);
+        let x_scale = Scale::new(self.xmin..self.xmax, 0.0..self.width as f64);
 
         let ys: Vec<_> = match shape {
             Shape::Continuous(f) => (0..self.width)
                 .filter_map(|i| {
-                    let x = x_scale.inv_linear(i as f32);
+                    let x = x_scale.inv_linear(i as f64);
                     let y = f(x);
-                    if y.is_normal() {
+                    if y.is_finite() {
                         Some(y)
                     } else {
                         None
                     }
                 })
                 .collect(),
-            Shape::Points(dt) | Shape::Lines(dt) | Shape::Steps(dt) | Shape::Bars(dt) => dt
+            Shape::Points(dt) | Shape::Lines(dt) | Shape::Steps(dt, _) | Shape::Bars(dt) => dt
                 .iter()
                 .filter_map(|(x, y)| {
-                    if *x >= self.xmin && *x <= self.xmax {
+                    if *x >= self.xmin && *x <= self.xmax && y.is_finite() {
                         Some(*y)
                     } else {
                         None
                     }
                 })
                 .collect(),
+            // and zero, which the stems rise or fall from
+            Shape::Stems(dt) => dt
+                .iter()
+                .filter(|(x, y)| *x >= self.xmin && *x <= self.xmax && y.is_finite())
+                .map(|(_, y)| *y)
+                .chain(std::iter::once(0.0))
+                .collect(),
+            Shape::Candles(candles) => candles
+                .iter()
+                .filter(|c| c.x >= self.xmin && c.x <= self.xmax)
+                .flat_map(|c| [c.high, c.low])
+                .filter(|y| y.is_finite())
+                .collect(),
+            Shape::Heatmap(heatmap) => heatmap.y.iter().copied().filter(|y| y.is_finite()).collect(),
         };
 
         let ymax = *ys
@@ -393,27 +1181,31 @@ impl<'a> Chart<'a> {
             .min_by(|x, y| x.partial_cmp(y).unwrap_or(cmp::Ordering::Equal))
             .unwrap_or(&0.0);
 
-        self.ymin = f32::min(self.ymin, ymin);
-        self.ymax = f32::max(self.ymax, ymax);
+        self.ymin = f64::min(self.ymin, ymin);
+        self.ymax = f64::max(self.ymax, ymax);
     }
 }
 
-impl<'a> ColorPlot<'a> for Chart<'a> {
-    fn linecolorplot(&'a mut self, shape: &'a Shape, color: PixelColor) -> &'a mut Chart {
-        self.shapes.push((shape, Some(color)));
+impl ColorPlot for Chart {
+    fn linecolorplot(&mut self, shape: Shape, color: PixelColor) -> &mut Chart {
         if self.y_ranging == ChartRangeMethod::AutoRange {
-            self.rescale(shape);
+            self.rescale(&shape);
         }
+        self.shapes.push((shape, Some(color)));
+        self.styles.push(LineStyle::Solid);
+        self.widths.push(1);
         self
     }
 }
 
-impl<'a> Plot<'a> for Chart<'a> {
-    fn lineplot(&'a mut self, shape: &'a Shape) -> &'a mut Chart {
-        self.shapes.push((shape, None));
+impl Plot for Chart {
+    fn lineplot(&mut self, shape: Shape) -> &mut Chart {
         if self.y_ranging == ChartRangeMethod::AutoRange {
-            self.rescale(shape);
+            self.rescale(&shape);
         }
+        self.shapes.push((shape, None));
+        self.styles.push(LineStyle::Solid);
+        self.widths.push(1);
         self
     }
 }
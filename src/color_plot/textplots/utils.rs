@@ -3,20 +3,21 @@
 //! Merely a bunch of functions hanging around while the library API is taking shape.
 
 /// Transforms points into frequency distribution (for using in histograms).
-/// Values outside of [`min`, `max`] interval are ignored, and everything that
-/// falls into the specified interval is grouped into `bins` number of buckets of equal width.
+/// Values outside of [`min`, `max`] interval and values that are not finite are ignored,
+/// and everything that falls into the specified interval is grouped into `bins` number
+/// of buckets of equal width.
 ///
 /// ```
-/// # use textplots::utils::histogram;
+/// # use nu_plugin_plot::color_plot::textplots::utils::histogram;
 /// assert_eq!(vec![(0.0, 1.0), (5.0, 1.0)], histogram( &[ (0.0, 0.0), (9.0, 9.0), (10.0, 10.0) ], 0.0, 10.0, 2 ));
 /// ```
-pub fn histogram(data: &[(f32, f32)], min: f32, max: f32, bins: usize) -> Vec<(f32, f32)> {
+pub fn histogram(data: &[(f64, f64)], min: f64, max: f64, bins: usize) -> Vec<(f64, f64)> {
     let mut output = vec![0; bins];
 
-    let step = (max - min) / bins as f32;
+    let step = (max - min) / bins as f64;
 
     for &(_x, y) in data.iter() {
-        if y < min || y > max {
+        if !y.is_finite() || y < min || y > max {
             continue;
         }
 
@@ -29,6 +30,40 @@ pub fn histogram(data: &[(f32, f32)], min: f32, max: f32, bins: usize) -> Vec<(f
     output
         .into_iter()
         .enumerate()
-        .map(|(x, y)| ((min + (x as f32) * step), y as f32))
+        .map(|(x, y)| ((min + (x as f64) * step), y as f64))
+        .collect()
+}
+
+/// Transforms points into a frequency distribution over the bins between consecutive
+/// `edges`, which must be increasing. Each bin includes its left edge, and the last bin
+/// also includes its right edge. Values outside of the edges and values that are not
+/// finite are ignored. Each bin is returned as its left edge and its count.
+///
+/// ```
+/// # use nu_plugin_plot::color_plot::textplots::utils::histogram_edges;
+/// assert_eq!(vec![(0.0, 2.0), (2.0, 1.0), (5.0, 2.0)], histogram_edges( &[ (0.0, 0.0), (1.0, 1.5), (2.0, 2.0), (3.0, 5.0), (4.0, 10.0), (5.0, 11.0) ], &[0.0, 2.0, 5.0, 10.0] ));
+/// ```
+pub fn histogram_edges(data: &[(f64, f64)], edges: &[f64]) -> Vec<(f64, f64)> {
+    let bins = edges.len().saturating_sub(1);
+    if bins == 0 {
+        return vec![];
+    }
+
+    let mut output = vec![0; bins];
+
+    for &(_x, y) in data.iter() {
+        if !y.is_finite() || y < edges[0] || y > edges[bins] {
+            continue;
+        }
+
+        // the number of edges at or below y, less one, is the bin
+        let bucket_id = edges.partition_point(|e| *e <= y) - 1;
+        output[bucket_id.min(bins - 1)] += 1;
+    }
+
+    output
+        .into_iter()
+        .enumerate()
+        .map(|(x, y)| (edges[x], y as f64))
         .collect()
 }
@@ -0,0 +1,118 @@
+//! The `plot compose` command.
+//!
+//! Lays out charts which have already been drawn as text side by side,
+//! one above the other, or in a grid as wide as the terminal, so that
+//! charts of different data, or drawn by different commands, can be
+//! read together.
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::{Category, LabeledError, PipelineData, Signature, SyntaxShape, Type, Value};
+
+use crate::chart_value::ChartValue;
+use crate::{indent, layout, terminal_size, PluginPlot, Titles};
+
+pub struct CommandCompose;
+
+/// How to lay out the charts.
+#[derive(Clone, Copy, PartialEq)]
+enum Layout {
+    Horizontal,
+    Vertical,
+    Grid,
+}
+
+/// The charts in `input`, a list of charts drawn as text, of the
+/// records `--meta` returns them in, or of chart values.
+fn charts(call: &EvaluatedCall, input: PipelineData) -> Result<Vec<String>, LabeledError> {
+    let not_charts = |span| {
+        LabeledError::new("plot compose needs a list of charts drawn as text.").with_label("Incorrect input type.", span)
+    };
+    match input {
+        PipelineData::ListStream(..) | PipelineData::Value(Value::List { .. }, ..) => input
+            .into_iter()
+            .map(|chart| match &chart {
+                Value::String { val, .. } => Ok(val.clone()),
+                Value::Record { val, .. } => match val.get("chart") {
+                    Some(Value::String { val, .. }) => Ok(val.clone()),
+                    _ => Err(not_charts(chart.span())),
+                },
+                Value::Custom { val, .. } => match val.as_any().downcast_ref::<ChartValue>() {
+                    Some(object) => Ok(object.frame.clone()),
+                    None => Err(not_charts(chart.span())),
+                },
+                _ => Err(not_charts(chart.span())),
+            })
+            .collect(),
+        _ => Err(not_charts(call.head)),
+    }
+}
+
+impl PluginCommand for CommandCompose {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "plot compose"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("plot compose")
+            .description("Lay out a list of charts drawn as text together.")
+            .named(
+                "layout",
+                SyntaxShape::String,
+                "Put the charts side by side (horizontal, the default), one above the other (vertical), or in a grid as wide as the terminal.",
+                None,
+            )
+            .named(
+                "title",
+                SyntaxShape::String,
+                "Provide a title to the plot.",
+                Some('t'),
+            )
+            .named("subtitle", SyntaxShape::String, "A line under the title.", None)
+            .named("caption", SyntaxShape::String, "A line under the chart, such as where its data is from.", None)
+            .named("title-align", SyntaxShape::String, "Line the title, subtitle and caption up across the chart: left (default), center or right.", None)
+            .named("pad", SyntaxShape::Int, "Indent each line of a text chart by this many spaces (default 4), or not at all with 0.", None)
+            .input_output_types(vec![
+                (Type::List(Box::new(Type::String)), Type::String),
+                (Type::table(), Type::String),
+                (Type::List(Box::new(Type::Custom("chart".into()))), Type::String),
+            ])
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Lay out a list of charts drawn as text side by side, one above the other, or in a grid."
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        let layout = match call.get_flag::<String>("layout")?.as_deref() {
+            None | Some("horizontal") => Layout::Horizontal,
+            Some("vertical") => Layout::Vertical,
+            Some("grid") => Layout::Grid,
+            Some(l) => {
+                return Err(LabeledError::new(format!("Unknown layout {}, use horizontal, vertical or grid.", l))
+                    .with_label("Layout error.", call.get_flag_span("layout").unwrap_or(call.head)))
+            }
+        };
+        let charts = charts(call, input)?;
+        if charts.is_empty() {
+            return Err(LabeledError::new("Can't compose a zero element list.")
+                .with_label("No charts in the list.", call.head));
+        }
+
+        let out = match layout {
+            Layout::Horizontal => layout::hstack(&charts),
+            Layout::Vertical => layout::vstack(&charts),
+            Layout::Grid => layout::grid(&charts, terminal_size(engine)?.map_or(80, |(columns, _)| columns as usize)),
+        };
+        let out = Titles::from_call(call)?.around(&out, &indent(call)?);
+        Ok(PipelineData::Value(Value::string(out, call.head), None))
+    }
+}
@@ -0,0 +1,305 @@
+//! The `corrplot` command.
+//!
+//! Draws the correlation of every pair of numeric columns of a table as
+//! a heatmap, a row and a column for each, named down the left and
+//! along the top. Each cell is shaded as densely as the correlation is
+//! strong and colored along a diverging colormap, blue where the two
+//! fall together and red where they rise together.
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::{Category, Example, LabeledError, PipelineData, Signature, SyntaxShape, Type, Value};
+
+use crate::color_plot::colormap::Colormap;
+use crate::color_plot::textplots::label::LabelFormat;
+use crate::color_plot::textplots::{Annotation, Heatmap, Plot, Shape};
+use crate::stats::{pearson, spearman};
+use crate::{finish_chart, new_chart, output_chart, parse_cli_opts, truncate, value_to_f64, with_config, Meta, PluginPlot, MAX_NAME};
+
+pub struct CommandCorrplot;
+
+/// How to correlate two columns.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Method {
+    Pearson,
+    Spearman,
+}
+
+impl Method {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "pearson" => Some(Self::Pearson),
+            "spearman" => Some(Self::Spearman),
+            _ => None,
+        }
+    }
+
+    /// The name of the correlation coefficient, for the legend.
+    fn coefficient(self) -> &'static str {
+        match self {
+            Self::Pearson => "Pearson r",
+            Self::Spearman => "Spearman ρ",
+        }
+    }
+
+    fn correlate(self, x: &[f64], y: &[f64]) -> f64 {
+        match self {
+            Self::Pearson => pearson(x, y),
+            Self::Spearman => spearman(x, y),
+        }
+    }
+}
+
+/// The numeric columns of a table with their names, and the number of
+/// its rows and of the nulls among those columns.
+type Columns = (Vec<Vec<f64>>, Vec<String>, usize, usize);
+
+/// The numeric columns of a table, as lists with nulls as NaN, with
+/// their names, in the order of the first row. A column is numeric if
+/// each of its cells is a number, filesize, duration, bool or null, and
+/// not all null. Returns the number of rows and of nulls too.
+fn numeric_columns(call: &EvaluatedCall, input: PipelineData) -> Result<Columns, LabeledError> {
+    let not_table = |span| LabeledError::new("corrplot needs a table of numeric columns.").with_label("Incorrect input type.", span);
+    let rows: Vec<Value> = match input {
+        PipelineData::ListStream(..) | PipelineData::Value(Value::List { .. }, ..) => input.into_iter().collect(),
+        _ => return Err(not_table(call.head)),
+    };
+    let Some(first) = rows.first() else {
+        return Err(LabeledError::new("Can't correlate the columns of a zero row table.").with_label("No rows in the table.", call.head));
+    };
+    let names: Vec<String> = first.as_record().map_err(|_| not_table(first.span()))?.columns().cloned().collect();
+
+    let (mut columns, mut numeric, mut nulls) = (vec![], vec![], 0);
+    for name in names {
+        let cells = rows
+            .iter()
+            .map(|row| Ok(row.as_record().map_err(|_| not_table(row.span()))?.get(&name)))
+            .collect::<Result<Vec<Option<&Value>>, LabeledError>>()?;
+        let values: Option<Vec<f64>> = cells
+            .iter()
+            .map(|cell| match cell {
+                None | Some(Value::Nothing { .. }) => Some(f64::NAN),
+                Some(v) => value_to_f64(v, call).ok(),
+            })
+            .collect();
+        if let Some(values) = values.filter(|values| values.iter().any(|v| !v.is_nan())) {
+            nulls += values.iter().filter(|v| v.is_nan()).count();
+            columns.push(values);
+            numeric.push(name);
+        }
+    }
+    Ok((columns, numeric, rows.len(), nulls))
+}
+
+impl PluginCommand for CommandCorrplot {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "corrplot"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("corrplot")
+            .description("Render an ASCII heatmap of the correlations between the numeric columns of a table.")
+            .named(
+                "method",
+                SyntaxShape::String,
+                "The correlation to find: pearson (default), of a straight line, or spearman, of the ranks.",
+                Some('m'),
+            )
+            .switch("labels", "Write each correlation in its cell, where there is room.", None)
+            .named(
+                "width",
+                SyntaxShape::OneOf(vec![SyntaxShape::Number, SyntaxShape::String]),
+                "The maximum width of the plot, in dots, or as a percentage of the terminal such as 80%.",
+                None,
+            )
+            .named(
+                "height",
+                SyntaxShape::OneOf(vec![SyntaxShape::Number, SyntaxShape::String]),
+                "The maximum height of the plot, in dots, or as a percentage of the terminal such as 80%.",
+                None,
+            )
+            .named(
+                "max-lines",
+                SyntaxShape::Int,
+                "The most lines of output, with the title, labels and legend, shrinking the chart to fit.",
+                None,
+            )
+            .named(
+                "title",
+                SyntaxShape::String,
+                "Provide a title to the plot.",
+                Some('t'),
+            )
+            .named("subtitle", SyntaxShape::String, "A line under the title.", None)
+            .named("caption", SyntaxShape::String, "A line under the chart, such as where its data is from.", None)
+            .named("title-align", SyntaxShape::String, "Line the title, subtitle and caption up across the chart: left (default), center or right.", None)
+            .named("pad", SyntaxShape::Int, "Indent each line of a text chart by this many spaces (default 4), or not at all with 0.", None)
+            .switch("legend", "Plot a tiny, maybe useful legend.", Some('l'))
+            .named(
+                "legend-pos",
+                SyntaxShape::String,
+                "Where to put the legend: top, bottom (default), right or inside.",
+                None,
+            )
+            .named(
+                "format",
+                SyntaxShape::String,
+                "Draw the chart as text (default), svg, png, or vega for a Vega-Lite spec.",
+                None,
+            )
+            .named(
+                "charset",
+                SyntaxShape::String,
+                "The characters to draw a text chart with: braille (default), ascii or blocks.",
+                None,
+            )
+            .named(
+                "gradient",
+                SyntaxShape::String,
+                "The colormap to color the cells along, from -1 to 1: blue-red (default), green-red or viridis.",
+                None,
+            )
+            .named(
+                "color",
+                SyntaxShape::String,
+                "Color a text chart always, never, or auto (default) when NO_COLOR and CLICOLOR=0 are unset and the output is a terminal.",
+                None,
+            )
+            .switch("raw", "Return plain text without color or any other escape codes, whatever --color says.", None)
+            .switch("print", "Print the chart as soon as it is drawn rather than returning it, so each chart of a loop or script is shown.", None)
+            .switch("object", "Return the chart as a chart value, shown as the chart, with its data, ranges and facts as cells such as ymax.", None)
+            .named(
+                "output",
+                SyntaxShape::Filepath,
+                "Save the chart to this file rather than returning it, as SVG, PNG or a Vega-Lite spec if it ends in .svg, .png or .json.",
+                Some('o'),
+            )
+            .switch("meta", "Return a record of the chart and facts about the input.", None)
+            .input_output_type(Type::table(), Type::Any)
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render an ASCII heatmap of the Pearson or Spearman correlation of every pair of numeric columns of a table, named down the left and along the top, each cell shaded as densely as the correlation is strong and colored from blue for -1 to red for 1. Pairs with a null are left out of each correlation."
+    }
+
+    fn search_terms(&self) -> Vec<&str> {
+        vec!["correlation", "heatmap", "matrix", "pearson", "spearman"]
+    }
+
+    fn examples(&self) -> Vec<Example<'_>> {
+        vec![
+            Example {
+                example: "open cars.csv | corrplot --labels -l",
+                description: "Correlate each numeric column of a table with every other, writing each correlation in its cell.",
+                result: None,
+            },
+            Example {
+                example: "open cars.csv | corrplot --method spearman --gradient viridis",
+                description: "Correlate the ranks of the columns, colored along another colormap.",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        let call = &with_config(engine, call)?;
+        let opts = parse_cli_opts(call)?;
+        let method = match call.get_flag::<String>("method")? {
+            None => Method::Pearson,
+            Some(m) => Method::from_name(&m).ok_or_else(|| {
+                LabeledError::new(format!("Unknown method {}, use pearson or spearman.", m)).with_label("Method error.", call.get_flag_span("method").unwrap_or(call.head))
+            })?,
+        };
+        let (columns, names, rows, nulls) = numeric_columns(call, input)?;
+        if rows < 2 {
+            return Err(LabeledError::new("A correlation plot needs a table with at least two rows.").with_label("Too few rows.", call.head));
+        }
+        let k = names.len();
+        if k < 2 {
+            return Err(LabeledError::new("A correlation plot needs a table with at least two numeric columns.").with_label("Too few numeric columns.", call.head));
+        }
+
+        // laid out in characters: the names down the left, and a row of
+        // them along the top, then a cell of some columns and rows of
+        // characters for each pair, about twice as wide as high so they
+        // look square
+        let gutter = names.iter().map(|n| n.chars().count()).max().unwrap_or(0).min(MAX_NAME) + 1;
+        let max_x = opts.width_op.unwrap_or(200) as usize;
+        let max_y = opts.height_op.unwrap_or(50) as usize;
+        let cell_rows = (max_y / 4).saturating_sub(1).checked_div(k).unwrap_or(0).max(1);
+        let widest = (2 * cell_rows).max(gutter).max(6);
+        let cell_cols = (max_x / 2).saturating_sub(gutter).checked_div(k).unwrap_or(0).clamp(1, widest);
+        let width = (2 * (gutter + k * cell_cols)).max(32);
+        let height = (4 * (1 + k * cell_rows)).max(32);
+
+        // a unit is a point, from the left and from the bottom, with any
+        // room below the cells left empty; they count from 1, so 0 is off
+        // the chart and no axis is drawn across it
+        let (x, y) = (|col: usize| (2 * col + 1) as f64, |row: usize| (height - 4 * row + 1) as f64);
+        let mut x_edges = vec![x(0)];
+        x_edges.extend((0..=k).map(|j| x(gutter + j * cell_cols)));
+        let mut y_edges: Vec<f64> = (0..=k).rev().map(|i| y(1 + i * cell_rows)).collect();
+        if y_edges[0] > y(height / 4) {
+            y_edges.insert(0, y(height / 4));
+        }
+        y_edges.push(y(0));
+
+        let correlations: Vec<Vec<f64>> = columns.iter().map(|a| columns.iter().map(|b| method.correlate(a, b)).collect()).collect();
+        // the rows from the bottom, the last column first, beside an
+        // empty cell of the gutter, and empty rows below and above
+        let below = y_edges.len() - 2 - k;
+        let mut values = vec![f64::NAN; (k + 1) * below];
+        for row in correlations.iter().rev() {
+            values.push(f64::NAN);
+            values.extend(row);
+        }
+        values.extend(vec![f64::NAN; k + 1]);
+
+        let mut annotations = vec![];
+        for (i, name) in names.iter().enumerate() {
+            let middle = 1 + i * cell_rows + (cell_rows - 1) / 2;
+            annotations.push(Annotation::Text(x(0), y(middle), truncate(name, gutter - 1)));
+            annotations.push(Annotation::Text(x(gutter + i * cell_cols), y(0), truncate(name, cell_cols.saturating_sub(1).max(1))));
+            if opts.labels {
+                for (j, r) in correlations[i].iter().enumerate().filter(|(_, r)| r.is_finite()) {
+                    let text = format!("{:.2}", r);
+                    let len = text.chars().count();
+                    if len < cell_cols {
+                        annotations.push(Annotation::Text(x(gutter + j * cell_cols + (cell_cols - len) / 2), y(middle), text));
+                    }
+                }
+            }
+        }
+
+        let colormap = opts.palette.gradient.unwrap_or(opts.colormap(Colormap::BLUE_RED));
+        let mut entries = vec![];
+        if opts.legend {
+            let mut entry = vec![(format!("{}: -1 ", method.coefficient()), None)];
+            entry.extend(colormap.swatches().iter().map(|c| ("-".to_string(), Some(*c))));
+            entry.push((" 1".to_string(), None));
+            entries.push(entry);
+        }
+
+        let mut chart = new_chart(opts.format, opts.charset, (LabelFormat::Hidden, LabelFormat::Hidden), width as u32, height as u32, x(0), x(width / 2));
+        chart.gradient(colormap);
+        chart.lineplot(Shape::Heatmap(Heatmap { x: x_edges, y: y_edges, values, range: (-1.0, 1.0), midpoint: Some(0.0) }));
+
+        let meta = Meta {
+            series: k,
+            points: rows * k,
+            nulls,
+            ..Meta::default()
+        };
+        let names = vec![method.coefficient().to_string()];
+        let chart = finish_chart(&mut chart, opts.format, &names, &annotations, &opts.titles, &entries, opts.legend_pos, &opts.pad, opts.object, call.head);
+        output_chart(engine, call, chart, meta)
+    }
+}
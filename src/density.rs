@@ -0,0 +1,206 @@
+//! The `density` command.
+//!
+//! Draws a kernel density estimate, a smoothed histogram, of a numeric
+//! list or nested list. The curves are ranged and rendered through the
+//! same path as `hist`.
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::{Category, LabeledError, PipelineData, Signature, SyntaxShape, Value};
+
+use crate::kde::{kde, silverman, Kernel};
+use crate::{common_chart_flags, min_max, no_finite_values, numeric_input_types, parse_cli_opts, pooled, render_series, Meta, Plotter, PluginPlot};
+
+pub struct CommandDensity;
+
+/// The kernel given with `--kernel`, Gaussian if none was.
+pub(crate) fn kernel(call: &EvaluatedCall) -> Result<Kernel, LabeledError> {
+    match call.get_flag::<String>("kernel")? {
+        None => Ok(Kernel::Gaussian),
+        Some(k) => Kernel::from_name(&k).ok_or_else(|| {
+            LabeledError::new(format!("Unknown kernel {}, use gaussian, epanechnikov, triangular or uniform.", k))
+                .with_label("Kernel error.", call.head)
+        }),
+    }
+}
+
+/// The bandwidth given with `--bandwidth`, or by Silverman's rule of
+/// thumb for `pooled`, the values of every series together.
+pub(crate) fn bandwidth(call: &EvaluatedCall, pooled: &[f64]) -> Result<f64, LabeledError> {
+    match call.get_flag::<f64>("bandwidth")? {
+        None => Ok(silverman(pooled)),
+        Some(bw) if bw.is_finite() && bw > 0.0 => Ok(bw),
+        Some(_) => Err(LabeledError::new("The bandwidth must be a positive number.")
+            .with_label("Bandwidth error.", call.head)),
+    }
+}
+
+impl Plotter for CommandDensity {
+    fn plot(
+        &self,
+        call: &EvaluatedCall,
+        input: Vec<f64>,
+        meta: &mut Meta,
+    ) -> Result<Value, LabeledError> {
+        self.plot_nested(call, vec![input], meta)
+    }
+
+    fn plot_nested(
+        &self,
+        call: &EvaluatedCall,
+        input: Vec<Vec<f64>>,
+        meta: &mut Meta,
+    ) -> Result<Value, LabeledError> {
+        let opts = parse_cli_opts(call)?;
+
+        if input.len() > 5 {
+            return Err(LabeledError::new("Nested list can't contain more than 5 inner lists.").with_label("Nested list error.", call.head));
+        }
+
+        let kernel = kernel(call)?;
+
+        // all series share the same bandwidth, chosen from the pooled values
+        let pooled = pooled(&input);
+        let bandwidth = bandwidth(call, &pooled)?;
+        meta.bandwidth = Some(bandwidth);
+        meta.kernel = Some(kernel);
+
+        // leave room for the tails of the curve
+        let (min, max) = min_max(&pooled).ok_or_else(|| no_finite_values(call))?;
+        let range = (min - kernel.reach() * bandwidth, max + kernel.reach() * bandwidth);
+        let points = opts.width_op.unwrap_or(200) as usize;
+
+        let curves: Vec<Vec<(f64, f64)>> = input
+            .iter()
+            .map(|list| kde(list, bandwidth, kernel, range, points))
+            .collect();
+
+        let legend = vec![vec![(format!("Bandwidth: {:.3} ({})", bandwidth, kernel.name()), None)]];
+        render_series(call, &opts, range, curves, &legend, meta.units_from(0), false)
+    }
+}
+
+impl PluginCommand for CommandDensity {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "density"
+    }
+
+    fn signature(&self) -> Signature {
+        common_chart_flags(Signature::build("density").description("Render an ASCII kernel density estimate from a list of values."))
+            .switch("square", "Draw the chart as tall as it is wide, as many dots across as up.", None)
+            .named(
+                "bandwidth",
+                SyntaxShape::Number,
+                "The width of the kernel, default is Silverman's rule of thumb.",
+                None,
+            )
+            .named(
+                "kernel",
+                SyntaxShape::String,
+                "The kernel: gaussian (default), epanechnikov, triangular or uniform.",
+                Some('k'),
+            )
+            .named(
+                "names",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "Names for the series in the legend, one for each.",
+                None,
+            )
+            .named(
+                "markers",
+                SyntaxShape::OneOf(vec![SyntaxShape::List(Box::new(SyntaxShape::String)), SyntaxShape::String]),
+                "Mark each series with its own glyph, so they can be told apart without color: default or ascii, or a list of glyphs such as [o x + *].",
+                None,
+            )
+            .named(
+                "styles",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "Line styles for the series in turn, such as [solid dashed dotted], so lines can be told apart without color.",
+                None,
+            )
+            .named(
+                "gradient",
+                SyntaxShape::String,
+                "Color a single series by its values along a colormap: green-red, blue-red or viridis.",
+                None,
+            )
+            .named(
+                "label-format",
+                SyntaxShape::String,
+                "How to write the numbers of the axes and labels: %.3f, %.2e, scientific, si, percent, thousands, bytes, duration or date, all but date with :N decimal places.",
+                None,
+            )
+            .switch("bars", "Change lines to bars.", Some('b'))
+            .switch("steps", "Change lines to steps.", Some('s'))
+            .named(
+                "step-mode",
+                SyntaxShape::String,
+                "Where a step plot steps: pre (default), at each point to the next value; post, at the next point, holding each value until then as a counter does; or mid, halfway between.",
+                None,
+            )
+            .switch("points", "Change lines to points.", Some('p'))
+            .switch("skip-nulls", "Leave out null values.", None)
+            .named(
+                "fill-nulls",
+                SyntaxShape::String,
+                "Replace null values using linear, previous or zero.",
+                None,
+            )
+            .switch("grid", "Draw dim grid lines across and down the chart, behind the series.", None)
+            .named("bg", SyntaxShape::String, "The color behind a text chart, such as black, or bright-white on a dark terminal.", None)
+            .named(
+                "hline",
+                SyntaxShape::Any,
+                "Draw horizontal reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .named(
+                "vline",
+                SyntaxShape::Any,
+                "Draw vertical reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .named(
+                "shade-above",
+                SyntaxShape::Number,
+                "Shade the region above this y value.",
+                None,
+            )
+            .named(
+                "shade-below",
+                SyntaxShape::Number,
+                "Shade the region below this y value.",
+                None,
+            )
+            .named(
+                "shade-x",
+                SyntaxShape::List(Box::new(SyntaxShape::Number)),
+                "Shade the region between two x values, given as [from to].",
+                None,
+            )
+            .named(
+                "annotate",
+                SyntaxShape::String,
+                "Label points of each series with their value: any of min, max and last, separated by commas.",
+                None,
+            )
+            .switch("meta", "Return a record of the chart and facts about the input.", None)
+            .input_output_types(numeric_input_types())
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render an ASCII kernel density estimate from a list of values."
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        self.plot_input(engine, call, input)
+    }
+}
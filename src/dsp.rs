@@ -0,0 +1,155 @@
+//! Signal processing: the frequency spectrum of evenly sampled values.
+//!
+//! The values are weighted by a window, which tapers them to zero at
+//! either end so a frequency which doesn't fit a whole number of times
+//! leaks less into its neighbours, and zero padded to a power of two
+//! for a fast Fourier transform.
+
+use std::f64::consts::PI;
+use std::iter;
+
+/// The weights the values are multiplied by before the transform.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Window {
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+}
+
+impl Window {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rectangular" | "none" => Some(Self::Rectangular),
+            "hann" => Some(Self::Hann),
+            "hamming" => Some(Self::Hamming),
+            "blackman" => Some(Self::Blackman),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Rectangular => "rectangular",
+            Self::Hann => "hann",
+            Self::Hamming => "hamming",
+            Self::Blackman => "blackman",
+        }
+    }
+
+    /// The weight of the `i`th of `n` values.
+    pub fn weight(self, i: usize, n: usize) -> f64 {
+        if n < 2 {
+            return 1.0;
+        }
+        let t = 2.0 * PI * i as f64 / (n - 1) as f64;
+        match self {
+            Self::Rectangular => 1.0,
+            Self::Hann => 0.5 - 0.5 * t.cos(),
+            Self::Hamming => 0.54 - 0.46 * t.cos(),
+            Self::Blackman => 0.42 - 0.5 * t.cos() + 0.08 * (2.0 * t).cos(),
+        }
+    }
+}
+
+/// The discrete Fourier transform of the complex values with real parts
+/// `re` and imaginary parts `im`, in place, by the iterative radix-2
+/// Cooley-Tukey algorithm. Their length must be a power of two.
+///
+/// ```
+/// # use nu_plugin_plot::dsp::fft;
+/// let (mut re, mut im) = (vec![1.0, 0.0, 0.0, 0.0], vec![0.0; 4]);
+/// fft(&mut re, &mut im);
+/// assert_eq!((re, im), (vec![1.0; 4], vec![0.0; 4]));
+/// ```
+pub fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    // put each value at the index with its bits reversed
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    // then combine transforms of twice the length each time
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f64).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let (tr, ti) = (re[b] * cos - im[b] * sin, re[b] * sin + im[b] * cos);
+                (re[b], im[b]) = (re[a] - tr, im[a] - ti);
+                (re[a], im[a]) = (re[a] + tr, im[a] + ti);
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// The one-sided amplitude spectrum of `data`, sampled `rate` times a
+/// unit of time, weighted by `window`: points of a frequency, from 0 to
+/// half the rate, and the amplitude of the sine wave at it. So a sine
+/// wave of amplitude 2 peaks at 2, and a constant is its value at 0.
+///
+/// The data is zero padded to a power of two, which spaces the
+/// frequencies `rate` over that apart.
+///
+/// ```
+/// # use nu_plugin_plot::dsp::{spectrum, Window};
+/// let wave: Vec<f64> = (0..64).map(|i| 2.0 * (i as f64 * std::f64::consts::PI / 4.0).sin()).collect();
+/// let points = spectrum(&wave, Window::Rectangular, 1.0);
+/// assert_eq!(points.len(), 33);
+/// assert_eq!(points[8].0, 0.125);
+/// assert!((points[8].1 - 2.0).abs() < 1e-9);
+/// ```
+pub fn spectrum(data: &[f64], window: Window, rate: f64) -> Vec<(f64, f64)> {
+    let n = data.len();
+    let size = n.next_power_of_two();
+    let weights: Vec<f64> = (0..n).map(|i| window.weight(i, n)).collect();
+    let gain: f64 = weights.iter().sum();
+
+    let mut re: Vec<f64> = data.iter().zip(&weights).map(|(v, w)| v * w).chain(iter::repeat(0.0)).take(size).collect();
+    let mut im = vec![0.0; size];
+    fft(&mut re, &mut im);
+    (0..=size / 2)
+        .map(|k| {
+            let amplitude = re[k].hypot(im[k]) / gain;
+            // every frequency but 0 and half the rate has a mirror image
+            // above half the rate, with the other half of its amplitude
+            let amplitude = if k == 0 || k == size / 2 { amplitude } else { 2.0 * amplitude };
+            (k as f64 * rate / size as f64, amplitude)
+        })
+        .collect()
+}
+
+/// The amplitudes of `spectrum` in decibels, 20 log10 of each, in
+/// place. Amplitudes more than 120 dB below the peak, zero among them,
+/// are raised to that, so they stay on the chart. If every one is zero,
+/// there is no peak, and they are all -120 dB.
+///
+/// ```
+/// # use nu_plugin_plot::dsp::decibels;
+/// let mut points = vec![(0.0, 10.0), (1.0, 0.1), (2.0, 0.0)];
+/// decibels(&mut points);
+/// assert_eq!(points, vec![(0.0, 20.0), (1.0, -20.0), (2.0, -100.0)]);
+///
+/// let mut silence = vec![(0.0, 0.0), (1.0, 0.0)];
+/// decibels(&mut silence);
+/// assert_eq!(silence, vec![(0.0, -120.0), (1.0, -120.0)]);
+/// ```
+pub fn decibels(spectrum: &mut [(f64, f64)]) {
+    let peak = spectrum.iter().map(|(_, a)| *a).fold(0.0, f64::max);
+    let floor = if peak > 0.0 { peak * 1e-6 } else { 1e-6 };
+    for (_, amplitude) in spectrum.iter_mut() {
+        *amplitude = 20.0 * amplitude.max(floor).log10();
+    }
+}
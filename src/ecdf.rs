@@ -0,0 +1,150 @@
+//! The `ecdf` command.
+//!
+//! Draws the empirical cumulative distribution function of a numeric
+//! list, or of each list in a nested list, as steps rising from 0 to 1.
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::{Category, LabeledError, PipelineData, Signature, SyntaxShape, Value};
+
+use crate::cdf::ecdf;
+use crate::{common_chart_flags, extent, no_finite_values, numeric_input_types, parse_cli_opts, render_series, Meta, Plotter, PluginPlot};
+
+pub struct CommandEcdf;
+
+impl Plotter for CommandEcdf {
+    fn plot(
+        &self,
+        call: &EvaluatedCall,
+        input: Vec<f64>,
+        meta: &mut Meta,
+    ) -> Result<Value, LabeledError> {
+        self.plot_nested(call, vec![input], meta)
+    }
+
+    fn plot_nested(
+        &self,
+        call: &EvaluatedCall,
+        input: Vec<Vec<f64>>,
+        meta: &mut Meta,
+    ) -> Result<Value, LabeledError> {
+        let mut opts = parse_cli_opts(call)?;
+        opts.steps = true;
+
+        if input.len() > 5 {
+            return Err(LabeledError::new("Nested list can't contain more than 5 inner lists.").with_label("Nested list error.", call.head));
+        }
+
+        let (min, max) = extent(input.iter().flatten().copied()).ok_or_else(|| no_finite_values(call))?;
+        // a single distinct value still needs a non-empty x range
+        let range = if max > min { (min, max) } else { (min - 0.5, max + 0.5) };
+
+        let steps: Vec<Vec<(f64, f64)>> = input.iter().map(|list| ecdf(list)).collect();
+
+        render_series(call, &opts, range, steps, &[], meta.units_from(0), false)
+    }
+}
+
+impl PluginCommand for CommandEcdf {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "ecdf"
+    }
+
+    fn signature(&self) -> Signature {
+        common_chart_flags(Signature::build("ecdf").description("Render an ASCII empirical cumulative distribution from a list of values."))
+            .switch("square", "Draw the chart as tall as it is wide, as many dots across as up.", None)
+            .named(
+                "names",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "Names for the series in the legend, one for each.",
+                None,
+            )
+            .named(
+                "markers",
+                SyntaxShape::OneOf(vec![SyntaxShape::List(Box::new(SyntaxShape::String)), SyntaxShape::String]),
+                "Mark each series with its own glyph, so they can be told apart without color: default or ascii, or a list of glyphs such as [o x + *].",
+                None,
+            )
+            .named(
+                "styles",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "Line styles for the series in turn, such as [solid dashed dotted], so lines can be told apart without color.",
+                None,
+            )
+            .named(
+                "gradient",
+                SyntaxShape::String,
+                "Color a single series by its values along a colormap: green-red, blue-red or viridis.",
+                None,
+            )
+            .named(
+                "label-format",
+                SyntaxShape::String,
+                "How to write the numbers of the axes and labels: %.3f, %.2e, scientific, si, percent, thousands, bytes, duration or date, all but date with :N decimal places.",
+                None,
+            )
+            .switch("skip-nulls", "Leave out null values.", None)
+            .named(
+                "fill-nulls",
+                SyntaxShape::String,
+                "Replace null values using linear, previous or zero.",
+                None,
+            )
+            .switch("grid", "Draw dim grid lines across and down the chart, behind the series.", None)
+            .named("bg", SyntaxShape::String, "The color behind a text chart, such as black, or bright-white on a dark terminal.", None)
+            .named(
+                "hline",
+                SyntaxShape::Any,
+                "Draw horizontal reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .named(
+                "vline",
+                SyntaxShape::Any,
+                "Draw vertical reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .named(
+                "shade-above",
+                SyntaxShape::Number,
+                "Shade the region above this y value.",
+                None,
+            )
+            .named(
+                "shade-below",
+                SyntaxShape::Number,
+                "Shade the region below this y value.",
+                None,
+            )
+            .named(
+                "shade-x",
+                SyntaxShape::List(Box::new(SyntaxShape::Number)),
+                "Shade the region between two x values, given as [from to].",
+                None,
+            )
+            .named(
+                "annotate",
+                SyntaxShape::String,
+                "Label points of each series with their value: any of min, max and last, separated by commas.",
+                None,
+            )
+            .switch("meta", "Return a record of the chart and facts about the input.", None)
+            .input_output_types(numeric_input_types())
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render an ASCII empirical cumulative distribution from a list of values."
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        self.plot_input(engine, call, input)
+    }
+}
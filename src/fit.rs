@@ -0,0 +1,233 @@
+//! Fitting trend lines to `(x, y)` data.
+//!
+//! Polynomials, including straight lines, are fitted by least squares.
+//! LOESS fits a weighted straight line around every point it is asked
+//! about, so it follows the data without assuming a shape.
+
+/// Highest polynomial degree we will fit; beyond this the fit is
+/// dominated by rounding error.
+pub const MAX_DEGREE: usize = 10;
+
+/// Fraction of the data used for each local fit in LOESS.
+pub const LOESS_SPAN: f64 = 0.75;
+
+/// A kind of trend line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trend {
+    /// A straight line.
+    Linear,
+    /// A polynomial of the given degree.
+    Poly(usize),
+    /// Locally weighted linear regression.
+    Loess,
+}
+
+/// A fitted trend line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fit {
+    /// Points along the fitted curve.
+    pub curve: Vec<(f64, f64)>,
+    /// Slope and intercept, for a straight line.
+    pub line: Option<(f64, f64)>,
+    /// Coefficient of determination, for a polynomial.
+    pub r_squared: Option<f64>,
+}
+
+impl Trend {
+    /// The trend with the given name: `linear`, `poly:N` or `loess`.
+    ///
+    /// ```
+    /// # use nu_plugin_plot::fit::Trend;
+    /// assert_eq!(Trend::from_name("poly:3"), Some(Trend::Poly(3)));
+    /// assert_eq!(Trend::from_name("poly:0"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "linear" => Some(Trend::Linear),
+            "loess" => Some(Trend::Loess),
+            _ => {
+                let degree: usize = name.strip_prefix("poly:")?.parse().ok()?;
+                (1..=MAX_DEGREE).contains(&degree).then_some(Trend::Poly(degree))
+            }
+        }
+    }
+
+    /// Fit the finite points of `data`, and evaluate the fit at
+    /// `points` evenly spaced x values between `xmin` and `xmax`.
+    ///
+    /// Returns `None` if there are too few distinct x values to fit.
+    ///
+    /// ```
+    /// # use nu_plugin_plot::fit::Trend;
+    /// let data: Vec<(f64, f64)> = (0..10).map(|i| (i as f64, 2.0 * i as f64 + 1.0)).collect();
+    /// let fit = Trend::Linear.fit(&data, (0.0, 9.0), 10).unwrap();
+    /// let (slope, intercept) = fit.line.unwrap();
+    /// assert!((slope - 2.0).abs() < 1e-9 && (intercept - 1.0).abs() < 1e-9);
+    /// assert!((fit.r_squared.unwrap() - 1.0).abs() < 1e-9);
+    /// ```
+    pub fn fit(self, data: &[(f64, f64)], (xmin, xmax): (f64, f64), points: usize) -> Option<Fit> {
+        let finite: Vec<(f64, f64)> = data
+            .iter()
+            .copied()
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .collect();
+        let step = (xmax - xmin) / (points.max(2) - 1) as f64;
+        let grid = (0..points).map(|i| xmin + i as f64 * step);
+
+        match self {
+            Trend::Linear | Trend::Poly(_) => {
+                let degree = if let Trend::Poly(d) = self { d } else { 1 };
+                let poly = Polynomial::fit(&finite, degree)?;
+                let line = (degree == 1).then(|| poly.line());
+                Some(Fit {
+                    curve: grid.map(|x| (x, poly.eval(x))).collect(),
+                    line,
+                    r_squared: Some(r_squared(&finite, |x| poly.eval(x))),
+                })
+            }
+            Trend::Loess => {
+                let distinct = finite.windows(2).any(|w| w[0].0 != w[1].0);
+                if finite.len() < 3 || !distinct {
+                    return None;
+                }
+                Some(Fit {
+                    curve: grid.map(|x| (x, loess(&finite, LOESS_SPAN, x))).collect(),
+                    line: None,
+                    r_squared: None,
+                })
+            }
+        }
+    }
+}
+
+/// A polynomial in `(x - center) / scale`, which keeps the least
+/// squares problem well conditioned for large x.
+struct Polynomial {
+    coefficients: Vec<f64>,
+    center: f64,
+    scale: f64,
+}
+
+impl Polynomial {
+    /// The least squares polynomial of `degree` through `data`.
+    fn fit(data: &[(f64, f64)], degree: usize) -> Option<Self> {
+        let (min, max) = data
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (x, _)| (lo.min(*x), hi.max(*x)));
+        if data.len() <= degree || max <= min {
+            return None;
+        }
+        let center = (min + max) / 2.0;
+        let scale = (max - min) / 2.0;
+
+        // normal equations, A^T A c = A^T y
+        let n = degree + 1;
+        let mut ata = vec![vec![0.0; n]; n];
+        let mut aty = vec![0.0; n];
+        for (x, y) in data {
+            let u = (x - center) / scale;
+            let powers: Vec<f64> = (0..n).map(|i| u.powi(i as i32)).collect();
+            for i in 0..n {
+                aty[i] += powers[i] * y;
+                for j in 0..n {
+                    ata[i][j] += powers[i] * powers[j];
+                }
+            }
+        }
+
+        Some(Polynomial {
+            coefficients: solve(ata, aty)?,
+            center,
+            scale,
+        })
+    }
+
+    fn eval(&self, x: f64) -> f64 {
+        let u = (x - self.center) / self.scale;
+        self.coefficients.iter().rev().fold(0.0, |acc, c| acc * u + c)
+    }
+
+    /// Slope and intercept in terms of x, for a degree 1 polynomial.
+    fn line(&self) -> (f64, f64) {
+        let slope = self.coefficients[1] / self.scale;
+        (slope, self.coefficients[0] - slope * self.center)
+    }
+}
+
+/// Solve `a x = b` by Gaussian elimination with partial pivoting.
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|i, j| a[*i][col].abs().total_cmp(&a[*j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        let pivot_row = a[col].clone();
+        for row in col + 1..n {
+            let factor = a[row][col] / pivot_row[col];
+            for (v, p) in a[row].iter_mut().zip(&pivot_row).skip(col) {
+                *v -= factor * p;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+/// The proportion of the variance in y explained by `predict`.
+fn r_squared(data: &[(f64, f64)], predict: impl Fn(f64) -> f64) -> f64 {
+    let mean = data.iter().map(|(_, y)| y).sum::<f64>() / data.len() as f64;
+    let total: f64 = data.iter().map(|(_, y)| (y - mean).powi(2)).sum();
+    let residual: f64 = data.iter().map(|(x, y)| (y - predict(*x)).powi(2)).sum();
+    if total > 0.0 {
+        1.0 - residual / total
+    } else {
+        1.0
+    }
+}
+
+/// The LOESS estimate at `x0`: a straight line fitted to the nearest
+/// `span` fraction of `data`, weighted by the tricube of distance.
+///
+/// ```
+/// # use nu_plugin_plot::fit::loess;
+/// let data: Vec<(f64, f64)> = (0..20).map(|i| (i as f64, 3.0 * i as f64)).collect();
+/// assert!((loess(&data, 0.5, 7.5) - 22.5).abs() < 1e-9);
+/// ```
+pub fn loess(data: &[(f64, f64)], span: f64, x0: f64) -> f64 {
+    let q = ((span * data.len() as f64).ceil() as usize).clamp(2, data.len());
+    let mut distances: Vec<f64> = data.iter().map(|(x, _)| (x - x0).abs()).collect();
+    let (_, reach, _) = distances.select_nth_unstable_by(q - 1, |a, b| a.total_cmp(b));
+    // a little beyond the qth point, so it keeps some weight
+    let reach = reach.max(f64::EPSILON) * 1.0001;
+
+    let (mut sw, mut sx, mut sy, mut sxx, mut sxy) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for (x, y) in data {
+        let d = (x - x0).abs() / reach;
+        if d >= 1.0 {
+            continue;
+        }
+        let w = (1.0 - d.powi(3)).powi(3);
+        sw += w;
+        sx += w * x;
+        sy += w * y;
+        sxx += w * x * x;
+        sxy += w * x * y;
+    }
+
+    let (mx, my) = (sx / sw, sy / sw);
+    let var = sxx / sw - mx * mx;
+    if var.abs() < 1e-12 {
+        return my;
+    }
+    let slope = (sxy / sw - mx * my) / var;
+    my + slope * (x0 - mx)
+}
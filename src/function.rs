@@ -0,0 +1,194 @@
+//! The `plot fn` command.
+//!
+//! Draws a function of x, given as a closure, across an x range. The
+//! closure is evaluated by nushell at each column of the chart as it's
+//! drawn, so the curve is as smooth as the chart is wide.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::engine::Closure;
+use nu_protocol::{Category, LabeledError, PipelineData, Signature, Spanned, SyntaxShape, Type, Value};
+
+use crate::color_plot::textplots::Shape;
+use crate::spec::range;
+use crate::{common_chart_flags, finish_chart, label_formats, new_chart, output_chart, parse_cli_opts, series_names, value_to_f64, with_config, Meta, PluginPlot};
+
+/// The x range of a function not given one.
+const X_RANGE: (f64, f64) = (-10.0, 10.0);
+
+pub struct CommandFunction;
+
+/// How a function has gone: whether any x had a value, and the first
+/// error, from nushell or of a result that isn't a number.
+#[derive(Default)]
+struct Evaluated {
+    any: bool,
+    error: Option<LabeledError>,
+}
+
+/// The closure `closure` as a function of x, which nushell evaluates with
+/// x as its parameter and its input. A null or an error is a gap in the
+/// curve, as at a pole of `1 / $x`, and `evaluated` is kept up to date.
+fn function(
+    engine: &nu_plugin::EngineInterface,
+    call: &EvaluatedCall,
+    closure: Spanned<Closure>,
+    evaluated: Rc<RefCell<Evaluated>>,
+) -> Box<dyn Fn(f64) -> f64> {
+    let (engine, call) = (engine.clone(), call.clone());
+    Box::new(move |x| {
+        let x = Value::float(x, call.head);
+        let y = engine
+            .eval_closure(&closure, vec![x.clone()], Some(x))
+            .map_err(LabeledError::from)
+            .and_then(|y| match y {
+                Value::Nothing { .. } => Ok(f64::NAN),
+                y => value_to_f64(&y, &call),
+            });
+        let mut evaluated = evaluated.borrow_mut();
+        match y {
+            Ok(y) => {
+                evaluated.any |= y.is_finite();
+                y
+            }
+            Err(e) => {
+                evaluated.error.get_or_insert(e);
+                f64::NAN
+            }
+        }
+    })
+}
+
+impl PluginCommand for CommandFunction {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "plot fn"
+    }
+
+    fn signature(&self) -> Signature {
+        common_chart_flags(Signature::build("plot fn").description("Render an ASCII plot of a function of x, given as a closure."))
+            .required(
+                "closure",
+                SyntaxShape::Closure(Some(vec![SyntaxShape::Number])),
+                "The function to plot, taking x and returning y, such as {|x| $x * $x }.",
+            )
+            .named(
+                "x-range",
+                SyntaxShape::List(Box::new(SyntaxShape::Number)),
+                "The range of x to plot the function over, as [from to], by default [-10 10].",
+                None,
+            )
+            .named(
+                "y-range",
+                SyntaxShape::List(Box::new(SyntaxShape::Number)),
+                "Fix the range of y, as [from to], rather than fitting it to the function.",
+                None,
+            )
+            .switch("square", "Draw the chart as tall as it is wide, as many dots across as up.", None)
+            .named(
+                "names",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "A name for the function in the legend.",
+                None,
+            )
+            .named(
+                "gradient",
+                SyntaxShape::String,
+                "Color the function by its values along a colormap: green-red, blue-red or viridis.",
+                None,
+            )
+            .named(
+                "label-format",
+                SyntaxShape::String,
+                "How to write the numbers of the axes and labels: %.3f, %.2e, scientific, si, percent, thousands, bytes, duration or date, all but date with :N decimal places.",
+                None,
+            )
+            .switch("grid", "Draw dim grid lines across and down the chart, behind the series.", None)
+            .named("bg", SyntaxShape::String, "The color behind a text chart, such as black, or bright-white on a dark terminal.", None)
+            .named(
+                "hline",
+                SyntaxShape::Any,
+                "Draw horizontal reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .named(
+                "vline",
+                SyntaxShape::Any,
+                "Draw vertical reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .named(
+                "shade-above",
+                SyntaxShape::Number,
+                "Shade the region above this y value.",
+                None,
+            )
+            .named(
+                "shade-below",
+                SyntaxShape::Number,
+                "Shade the region below this y value.",
+                None,
+            )
+            .named(
+                "shade-x",
+                SyntaxShape::List(Box::new(SyntaxShape::Number)),
+                "Shade the region between two x values, given as [from to].",
+                None,
+            )
+            .input_output_type(Type::Nothing, Type::Any)
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render an ASCII plot of a function of x, given as a closure which nushell evaluates across the x range."
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        _input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        let call = &with_config(engine, call)?;
+        let opts = parse_cli_opts(call)?;
+        let closure: Spanned<Closure> = call.req(0)?;
+        let closure_span = closure.span;
+
+        let (xmin, xmax) = match call.get_flag_value("x-range") {
+            Some(value) => range(call, &value)?,
+            None => X_RANGE,
+        };
+        let max_x = opts.width_op.unwrap_or(200);
+        let max_y = opts.height_op.unwrap_or(50);
+
+        let labels = label_formats(opts.label_format, Default::default());
+        let names = series_names(call, opts.names.as_deref(), 1)?;
+        let mut legend = vec![opts.palette.entry(&names[0], 0, 1, None, labels.1)];
+        if !opts.legend {
+            legend.clear();
+        }
+
+        let evaluated = Rc::new(RefCell::new(Evaluated::default()));
+        let mut chart = new_chart(opts.format, opts.charset, labels, max_x, max_y, xmin, xmax);
+        if let Some(value) = call.get_flag_value("y-range") {
+            let (ymin, ymax) = range(call, &value)?;
+            chart.fix_y_range(ymin, ymax);
+        }
+        opts.palette.plot_nth(&mut chart, 0, 1, Shape::Continuous(function(engine, call, closure, evaluated.clone())));
+        let chart = finish_chart(&mut chart, opts.format, &names, &opts.annotations, &opts.titles, &legend, opts.legend_pos, &opts.pad, opts.object, call.head);
+        // a function that fails everywhere is more likely a mistake than a
+        // curve with gaps, and one with no value anywhere draws nothing
+        let evaluated = evaluated.take();
+        if !evaluated.any {
+            return Err(evaluated.error.unwrap_or_else(|| {
+                LabeledError::new("The function has no finite values in the x range.").with_label("No values.", closure_span)
+            }));
+        }
+        let meta = Meta { series: 1, ..Meta::default() };
+        output_chart(engine, call, chart, meta)
+    }
+}
@@ -0,0 +1,234 @@
+//! The `gantt` command.
+//!
+//! Draws a table of tasks, each with a start and an end, as a Gantt
+//! chart: a bar for each task from its start to its end, one under the
+//! other in the order of the table, on a shared time axis, with the
+//! names of the tasks in a gutter to the left. The starts and ends may
+//! be dates, durations from some beginning, or plain numbers.
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::{Category, Example, LabeledError, PipelineData, Signature, SyntaxShape, Type, Value};
+
+use crate::color_plot::textplots::label::LabelFormat;
+use crate::color_plot::textplots::{Annotation, Heatmap, Shape};
+use crate::{finish_chart, label_formats, new_chart, output_chart, parse_cli_opts, truncate, value_to_f64, with_config, Meta, PluginPlot, Unit, MAX_NAME};
+
+pub struct CommandGantt;
+
+/// A bar of the chart: the name of a task, and its start and end.
+struct Task {
+    name: String,
+    start: f64,
+    end: f64,
+}
+
+/// The tasks of a table of `{task, start, end}` records, in its order,
+/// with the unit of their starts and ends.
+fn tasks(call: &EvaluatedCall, input: PipelineData) -> Result<(Vec<Task>, Unit), LabeledError> {
+    let not_table = |span| LabeledError::new("gantt needs a table of {task, start, end} records.").with_label("Incorrect input type.", span);
+    let rows: Vec<Value> = match input {
+        PipelineData::ListStream(..) | PipelineData::Value(Value::List { .. }, ..) => input.into_iter().collect(),
+        _ => return Err(not_table(call.head)),
+    };
+    if rows.is_empty() {
+        return Err(LabeledError::new("Can't draw a Gantt chart of no tasks.").with_label("No rows in the table.", call.head));
+    }
+
+    let mut unit = None;
+    let tasks = rows
+        .iter()
+        .map(|row| {
+            let record = row.as_record().map_err(|_| not_table(row.span()))?;
+            let cell = |column: &str| {
+                record.get(column).filter(|v| !matches!(v, Value::Nothing { .. })).ok_or_else(|| {
+                    LabeledError::new(format!("Each task needs a value in the `{}` column.", column)).with_label("Missing cell.", row.span())
+                })
+            };
+            let name = cell("task")?.coerce_string().map_err(|_| LabeledError::new("The name of a task must be text.").with_label("Incorrect task name.", row.span()))?;
+            let (start, end) = (cell("start")?, cell("end")?);
+            Unit::see(&mut unit, start);
+            Unit::see(&mut unit, end);
+            let (start, end) = (value_to_f64(start, call)?, value_to_f64(end, call)?);
+            if end < start {
+                return Err(LabeledError::new(format!("The task {} ends before it starts.", name)).with_label("Task ends too soon.", row.span()));
+            }
+            Ok(Task { name, start, end })
+        })
+        .collect::<Result<Vec<Task>, LabeledError>>()?;
+    Ok((tasks, unit.unwrap_or_default()))
+}
+
+impl PluginCommand for CommandGantt {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "gantt"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("gantt")
+            .description("Render an ASCII Gantt chart of a table of tasks, each a bar from its start to its end.")
+            .named(
+                "width",
+                SyntaxShape::OneOf(vec![SyntaxShape::Number, SyntaxShape::String]),
+                "The maximum width of the plot, in dots, or as a percentage of the terminal such as 80%.",
+                None,
+            )
+            .named(
+                "title",
+                SyntaxShape::String,
+                "Provide a title to the plot.",
+                Some('t'),
+            )
+            .named("subtitle", SyntaxShape::String, "A line under the title.", None)
+            .named("caption", SyntaxShape::String, "A line under the chart, such as where its data is from.", None)
+            .named("title-align", SyntaxShape::String, "Line the title, subtitle and caption up across the chart: left (default), center or right.", None)
+            .named("pad", SyntaxShape::Int, "Indent each line of a text chart by this many spaces (default 4), or not at all with 0.", None)
+            .switch("legend", "Plot a tiny, maybe useful legend.", Some('l'))
+            .named(
+                "legend-pos",
+                SyntaxShape::String,
+                "Where to put the legend: top, bottom (default), right or inside.",
+                None,
+            )
+            .named(
+                "format",
+                SyntaxShape::String,
+                "Draw the chart as text (default), svg, png, or vega for a Vega-Lite spec.",
+                None,
+            )
+            .named(
+                "charset",
+                SyntaxShape::String,
+                "The characters to draw a text chart with: braille (default), ascii or blocks.",
+                None,
+            )
+            .named(
+                "palette",
+                SyntaxShape::OneOf(vec![SyntaxShape::List(Box::new(SyntaxShape::String)), SyntaxShape::String]),
+                "Colors for the tasks in turn: default, okabe-ito, viridis, mono or high-contrast, or a list such as [red bright-blue \"#ff8800\"].",
+                None,
+            )
+            .named(
+                "label-format",
+                SyntaxShape::String,
+                "How to write the times of the axis and legend: %.3f, %.2e, scientific, si, percent, thousands, bytes, duration or date, all but date with :N decimal places.",
+                None,
+            )
+            .named(
+                "color",
+                SyntaxShape::String,
+                "Color a text chart always, never, or auto (default) when NO_COLOR and CLICOLOR=0 are unset and the output is a terminal.",
+                None,
+            )
+            .switch("raw", "Return plain text without color or any other escape codes, whatever --color says.", None)
+            .switch("print", "Print the chart as soon as it is drawn rather than returning it, so each chart of a loop or script is shown.", None)
+            .switch("object", "Return the chart as a chart value, shown as the chart, with its data, ranges and facts as cells such as ymax.", None)
+            .named(
+                "output",
+                SyntaxShape::Filepath,
+                "Save the chart to this file rather than returning it, as SVG, PNG or a Vega-Lite spec if it ends in .svg, .png or .json.",
+                Some('o'),
+            )
+            .switch("meta", "Return a record of the chart and facts about the input.", None)
+            .input_output_type(Type::table(), Type::Any)
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render an ASCII Gantt chart from a table of {task, start, end} records: a bar for each task from its start to its end, one under the other in the order of the table, on a shared time axis, with the task names in a gutter on the left. The starts and ends may be dates, durations from some beginning, or plain numbers."
+    }
+
+    fn search_terms(&self) -> Vec<&str> {
+        vec!["gantt", "timeline", "schedule", "tasks", "project"]
+    }
+
+    fn examples(&self) -> Vec<Example<'_>> {
+        vec![
+            Example {
+                example: "open plan.csv | update start { into datetime } | update end { into datetime } | gantt -t Plan",
+                description: "Draw a project plan of dated tasks.",
+                result: None,
+            },
+            Example {
+                example: "[[task start end]; [build 0sec 4min] [test 4min 7min] [deploy 7min 8min]] | gantt",
+                description: "Draw the steps of a pipeline, each a duration from its beginning.",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        let call = &with_config(engine, call)?;
+        let opts = parse_cli_opts(call)?;
+        let (tasks, unit) = tasks(call, input)?;
+        let n = tasks.len();
+        let (format, _) = label_formats(opts.label_format, (unit, Unit::Number));
+
+        // laid out in points: the names in a gutter of characters on the
+        // left, then the time axis across the rest; a line of characters
+        // for each task, and one under them for the ends of the axis
+        let gutter = tasks.iter().map(|t| t.name.chars().count()).max().unwrap_or(0).min(MAX_NAME) + 1;
+        let width = (opts.width_op.unwrap_or(200) as usize).max(2 * gutter + 32);
+        let height = (4 * (n + 1)).max(32);
+        let xmin = tasks.iter().map(|t| t.start).fold(f64::INFINITY, f64::min);
+        let xmax = tasks.iter().map(|t| t.end).fold(f64::NEG_INFINITY, f64::max);
+        let span = if xmax > xmin { xmax - xmin } else { 1.0 };
+        let per_point = span / (width - 2 * gutter) as f64;
+
+        // a unit is a point, from the left and down from the top, with
+        // the times along after the gutter; they count from 1, so 0 is
+        // off the chart and no axis is drawn across it
+        let x = |col: usize| (2 * col + 1) as f64;
+        let time = |t: f64| x(gutter) + (t - xmin) / per_point;
+        let y = |dot: usize| (height - dot + 1) as f64;
+
+        let mut chart = new_chart(opts.format, opts.charset, (LabelFormat::Hidden, LabelFormat::Hidden), width as u32, height as u32, x(0), x(width / 2));
+        chart.fix_y_range(y(height), y(0));
+        let mut annotations = vec![];
+        for (i, task) in tasks.iter().enumerate() {
+            // three points of the task's line, and at least one across,
+            // so every task shows
+            let start = time(task.start).min(x(width / 2) - 1.0);
+            let end = time(task.end).max(start + 1.0);
+            let bar = Heatmap { x: vec![start, end], y: vec![y(4 * i + 3), y(4 * i)], values: vec![1.0], range: (0.0, 1.0), midpoint: None };
+            opts.palette.plot_nth(&mut chart, i, n, Shape::Heatmap(bar));
+            annotations.push(Annotation::Text(x(0), y(4 * i), truncate(&task.name, gutter - 1)));
+        }
+
+        // the ends of the axis, on the bottom line under the first and
+        // last columns of it, as finely as a tenth of it needs
+        let (first, last) = (format.axis(xmin, span / 10.0), format.axis(xmin + span, span / 10.0));
+        let (first_len, last_len) = (first.chars().count(), last.chars().count());
+        let bottom = height;
+        annotations.push(Annotation::Text(x(gutter), y(bottom), first));
+        if gutter + first_len < width / 2 - last_len {
+            annotations.push(Annotation::Text(x(width / 2 - last_len), y(bottom), last));
+        }
+
+        let mut entries = vec![];
+        if opts.legend {
+            let length = match unit {
+                Unit::Date | Unit::Nanoseconds => LabelFormat::Duration(None).value(xmax - xmin),
+                _ => format.value(xmax - xmin),
+            };
+            entries.push(vec![(format!("{} tasks, from {} to {}, {} in all", n, format.value(xmin), format.value(xmax), length), None)]);
+        }
+
+        let meta = Meta {
+            series: n,
+            points: 2 * n,
+            units: vec![unit],
+            ..Meta::default()
+        };
+        let names: Vec<String> = tasks.into_iter().map(|t| t.name).collect();
+        let chart = finish_chart(&mut chart, opts.format, &names, &annotations, &opts.titles, &entries, opts.legend_pos, &opts.pad, opts.object, call.head);
+        output_chart(engine, call, chart, meta)
+    }
+}
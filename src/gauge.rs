@@ -0,0 +1,315 @@
+//! The `gauge` command.
+//!
+//! Draws one or more values as horizontal gauges, each a bar along a
+//! track from a minimum to a maximum, with its value written at the
+//! end, one under the other, with their names in a gutter to the left.
+//! Bands split the track into zones, colored low to high along a
+//! colormap, each bar in the color of the zone it reaches, and a
+//! target is a tick across the track, as on a bullet chart. Small and
+//! quick to read, a few gauges suit a dashboard redrawn in a loop.
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::{Category, Example, LabeledError, PipelineData, Signature, SyntaxShape, Type, Value};
+
+use crate::color_plot::colormap::Colormap;
+use crate::color_plot::drawille::PixelColor;
+use crate::color_plot::textplots::label::LabelFormat;
+use crate::color_plot::textplots::{Annotation, ColorPlot, Heatmap, Shape};
+use crate::{finish_chart, label_formats, legend_entry, new_chart, output_chart, parse_cli_opts, truncate, value_to_f64, with_config, Meta, PluginPlot, Unit, MAX_NAME};
+
+pub struct CommandGauge;
+
+/// The glyph of a target, across the line of its gauge.
+const TARGET: char = '|';
+
+/// The value of each gauge, with its name, if it has one.
+type Gauges = Vec<(Option<String>, f64)>;
+
+/// The values of the gauges, each with its name if it has one, and their
+/// unit: a single value, a list of them, or a record of them by name.
+fn gauges(call: &EvaluatedCall, input: PipelineData) -> Result<(Gauges, Unit), LabeledError> {
+    let mut unit = None;
+    let mut value = |v: &Value| {
+        Unit::see(&mut unit, v);
+        value_to_f64(v, call)
+    };
+    let gauges = match input {
+        PipelineData::Value(Value::Record { val, .. }, ..) => val.iter().map(|(name, v)| Ok((Some(name.clone()), value(v)?))).collect::<Result<Vec<_>, LabeledError>>()?,
+        PipelineData::ListStream(..) | PipelineData::Value(Value::List { .. }, ..) => input.into_iter().map(|v| Ok((None, value(&v)?))).collect::<Result<Vec<_>, LabeledError>>()?,
+        PipelineData::Value(v, ..) => vec![(None, value(&v)?)],
+        _ => return Err(LabeledError::new("gauge needs a number, a list of numbers, or a record of them by name.").with_label("Incorrect input type.", call.head)),
+    };
+    if gauges.is_empty() {
+        return Err(LabeledError::new("Can't draw a gauge of no values.").with_label("No values.", call.head));
+    }
+    Ok((gauges, unit.unwrap_or_default()))
+}
+
+/// The number given to the `flag` named, if it was, such as a filesize.
+fn number_flag(call: &EvaluatedCall, flag: &str) -> Result<Option<f64>, LabeledError> {
+    call.get_flag::<Value>(flag)?.map(|v| value_to_f64(&v, call)).transpose()
+}
+
+/// The targets of `n` gauges, from `--target`: one for them all, one for
+/// each, or none.
+fn targets(call: &EvaluatedCall, n: usize) -> Result<Vec<Option<f64>>, LabeledError> {
+    match call.get_flag::<Value>("target")? {
+        None => Ok(vec![None; n]),
+        Some(Value::List { vals, .. }) if vals.len() == n => vals.iter().map(|v| Ok(Some(value_to_f64(v, call)?))).collect(),
+        Some(Value::List { vals, .. }) => Err(LabeledError::new(format!("Got {} targets for {} gauges, --target needs one for them all or one for each.", vals.len(), n))
+            .with_label("Target error.", call.get_flag_span("target").unwrap_or(call.head))),
+        Some(v) => Ok(vec![Some(value_to_f64(&v, call)?); n]),
+    }
+}
+
+impl PluginCommand for CommandGauge {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "gauge"
+    }
+
+    fn signature(&self) -> Signature {
+        let number = || SyntaxShape::OneOf(vec![SyntaxShape::Number, SyntaxShape::Filesize, SyntaxShape::Duration]);
+        Signature::build("gauge")
+            .description("Render ASCII gauges of a value, a list of them, or a record of them by name, each a bar along a track.")
+            .named(
+                "width",
+                SyntaxShape::OneOf(vec![SyntaxShape::Number, SyntaxShape::String]),
+                "The maximum width of the plot, in dots, or as a percentage of the terminal such as 80%.",
+                None,
+            )
+            .named("min", number(), "The value at the start of each track, 0 or the least value if not given.", None)
+            .named("max", number(), "The value at the end of each track, the greatest value, target or band if not given.", None)
+            .named(
+                "target",
+                SyntaxShape::OneOf(vec![SyntaxShape::Number, SyntaxShape::Filesize, SyntaxShape::Duration, SyntaxShape::List(Box::new(SyntaxShape::Any))]),
+                "A value to mark with a tick across each gauge, or a list with one for each.",
+                None,
+            )
+            .named(
+                "bands",
+                SyntaxShape::List(Box::new(SyntaxShape::Any)),
+                "Values splitting the tracks into zones, colored low to high along the gradient, such as [60 85].",
+                None,
+            )
+            .named(
+                "title",
+                SyntaxShape::String,
+                "Provide a title to the plot.",
+                Some('t'),
+            )
+            .named("subtitle", SyntaxShape::String, "A line under the title.", None)
+            .named("caption", SyntaxShape::String, "A line under the chart, such as where its data is from.", None)
+            .named("title-align", SyntaxShape::String, "Line the title, subtitle and caption up across the chart: left (default), center or right.", None)
+            .named("pad", SyntaxShape::Int, "Indent each line of a text chart by this many spaces (default 4), or not at all with 0.", None)
+            .switch("legend", "Plot a tiny, maybe useful legend.", Some('l'))
+            .named(
+                "legend-pos",
+                SyntaxShape::String,
+                "Where to put the legend: top, bottom (default), right or inside.",
+                None,
+            )
+            .named(
+                "format",
+                SyntaxShape::String,
+                "Draw the chart as text (default), svg, png, or vega for a Vega-Lite spec.",
+                None,
+            )
+            .named(
+                "charset",
+                SyntaxShape::String,
+                "The characters to draw a text chart with: braille (default), ascii or blocks.",
+                None,
+            )
+            .named(
+                "palette",
+                SyntaxShape::OneOf(vec![SyntaxShape::List(Box::new(SyntaxShape::String)), SyntaxShape::String]),
+                "Colors for the gauges in turn, without bands: default, okabe-ito, viridis, mono or high-contrast, or a list such as [red bright-blue \"#ff8800\"].",
+                None,
+            )
+            .named(
+                "gradient",
+                SyntaxShape::String,
+                "The colormap of the bands, green-red (default), blue-red or viridis, or without bands, to color each gauge by how full it is.",
+                None,
+            )
+            .named(
+                "label-format",
+                SyntaxShape::String,
+                "How to write the values, ends and bands: %.3f, %.2e, scientific, si, percent, thousands, bytes, duration or date, all but date with :N decimal places.",
+                None,
+            )
+            .named(
+                "color",
+                SyntaxShape::String,
+                "Color a text chart always, never, or auto (default) when NO_COLOR and CLICOLOR=0 are unset and the output is a terminal.",
+                None,
+            )
+            .switch("raw", "Return plain text without color or any other escape codes, whatever --color says.", None)
+            .switch("print", "Print the chart as soon as it is drawn rather than returning it, so each chart of a loop or script is shown.", None)
+            .switch("object", "Return the chart as a chart value, shown as the chart, with its data, ranges and facts as cells such as ymax.", None)
+            .named(
+                "output",
+                SyntaxShape::Filepath,
+                "Save the chart to this file rather than returning it, as SVG, PNG or a Vega-Lite spec if it ends in .svg, .png or .json.",
+                Some('o'),
+            )
+            .switch("meta", "Return a record of the chart and facts about the input.", None)
+            .input_output_type(Type::Number, Type::Any)
+            .input_output_type(Type::Filesize, Type::Any)
+            .input_output_type(Type::Duration, Type::Any)
+            .input_output_type(Type::List(Box::new(Type::Any)), Type::Any)
+            .input_output_type(Type::record(), Type::Any)
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render ASCII gauges of a value, a list of values, or a record of them by name: each a bar along a track from --min to --max with its value written at the end, one under the other, named on the left. --bands split the tracks into zones colored low to high, each bar in the color of the zone it reaches, and --target marks a tick across each, as on a bullet chart. Drawn with --print in a loop, they make a small dashboard."
+    }
+
+    fn search_terms(&self) -> Vec<&str> {
+        vec!["gauge", "bullet", "meter", "progress", "dashboard", "utilization"]
+    }
+
+    fn examples(&self) -> Vec<Example<'_>> {
+        vec![
+            Example {
+                example: "{cpu: 73 memory: 41 disk: 88} | gauge --max 100 --bands [60 85] --target 80 -l",
+                description: "Draw how much of each resource is in use, against warning and critical levels and a target.",
+                result: None,
+            },
+            Example {
+                example: "loop { let mem = sys mem; clear; {used: $mem.used swap: $mem.'swap used'} | gauge --max $mem.total -t Memory --print; sleep 2sec }",
+                description: "Watch the memory in use, redrawn every two seconds.",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        let call = &with_config(engine, call)?;
+        let opts = parse_cli_opts(call)?;
+        let (gauges, unit) = gauges(call, input)?;
+        let n = gauges.len();
+        let (format, _) = label_formats(opts.label_format, (unit, Unit::Number));
+        let targets = targets(call, n)?;
+        let bands = call
+            .get_flag::<Vec<Value>>("bands")?
+            .unwrap_or_default()
+            .iter()
+            .map(|v| value_to_f64(v, call))
+            .collect::<Result<Vec<f64>, LabeledError>>()?;
+        if bands.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(LabeledError::new("--bands needs values in rising order, such as [60 85].").with_label("Bands error.", call.get_flag_span("bands").unwrap_or(call.head)));
+        }
+
+        // the track runs from 0, or below it for negative values, up to
+        // whatever there is to show, unless told otherwise
+        let values = gauges.iter().map(|(_, v)| *v);
+        let marks = values.clone().chain(targets.iter().flatten().copied()).chain(bands.iter().copied());
+        let min = number_flag(call, "min")?.unwrap_or_else(|| values.clone().fold(0.0, f64::min));
+        let max = number_flag(call, "max")?.unwrap_or_else(|| marks.fold(f64::NEG_INFINITY, f64::max));
+        if min >= max || min.is_nan() || max.is_nan() {
+            return Err(LabeledError::new(format!("A gauge can't run from {} to {}, --max must be greater than --min.", format.value(min), format.value(max)))
+                .with_label("Range error.", call.head));
+        }
+
+        // laid out in points: the names in a gutter of characters on the
+        // left, the values in one on the right and the tracks between;
+        // a line of characters for each gauge, with one between them, and
+        // one under them for the ends of the tracks
+        let labels: Vec<String> = gauges.iter().map(|(_, v)| format.value(*v)).collect();
+        let named = gauges.iter().any(|(name, _)| name.is_some());
+        let gutter = match named {
+            true => gauges.iter().filter_map(|(name, _)| name.as_ref()).map(|name| name.chars().count()).max().unwrap_or(0).min(MAX_NAME) + 1,
+            false => 0,
+        };
+        let right = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0) + 1;
+        let width = (opts.width_op.unwrap_or(200) as usize).max(2 * (gutter + right) + 32);
+        let height = (8 * n).max(32);
+        let (start, end) = (gutter, width / 2 - right);
+
+        // a unit is a point, from the left and down from the top, with
+        // the values along the track; they count from 1, so 0 is off the
+        // chart and no axis is drawn across it
+        let x = |col: usize| (2 * col + 1) as f64;
+        let along = |v: f64| x(start) + (v.clamp(min, max) - min) / (max - min) * (x(end) - x(start));
+        let y = |dot: usize| (height - dot + 1) as f64;
+
+        // the zones between the bands, with the color of each, low to
+        // high along the colormap
+        let colormap = opts.palette.gradient.unwrap_or(opts.colormap(Colormap::GREEN_RED));
+        let edges: Vec<f64> = std::iter::once(min).chain(bands.iter().map(|b| b.clamp(min, max))).chain(std::iter::once(max)).collect();
+        let zones: Vec<((f64, f64), PixelColor)> = edges.windows(2).enumerate().map(|(i, pair)| ((pair[0], pair[1]), colormap.color((i as f64 + 0.5) / (edges.len() - 1) as f64))).collect();
+
+        let mut chart = new_chart(opts.format, opts.charset, (LabelFormat::Hidden, LabelFormat::Hidden), width as u32, height as u32, x(0), x(width / 2));
+        chart.fix_y_range(y(height), y(0));
+        let mut annotations = vec![];
+        for (i, ((name, value), label)) in gauges.iter().zip(&labels).enumerate() {
+            let (top, bottom) = (y(8 * i), y(8 * i + 3));
+            // the track, shaded in each zone's color if there are bands
+            for ((from, to), color) in &zones {
+                let color = if bands.is_empty() { None } else { Some(*color) };
+                annotations.push(Annotation::Area(vec![(along(*from), bottom, top), (along(*to), bottom, top)], color));
+            }
+
+            // the bar, at least a point long so every gauge shows, in the
+            // color of its zone, or of how full it is along a gradient
+            let bar = Heatmap { x: vec![x(start), along(*value).max(x(start) + 1.0)], y: vec![bottom, top], values: vec![1.0], range: (0.0, 1.0), midpoint: None };
+            let zone = zones.iter().rposition(|((from, _), _)| *value >= *from).unwrap_or(0);
+            match (bands.is_empty(), opts.palette.gradient) {
+                (false, _) => chart.linecolorplot(Shape::Heatmap(bar), zones[zone].1),
+                (true, Some(colormap)) => chart.linecolorplot(Shape::Heatmap(bar), colormap.color((value - min) / (max - min))),
+                (true, None) => opts.palette.plot_nth(&mut chart, i, n, Shape::Heatmap(bar)),
+            };
+
+            if let Some(target) = targets[i] {
+                annotations.push(Annotation::Marker(along(target), top, TARGET, None));
+            }
+            if let Some(name) = name {
+                annotations.push(Annotation::Text(x(0), top, truncate(name, gutter - 1)));
+            }
+            annotations.push(Annotation::Text(x(end + 1), top, label.clone()));
+        }
+
+        // the ends of the tracks, on the line under the last of them
+        let (first, last) = (format.value(min), format.value(max));
+        let (first_len, last_len) = (first.chars().count(), last.chars().count());
+        let axis = y(8 * n - 4);
+        annotations.push(Annotation::Text(x(start), axis, first));
+        if start + first_len < end.saturating_sub(last_len) {
+            annotations.push(Annotation::Text(x(end - last_len), axis, last));
+        }
+
+        let mut entries = vec![];
+        if opts.legend {
+            for ((from, to), color) in zones.iter().filter(|_| !bands.is_empty()) {
+                entries.push(legend_entry(&format!("{} to {}", format.value(*from), format.value(*to)), *color, None, None, LabelFormat::Hidden));
+            }
+            let mut set: Vec<f64> = targets.iter().flatten().copied().collect();
+            set.dedup();
+            match set[..] {
+                [] => {}
+                [target] => entries.push(vec![(format!("{} target {}", TARGET, format.value(target)), None)]),
+                _ => entries.push(vec![(format!("{} targets", TARGET), None)]),
+            }
+        }
+
+        let meta = Meta {
+            series: n,
+            points: n,
+            units: vec![unit],
+            ..Meta::default()
+        };
+        let names: Vec<String> = gauges.into_iter().enumerate().map(|(i, (name, _))| name.unwrap_or_else(|| format!("Gauge {}", i + 1))).collect();
+        let chart = finish_chart(&mut chart, opts.format, &names, &annotations, &opts.titles, &entries, opts.legend_pos, &opts.pad, opts.object, call.head);
+        output_chart(engine, call, chart, meta)
+    }
+}
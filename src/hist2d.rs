@@ -0,0 +1,202 @@
+//! The `hist2d` command.
+//!
+//! Bins pairs of x and y values into a grid and shades each cell by how
+//! many fall in it, densely in dots or along a colormap with
+//! `--gradient`. Where a scatter of many points is a solid blot, the
+//! grid still shows where they crowd.
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::{Category, Example, LabeledError, PipelineData, Signature, SyntaxShape, Value};
+
+use crate::bins::{grid_counts, Bins};
+use crate::color_plot::textplots::{Heatmap, Shape};
+use crate::{common_chart_flags, extent, finish_chart, label_formats, new_chart, numeric_input_types, parse_cli_opts, series_names, xy_values, Group, Meta, Plotter, PluginPlot, Unit};
+
+pub struct CommandHist2d;
+
+impl CommandHist2d {
+    /// The chart of `points`, x and y values in `units`, binned into a grid.
+    fn grid_chart(&self, call: &EvaluatedCall, points: Vec<(f64, f64)>, units: (Unit, Unit)) -> Result<Value, LabeledError> {
+        let opts = parse_cli_opts(call)?;
+        let points: Vec<(f64, f64)> = points.into_iter().filter(|(x, y)| x.is_finite() && y.is_finite()).collect();
+        let (Some(x_range), Some(y_range)) = (extent(points.iter().map(|(x, _)| *x)), extent(points.iter().map(|(_, y)| *y))) else {
+            return Err(LabeledError::new("A 2D histogram needs at least one point with finite x and y values.").with_label("No points.", call.head));
+        };
+
+        // each axis binned from its own values, the same way
+        let bins = opts.bins.clone().unwrap_or(Bins::Count(20));
+        let edges = |values: Vec<f64>, (min, max): (f64, f64)| {
+            // a single distinct value still needs a non-empty range
+            let (min, max) = if max > min { (min, max) } else { (min - 0.5, max + 0.5) };
+            bins.edges(&values, min, max)
+        };
+        let x_edges = edges(points.iter().map(|(x, _)| *x).collect(), x_range);
+        let y_edges = edges(points.iter().map(|(_, y)| *y).collect(), y_range);
+        let counts = grid_counts(&points, &x_edges, &y_edges);
+        let most = counts.iter().copied().fold(0.0, f64::max);
+
+        // a logarithm shows sparse cells beside crowded ones
+        let log = call.has_flag("log")?;
+        let (values, range) = match log {
+            true => (counts.iter().map(|c| c.ln_1p()).collect(), (0.0, most.ln_1p())),
+            false => (counts, (0.0, most)),
+        };
+
+        let max_x = opts.width_op.unwrap_or(200);
+        let max_y = opts.height_op.unwrap_or(50);
+        let labels = label_formats(opts.label_format, units);
+        let names = match &opts.names {
+            Some(_) => series_names(call, opts.names.as_deref(), 1)?,
+            None => vec!["Count".to_string()],
+        };
+        let mut entries = vec![];
+        if opts.legend {
+            entries.push(opts.palette.entry(&names[0], 0, 1, None, labels.1));
+            let scale = if log { ", log scale" } else { "" };
+            entries.push(vec![(format!("Bins: {} × {}, most in one {}{}", x_edges.len() - 1, y_edges.len() - 1, most, scale), None)]);
+        }
+
+        let (xmin, xmax) = (x_edges[0], x_edges[x_edges.len() - 1]);
+        let mut chart = new_chart(opts.format, opts.charset, labels, max_x, max_y, xmin, xmax);
+        opts.palette.plot(&mut chart, Shape::Heatmap(Heatmap { x: x_edges, y: y_edges, values, range, midpoint: None }));
+        Ok(finish_chart(&mut chart, opts.format, &names, &opts.annotations, &opts.titles, &entries, opts.legend_pos, &opts.pad, opts.object, call.head))
+    }
+}
+
+impl Plotter for CommandHist2d {
+    fn plot(
+        &self,
+        call: &EvaluatedCall,
+        _input: Vec<f64>,
+        _meta: &mut Meta,
+    ) -> Result<Value, LabeledError> {
+        Err(LabeledError::new("A 2D histogram needs pairs of x and y values, not a single list.").with_label("Plot type error.", call.head))
+    }
+
+    fn plot_nested(
+        &self,
+        call: &EvaluatedCall,
+        input: Vec<Vec<f64>>,
+        meta: &mut Meta,
+    ) -> Result<Value, LabeledError> {
+        let [xs, ys] = &input[..] else {
+            return Err(LabeledError::new("hist2d bins a nested list of two lists, of x values and of y values, or a table of two columns.").with_label("Nested list error.", call.head));
+        };
+        let points = xs.iter().copied().zip(ys.iter().copied()).collect();
+        self.grid_chart(call, points, (meta.units_from(0), meta.units_from(1)))
+    }
+
+    /// The points of every series are binned together.
+    fn plot_pairs(
+        &self,
+        call: &EvaluatedCall,
+        input: Vec<Group>,
+        meta: &mut Meta,
+    ) -> Result<Value, LabeledError> {
+        let points = input.into_iter().flat_map(|group| group.points).collect();
+        self.grid_chart(call, points, meta.xy_units())
+    }
+}
+
+impl PluginCommand for CommandHist2d {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "hist2d"
+    }
+
+    fn signature(&self) -> Signature {
+        common_chart_flags(Signature::build("hist2d").description("Render an ASCII 2D histogram, pairs of x and y values counted in a grid of cells, each shaded by its count."))
+            .named(
+                "bins",
+                SyntaxShape::OneOf(vec![SyntaxShape::Int, SyntaxShape::String]),
+                "The number of bins along each axis, default is 20, or auto, sturges, fd or scott to choose from the values of each.",
+                None,
+            )
+            .switch("log", "Shade cells by the logarithm of their counts, so sparse cells show beside crowded ones.", None)
+            .switch("square", "Draw the chart as tall as it is wide, as many dots across as up.", None)
+            .named(
+                "names",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "A name for the counts in the legend, rather than Count.",
+                None,
+            )
+            .named(
+                "gradient",
+                SyntaxShape::String,
+                "Color each cell by its count along a colormap: green-red, blue-red or viridis.",
+                None,
+            )
+            .named(
+                "label-format",
+                SyntaxShape::String,
+                "How to write the numbers of the axes and labels: %.3f, %.2e, scientific, si, percent, thousands, bytes, duration or date, all but date with :N decimal places.",
+                None,
+            )
+            .switch("skip-nulls", "Leave out null values.", None)
+            .named(
+                "fill-nulls",
+                SyntaxShape::String,
+                "Replace null values using linear, previous or zero.",
+                None,
+            )
+            .switch("grid", "Draw dim grid lines across and down the chart, behind the series.", None)
+            .named("bg", SyntaxShape::String, "The color behind a text chart, such as black, or bright-white on a dark terminal.", None)
+            .named(
+                "hline",
+                SyntaxShape::Any,
+                "Draw horizontal reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .named(
+                "vline",
+                SyntaxShape::Any,
+                "Draw vertical reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .switch("meta", "Return a record of the chart and facts about the input.", None)
+            .input_output_types(numeric_input_types())
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render an ASCII 2D histogram: pairs of x and y values, from a nested list of two lists, records of x and y or a table of two columns, counted in a grid of --bins cells along each axis, each shaded with as many dots as its count, or colored by it along a --gradient, so that crowds of points too many to scatter can be told apart."
+    }
+
+    fn search_terms(&self) -> Vec<&str> {
+        vec!["histogram", "heatmap", "density", "scatter", "2d", "hexbin"]
+    }
+
+    fn examples(&self) -> Vec<Example<'_>> {
+        vec![
+            Example {
+                example: "open points.csv | select x y | hist2d --bins 30 --gradient viridis",
+                description: "Count thousands of points in a 30 by 30 grid, colored by count.",
+                result: None,
+            },
+            Example {
+                example: "[$xs $ys] | hist2d --log -l",
+                description: "Bin a list of x values against one of y values, shaded by the logarithm of the counts.",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        // a table is binned by its two columns
+        let input = match input {
+            PipelineData::ListStream(..) | PipelineData::Value(Value::List { .. }, ..) => {
+                let (values, _) = xy_values(call, input.into_iter().collect())?;
+                PipelineData::Value(Value::list(values, call.head), None)
+            }
+            input => input,
+        };
+        self.plot_input(engine, call, input)
+    }
+}
@@ -0,0 +1,60 @@
+//! Jitter: small random offsets added to points, so points which share
+//! a value, as integer-valued data often do, spread out rather than all
+//! land on the same pixel.
+//!
+//! The offsets come from a seeded generator, so the same seed jitters
+//! the same points the same way every time a chart is drawn.
+
+/// A pseudo-random number generator, SplitMix64, which is small and
+/// fast and good enough to scatter points, but not for anything that
+/// needs to be unpredictable.
+///
+/// ```
+/// # use nu_plugin_plot::jitter::Rng;
+/// let (mut a, mut b) = (Rng::new(7), Rng::new(7));
+/// assert_eq!(a.next_u64(), b.next_u64());
+/// assert!((0..100).map(|_| a.uniform()).all(|u| (0.0..1.0).contains(&u)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    /// A generator whose numbers all follow from `seed`.
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    /// The next number, from the whole range of `u64`.
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// The next number, uniform from 0 up to but not including 1.
+    pub fn uniform(&mut self) -> f64 {
+        // the top 53 bits, as many as an f64 holds exactly
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// `data` with each point moved by up to `amount` either way in x and
+/// in y, uniformly at random as `rng` picks. Points which are not finite
+/// are left as they are, so they still break a line.
+///
+/// ```
+/// # use nu_plugin_plot::jitter::{jitter, Rng};
+/// let data = vec![(1.0, 1.0); 50];
+/// let jittered = jitter(&data, 0.25, &mut Rng::new(0));
+/// assert!(jittered.iter().all(|(x, y)| (x - 1.0).abs() <= 0.25 && (y - 1.0).abs() <= 0.25));
+/// assert!(jittered.windows(2).any(|pair| pair[0] != pair[1]));
+/// assert_eq!(jittered, jitter(&data, 0.25, &mut Rng::new(0)));
+/// ```
+pub fn jitter(data: &[(f64, f64)], amount: f64, rng: &mut Rng) -> Vec<(f64, f64)> {
+    let mut offset = || amount * (2.0 * rng.uniform() - 1.0);
+    data.iter()
+        .map(|&(x, y)| if x.is_finite() && y.is_finite() { (x + offset(), y + offset()) } else { (x, y) })
+        .collect()
+}
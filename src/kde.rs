@@ -0,0 +1,126 @@
+//! Kernel density estimation.
+//!
+//! A smooth alternative to a histogram: each value contributes a small
+//! bump, the kernel, and the bumps are summed into a curve whose area
+//! is 1.
+
+use crate::bins::quantile;
+
+/// The shape of the bump each value contributes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kernel {
+    /// The normal distribution, smooth and unbounded.
+    Gaussian,
+    /// A parabola, the most efficient kernel in theory.
+    Epanechnikov,
+    /// A triangle.
+    Triangular,
+    /// A box, which gives a moving histogram.
+    Uniform,
+}
+
+impl Kernel {
+    /// The kernel with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "gaussian" => Some(Kernel::Gaussian),
+            "epanechnikov" => Some(Kernel::Epanechnikov),
+            "triangular" => Some(Kernel::Triangular),
+            "uniform" => Some(Kernel::Uniform),
+            _ => None,
+        }
+    }
+
+    /// The name of the kernel, as given on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Kernel::Gaussian => "gaussian",
+            Kernel::Epanechnikov => "epanechnikov",
+            Kernel::Triangular => "triangular",
+            Kernel::Uniform => "uniform",
+        }
+    }
+
+    /// The kernel at `u` bandwidths from a value.
+    pub fn weight(self, u: f64) -> f64 {
+        match self {
+            Kernel::Gaussian => (-0.5 * u * u).exp() / (2.0 * std::f64::consts::PI).sqrt(),
+            _ if u.abs() > 1.0 => 0.0,
+            Kernel::Epanechnikov => 0.75 * (1.0 - u * u),
+            Kernel::Triangular => 1.0 - u.abs(),
+            Kernel::Uniform => 0.5,
+        }
+    }
+
+    /// How many bandwidths either side of a value the kernel reaches,
+    /// or where the Gaussian is too small to see.
+    pub fn reach(self) -> f64 {
+        match self {
+            Kernel::Gaussian => 3.0,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Silverman's rule of thumb for the bandwidth.
+///
+/// Values which are not finite are ignored. If the data has no spread,
+/// the bandwidth is 1.
+///
+/// ```
+/// # use nu_plugin_plot::kde::silverman;
+/// assert_eq!(silverman(&[2.0, 2.0, 2.0]), 1.0);
+/// assert!(silverman(&[1.0, 2.0, 3.0, 4.0]) > 0.0);
+/// ```
+pub fn silverman(data: &[f64]) -> f64 {
+    let mut sorted: Vec<f64> = data.iter().copied().filter(|e| e.is_finite()).collect();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let n = sorted.len() as f64;
+    if sorted.len() < 2 {
+        return 1.0;
+    }
+    let mean = sorted.iter().sum::<f64>() / n;
+    let sd = (sorted.iter().map(|e| (e - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+    let iqr = quantile(&sorted, 0.75) - quantile(&sorted, 0.25);
+
+    let spread = if iqr > 0.0 { sd.min(iqr / 1.34) } else { sd };
+    if spread > 0.0 {
+        0.9 * spread * n.powf(-0.2)
+    } else {
+        1.0
+    }
+}
+
+/// The density of `data` at `points` evenly spaced x values between
+/// `min` and `max`.
+///
+/// Values which are not finite are ignored.
+///
+/// ```
+/// # use nu_plugin_plot::kde::{kde, Kernel};
+/// let curve = kde(&[0.0], 1.0, Kernel::Uniform, (-2.0, 2.0), 5);
+/// assert_eq!(curve, vec![(-2.0, 0.0), (-1.0, 0.5), (0.0, 0.5), (1.0, 0.5), (2.0, 0.0)]);
+/// ```
+pub fn kde(
+    data: &[f64],
+    bandwidth: f64,
+    kernel: Kernel,
+    (min, max): (f64, f64),
+    points: usize,
+) -> Vec<(f64, f64)> {
+    let finite: Vec<f64> = data.iter().copied().filter(|e| e.is_finite()).collect();
+    let scale = 1.0 / (finite.len() as f64 * bandwidth);
+    let step = (max - min) / (points.max(2) - 1) as f64;
+
+    (0..points)
+        .map(|i| {
+            let x = min + i as f64 * step;
+            let y = finite
+                .iter()
+                .map(|e| kernel.weight((x - e) / bandwidth))
+                .sum::<f64>();
+            (x, y * scale)
+        })
+        .collect()
+}
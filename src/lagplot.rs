@@ -0,0 +1,148 @@
+//! The `lagplot` command.
+//!
+//! Scatters each value of a numeric list, a time series, against the
+//! value `--lag` steps before it, through the same path as `xyplot`.
+//! Points along a line show autocorrelation, a cloud shows none, and
+//! an ellipse or loop a cycle. Both axes are in the units of the series
+//! and drawn to the same scale, unless `--aspect` says otherwise.
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::{Category, LabeledError, PipelineData, Signature, SyntaxShape, Value};
+
+use crate::{common_chart_flags, numeric_input_types, parse_cli_opts, series_names, xy_chart, Meta, Plotter, PluginPlot};
+
+pub struct CommandLagplot;
+
+/// The points of `series` at each value, as y, and the value `lag`
+/// steps before it, as x.
+fn lagged(series: &[f64], lag: usize) -> Vec<(f64, f64)> {
+    series.iter().copied().zip(series.iter().copied().skip(lag)).collect()
+}
+
+impl Plotter for CommandLagplot {
+    fn plot(
+        &self,
+        call: &EvaluatedCall,
+        input: Vec<f64>,
+        meta: &mut Meta,
+    ) -> Result<Value, LabeledError> {
+        self.plot_nested(call, vec![input], meta)
+    }
+
+    fn plot_nested(
+        &self,
+        call: &EvaluatedCall,
+        input: Vec<Vec<f64>>,
+        meta: &mut Meta,
+    ) -> Result<Value, LabeledError> {
+        let mut opts = parse_cli_opts(call)?;
+        opts.points = true;
+        opts.aspect = opts.aspect.or(Some(1.0));
+
+        if input.len() > 5 {
+            return Err(LabeledError::new("Nested list can't contain more than 5 inner lists.").with_label("Nested list error.", call.head));
+        }
+        let lag = match call.get_flag::<i64>("lag")? {
+            None => 1,
+            Some(lag) if lag > 0 => lag as usize,
+            Some(_) => return Err(LabeledError::new("The lag must be a positive integer.").with_label("Lag error.", call.get_flag_span("lag").unwrap_or(call.head))),
+        };
+        if input.iter().any(|list| list.len() <= lag) {
+            return Err(LabeledError::new(format!("A lag of {} needs a series longer than that.", lag)).with_label("Lag error.", call.get_flag_span("lag").unwrap_or(call.head)));
+        }
+
+        let series: Vec<Vec<(f64, f64)>> = input.iter().map(|list| lagged(list, lag)).collect();
+        let names = series_names(call, opts.names.as_deref(), series.len())?;
+        let unit = meta.units_from(0);
+        xy_chart(call, opts, series, names, None, (unit, unit))
+    }
+
+    /// Each series is lagged against itself alone.
+    fn ragged(&self) -> bool {
+        true
+    }
+}
+
+impl PluginCommand for CommandLagplot {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "lagplot"
+    }
+
+    fn signature(&self) -> Signature {
+        common_chart_flags(Signature::build("lagplot").description("Render an ASCII lag plot, each value of a list against the value --lag steps before it."))
+            .named(
+                "lag",
+                SyntaxShape::Int,
+                "How many steps before each value to take the value it is drawn against, 1 by default.",
+                None,
+            )
+            .named(
+                "aspect",
+                SyntaxShape::Number,
+                "Draw a y unit this many times as long as an x unit, 1 by default, shrinking the chart to fit.",
+                None,
+            )
+            .named(
+                "names",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "Names for the series in the legend, one for each.",
+                None,
+            )
+            .named(
+                "markers",
+                SyntaxShape::OneOf(vec![SyntaxShape::List(Box::new(SyntaxShape::String)), SyntaxShape::String]),
+                "Mark each series with its own glyph, so they can be told apart without color: default or ascii, or a list of glyphs such as [o x + *].",
+                None,
+            )
+            .named(
+                "label-format",
+                SyntaxShape::String,
+                "How to write the numbers of the axes and labels: %.3f, %.2e, scientific, si, percent, thousands, bytes, duration or date, all but date with :N decimal places.",
+                None,
+            )
+            .switch("skip-nulls", "Leave out null values.", None)
+            .named(
+                "fill-nulls",
+                SyntaxShape::String,
+                "Replace null values using linear, previous or zero.",
+                None,
+            )
+            .switch("grid", "Draw dim grid lines across and down the chart, behind the series.", None)
+            .named("bg", SyntaxShape::String, "The color behind a text chart, such as black, or bright-white on a dark terminal.", None)
+            .named(
+                "hline",
+                SyntaxShape::Any,
+                "Draw horizontal reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .named(
+                "vline",
+                SyntaxShape::Any,
+                "Draw vertical reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .switch("meta", "Return a record of the chart and facts about the input.", None)
+            .input_output_types(numeric_input_types())
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render an ASCII lag plot: each value of a list, a time series, against the value --lag steps before it, drawn to the same scale both ways, so autocorrelation shows as points along a line."
+    }
+
+    fn search_terms(&self) -> Vec<&str> {
+        vec!["lag", "autocorrelation", "time series", "scatter"]
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        self.plot_input(engine, call, input)
+    }
+}
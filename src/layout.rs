@@ -0,0 +1,73 @@
+//! Layout of several text charts together.
+//!
+//! Charts drawn on their own are laid out line by line: side by side,
+//! each padded to its width as measured without color codes, one above
+//! the other, or in rows of as many as fit across the terminal, padded
+//! to the width of the widest so that they line up in columns. A panel
+//! under another can share its x axis, lined up with it.
+
+use crate::visible_len;
+
+/// Number of spaces between two charts in a row.
+const GAP: usize = 2;
+
+/// The width of the widest line of `frame`, leaving out color codes.
+fn frame_width(frame: &str) -> usize {
+    frame.lines().map(visible_len).max().unwrap_or(0)
+}
+
+/// `frames` side by side, each padded to the width in `widths`, lining
+/// up their first lines.
+fn side_by_side(frames: &[String], widths: &[usize]) -> String {
+    let frames: Vec<Vec<&str>> = frames.iter().map(|frame| frame.lines().collect()).collect();
+    let height = frames.iter().map(Vec::len).max().unwrap_or(0);
+    let mut out = String::new();
+    for i in 0..height {
+        let mut line = String::new();
+        for (frame, width) in frames.iter().zip(widths) {
+            let text = frame.get(i).copied().unwrap_or("");
+            line += text;
+            line += &" ".repeat((width + GAP).saturating_sub(visible_len(text)));
+        }
+        out += line.trim_end();
+        out += "\n";
+    }
+    out
+}
+
+/// `frames` side by side, from left to right.
+pub(crate) fn hstack(frames: &[String]) -> String {
+    let widths: Vec<usize> = frames.iter().map(|frame| frame_width(frame)).collect();
+    side_by_side(frames, &widths)
+}
+
+/// `frames` one above the other, with a blank line between each.
+pub(crate) fn vstack(frames: &[String]) -> String {
+    let frames: Vec<&str> = frames.iter().map(|frame| frame.trim_end()).collect();
+    frames.join("\n\n") + "\n"
+}
+
+/// `bottom` under `top`, sharing its x axis: the x labels of `top`, its
+/// last line, are left out, and the y labels of the narrower are moved
+/// over to the width of the wider, so that the two plots line up.
+pub(crate) fn share_x(top: &str, bottom: &str) -> String {
+    // the x labels start where the plot does, past the y labels
+    let gutter = |frame: &str| frame.lines().last().map_or(0, |line| line.len() - line.trim_start().len());
+    let width = gutter(top).max(gutter(bottom));
+    let indent = |frame: &str, lines: usize| {
+        let pad = " ".repeat(width - gutter(frame));
+        frame.lines().take(lines).map(|line| format!("{}{}", pad, line)).collect::<Vec<_>>().join("\n")
+    };
+    vstack(&[indent(top, top.lines().count().saturating_sub(1)), indent(bottom, usize::MAX)])
+}
+
+/// `frames` laid out in rows no wider than `width` characters, or one
+/// to a row if even one is wider.
+pub(crate) fn grid(frames: &[String], width: usize) -> String {
+    let cell = frames.iter().map(|frame| frame_width(frame)).max().unwrap_or(0);
+    let columns = ((width + GAP) / (cell + GAP)).max(1);
+    frames
+        .chunks(columns)
+        .map(|row| side_by_side(row, &vec![cell; row.len()]))
+        .collect()
+}
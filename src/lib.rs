@@ -5,20 +5,93 @@
 //! - `plot` plots a 1-dimensional numeric list/nested list
 //! - `hist` plots a 1-dimensional numeric list/nested list
 //! - `xyplot` plots a 2-dimensional numeric list (nested list with length == 2)
+//!
+//! Further commands:
+//! - `barchart` plots labelled values as horizontal bars
+//! - `density` plots a kernel density estimate of a numeric list
+//! - `ecdf` plots the empirical cumulative distribution of a numeric list
+#![allow(clippy::result_large_err)]
 
-use nu_plugin::{EvaluatedCall, Plugin, SimplePluginCommand};
-use nu_protocol::{Category, LabeledError, Signature, SyntaxShape, Type, Value};
+use nu_plugin::{EvaluatedCall, Plugin, PluginCommand, SimplePluginCommand};
+use nu_protocol::{record, Category, Config, Example, LabeledError, ListStream, PipelineData, Range, Record, ShellError, Signals, Signature, Span, Spanned, SyntaxShape, Type, Value};
+pub mod bars;
+pub mod bins;
+pub mod bucket;
+pub mod cdf;
 pub mod color_plot;
+pub mod dsp;
+pub mod fit;
+pub mod jitter;
+pub mod kde;
+pub mod resample;
+pub mod stats;
+mod vega;
+mod acf;
+mod barchart;
+mod candle;
+mod chart_value;
+mod compose;
+mod corrplot;
+mod density;
+mod ecdf;
+mod function;
+mod gantt;
+mod gauge;
+mod hist2d;
+mod lagplot;
+mod layout;
+mod live;
+mod merge;
+mod nullmap;
+mod overlay;
+mod qqplot;
+mod radar;
+mod ridgeline;
+mod spec;
+mod spectrum;
+mod subplot;
 
-use color_plot::drawille::PixelColor;
-use color_plot::textplots::{utils::histogram, Chart, ColorPlot, Plot, Shape};
+use chart_value::ChartValue;
+use color_plot::drawille::{Blend, Canvas as BrailleCanvas, Charset, ColorDepth, PixelColor};
+use color_plot::blocks::BlockCanvas;
+use color_plot::colormap::Colormap;
+use color_plot::png::PngCanvas;
+use color_plot::svg::SvgCanvas;
+use color_plot::textplots::{label::LabelFormat, utils::histogram_edges, Annotation, Chart, ColorPlot, LegendEntry, LineStyle, Plot, Shape, StepMode};
+use bars::{arrange, BarLayout};
+use bins::{normalize, BinRule, Bins};
+use fit::{Fit, Trend};
+use jitter::Rng;
+use kde::Kernel;
+use resample::{winsorize, Aggregate, Decimation, Normalize, Rescale, Running};
 use owo_colors::OwoColorize;
+use std::borrow::Cow;
+use std::io::IsTerminal;
+use std::ops::Bound;
+use std::path::Path;
+use terminal_size::{Height, Width};
 
 
-/// So the chart is not hard up against the left of the terminal.
+/// So the chart is not hard up against the left of the terminal, unless
+/// `--pad` says otherwise.
 const TAB: &str = "    ";
 
-/// Colors, five of them.
+/// Braille dots in a character, across and up.
+const DOTS: (u32, u32) = (2, 4);
+
+/// Columns of the terminal kept free of a chart sized to it, for the y
+/// labels and the `TAB` to its left.
+const MARGIN: u32 = 12;
+
+/// Where `with_terminal` leaves the columns and lines of the terminal in
+/// a call. No command has a flag of this name.
+const TERMINAL: &str = "terminal";
+
+/// Where `with_terminal` leaves how many colors the terminal shows in a
+/// call. No command has a flag of this name either.
+const COLOR_DEPTH: &str = "color-depth";
+
+/// The default palette, five colors.
 const COLORS: &[PixelColor] = &[
     PixelColor::BrightWhite,
     PixelColor::BrightRed,
@@ -27,6 +100,216 @@ const COLORS: &[PixelColor] = &[
     PixelColor::Cyan,
 ];
 
+/// Colorblind-safe colors after Okabe and Ito, as near as the terminal
+/// colors get: orange, sky blue, bluish green, yellow, blue, vermillion
+/// and reddish purple.
+const OKABE_ITO: &[PixelColor] = &[
+    PixelColor::Yellow,
+    PixelColor::BrightCyan,
+    PixelColor::Green,
+    PixelColor::BrightYellow,
+    PixelColor::Blue,
+    PixelColor::Red,
+    PixelColor::Magenta,
+];
+
+/// One color, leaving markers to tell the series apart.
+const MONO: &[PixelColor] = &[PixelColor::White];
+
+/// Bright colors which stand out on a dark background.
+const HIGH_CONTRAST: &[PixelColor] = &[
+    PixelColor::BrightWhite,
+    PixelColor::BrightYellow,
+    PixelColor::BrightCyan,
+    PixelColor::BrightMagenta,
+    PixelColor::BrightGreen,
+];
+
+/// Glyphs marking the series in turn, with `--markers true`.
+const MARKERS: &[char] = &['●', '■', '▲', '◆', '▼'];
+
+/// Markers for ASCII charts, and PNG images, whose font is ASCII only.
+const ASCII_MARKERS: &[char] = &['o', 'x', '#', '@', '%'];
+
+/// Points between two markers on a series.
+const MARKER_SPACING: usize = 16;
+
+/// The most points across a line is drawn with `--line-width`, past
+/// which it covers more than it shows.
+const MAX_LINE_WIDTH: u32 = 4;
+
+/// The most characters of a column's name written beside a grid of
+/// cells, as by `corrplot`.
+const MAX_NAME: usize = 16;
+
+/// The named palette `name`.
+fn named_palette(name: &str) -> Option<&'static [PixelColor]> {
+    match name {
+        "default" => Some(COLORS),
+        "okabe-ito" => Some(OKABE_ITO),
+        "viridis" => Some(Colormap::VIRIDIS.colors()),
+        "mono" => Some(MONO),
+        "high-contrast" => Some(HIGH_CONTRAST),
+        _ => None,
+    }
+}
+
+/// Colors for the series of a chart, in turn, and markers to tell them
+/// apart where color can't be relied on.
+///
+/// A lone series is drawn in the default color unless a palette was
+/// chosen, with `--palette` or in the plugin's config, or along a
+/// colormap by its values with `--gradient`.
+#[derive(Clone, Default)]
+struct Palette {
+    /// The colors, if a palette was chosen.
+    colors: Option<Vec<PixelColor>>,
+    /// Glyphs marking the series, with `--markers`.
+    markers: Option<Vec<char>>,
+    /// The colormap a lone series is drawn along, with `--gradient`.
+    gradient: Option<Colormap>,
+    /// How the lines of the series are drawn in turn, with `--styles`.
+    styles: Option<Vec<LineStyle>>,
+    /// How many points across the lines of the series are in turn, with
+    /// `--line-width`.
+    widths: Option<Vec<u32>>,
+}
+
+impl Palette {
+    /// The color of the `i`th series, going round the palette.
+    fn nth(&self, i: usize) -> PixelColor {
+        let colors = self.colors.as_deref().unwrap_or(COLORS);
+        colors[i % colors.len()]
+    }
+
+    /// Color the `i`th of `count` series as it would be, and the rest
+    /// grey, so the one stands out from the crowd.
+    fn highlight(&mut self, i: usize, count: usize) {
+        let colors = (0..count).map(|j| if j == i { self.nth(i) } else { PixelColor::BrightBlack }).collect();
+        self.colors = Some(colors);
+    }
+
+    /// The color of a lone series, if a palette was chosen.
+    fn single(&self) -> Option<PixelColor> {
+        self.colors.as_ref().map(|colors| colors[0])
+    }
+
+    /// Draw a lone series on `chart`, in its color or along the gradient.
+    fn plot<'a>(&self, chart: &'a mut Chart, shape: Shape) -> &'a mut Chart {
+        let marks = self.point_marks(0, 1, &shape);
+        match (self.gradient, self.single()) {
+            (Some(colormap), _) => {
+                chart.gradient(colormap);
+                chart.lineplot(shape)
+            }
+            (None, Some(color)) => chart.linecolorplot(shape, color),
+            (None, None) => chart.lineplot(shape),
+        }
+        .line_style(self.style(0))
+        .line_width(self.width(0));
+        annotate(chart, &marks)
+    }
+
+    /// Draw the `i`th of `count` series on `chart`: a lone series as
+    /// [`Palette::plot`] does, and any other in its turn of the palette.
+    fn plot_nth<'a>(&self, chart: &'a mut Chart, i: usize, count: usize, shape: Shape) -> &'a mut Chart {
+        match count {
+            1 => self.plot(chart, shape),
+            _ => {
+                let marks = self.point_marks(i, count, &shape);
+                annotate(chart.linecolorplot(shape, self.nth(i)).line_style(self.style(i)).line_width(self.width(i)), &marks)
+            }
+        }
+    }
+
+    /// The legend entry for the `i`th of `count` series, called `name`,
+    /// with the values in `stats`, as [`legend_entry`] has it. Along a
+    /// gradient, the sample runs through the colormap.
+    fn entry(&self, name: &str, i: usize, count: usize, stats: Option<&[f64]>, format: LabelFormat) -> LegendEntry {
+        let color = if count == 1 { self.single().unwrap_or(PixelColor::White) } else { self.nth(i) };
+        let mut entry = legend_entry(name, color, self.marker(i), stats, format);
+        // a marker tells the series apart already
+        match (self.marker(i), self.style(i)) {
+            (None, LineStyle::Dashed) => entry[1].0 = "- -".to_string(),
+            (None, LineStyle::Dotted) => entry[1].0 = "...".to_string(),
+            _ => {}
+        }
+        if let Some(colormap) = self.gradient {
+            entry.splice(1..2, colormap.swatches().iter().map(|c| ("-".to_string(), Some(*c))));
+        }
+        entry
+    }
+
+    /// The line style of the `i`th series, going round the styles, or
+    /// solid without them.
+    fn style(&self, i: usize) -> LineStyle {
+        self.styles.as_ref().map_or(LineStyle::Solid, |styles| styles[i % styles.len()])
+    }
+
+    /// How many points across the line of the `i`th series is, going
+    /// round the widths, or one without them.
+    fn width(&self, i: usize) -> u32 {
+        self.widths.as_ref().map_or(1, |widths| widths[i % widths.len()])
+    }
+
+    /// The marker of the `i`th series, going round the markers.
+    fn marker(&self, i: usize) -> Option<char> {
+        self.markers.as_ref().map(|markers| markers[i % markers.len()])
+    }
+
+    /// A marker on every point of `shape`, the `i`th of `count` series,
+    /// if it's drawn as points, so each series' points show as its glyph
+    /// rather than as dots like any other's.
+    fn point_marks(&self, i: usize, count: usize, shape: &Shape) -> Vec<Annotation> {
+        let (Some(marker), Shape::Points(points)) = (self.marker(i), shape) else {
+            return vec![];
+        };
+        let color = if count == 1 { self.single() } else { Some(self.nth(i)) };
+        points.iter().filter(|(x, y)| x.is_finite() && y.is_finite()).map(|&(x, y)| Annotation::Marker(x, y, marker, color)).collect()
+    }
+
+    /// Markers along `data`, the `i`th of `count` series, every
+    /// `MARKER_SPACING` points across a chart `width` points wide over
+    /// `(xmin, xmax)`. Each series starts a little further along, so
+    /// markers on lines which overlap don't cover each other.
+    fn marks(&self, i: usize, count: usize, data: &[(f64, f64)], (xmin, xmax): (f64, f64), width: u32) -> Vec<Annotation> {
+        let Some(marker) = self.marker(i) else {
+            return vec![];
+        };
+        let color = if count == 1 { self.single() } else { Some(self.nth(i)) };
+        let scale = width as f64 / (xmax - xmin);
+        let mut next = (i * MARKER_SPACING / count) as f64;
+        data.iter()
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .filter_map(|&(x, y)| {
+                let col = (x - xmin) * scale;
+                (col >= next).then(|| {
+                    next = col + MARKER_SPACING as f64;
+                    Annotation::Marker(x, y, marker, color)
+                })
+            })
+            .collect()
+    }
+}
+
+/// A dimmer shade of `color`, a series' color or the default one, for
+/// what's drawn behind the series: the plain color of a bright one,
+/// grey for white and the default, or any other at half its brightness.
+fn dimmer(color: Option<PixelColor>) -> PixelColor {
+    match color {
+        Some(PixelColor::Rgb(r, g, b)) => PixelColor::Rgb(r / 2, g / 2, b / 2),
+        Some(PixelColor::BrightRed) => PixelColor::Red,
+        Some(PixelColor::BrightGreen) => PixelColor::Green,
+        Some(PixelColor::BrightYellow) => PixelColor::Yellow,
+        Some(PixelColor::BrightBlue) => PixelColor::Blue,
+        Some(PixelColor::BrightMagenta) => PixelColor::Magenta,
+        Some(PixelColor::BrightCyan) => PixelColor::Cyan,
+        Some(PixelColor::BrightBlack) => PixelColor::Black,
+        None | Some(PixelColor::White | PixelColor::BrightWhite | PixelColor::Default) => PixelColor::BrightBlack,
+        Some(color) => color,
+    }
+}
+
 /// The command line options.
 ///
 /// These apply to `plot`, `hist`, and `xyplot`.
@@ -37,164 +320,2497 @@ struct CliOpts {
     width_op: Option<u32>,
     /// Add a legend to the plot.
     legend: bool,
+    /// Give statistics for each series in the legend.
+    legend_stats: bool,
+    /// Where to put the legend.
+    legend_pos: LegendPos,
+    /// The indent before each line of a text chart.
+    pad: String,
+    /// Names for the series in the legend.
+    names: Option<Vec<String>>,
+    /// What to draw the chart as.
+    format: Format,
+    /// The characters to draw a text chart with.
+    charset: Charset,
+    /// Colors for the series, in turn.
+    palette: Palette,
     /// Render a step plot, instead of a line plot.
     steps: bool,
+    /// Where the steps of a step plot step.
+    step_mode: StepMode,
     /// Render a bar plot, instead of a line plot.
     bars: bool,
     /// Render single points, instead of line plot.
     points: bool,
-    /// Add a title to the plot.
-    title: Option<String>,
-    /// Number of bins in the histogram
-    bins: Option<u32>,
+    /// Render a stem from zero to each point, instead of a line plot.
+    stem: bool,
+    /// The shape of each series in turn, instead of one for them all.
+    shapes: Option<Vec<SeriesShape>>,
+    /// Draw the bars of several series stacked or side by side.
+    bar_layout: Option<BarLayout>,
+    /// Shade the area between a lone series and zero.
+    fill: bool,
+    /// The two series, by name or index, to shade between as a band
+    /// rather than draw.
+    band: Option<Vec<Value>>,
+    /// Add a title, subtitle and caption to the plot.
+    titles: Titles,
+    /// The bins of the histogram, or how to choose them.
+    bins: Option<Bins>,
+    /// Accumulate the histogram counts from left to right.
+    cumulative: bool,
+    /// Scale the histogram so its area sums to 1.
+    density: bool,
+    /// How to downsample series with more points than the plot is wide.
+    decimate: Decimation,
+    /// Join the line across gaps left by nulls and non-finite values,
+    /// unless `--break-gaps` asks to break it.
+    connect_gaps: bool,
+    /// Move points by up to this amount at random, from this seed.
+    jitter: Option<(f64, u64)>,
+    /// The series to draw over the rest, by name or index, the last on top.
+    order: Option<Vec<Value>>,
+    /// The series, by name or index, to draw in its color with the rest
+    /// grey.
+    highlight: Option<Value>,
+    /// The series, by name or index, to draw in a second panel under the
+    /// chart.
+    panel: Option<Value>,
+    /// How series are colored where they cross.
+    blend: Blend,
+    /// Fill the corners of the diagonal steps of lines.
+    smooth: bool,
+    /// Fit and overlay a trend line.
+    trend: Option<Trend>,
+    /// Reference lines and shaded regions.
+    annotations: Vec<Annotation>,
+    /// Points of each series to label with their value.
+    marks: Vec<Mark>,
+    /// Label every point with its value, where there is room.
+    labels: bool,
+    /// How to write the numbers of the axes and labels, if not as suits
+    /// the units of the values.
+    label_format: Option<LabelFormat>,
+    /// Take the x and y values of an xyplot as the angle and radius of
+    /// polar coordinates.
+    polar: bool,
+    /// Take the lists of an xyplot in pairs, as the x and y values of a
+    /// parametric curve.
+    parametric: bool,
+    /// Sort the points of each series of an xyplot by x.
+    sort_x: bool,
+    /// Combine the points of each series of an xyplot which share an x
+    /// value, sorting them by x.
+    aggregate: Option<Aggregate>,
+    /// The width of the buckets to combine the points of each series of
+    /// an xyplot in, and whether it's a duration or a number.
+    every: Option<(f64, Unit)>,
+    /// How many times as long a y unit is drawn as an x unit.
+    aspect: Option<f64>,
+    /// What is done to the y values of each series before plotting.
+    prepare: Prepare,
+    /// Return the chart as a chart value, with the data it was drawn
+    /// from, rather than as text.
+    object: bool,
+    /// How many colors the chart can be drawn in: as many as the
+    /// terminal shows for a text chart, or any for an image.
+    depth: ColorDepth,
 }
 
-/// Parse the command line options.
-fn parse_cli_opts(call: &EvaluatedCall) -> Result<CliOpts, LabeledError> {
-    // scale the width and height to the size of the terminal unless specified on the cli
-    let height_op: Option<u32> = call.get_flag("height").map(|e| e.map(|f: i64| f as u32))?;
-    let width_op: Option<u32> = call.get_flag("width").map(|e| e.map(|f: i64| f as u32))?;
+impl CliOpts {
+    /// `colormap`, blending smoothly between its stops where the chart
+    /// can be drawn in more than sixteen colors.
+    fn colormap(&self, colormap: Colormap) -> Colormap {
+        smooth_if(colormap, self.depth)
+    }
+}
 
-    let mut height: Option<u32>;
-    let mut width: Option<u32>;
+/// `colormap`, smooth unless `depth` has only the sixteen terminal colors.
+fn smooth_if(colormap: Colormap, depth: ColorDepth) -> Colormap {
+    match depth {
+        ColorDepth::Ansi16 => colormap,
+        _ => colormap.smooth(),
+    }
+}
 
-    if let Some((w, h)) = term_size::dimensions() {
-        // don't know why I need to scale this, but I do - I hope it works
-        // as intended for other terminals.
-        height = Some(height_op.unwrap_or((h as f32 * 1.7) as u32));
-        width = Some(width_op.unwrap_or((w as f32 * 1.7) as u32));
+/// The indent before each line of a text chart: as many spaces as
+/// `--pad` gives, or else a `TAB`.
+fn indent(call: &EvaluatedCall) -> Result<String, LabeledError> {
+    match call.get_flag::<i64>("pad")? {
+        None => Ok(TAB.to_string()),
+        Some(pad) if pad >= 0 => Ok(" ".repeat(pad as usize)),
+        Some(pad) => Err(LabeledError::new(format!("The padding must be 0 or more spaces, not {}.", pad))
+            .with_label("Padding error.", call.get_flag_span("pad").unwrap_or(call.head))),
+    }
+}
 
-        // textplot panics if either of these are below 32 units.
-        if height.unwrap() < 32 {
-            height = Some(32);
-        }
-        if width.unwrap() < 32 {
-            width = Some(32);
+/// The dots up a chart `rows` characters tall, which textplots draws a
+/// row taller than its height in dots fills.
+fn rows_to_dots(rows: u32) -> u32 {
+    DOTS.1 * rows.saturating_sub(1)
+}
+
+/// The width and height of a chart with `lines_around` lines of labels,
+/// title and legend, in dots, from `--width`, `--height`, `--max-lines`
+/// and `--square`. Left to the terminal, a chart is as wide as it, less
+/// the `MARGIN`, and takes half its lines; 100% of them leaves a line
+/// for the prompt.
+fn chart_size(call: &EvaluatedCall, lines_around: u32) -> Result<(Option<u32>, Option<u32>), LabeledError> {
+    let terminal = terminal(call)?;
+    let margin = (MARGIN as usize + indent(call)?.len()).saturating_sub(TAB.len()) as u32;
+    let whole = terminal.map(|(columns, lines)| (DOTS.0 * columns.saturating_sub(margin), rows_to_dots(lines.saturating_sub(lines_around + 1))));
+    let width = size_flag(call, "width", whole.map(|(w, _)| w))?.or(whole.map(|(w, _)| w));
+    let mut height = size_flag(call, "height", whole.map(|(_, h)| h))?.or(terminal.map(|(_, lines)| rows_to_dots((lines / 2).saturating_sub(lines_around))));
+
+    if let Some(max_lines) = call.get_flag::<i64>("max-lines")? {
+        let most = rows_to_dots((max_lines.max(0) as u32).saturating_sub(lines_around));
+        if most < 32 {
+            return Err(LabeledError::new(format!("--max-lines {} leaves too few lines for the chart, which needs {}.", max_lines, lines_around + 9))
+                .with_label("Size error.", call.get_flag_span("max-lines").unwrap_or(call.head)));
         }
-    } else {
-        // we couldnt detect terminal size for some reason
-        height = height_op;
-        width = width_op;
+        height = Some(height.unwrap_or(50).min(most));
+    }
+
+    // textplot panics if either of these are below 32 units.
+    let mut width = width.map(|w| w.max(32));
+    let mut height = height.map(|h| h.max(32));
+
+    // dots are square, so a square chart is as many dots across as up
+    if call.has_flag("square")? {
+        let side = width.unwrap_or(200).min(height.unwrap_or(50));
+        (width, height) = (Some(side), Some(side));
     }
+    Ok((width, height))
+}
 
-    let legend = call.has_flag("legend")?;
-    let steps = call.has_flag("steps")?;
-    let bars = call.has_flag("bars")?;
+/// Parse the command line options.
+fn parse_cli_opts(call: &EvaluatedCall) -> Result<CliOpts, LabeledError> {
+    let legend_stats = call.has_flag("legend-stats")?;
+    let legend_pos_op: Option<String> = call.get_flag("legend-pos")?;
+    let legend_pos = match legend_pos_op.as_deref() {
+        None | Some("bottom") => LegendPos::Bottom,
+        Some("top") => LegendPos::Top,
+        Some("right") => LegendPos::Right,
+        Some("inside") => LegendPos::Inside,
+        Some(p) => return Err(LabeledError::new(format!("Unknown legend position {}, use top, bottom, right or inside.", p)).with_label("Legend error.", call.get_flag_span("legend-pos").unwrap_or(call.head))),
+    };
+    let legend = call.has_flag("legend")? || legend_stats || legend_pos_op.is_some();
+    let names: Option<Vec<String>> = call.get_flag("names")?;
+    let output: Option<String> = call.get_flag("output")?;
+    let format = match call.get_flag::<String>("format")?.as_deref() {
+        // an .svg, .png or .json file is written as such unless we are told otherwise
+        None if output.as_deref().is_some_and(|o| o.ends_with(".svg")) => Format::Svg,
+        None if output.as_deref().is_some_and(|o| o.ends_with(".png")) => Format::Png,
+        None if output.as_deref().is_some_and(|o| o.ends_with(".json")) => Format::Vega,
+        None | Some("text") => Format::Text,
+        Some("svg") => Format::Svg,
+        Some("png") => Format::Png,
+        Some("vega") => Format::Vega,
+        Some(f) => return Err(LabeledError::new(format!("Unknown format {}, use text, svg, png or vega.", f)).with_label("Format error.", call.get_flag_span("format").unwrap_or(call.head))),
+    };
+    let charset = match call.get_flag::<String>("charset")?.as_deref() {
+        None | Some("braille") => Charset::Braille,
+        Some("ascii") => Charset::Ascii,
+        Some("blocks") => Charset::Blocks,
+        Some(c) => return Err(LabeledError::new(format!("Unknown charset {}, use braille, ascii or blocks.", c)).with_label("Charset error.", call.get_flag_span("charset").unwrap_or(call.head))),
+    };
+    let palette_flag: Option<Value> = call.get_flag("palette")?;
+    let palette_span = call.get_flag_span("palette").unwrap_or(call.head);
+    let palette_error = |msg: String| LabeledError::new(msg).with_label("Palette error.", palette_span);
+    let colors = match &palette_flag {
+        None => None,
+        Some(Value::String { val, .. }) => Some(
+            named_palette(val)
+                .ok_or_else(|| palette_error(format!("Unknown palette {}, use default, okabe-ito, viridis, mono or high-contrast, or a list of colors.", val)))?
+                .to_vec(),
+        ),
+        Some(Value::List { vals, .. }) => {
+            let colors = vals
+                .iter()
+                .map(|v| {
+                    v.as_str().ok().and_then(color_from_name).ok_or_else(|| {
+                        palette_error(format!(
+                            "Unknown color {}, use black, red, green, yellow, blue, magenta, cyan or white, bright-red and so on, or #rrggbb.",
+                            v.to_abbreviated_string(&Default::default())
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if colors.is_empty() {
+                return Err(palette_error("The palette needs at least one color.".to_string()));
+            }
+            Some(colors)
+        }
+        Some(_) => return Err(palette_error("--palette takes the name of a palette, or a list of colors.".to_string())),
+    };
+    // one color alone can't tell the series apart
+    let mono = matches!(&palette_flag, Some(Value::String { val, .. }) if val == "mono");
+    // ASCII glyphs where the charset, or the font of an image, has no others
+    let default_markers = || if charset == Charset::Ascii || format == Format::Png { ASCII_MARKERS } else { MARKERS }.to_vec();
+    let markers_error = |msg: String| LabeledError::new(msg).with_label("Markers error.", call.get_flag_span("markers").unwrap_or(call.head));
+    let markers = match call.get_flag_value("markers") {
+        Some(Value::List { vals, .. }) => {
+            let glyphs = vals
+                .iter()
+                .map(|v| {
+                    let glyph = v.as_str().ok().filter(|s| s.chars().count() == 1).and_then(|s| s.chars().next());
+                    glyph.ok_or_else(|| markers_error(format!("Unknown marker {}, use single characters such as [o x + *].", v.to_abbreviated_string(&Default::default()))))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if glyphs.is_empty() {
+                return Err(markers_error("--markers needs at least one glyph.".to_string()));
+            }
+            Some(glyphs)
+        }
+        Some(Value::String { val, .. }) if val == "default" => Some(default_markers()),
+        Some(Value::String { val, .. }) if val == "ascii" => Some(ASCII_MARKERS.to_vec()),
+        // true or false in the config, as when it was a switch
+        Some(Value::Bool { val, .. }) => (val || mono).then(default_markers),
+        None => mono.then(default_markers),
+        Some(v) => return Err(markers_error(format!("Unknown markers {}, use default or ascii, or a list of glyphs such as [o x + *].", v.to_abbreviated_string(&Default::default())))),
+    };
+    let depth = match format {
+        Format::Text => color_depth(call)?,
+        _ => ColorDepth::TrueColor,
+    };
+    let gradient = match call.get_flag::<String>("gradient")? {
+        None => None,
+        Some(g) => Some(smooth_if(
+            Colormap::from_name(&g).ok_or_else(|| {
+                LabeledError::new(format!("Unknown gradient {}, use green-red, blue-red or viridis.", g)).with_label("Gradient error.", call.get_flag_span("gradient").unwrap_or(call.head))
+            })?,
+            depth,
+        )),
+    };
+    let styles = match call.get_flag::<Vec<String>>("styles")? {
+        None => None,
+        Some(names) => {
+            let styles_error = |msg: String| LabeledError::new(msg).with_label("Styles error.", call.get_flag_span("styles").unwrap_or(call.head));
+            let styles = names
+                .iter()
+                .map(|name| LineStyle::from_name(name).ok_or_else(|| styles_error(format!("Unknown line style {}, use solid, dashed or dotted.", name))))
+                .collect::<Result<Vec<_>, _>>()?;
+            if styles.is_empty() {
+                return Err(styles_error("--styles needs at least one style.".to_string()));
+            }
+            Some(styles)
+        }
+    };
+    let widths = match call.get_flag::<Value>("line-width")? {
+        None => None,
+        Some(value) => {
+            let span = call.get_flag_span("line-width").unwrap_or(call.head);
+            let widths = match value {
+                Value::List { vals, .. } => vals.iter().map(|v| v.as_int()).collect::<Result<Vec<_>, _>>()?,
+                v => vec![v.as_int()?],
+            };
+            if widths.is_empty() {
+                return Err(LabeledError::new("--line-width needs at least one width.").with_label("Line width error.", span));
+            }
+            if let Some(w) = widths.iter().find(|w| !(1..=MAX_LINE_WIDTH as i64).contains(*w)) {
+                return Err(LabeledError::new(format!("The line width must be from 1 to {} dots, not {}.", MAX_LINE_WIDTH, w)).with_label("Line width error.", span));
+            }
+            Some(widths.into_iter().map(|w| w as u32).collect())
+        }
+    };
+    let palette = Palette { colors, markers, gradient, styles, widths };
+    let step_mode = match call.get_flag::<String>("step-mode")? {
+        None => None,
+        Some(m) => Some(StepMode::from_name(&m).ok_or_else(|| {
+            LabeledError::new(format!("Unknown step mode {}, use pre, post or mid.", m)).with_label("Step mode error.", call.get_flag_span("step-mode").unwrap_or(call.head))
+        })?),
+    };
+    // a step mode draws steps whatever else is said
+    let steps = call.has_flag("steps")? || step_mode.is_some();
+    let step_mode = step_mode.unwrap_or_default();
     let points = call.has_flag("points")?;
-    let bins: Option<u32> = call.get_flag("bins").map(|e| e.map(|f: i64| f as u32))?;
-    let title: Option<String> = call.get_flag("title")?;
+    let stem = call.has_flag("stem")?;
+    let bar_layout = match (call.has_flag("stacked")?, call.has_flag("dodge")?) {
+        (false, false) => None,
+        (true, false) => Some(BarLayout::Stacked),
+        (false, true) => Some(BarLayout::Dodged),
+        (true, true) => return Err(LabeledError::new("Bars can be either stacked or dodged, not both.").with_label("Chart shape error", call.head)),
+    };
+    if bar_layout.is_some() && (steps || points || stem) {
+        return Err(LabeledError::new("Stacked and dodged bars can't be drawn as steps, points or stems.").with_label("Chart shape error", call.head));
+    }
+    // stacked and dodged series are drawn as bars whatever else is said
+    let bars = call.has_flag("bars")? || bar_layout.is_some();
+    let shapes = match call.get_flag::<Vec<String>>("shapes")? {
+        None => None,
+        Some(names) => {
+            let shapes_error = |msg: String| LabeledError::new(msg).with_label("Chart shape error", call.get_flag_span("shapes").unwrap_or(call.head));
+            if call.has_flag("steps")? || bars || points || stem {
+                return Err(shapes_error("--shapes gives each series its shape, so can't go with --steps, --bars, --points, --stem, --stacked or --dodge.".to_string()));
+            }
+            let shapes = names
+                .iter()
+                .map(|name| {
+                    SeriesShape::from_name(name, step_mode)
+                        .ok_or_else(|| shapes_error(format!("Unknown shape {}, use lines, points, steps, steps:pre, steps:post, steps:mid, bars or stems.", name)))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if shapes.is_empty() {
+                return Err(shapes_error("--shapes needs at least one shape.".to_string()));
+            }
+            Some(shapes)
+        }
+    };
+    // a step mode says how the steps among the shapes step
+    let steps = steps && shapes.is_none();
+    let fill = call.has_flag("fill")?;
+    let filled = match shapes.as_deref() {
+        Some([first, ..]) => matches!(first, SeriesShape::Lines | SeriesShape::Steps(_)),
+        _ => !(bars || points || stem),
+    };
+    if fill && !filled {
+        return Err(LabeledError::new("Only lines and steps can be filled.").with_label("Fill error.", call.get_flag_span("fill").unwrap_or(call.head)));
+    }
+    let band: Option<Vec<Value>> = call.get_flag("band")?;
+    if band.as_ref().is_some_and(|ends| ends.len() != 2) {
+        return Err(LabeledError::new("--band needs the low and high ends of the band, as [low high].").with_label("Band error.", call.get_flag_span("band").unwrap_or(call.head)));
+    }
+    let bins = match call.get_flag::<Value>("bins")? {
+        None => None,
+        Some(v) => match &v {
+            Value::Int { val, .. } if *val > 0 => Some(Bins::Count(*val as usize)),
+            Value::String { val, .. } => BinRule::from_name(val).map(Bins::Rule),
+            _ => None,
+        }
+        .map(Some)
+        .ok_or_else(|| LabeledError::new("Bins must be a positive integer, or one of auto, sturges, fd or scott.").with_label("Bins error.", v.span()))?,
+    };
+    let bin_width: Option<f64> = call.get_flag("bin-width")?;
+    let edges: Option<Vec<f64>> = call.get_flag("edges")?;
+    let bins = match (bins, bin_width, edges) {
+        (bins, None, None) => bins,
+        (None, Some(width), None) if width.is_finite() && width > 0.0 => Some(Bins::Width(width)),
+        (None, Some(_), None) => return Err(LabeledError::new("The bin width must be a positive number.").with_label("Bins error.", call.head)),
+        (None, None, Some(edges)) if edges.len() >= 2 && edges.iter().all(|e| e.is_finite()) && edges.windows(2).all(|w| w[0] < w[1]) => Some(Bins::Edges(edges)),
+        (None, None, Some(_)) => return Err(LabeledError::new("Edges must be at least two increasing numbers.").with_label("Bins error.", call.head)),
+        _ => return Err(LabeledError::new("Use only one of --bins, --bin-width or --edges.").with_label("Bins error.", call.head)),
+    };
+    let titles = Titles::from_call(call)?;
+    // the rows of a text chart come with a line of x labels and a line
+    // for a legend below, blank without one, and any titles and legend
+    let lines_around = 2 + titles.lines() + (legend && legend_pos == LegendPos::Top) as u32;
+    let (width, height) = chart_size(call, lines_around)?;
+    let cumulative = call.has_flag("cumulative")?;
+    let density = call.has_flag("density")?;
+    let connect_gaps = !call.has_flag("break-gaps")?;
+    let trend = match call.get_flag::<String>("trend")? {
+        None => None,
+        Some(t) => Some(Trend::from_name(&t).ok_or_else(|| {
+            LabeledError::new(format!("Unknown trend {}, use linear, poly:N (N from 1 to {}) or loess.", t, fit::MAX_DEGREE))
+                .with_label("Trend error.", call.head)
+        })?),
+    };
+    let decimate = match (call.get_flag::<String>("decimate")?.as_deref(), call.has_flag("no-downsample")?) {
+        (None, true) => Decimation::Off,
+        (None | Some("lttb"), false) => Decimation::Lttb,
+        (Some("minmax"), false) => Decimation::MinMax,
+        (Some(_), true) => return Err(LabeledError::new("Use either --decimate or --no-downsample, not both.").with_label("Downsampling error.", call.head)),
+        (Some(d), false) => return Err(LabeledError::new(format!("Unknown decimation method {}, use lttb or minmax.", d)).with_label("Downsampling error.", call.head)),
+    };
+    let jitter = match call.get_flag::<f64>("jitter")? {
+        None => None,
+        Some(amount) if amount.is_finite() && amount > 0.0 => Some((amount, call.get_flag::<i64>("seed")?.unwrap_or(0) as u64)),
+        Some(_) => return Err(LabeledError::new("The jitter must be a positive number.").with_label("Jitter error.", call.get_flag_span("jitter").unwrap_or(call.head))),
+    };
+    let order: Option<Vec<Value>> = call.get_flag("order")?;
+    let highlight: Option<Value> = call.get_flag("highlight")?;
+    let panel: Option<Value> = call.get_flag("panel")?;
+    let blend = match call.get_flag::<String>("blend")? {
+        None => Blend::Last,
+        Some(b) => Blend::from_name(&b)
+            .ok_or_else(|| LabeledError::new(format!("Unknown blend {}, use last, mix or highlight.", b)).with_label("Blend error.", call.get_flag_span("blend").unwrap_or(call.head)))?,
+    };
+
+    let mut annotations: Vec<Annotation> = reference_lines(call, "hline")?
+        .into_iter()
+        .map(|(y, label)| Annotation::HLine(y, label))
+        .collect();
+    annotations.extend(
+        reference_lines(call, "vline")?
+            .into_iter()
+            .map(|(x, label)| Annotation::VLine(x, label)),
+    );
+    if let Some(y) = call.get_flag::<f64>("shade-above")? {
+        annotations.push(Annotation::ShadeAbove(y));
+    }
+    if let Some(y) = call.get_flag::<f64>("shade-below")? {
+        annotations.push(Annotation::ShadeBelow(y));
+    }
+    if let Some(range) = call.get_flag::<Vec<f64>>("shade-x")? {
+        match range[..] {
+            [from, to] => annotations.push(Annotation::ShadeX(from, to)),
+            _ => return Err(LabeledError::new("--shade-x takes a list of two numbers, [from to].").with_label("Shading error.", call.head)),
+        }
+    }
+    if call.has_flag("grid")? {
+        annotations.push(Annotation::Grid);
+    }
+    if let Some(name) = call.get_flag::<Spanned<String>>("bg")? {
+        let color = color_from_name(&name.item).ok_or_else(|| {
+            LabeledError::new(format!("Unknown background color {}, use black, red, green, yellow, blue, magenta, cyan or white, bright-red and so on, or #rrggbb.", name.item))
+                .with_label("Background error.", name.span)
+        })?;
+        annotations.push(Annotation::Background(color));
+    }
+
+    let labels = call.has_flag("labels")?;
+    let label_format = match call.get_flag::<String>("label-format")? {
+        None => None,
+        Some(f) => Some(LabelFormat::from_spec(&f).ok_or_else(|| {
+            LabeledError::new(format!("Unknown label format {}, use %.Nf, %.Ne, scientific, si, percent, thousands, bytes, duration or date, or any but the first two and date with :N.", f))
+                .with_label("Label format error.", call.head)
+        })?),
+    };
+    let marks = match call.get_flag::<String>("annotate")? {
+        None => vec![],
+        Some(names) => names
+            .split(',')
+            .map(|name| match name.trim() {
+                "min" => Ok(Mark::Min),
+                "max" => Ok(Mark::Max),
+                "last" => Ok(Mark::Last),
+                m => Err(LabeledError::new(format!("Unknown point to annotate {}, use min, max or last.", m)).with_label("Annotation error.", call.head)),
+            })
+            .collect::<Result<Vec<Mark>, _>>()?,
+    };
+    let polar = call.has_flag("polar")?;
+    let parametric = call.has_flag("parametric")?;
+    let sort_x = call.has_flag("sort-x")?;
+    let aggregate = match call.get_flag::<String>("agg")? {
+        None => None,
+        Some(a) => Some(Aggregate::from_name(&a).ok_or_else(|| {
+            LabeledError::new(format!("Unknown aggregate {}, use mean, sum or max.", a)).with_label("Aggregate error.", call.get_flag_span("agg").unwrap_or(call.head))
+        })?),
+    };
+    let every = match call.get_flag::<Value>("every")? {
+        None => None,
+        Some(Value::Duration { val, .. }) if val > 0 => Some((val as f64, Unit::Nanoseconds)),
+        Some(v @ (Value::Int { .. } | Value::Float { .. })) if value_to_f64(&v, call)? > 0.0 => Some((value_to_f64(&v, call)?, Unit::Number)),
+        Some(v) => {
+            return Err(LabeledError::new(format!("The bucket width must be a positive duration or number, not {}.", v.to_expanded_string(", ", &Config::default())))
+                .with_label("Bucket error.", call.get_flag_span("every").unwrap_or(call.head)))
+        }
+    };
+    if (sort_x || aggregate.is_some() || every.is_some()) && (polar || parametric) {
+        return Err(LabeledError::new("--sort-x, --agg and --every order points by x, so can't go with --polar or --parametric, which draw them in order.").with_label("Sort error.", call.head));
+    }
+    let aspect = match call.get_flag::<f64>("aspect")? {
+        Some(a) if !(a.is_finite() && a > 0.0) => {
+            return Err(LabeledError::new("--aspect takes a ratio greater than 0.").with_label("Aspect error.", call.get_flag_span("aspect").unwrap_or(call.head)))
+        }
+        aspect => aspect,
+    };
+    if panel.is_some() && format != Format::Text {
+        return Err(LabeledError::new("A second panel can only be drawn as text.").with_label("Format error.", call.get_flag_span("format").unwrap_or(call.head)));
+    }
+    let object = call.has_flag("object")?;
+    if object && format != Format::Text {
+        return Err(LabeledError::new("Only a text chart can be returned as a chart value.").with_label("Format error.", call.get_flag_span("object").unwrap_or(call.head)));
+    }
 
     Ok(CliOpts {
         height_op: height,
         width_op: width,
         legend,
+        legend_stats,
+        legend_pos,
+        pad: indent(call)?,
+        names,
+        format,
+        charset,
+        palette,
         steps,
+        step_mode,
         bars,
         points,
+        stem,
+        shapes,
+        bar_layout,
+        fill,
+        band,
         bins,
-        title,
+        cumulative,
+        density,
+        titles,
+        decimate,
+        connect_gaps,
+        jitter,
+        order,
+        highlight,
+        panel,
+        blend,
+        smooth: call.has_flag("smooth")?,
+        trend,
+        annotations,
+        marks,
+        labels,
+        label_format,
+        polar,
+        parametric,
+        sort_x,
+        aggregate,
+        every,
+        aspect,
+        prepare: Prepare::from_call(call)?,
+        object,
+        depth,
+    })
+}
+
+/// The color called `name`: one of the eight terminal colors, a bright
+/// one such as `bright-red`, or any other written `#rrggbb`.
+fn color_from_name(name: &str) -> Option<PixelColor> {
+    Some(match name {
+        hex if hex.starts_with('#') => return PixelColor::from_hex(hex),
+        "black" => PixelColor::Black,
+        "red" => PixelColor::Red,
+        "green" => PixelColor::Green,
+        "yellow" => PixelColor::Yellow,
+        "blue" => PixelColor::Blue,
+        "magenta" => PixelColor::Magenta,
+        "cyan" => PixelColor::Cyan,
+        "white" => PixelColor::White,
+        "bright-black" => PixelColor::BrightBlack,
+        "bright-red" => PixelColor::BrightRed,
+        "bright-green" => PixelColor::BrightGreen,
+        "bright-yellow" => PixelColor::BrightYellow,
+        "bright-blue" => PixelColor::BrightBlue,
+        "bright-magenta" => PixelColor::BrightMagenta,
+        "bright-cyan" => PixelColor::BrightCyan,
+        "bright-white" => PixelColor::BrightWhite,
+        _ => return None,
     })
 }
 
-/// The shape of the plot. Default is `Shape::Lines`,
-/// but also includes `Shape::Bars` and `Shape::Steps`.
-fn chart_shape<'a>(
+/// Parse the reference lines given to `flag`: a number, a `"value:label"`
+/// string, or a list of these.
+fn reference_lines(call: &EvaluatedCall, flag: &str) -> Result<Vec<(f64, Option<String>)>, LabeledError> {
+    let one = |v: &Value| -> Result<(f64, Option<String>), LabeledError> {
+        let err = || LabeledError::new(format!("--{} takes numbers, or \"value:label\" strings.", flag)).with_label("Reference line error.", v.span());
+        match v {
+            Value::Int { val, .. } => Ok((*val as f64, None)),
+            Value::Float { val, .. } => Ok((*val, None)),
+            Value::String { val, .. } => {
+                let (value, label) = match val.split_once(':') {
+                    Some((value, label)) => (value, Some(label.to_string())),
+                    None => (val.as_str(), None),
+                };
+                Ok((value.trim().parse().map_err(|_| err())?, label))
+            }
+            _ => Err(err()),
+        }
+    };
+    match call.get_flag::<Value>(flag)? {
+        None => Ok(vec![]),
+        Some(Value::List { vals, .. }) => vals.iter().map(one).collect(),
+        Some(v) => Ok(vec![one(&v)?]),
+    }
+}
+
+/// A point of a series which `--annotate` labels with its value.
+#[derive(Clone, Copy, PartialEq)]
+enum Mark {
+    /// The lowest point.
+    Min,
+    /// The highest point.
+    Max,
+    /// The final point.
+    Last,
+}
+
+/// Labels for the `marks` of a series, placed at the points themselves
+/// and written as `format` has it. The first of several equal extremes
+/// is labelled, and points which are not finite are passed over.
+fn mark_labels(marks: &[Mark], format: LabelFormat, series: &[(f64, f64)]) -> Vec<Annotation> {
+    let finite: Vec<(f64, f64)> = series
+        .iter()
+        .copied()
+        .filter(|(x, y)| x.is_finite() && y.is_finite())
+        .collect();
+    marks
+        .iter()
+        .filter_map(|mark| match mark {
+            Mark::Min => finite.iter().copied().reduce(|a, b| if b.1 < a.1 { b } else { a }),
+            Mark::Max => finite.iter().copied().reduce(|a, b| if b.1 > a.1 { b } else { a }),
+            Mark::Last => finite.last().copied(),
+        })
+        .map(|(x, y)| Annotation::Label(x, y, format.value(y)))
+        .collect()
+}
+
+/// Labels for the value of every finite point of a series, if `labels`
+/// is set, written as `format` has it. Those which would collide are
+/// left out when drawn.
+fn value_labels(labels: bool, format: LabelFormat, series: &[(f64, f64)]) -> Vec<Annotation> {
+    if !labels {
+        return vec![];
+    }
+    series
+        .iter()
+        .filter(|(x, y)| x.is_finite() && y.is_finite())
+        .map(|(x, y)| Annotation::Value(*x, *y, format.value(*y)))
+        .collect()
+}
+
+/// Add the reference lines to `chart`.
+fn annotate<'a>(chart: &'a mut Chart, annotations: &[Annotation]) -> &'a mut Chart {
+    for annotation in annotations {
+        chart.annotate(annotation.clone());
+    }
+    chart
+}
+
+/// The shape a series is drawn as, as named in `--shapes` or a spec.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SeriesShape {
+    Lines,
+    Points,
+    Steps(StepMode),
+    Bars,
+    Stems,
+}
+
+impl SeriesShape {
+    /// The shape called `name`: `lines`, `points`, `bars`, `stems` or
+    /// `steps`, stepping as `step_mode` says, or as `steps:pre`,
+    /// `steps:post` or `steps:mid` say.
+    fn from_name(name: &str, step_mode: StepMode) -> Option<Self> {
+        match name {
+            "lines" => Some(SeriesShape::Lines),
+            "points" => Some(SeriesShape::Points),
+            "steps" => Some(SeriesShape::Steps(step_mode)),
+            "bars" => Some(SeriesShape::Bars),
+            "stems" => Some(SeriesShape::Stems),
+            _ => name.strip_prefix("steps:").and_then(StepMode::from_name).map(SeriesShape::Steps),
+        }
+    }
+
+    /// The `points` drawn in this shape.
+    fn shape(self, points: Vec<(f64, f64)>) -> Shape {
+        match self {
+            SeriesShape::Lines => Shape::Lines(points),
+            SeriesShape::Points => Shape::Points(points),
+            SeriesShape::Steps(mode) => Shape::Steps(points, mode),
+            SeriesShape::Bars => Shape::Bars(points),
+            SeriesShape::Stems => Shape::Stems(points),
+        }
+    }
+}
+
+/// The shape of the `i`th series, going round `shapes`, if each series
+/// has its own.
+fn nth_shape(shapes: Option<&[SeriesShape]>, i: usize) -> Option<SeriesShape> {
+    shapes.map(|shapes| shapes[i % shapes.len()])
+}
+
+/// Where the `i`th series steps, if it's drawn as steps: as its turn of
+/// `shapes` says, or else as the flags say for them all.
+fn nth_steps(shapes: Option<&[SeriesShape]>, i: usize, steps: bool, step_mode: StepMode) -> Option<StepMode> {
+    match nth_shape(shapes, i) {
+        Some(SeriesShape::Steps(mode)) => Some(mode),
+        Some(_) => None,
+        None => steps.then_some(step_mode),
+    }
+}
+
+/// The shape of a series: `shape`, if it has its own, or else the one
+/// the flags give them all. Default is `Shape::Lines`, but also includes
+/// `Shape::Bars`, `Shape::Steps` and `Shape::Stems`.
+#[allow(clippy::too_many_arguments)]
+fn chart_shape(
+    shape: Option<SeriesShape>,
     steps: bool,
+    step_mode: StepMode,
     bars: bool,
     points: bool,
+    stem: bool,
     call: &EvaluatedCall,
-    v: &'a [(f32, f32)],
-) -> Result<Shape<'a>, LabeledError> {
-    match (steps, bars, points) {
-        (true, false, false) => Ok(Shape::Steps(v)),
-        (false, true, false) => Ok(Shape::Bars(v)),
-        (false, false, true) => Ok(Shape::Points(v)),
-        (false, false, false) => Ok(Shape::Lines(v)),
-        _ => Err(LabeledError::new("Shape must be either steps or bars or points, not more than one. Check your flags!").with_label("Chart shape error", call.head)),
+    v: Vec<(f64, f64)>,
+) -> Result<Shape, LabeledError> {
+    if let Some(shape) = shape {
+        return Ok(shape.shape(v));
+    }
+    match (steps, bars, points, stem) {
+        (true, false, false, false) => Ok(Shape::Steps(v, step_mode)),
+        (false, true, false, false) => Ok(Shape::Bars(v)),
+        (false, false, true, false) => Ok(Shape::Points(v)),
+        (false, false, false, true) => Ok(Shape::Stems(v)),
+        (false, false, false, false) => Ok(Shape::Lines(v)),
+        _ => Err(LabeledError::new("Shape must be either steps or bars or points or stems, not more than one. Check your flags!").with_label("Chart shape error", call.head)),
+    }
+}
+
+/// The shading between two lines, through `points` of an x value and a
+/// low and a high y value, in `color`, under the corners of their steps
+/// if they're drawn as steps.
+fn area(points: Vec<(f64, f64, f64)>, steps: Option<StepMode>, color: Option<PixelColor>) -> Annotation {
+    let points = match steps {
+        // each step rises or falls at the start of its segment, at its
+        // end, or halfway along it
+        Some(StepMode::Pre) => points.windows(2).flat_map(|pair| [pair[0], (pair[0].0, pair[1].1, pair[1].2), pair[1]]).collect(),
+        Some(StepMode::Post) => points.windows(2).flat_map(|pair| [pair[0], (pair[1].0, pair[0].1, pair[0].2), pair[1]]).collect(),
+        Some(StepMode::Mid) => points
+            .windows(2)
+            .flat_map(|pair| {
+                let mid = (pair[0].0 + pair[1].0) / 2.0;
+                [pair[0], (mid, pair[0].1, pair[0].2), (mid, pair[1].1, pair[1].2), pair[1]]
+            })
+            .collect(),
+        None => points,
+    };
+    Annotation::Area(points, color)
+}
+
+/// The points of a band between `low` and `high`, two lines at the same
+/// x values, without those where either isn't finite if gaps are joined.
+fn band_points(low: &[(f64, f64)], high: &[(f64, f64)], connect: bool) -> Vec<(f64, f64, f64)> {
+    let mut points: Vec<(f64, f64, f64)> = low.iter().zip(high).map(|(&(x, low), &(_, high))| (x, low, high)).collect();
+    if connect {
+        points.retain(|(x, low, high)| x.is_finite() && low.is_finite() && high.is_finite());
+    }
+    points
+}
+
+/// Takes the low and high ends of `band`, each the name or index of a
+/// series, out of `series` and their `names`, to shade between rather
+/// than draw.
+fn take_band<T>(call: &EvaluatedCall, band: Option<&[Value]>, series: &mut Vec<T>, names: &mut Vec<String>) -> Result<Option<(T, T)>, LabeledError> {
+    let Some(band) = band else {
+        return Ok(None);
+    };
+    let span = call.get_flag_span("band").unwrap_or(call.head);
+    let index = |end: &Value| match end {
+        Value::String { val, .. } => names
+            .iter()
+            .position(|name| name == val)
+            .ok_or_else(|| LabeledError::new(format!("No series called {} to draw a band with.", val)).with_label("Band error.", span)),
+        Value::Int { val, .. } => usize::try_from(*val)
+            .ok()
+            .filter(|i| *i < series.len())
+            .ok_or_else(|| LabeledError::new(format!("No series {} of {} to draw a band with.", val, series.len())).with_label("Band error.", span)),
+        v => Err(LabeledError::new(format!("Got {} for an end of the band, need the name or index of a series.", v.get_type())).with_label("Band error.", span)),
+    };
+    let (low, high) = (index(&band[0])?, index(&band[1])?);
+    if low == high {
+        return Err(LabeledError::new("The low and high ends of a band must be two series.").with_label("Band error.", span));
+    }
+    if series.len() < 3 {
+        return Err(LabeledError::new("A band needs a series to draw besides its low and high ends.").with_label("Band error.", span));
+    }
+    // the later first, so the earlier keeps its index
+    let (first, last) = (low.min(high), low.max(high));
+    names.remove(last);
+    names.remove(first);
+    let (last, first) = (series.remove(last), series.remove(first));
+    Ok(Some(if low < high { (first, last) } else { (last, first) }))
+}
+
+/// The index of `series`, the name or index of one of the series called
+/// `names`, given to the flag `flag` to `verb` it.
+fn series_index(call: &EvaluatedCall, series: &Value, names: &[String], flag: &str, verb: &str) -> Result<usize, LabeledError> {
+    let span = call.get_flag_span(flag).unwrap_or(call.head);
+    let label = format!("{}{} error.", flag[..1].to_uppercase(), &flag[1..]);
+    match series {
+        Value::String { val, .. } => names
+            .iter()
+            .position(|name| name == val)
+            .ok_or_else(|| LabeledError::new(format!("No series called {} to {}.", val, verb)).with_label(label, span)),
+        Value::Int { val, .. } => usize::try_from(*val)
+            .ok()
+            .filter(|i| *i < names.len())
+            .ok_or_else(|| LabeledError::new(format!("No series {} of {} to {}.", val, names.len(), verb)).with_label(label, span)),
+        v => Err(LabeledError::new(format!("Got {} to {}, need the name or index of a series.", v.get_type(), verb)).with_label(label, span)),
+    }
+}
+
+/// The indices of the series called `names` which `panel`, the name or
+/// index of one, or a list of them, moves to a second panel, leaving at
+/// least one in the first.
+fn panel_series(call: &EvaluatedCall, panel: Option<&Value>, names: &[String]) -> Result<Vec<usize>, LabeledError> {
+    let series = match panel {
+        None => return Ok(vec![]),
+        Some(Value::List { vals, .. }) => vals.as_slice(),
+        Some(series) => std::slice::from_ref(series),
+    };
+    let mut panel = series.iter().map(|series| series_index(call, series, names, "panel", "move to the panel")).collect::<Result<Vec<_>, _>>()?;
+    panel.sort_unstable();
+    panel.dedup();
+    if panel.len() == names.len() {
+        return Err(LabeledError::new("Every series was moved to the panel, leaving none to draw above it.").with_label("Panel error.", call.get_flag_span("panel").unwrap_or(call.head)));
+    }
+    Ok(panel)
+}
+
+/// The order to draw the series called `names` in: the rest first, then
+/// the `highlight`ed one, then those `order` gives, by name or index,
+/// each over the ones before, so the last given is drawn on top.
+fn draw_order(call: &EvaluatedCall, order: Option<&[Value]>, highlight: Option<usize>, names: &[String]) -> Result<Vec<usize>, LabeledError> {
+    let span = call.get_flag_span("order").unwrap_or(call.head);
+    let mut on_top = vec![];
+    for series in order.unwrap_or_default() {
+        let i = series_index(call, series, names, "order", "order")?;
+        if on_top.contains(&i) {
+            return Err(LabeledError::new(format!("The series {} is ordered more than once.", names[i])).with_label("Order error.", span));
+        }
+        on_top.push(i);
     }
+    if let Some(i) = highlight.filter(|i| !on_top.contains(i)) {
+        on_top.insert(0, i);
+    }
+    let mut order: Vec<usize> = (0..names.len()).filter(|i| !on_top.contains(i)).collect();
+    order.extend(on_top);
+    Ok(order)
 }
 
 /// Check the chart shape is Okay. If not returns an error.
-fn check_chart_shape<'a>(
+fn check_chart_shape(
     steps: bool,
     bars: bool,
     points: bool,
+    stem: bool,
     call: &EvaluatedCall,
 ) -> Result<(), LabeledError> {
-    match (steps, bars, points) {
-        (true, false, false) => Ok(()),
-        (false, true, false) => Ok(()),
-        (false, false, true) => Ok(()),
-        (false, false, false) => Ok(()),
-        _ => Err(LabeledError::new("Shape must be either steps or bars or points, not more than one. Check your flags!").with_label("Chart shape error", call.head)),
+    match (steps, bars, points, stem) {
+        (true, false, false, false) => Ok(()),
+        (false, true, false, false) => Ok(()),
+        (false, false, true, false) => Ok(()),
+        (false, false, false, true) => Ok(()),
+        (false, false, false, false) => Ok(()),
+        _ => Err(LabeledError::new("Shape must be either steps or bars or points or stems, not more than one. Check your flags!").with_label("Chart shape error", call.head)),
     }
 }
 
-/// Return the minimum and the maximum of a slice of `f32`.
-fn min_max(series: &[f32]) -> (f32, f32) {
-    let min = series
-        .iter()
-        .fold(std::f32::MAX, |accu, &x| if x < accu { x } else { accu });
-    let max = series
-        .iter()
-        .fold(std::f32::MIN, |accu, &x| if x > accu { x } else { accu });
-    (min, max)
+/// Return the minimum and the maximum of a slice of `f64`,
+/// ignoring values which are not finite, or `None` if none are.
+fn min_max(series: &[f64]) -> Option<(f64, f64)> {
+    extent(series.iter().copied())
+}
+
+/// The minimum and the maximum of `values`, found in one pass without
+/// collecting them, ignoring values which are not finite, or `None` if
+/// none are.
+fn extent(values: impl IntoIterator<Item = f64>) -> Option<(f64, f64)> {
+    values.into_iter().filter(|x| x.is_finite()).fold(None, |range, x| match range {
+        Some((min, max)) => Some((x.min(min), x.max(max))),
+        None => Some((x, x)),
+    })
+}
+
+/// The error for input with nothing to draw, every value of it null,
+/// NaN or infinite.
+fn no_finite_values(call: &EvaluatedCall) -> LabeledError {
+    LabeledError::new("There are no finite values to plot.").with_label("Every value is null, NaN or infinite.", call.head)
+}
+
+/// Sort the points of each of `series` by x if `sort` is set, and
+/// combine those sharing an x value, or in buckets `every` wide, as
+/// `aggregate` says, or into their mean.
+fn sort_series(series: &mut [Vec<(f64, f64)>], sort: bool, aggregate: Option<Aggregate>, every: Option<f64>) {
+    for points in series {
+        match (aggregate, every) {
+            (how, Some(width)) => *points = bucket::bucket(points, width, how.unwrap_or(Aggregate::Mean)),
+            (Some(how), None) => *points = resample::aggregate(points, how),
+            (None, None) if sort => *points = resample::sort_x(points),
+            (None, None) => {}
+        }
+    }
 }
 
-/// Get the type of a `Value`, and its length if it's a list.
-fn get_value_type_or_list_length(val: &Value) -> (Type, Option<usize>) {
-    let typ = val.get_type();
-    let len = match val.as_list() {
-        Ok(l) => Some(l.len()),
-        Err(_) => None,
+/// The width of the buckets `every` gives, after checking it's a
+/// duration for x values which are dates or durations, and a number
+/// for any others, and that it doesn't split `series` into too many.
+fn bucket_width(call: &EvaluatedCall, every: Option<(f64, Unit)>, x_unit: Unit, series: &[Vec<(f64, f64)>]) -> Result<Option<f64>, LabeledError> {
+    let Some((width, unit)) = every else {
+        return Ok(None);
     };
+    let span = call.get_flag_span("every").unwrap_or(call.head);
+    let timed = matches!(x_unit, Unit::Date | Unit::Nanoseconds);
+    if timed != (unit == Unit::Nanoseconds) {
+        let need = if timed { "a duration, as the x values are dates or durations" } else { "a number, as the x values are" };
+        return Err(LabeledError::new(format!("The bucket width must be {}.", need)).with_label("Bucket error.", span));
+    }
+    let range = extent(series.iter().flatten().map(|(x, _)| *x));
+    if range.is_some_and(|(min, max)| (max - min) / width > bucket::MAX_BUCKETS) {
+        return Err(LabeledError::new(format!("--every splits the x values into more than {} buckets, give it a wider one.", bucket::MAX_BUCKETS)).with_label("Bucket error.", span));
+    }
+    Ok(Some(width))
+}
 
-    (typ, len)
+/// Move the points of `series` as `--jitter` says, if at all, each
+/// series taking the next offsets from the one generator.
+fn jitter_series(series: &mut [Vec<(f64, f64)>], jitter: Option<(f64, u64)>) {
+    if let Some((amount, seed)) = jitter {
+        let mut rng = Rng::new(seed);
+        for points in series {
+            *points = jitter::jitter(points, amount, &mut rng);
+        }
+    }
 }
 
-/// Check a list of values for equality of type,
-/// length. Return the type.
-fn check_equality_of_list(
-    l: &[Value],
-    call: &EvaluatedCall,
-) -> Result<(Type, Option<usize>), LabeledError> {
-    let mut types = vec![];
-    let mut len_ops = vec![];
+/// The points of a series, and the x range of its finite points.
+type BuiltSeries = (Vec<(f64, f64)>, Option<(f64, f64)>);
 
-    for val in l {
-        let (typ, len_op) = get_value_type_or_list_length(val);
-        types.push(typ);
-        len_ops.push(len_op);
+/// The points of a series, built a value at a time along with the
+/// range of the x values of its finite points, so a chart can be
+/// ranged without another pass over the data.
+struct SeriesBuilder {
+    points: Vec<(f64, f64)>,
+    x_range: Option<(f64, f64)>,
+    /// Whether gaps are dropped as they come, as [`connect_gaps`] does.
+    connect: bool,
+}
+
+impl SeriesBuilder {
+    /// An empty series with room for `capacity` points.
+    fn new(capacity: usize, connect: bool) -> Self {
+        Self {
+            points: Vec::with_capacity(capacity),
+            x_range: None,
+            connect,
+        }
+    }
+
+    /// The series of `ys` against their indices.
+    fn indexed(ys: &[f64], connect: bool) -> Self {
+        let mut series = Self::new(ys.len(), connect);
+        for (i, y) in ys.iter().enumerate() {
+            series.push((i as f64, *y));
+        }
+        series
+    }
+
+    /// Add a point, unless it is a gap to be dropped.
+    fn push(&mut self, (x, y): (f64, f64)) {
+        if self.connect && !(x.is_finite() && y.is_finite()) {
+            return;
+        }
+        if x.is_finite() && y.is_finite() {
+            self.x_range = Some(self.x_range.map_or((x, x), |(min, max)| (x.min(min), x.max(max))));
+        }
+        self.points.push((x, y));
+    }
+
+    /// The points, and the minimum and maximum of the x values of the
+    /// finite ones, or `None` if none are.
+    fn build(self) -> BuiltSeries {
+        (self.points, self.x_range)
+    }
+}
+
+/// The values of all `series` together, borrowed if there is only one.
+fn pooled(series: &[Vec<f64>]) -> Cow<'_, [f64]> {
+    match series {
+        [single] => Cow::Borrowed(single),
+        series => Cow::Owned(series.concat()),
+    }
+}
+
+/// Drop the gaps from a series, if the line should be joined across them.
+fn connect_gaps(v: &mut Vec<(f64, f64)>, connect: bool) {
+    if connect {
+        v.retain(|(x, y)| x.is_finite() && y.is_finite());
+    }
+}
+
+/// What to do with null values in the input.
+#[derive(Clone, Copy, PartialEq)]
+enum Nulls {
+    /// Nulls are an error.
+    Error,
+    /// Drop the point, joining the points either side of it unless
+    /// `--break-gaps` breaks the line there.
+    Skip,
+    /// Interpolate linearly between the neighbouring values.
+    Linear,
+    /// Repeat the previous value.
+    Previous,
+    /// Replace with zero.
+    Zero,
+}
+
+/// Parse `--skip-nulls` and `--fill-nulls`.
+fn parse_nulls(call: &EvaluatedCall) -> Result<Nulls, LabeledError> {
+    let fill: Option<String> = call.get_flag("fill-nulls")?;
+    match (call.has_flag("skip-nulls")?, fill.as_deref()) {
+        (false, None) => Ok(Nulls::Error),
+        (true, None) => Ok(Nulls::Skip),
+        (false, Some("linear")) => Ok(Nulls::Linear),
+        (false, Some("previous")) => Ok(Nulls::Previous),
+        (false, Some("zero")) => Ok(Nulls::Zero),
+        (true, Some(_)) => Err(LabeledError::new("Use either --skip-nulls or --fill-nulls, not both.").with_label("Null handling error.", call.head)),
+        (false, Some(m)) => Err(LabeledError::new(format!("Unknown null fill method {}, use linear, previous or zero.", m)).with_label("Null handling error.", call.head)),
     }
+}
 
-    // check types are all the same
-    // e.g. Int/Float/List
-    let first_type = &types[0];
-    let check_type_pass = types.iter().all(|e| e == first_type);
+/// Whether `value` is a null, to be skipped or filled.
+fn is_null(value: &Value) -> bool {
+    matches!(value, Value::Nothing { .. })
+}
+
+/// Replace the gaps left by nulls (stored as `NaN`), at the positions
+/// `null` marks, according to `nulls`. Values which were NaN or
+/// infinite themselves stay gaps, and are not filled from.
+///
+/// Nulls at either end of the series, which have no value on one side,
+/// take the nearest value when filling with `Linear` or `Previous`.
+fn fill_nulls(series: &mut [f64], null: &[bool], nulls: Nulls) {
+    let known: Vec<usize> = match nulls {
+        Nulls::Error | Nulls::Skip => return,
+        Nulls::Zero => {
+            series.iter_mut().zip(null).filter(|(_, null)| **null).for_each(|(e, _)| *e = 0.0);
+            return;
+        }
+        Nulls::Linear | Nulls::Previous => (0..series.len()).filter(|&i| series[i].is_finite()).collect(),
+    };
+    let (Some(&first), Some(&last)) = (known.first(), known.last()) else {
+        return;
+    };
 
-    if !check_type_pass {
-        return Err(LabeledError::new("Can't plot a list of multiple types.").with_label("Type differences.", call.head) );
+    let (head, tail) = (series[first], series[last]);
+    (0..first).chain(last + 1..series.len()).filter(|&i| null[i]).for_each(|i| series[i] = if i < first { head } else { tail });
+
+    for pair in known.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        for i in (a + 1..b).filter(|&i| null[i]) {
+            series[i] = match nulls {
+                Nulls::Linear => series[a] + (series[b] - series[a]) * (i - a) as f64 / (b - a) as f64,
+                _ => series[a],
+            };
+        }
+    }
+}
+
+/// The quantile `--winsorize` clips at.
+const WINSORIZE: f64 = 0.99;
+
+/// What is done to the y values of each series before it is plotted,
+/// after nulls are filled, in this order.
+#[derive(Clone, Copy, Default)]
+struct Prepare {
+    /// A running sum or difference, from `--cumsum` or `--diff`.
+    running: Option<Running>,
+    /// Added to each value, from `--offset`.
+    offset: f64,
+    /// A function each value is put through, from `--transform`.
+    rescale: Option<Rescale>,
+    /// The quantile values beyond are capped at, from `--clip-quantile`
+    /// or `--winsorize`.
+    clip: Option<f64>,
+    /// From `--normalize`.
+    normalize: Option<Normalize>,
+    /// The number of values capped so far.
+    clipped: usize,
+}
+
+impl Prepare {
+    /// Parse `--cumsum`, `--diff`, `--offset`, `--transform`,
+    /// `--clip-quantile`, `--winsorize` and `--normalize`.
+    fn from_call(call: &EvaluatedCall) -> Result<Self, LabeledError> {
+        let running = match (call.has_flag("cumsum")?, call.has_flag("diff")?) {
+            (false, false) => None,
+            (true, false) => Some(Running::Cumsum),
+            (false, true) => Some(Running::Diff),
+            (true, true) => return Err(LabeledError::new("Use either --cumsum or --diff, not both.").with_label("Transform error.", call.head)),
+        };
+        let rescale = match call.get_flag::<String>("transform")? {
+            None => None,
+            Some(t) => Some(Rescale::from_name(&t).ok_or_else(|| {
+                LabeledError::new(format!("Unknown transform {}, use log, log1p or sqrt.", t)).with_label("Transform error.", call.get_flag_span("transform").unwrap_or(call.head))
+            })?),
+        };
+        let normalize = match call.get_flag::<String>("normalize")? {
+            None => None,
+            Some(n) => Some(Normalize::from_name(&n).ok_or_else(|| {
+                LabeledError::new(format!("Unknown normalization {}, use minmax, zscore or index100.", n))
+                    .with_label("Normalization error.", call.get_flag_span("normalize").unwrap_or(call.head))
+            })?),
+        };
+        let clip = match (call.get_flag::<f64>("clip-quantile")?, call.has_flag("winsorize")?) {
+            (None, false) => None,
+            (None, true) => Some(WINSORIZE),
+            (Some(q), false) if (0.0..=1.0).contains(&q) => Some(q),
+            (Some(q), false) => {
+                return Err(LabeledError::new(format!("The clip quantile must be between 0 and 1, not {}.", q)).with_label("Clipping error.", call.get_flag_span("clip-quantile").unwrap_or(call.head)))
+            }
+            (Some(_), true) => return Err(LabeledError::new("Use either --clip-quantile or --winsorize, not both.").with_label("Clipping error.", call.head)),
+        };
+        let offset = call.get_flag::<f64>("offset")?.unwrap_or(0.0);
+        Ok(Prepare { running, offset, rescale, clip, normalize, clipped: 0 })
+    }
+
+    /// Prepare the y values `ys` of a series, counting the values
+    /// clipped.
+    fn apply(&mut self, ys: &mut [f64]) {
+        if let Some(running) = self.running {
+            running.apply(ys);
+        }
+        if self.offset != 0.0 || self.rescale.is_some() {
+            ys.iter_mut().for_each(|y| *y = self.rescale.map_or(*y + self.offset, |r| r.apply(*y + self.offset)));
+        }
+        if let Some(q) = self.clip {
+            self.clipped += winsorize(ys, q);
+        }
+        if let Some(normalize) = self.normalize {
+            normalize.apply(ys);
+        }
+    }
+
+    /// Prepare the y values of each of `series`, leaving x as it is.
+    fn apply_points(&mut self, series: &mut [Vec<(f64, f64)>]) {
+        for points in series {
+            let mut ys: Vec<f64> = points.iter().map(|(_, y)| *y).collect();
+            self.apply(&mut ys);
+            points.iter_mut().zip(ys).for_each(|(point, y)| point.1 = y);
+        }
+    }
+
+    /// How to write the y labels of prepared values, `format` as it
+    /// would be: as the values they were shifted and rescaled from, in
+    /// plain decimals, unless normalizing leaves no way back to them.
+    fn y_labels(&self, format: LabelFormat) -> LabelFormat {
+        match self.normalize {
+            None if self.offset != 0.0 || self.rescale.is_some() => LabelFormat::Rescaled(self.rescale, self.offset),
+            _ => format,
+        }
+    }
+
+    /// Add the number of values clipped to `meta`, if any could be.
+    fn count(&self, meta: &mut Meta) {
+        if self.clip.is_some() {
+            *meta.clipped.get_or_insert(0) += self.clipped;
+        }
+    }
+
+    /// The legend entry for the values clipped, if any could be.
+    fn legend(&self) -> Vec<LegendEntry> {
+        match self.clip {
+            Some(q) => {
+                // 1 - q as written, rather than a rounding error off it
+                let (low, high) = (((1.0 - q) * 1e9).round() / 1e9, q);
+                vec![vec![(format!("Clipped: {} outside quantiles {} to {}", self.clipped, low.min(high), low.max(high)), None)]]
+            }
+            None => vec![],
+        }
+    }
+}
+
+/// Facts about the input, returned alongside the chart with `--meta`.
+#[derive(Default)]
+struct Meta {
+    /// Number of series plotted.
+    series: usize,
+    /// Number of values in the input.
+    points: usize,
+    /// Number of null values.
+    nulls: usize,
+    /// Number of `NaN` or infinite values, which are not plotted.
+    non_finite: usize,
+    /// Number of values capped by `--clip-quantile` or `--winsorize`.
+    clipped: Option<usize>,
+    /// Number of bins in a histogram.
+    bins: Option<usize>,
+    /// The rule used to choose the number of bins.
+    bin_rule: Option<BinRule>,
+    /// The bandwidth of a kernel density estimate.
+    bandwidth: Option<f64>,
+    /// The kernel of a kernel density estimate.
+    kernel: Option<Kernel>,
+    /// The unit of each list of the input: of the single list, of each
+    /// of a nested list, or of the x and the y values of records.
+    units: Vec<Unit>,
+}
+
+/// What the numbers of a list were before they were plotted: plain
+/// numbers, filesizes or durations, counted in bytes or nanoseconds,
+/// dates, in nanoseconds since 1970 began, bools, as 0 and 1, or the
+/// bytes of binary data.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Unit {
+    #[default]
+    Number,
+    Bytes,
+    Nanoseconds,
+    Date,
+    Bool,
+    Binary,
+}
+
+impl Unit {
+    /// The unit of `value`, or `None` if it's null.
+    fn of(value: &Value) -> Option<Unit> {
+        match value {
+            Value::Filesize { .. } => Some(Unit::Bytes),
+            Value::Duration { .. } => Some(Unit::Nanoseconds),
+            Value::Date { .. } => Some(Unit::Date),
+            Value::Bool { .. } => Some(Unit::Bool),
+            Value::Nothing { .. } => None,
+            _ => Some(Unit::Number),
+        }
+    }
+
+    /// Note the unit of `value` in `seen`, the unit of the values of a
+    /// list so far, which is plain numbers once they differ.
+    fn see(seen: &mut Option<Unit>, value: &Value) {
+        if let Some(unit) = Unit::of(value) {
+            *seen = Some(match *seen {
+                Some(s) if s != unit => Unit::Number,
+                _ => unit,
+            });
+        }
+    }
+
+    /// The unit of all of `units`, or plain numbers if they differ.
+    fn shared(mut units: impl Iterator<Item = Unit>) -> Unit {
+        let first = units.next().unwrap_or_default();
+        if units.all(|u| u == first) {
+            first
+        } else {
+            Unit::Number
+        }
+    }
+
+    /// How labels of numbers in this unit are written, unless
+    /// `--label-format` says otherwise.
+    fn label_format(self) -> LabelFormat {
+        match self {
+            Unit::Number | Unit::Bool | Unit::Binary => LabelFormat::Auto,
+            Unit::Bytes => LabelFormat::Bytes(None),
+            Unit::Nanoseconds => LabelFormat::Duration(None),
+            Unit::Date => LabelFormat::Date,
+        }
+    }
+}
+
+/// How to write the numbers of the x and y axes, of values in `units`:
+/// as `label_format`, from `--label-format`, has it, or else as suits
+/// the units.
+fn label_formats(label_format: Option<LabelFormat>, (x, y): (Unit, Unit)) -> (LabelFormat, LabelFormat) {
+    match label_format {
+        Some(format) => (format, format),
+        None => (x.label_format(), y.label_format()),
+    }
+}
+
+impl Meta {
+    /// The unit shared by the lists of the input from the `from`th on.
+    fn units_from(&self, from: usize) -> Unit {
+        Unit::shared(self.units.iter().skip(from).copied())
+    }
+
+    /// The units of the x and y values of an xyplot: of the first list
+    /// and the rest, or of the x and y values of records.
+    fn xy_units(&self) -> (Unit, Unit) {
+        (self.units.first().copied().unwrap_or_default(), self.units_from(1))
+    }
+
+    /// Add the counts of `other`, the input of another chart drawn
+    /// alongside this one.
+    fn add(&mut self, other: &Meta) {
+        self.series += other.series;
+        self.points += other.points;
+        self.nulls += other.nulls;
+        self.non_finite += other.non_finite;
+        if let Some(clipped) = other.clipped {
+            *self.clipped.get_or_insert(0) += clipped;
+        }
+    }
+
+    /// A record of the chart and the metadata.
+    fn into_value(self, chart: Value, span: Span) -> Value {
+        let mut record = record! { "chart" => chart };
+        record.extend(self.facts(span));
+        Value::record(record, span)
+    }
+
+    /// A record of the metadata alone.
+    fn facts(&self, span: Span) -> Record {
+        let mut record = record! {
+            "series" => Value::int(self.series as i64, span),
+            "points" => Value::int(self.points as i64, span),
+            "nulls" => Value::int(self.nulls as i64, span),
+            "non_finite" => Value::int(self.non_finite as i64, span),
+        };
+        if let Some(clipped) = self.clipped {
+            record.push("clipped", Value::int(clipped as i64, span));
+        }
+        if let Some(bins) = self.bins {
+            record.push("bins", Value::int(bins as i64, span));
+        }
+        if let Some(rule) = self.bin_rule {
+            record.push("bin_rule", Value::string(rule.name(), span));
+        }
+        if let Some(bandwidth) = self.bandwidth {
+            record.push("bandwidth", Value::float(bandwidth, span));
+        }
+        if let Some(kernel) = self.kernel {
+            record.push("kernel", Value::string(kernel.name(), span));
+        }
+        record
+    }
+}
+
+/// More bins than this can't be told apart on any terminal.
+const MAX_BINS: f64 = 10_000.0;
+
+/// The histogram bin edges for `data`, which lies between `min` and
+/// `max`, recording the number of bins in the metadata. Defaults to
+/// 20 bins.
+fn hist_edges(
+    bins: Option<Bins>,
+    data: &[f64],
+    (min, max): (f64, f64),
+    call: &EvaluatedCall,
+    meta: &mut Meta,
+) -> Result<Vec<f64>, LabeledError> {
+    let bins = bins.unwrap_or(Bins::Count(20));
+    if let Bins::Width(width) = bins {
+        if (max - min) / width > MAX_BINS {
+            return Err(LabeledError::new(format!("A bin width of {} gives too many bins for the data.", width)).with_label("Bins error.", call.head));
+        }
+    }
+    let edges = bins.edges(data, min, max);
+    meta.bins = Some(edges.len() - 1);
+    if let Bins::Rule(rule) = bins {
+        meta.bin_rule = Some(rule);
+    }
+    Ok(edges)
+}
+
+/// The names of `n` series for the legend: those given with `--names`,
+/// which must be one for each series, or else `Line 1`, `Line 2` and so on.
+fn series_names(call: &EvaluatedCall, names: Option<&[String]>, n: usize) -> Result<Vec<String>, LabeledError> {
+    match names {
+        None => Ok((1..=n).map(|l| format!("Line {}", l)).collect()),
+        Some(names) if names.len() == n => Ok(names.to_vec()),
+        Some(names) => Err(LabeledError::new(format!("Got {} names for {} series, --names needs one for each series.", names.len(), n)).with_label("Names error.", call.head)),
+    }
+}
+
+/// A gradient colors a lone series only, so error for a chart of several.
+fn check_gradient(palette: &Palette, call: &EvaluatedCall) -> Result<(), LabeledError> {
+    match palette.gradient {
+        Some(_) => Err(LabeledError::new("A gradient can only color a single series.").with_label("Gradient error.", call.head)),
+        None => Ok(()),
+    }
+}
+
+/// The legend entry for the series called `name`, drawn in `color` and
+/// with its `marker`, if it has one. Given the values of the series, the
+/// entry also shows their min, mean, max, last value and count, written
+/// as `format` has it, ignoring those which are not finite.
+fn legend_entry(name: &str, color: PixelColor, marker: Option<char>, stats: Option<&[f64]>, format: LabelFormat) -> LegendEntry {
+    let sample = match marker {
+        Some(marker) => format!("-{}-", marker),
+        None => "---".to_string(),
+    };
+    let mut entry = vec![(format!("{}: ", name), None), (sample, Some(color))];
+    let finite: Vec<f64> = stats.unwrap_or_default().iter().copied().filter(|y| y.is_finite()).collect();
+    if let (Some((min, max)), Some(&last)) = (min_max(&finite), finite.last()) {
+        let mean = finite.iter().sum::<f64>() / finite.len() as f64;
+        let desc = format!(
+            "min {} mean {} max {} last {} n {}",
+            format.value(min),
+            format.value(mean),
+            format.value(max),
+            format.value(last),
+            finite.len()
+        );
+        entry.push((format!(" {}", desc), Some(color)));
+    }
+    entry
+}
+
+/// The legend entry for a histogram whose bins were chosen by a rule.
+fn bins_legend(meta: &Meta) -> Vec<LegendEntry> {
+    match (meta.bins, meta.bin_rule) {
+        (Some(n), Some(rule)) => vec![vec![(format!("Bins: {} ({})", n, rule.name()), None)]],
+        _ => vec![],
+    }
+}
+
+/// Fit the `--trend`, if there is one, to `data`, evaluating it at
+/// `points` places across `x_range`.
+fn fit_trend(
+    call: &EvaluatedCall,
+    trend: Option<Trend>,
+    data: &[(f64, f64)],
+    x_range: (f64, f64),
+    points: u32,
+) -> Result<Option<Fit>, LabeledError> {
+    let Some(trend) = trend else {
+        return Ok(None);
+    };
+    trend
+        .fit(data, x_range, points as usize)
+        .map(Some)
+        .ok_or_else(|| LabeledError::new("Not enough distinct points to fit the trend.").with_label("Trend error.", call.head))
+}
+
+/// The legend entry for a fitted trend, drawn in `color`.
+fn trend_legend(fit: &Fit, color: PixelColor) -> LegendEntry {
+    let desc = match (fit.line, fit.r_squared) {
+        (Some((slope, intercept)), Some(r2)) => format!("y = {:.3}x + {:.3}, R² = {:.3}", slope, intercept, r2),
+        (None, Some(r2)) => format!("R² = {:.3}", r2),
+        _ => "loess".to_string(),
+    };
+    vec![
+        ("Trend: ".to_string(), None),
+        ("---".to_string(), Some(color)),
+        (format!(" {}", desc), None),
+    ]
+}
+
+/// The names of the shapes of a chart of one series, `names[0]`, and the
+/// trend line through it, if there is one.
+fn shape_names(names: &[String], fit: Option<&Fit>) -> Vec<String> {
+    names.iter().take(1).cloned().chain(fit.map(|_| "Trend".to_string())).collect()
+}
+
+/// Where the legend goes.
+#[derive(Clone, Copy, PartialEq)]
+enum LegendPos {
+    /// On a line above the plot.
+    Top,
+    /// On a line below the plot.
+    Bottom,
+    /// One entry per line, to the right of the plot.
+    Right,
+    /// In a box in the top right corner of the plot.
+    Inside,
+}
+
+/// How the title, subtitle and caption line up across the chart.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum TitleAlign {
+    /// Against the left of the chart.
+    #[default]
+    Left,
+    /// In the middle of it.
+    Center,
+    /// Against the right of it.
+    Right,
+}
+
+/// The title of a chart, a subtitle under it, and a caption under the
+/// chart, from `--title`, `--subtitle` and `--caption`, lined up as
+/// `--title-align` says.
+#[derive(Clone, Debug, Default)]
+struct Titles {
+    title: Option<String>,
+    subtitle: Option<String>,
+    caption: Option<String>,
+    align: TitleAlign,
+}
+
+impl Titles {
+    /// The titles `call` gives.
+    fn from_call(call: &EvaluatedCall) -> Result<Self, LabeledError> {
+        let align = match call.get_flag::<String>("title-align")?.as_deref() {
+            None | Some("left") => TitleAlign::Left,
+            Some("center") => TitleAlign::Center,
+            Some("right") => TitleAlign::Right,
+            Some(a) => {
+                return Err(LabeledError::new(format!("Unknown title alignment {}, use left, center or right.", a))
+                    .with_label("Title error.", call.get_flag_span("title-align").unwrap_or(call.head)))
+            }
+        };
+        Ok(Titles { title: call.get_flag("title")?, subtitle: call.get_flag("subtitle")?, caption: call.get_flag("caption")?, align })
+    }
+
+    /// The lines of text the titles take up, unwrapped.
+    fn lines(&self) -> u32 {
+        [&self.title, &self.subtitle, &self.caption].iter().filter(|t| t.is_some()).count() as u32
+    }
+
+    /// The title and subtitle, to go above a chart `width` columns wide
+    /// after `pad`.
+    fn head(&self, width: usize, pad: &str) -> String {
+        [&self.title, &self.subtitle].into_iter().flatten().map(|t| self.aligned(t, width, pad)).collect()
+    }
+
+    /// The caption, to go below a chart `width` columns wide after `pad`.
+    fn foot(&self, width: usize, pad: &str) -> String {
+        self.caption.iter().map(|t| self.aligned(t, width, pad)).collect()
+    }
+
+    /// `body`, charts already indented by `pad`, with the title and
+    /// subtitle above and the caption below, lined up across it.
+    fn around(&self, body: &str, pad: &str) -> String {
+        let width = body.lines().map(visible_len).max().unwrap_or(0).saturating_sub(pad.len());
+        let mut out = self.head(width, pad) + body;
+        if self.caption.is_some() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out + &self.foot(width, pad)
+    }
+
+    /// `text` wrapped to `width` columns, each line lined up across them
+    /// after `pad` and ending in a newline.
+    fn aligned(&self, text: &str, width: usize, pad: &str) -> String {
+        wrap(text, width)
+            .into_iter()
+            .map(|line| {
+                let space = width.saturating_sub(visible_len(&line));
+                let indent = match self.align {
+                    TitleAlign::Left => 0,
+                    TitleAlign::Center => space / 2,
+                    TitleAlign::Right => space,
+                };
+                format!("{}{}{}\n", pad, " ".repeat(indent), line)
+            })
+            .collect()
+    }
+}
+
+/// What to draw a chart as.
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    /// Braille and colored text, for the terminal.
+    Text,
+    /// An SVG document.
+    Svg,
+    /// A PNG image.
+    Png,
+    /// A Vega-Lite spec, as a record.
+    Vega,
+}
+
+/// A new chart, to be drawn as `format`, with the characters of
+/// `charset` if it's drawn as text, and the numbers of its x and y axes
+/// written as `labels` has them.
+fn new_chart(format: Format, charset: Charset, labels: (LabelFormat, LabelFormat), width: u32, height: u32, xmin: f64, xmax: f64) -> Chart {
+    let mut chart = match format {
+        Format::Text if charset == Charset::Blocks => Chart::with_backend(Box::new(BlockCanvas::new(width, height)), width, height, xmin, xmax),
+        Format::Text => Chart::with_backend(Box::new(BrailleCanvas::with_charset(width, height, charset)), width, height, xmin, xmax),
+        // a spec is made from the chart's shapes, which are never drawn
+        Format::Vega => Chart::new(width, height, xmin, xmax),
+        Format::Svg => Chart::with_backend(Box::new(SvgCanvas::new()), width, height, xmin, xmax),
+        Format::Png => Chart::with_backend(Box::new(PngCanvas::new(width, height)), width, height, xmin, xmax),
+    };
+    chart.label_format(labels.0, labels.1);
+    chart
+}
+
+/// A legend entry as colored text.
+fn legend_text(entry: &LegendEntry) -> String {
+    entry
+        .iter()
+        .map(|(text, color)| match color {
+            Some(color) => text.color(*color).to_string(),
+            None => text.clone(),
+        })
+        .collect()
+}
+
+/// The number of characters `s` takes up on the terminal, leaving out
+/// color codes.
+fn visible_len(s: &str) -> usize {
+    strip_escapes(s).chars().count()
+}
+
+/// `name` cut to `len` characters, ending in an ellipsis if it was cut.
+fn truncate(name: &str, len: usize) -> String {
+    if name.chars().count() <= len {
+        return name.to_string();
+    }
+    match len {
+        0 => String::new(),
+        len => name.chars().take(len - 1).chain(['…']).collect(),
+    }
+}
+
+/// The lines of `text` wrapped at spaces to fit in `width` columns, and
+/// words longer than that broken where they reach it. Lines of `text`
+/// stay apart.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = vec![];
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let chars: Vec<char> = word.chars().collect();
+            for piece in chars.chunks(width) {
+                let piece: String = piece.iter().collect();
+                if !line.is_empty() && visible_len(&line) + 1 + visible_len(&piece) > width {
+                    lines.push(std::mem::take(&mut line));
+                }
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line += &piece;
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// `s` with its escape codes, such as those coloring it, taken out.
+fn strip_escapes(s: &str) -> String {
+    #[derive(Clone, Copy)]
+    enum State {
+        Text,
+        Escape,
+        Sequence,
+    }
+    let mut state = State::Text;
+    s.chars()
+        .filter(|&c| {
+            let (next, keep) = match (state, c) {
+                (State::Text, '\x1b') => (State::Escape, false),
+                (State::Text, _) => (State::Text, true),
+                (State::Escape, '[') => (State::Sequence, false),
+                (State::Escape, _) => (State::Text, false),
+                // a sequence runs up to and including its final byte
+                (State::Sequence, '@'..='~') => (State::Text, false),
+                (State::Sequence, _) => (State::Sequence, false),
+            };
+            state = next;
+            keep
+        })
+        .collect()
+}
+
+/// Flags which can be given defaults in `$env.config.plugins.plot`.
+const CONFIG_FLAGS: &[&str] = &["palette", "markers", "charset", "legend", "width", "height", "pad"];
+
+/// `call` with the defaults in `$env.config.plugins.plot` added for the
+/// `CONFIG_FLAGS` it doesn't give itself, so flags on the command line
+/// win over the config.
+/// The size of the terminal goes along with them, as `with_terminal`
+/// leaves it.
+fn with_config(engine: &nu_plugin::EngineInterface, call: &EvaluatedCall) -> Result<EvaluatedCall, LabeledError> {
+    let mut call = with_terminal(engine, call)?;
+    let config = match engine.get_plugin_config()? {
+        None => return Ok(call),
+        Some(Value::Record { val, .. }) => val,
+        Some(v) => return Err(LabeledError::new("The config in $env.config.plugins.plot must be a record.").with_label("Config error.", v.span())),
+    };
+    for (name, value) in config.iter() {
+        if CONFIG_FLAGS.contains(&name.as_str()) && !call.named.iter().any(|(flag, _)| &flag.item == name) {
+            call.named.push((Spanned { item: name.clone(), span: value.span() }, Some(value.clone())));
+        }
+    }
+    Ok(call)
+}
+
+/// The columns and lines of the terminal nushell draws in: as
+/// `$env.COLUMNS` and `$env.LINES` give them, if they are set, or else
+/// those of the terminal the plugin shares with it.
+fn terminal_size(engine: &nu_plugin::EngineInterface) -> Result<Option<(u32, u32)>, LabeledError> {
+    let env = |name: &str| -> Result<Option<u32>, LabeledError> {
+        Ok(engine.get_env_var(name)?.and_then(|v| v.coerce_into_string().ok()).and_then(|v| v.trim().parse().ok()))
+    };
+    if let Some(size) = env("COLUMNS")?.zip(env("LINES")?) {
+        return Ok(Some(size));
+    }
+    // the first of stdout, stderr and stdin that is a terminal; over
+    // stdio, stdout is nushell's pipe, so it's stderr or stdin
+    Ok(terminal_size::terminal_size().map(|(Width(columns), Height(lines))| (columns as u32, lines as u32)))
+}
+
+/// `call` with the columns and lines of the terminal, if it has a size,
+/// left under `TERMINAL` for `terminal` to find, and how many colors it
+/// shows, under `COLOR_DEPTH` for `color_depth`.
+fn with_terminal(engine: &nu_plugin::EngineInterface, call: &EvaluatedCall) -> Result<EvaluatedCall, LabeledError> {
+    let mut call = call.clone();
+    if call.get_flag_span(TERMINAL).is_none() {
+        if let Some((columns, lines)) = terminal_size(engine)? {
+            let size = Value::list(vec![Value::int(columns as i64, call.head), Value::int(lines as i64, call.head)], call.head);
+            call.named.push((Spanned { item: TERMINAL.to_string(), span: call.head }, Some(size)));
+        }
+    }
+    if call.get_flag_span(COLOR_DEPTH).is_none() {
+        let colors = match terminal_color_depth(engine)? {
+            ColorDepth::Ansi16 => 16,
+            ColorDepth::Ansi256 => 256,
+            ColorDepth::TrueColor => 1 << 24,
+        };
+        call.named.push((Spanned { item: COLOR_DEPTH.to_string(), span: call.head }, Some(Value::int(colors, call.head))));
+    }
+    Ok(call)
+}
+
+/// How many colors the terminal shows: any, if `$env.COLORTERM` is
+/// `truecolor` or `24bit`, 256 if `$env.TERM` names a 256 color
+/// terminal, such as `xterm-256color`, or else the sixteen terminal
+/// colors.
+fn terminal_color_depth(engine: &nu_plugin::EngineInterface) -> Result<ColorDepth, LabeledError> {
+    let env = |name: &str| -> Result<Option<String>, LabeledError> {
+        Ok(engine.get_env_var(name)?.and_then(|v| v.coerce_into_string().ok()))
+    };
+    if matches!(env("COLORTERM")?.as_deref(), Some("truecolor" | "24bit")) {
+        return Ok(ColorDepth::TrueColor);
+    }
+    Ok(match env("TERM")? {
+        Some(term) if term.contains("256color") => ColorDepth::Ansi256,
+        _ => ColorDepth::Ansi16,
+    })
+}
+
+/// How many colors the terminal shows, as `with_terminal` left it in
+/// `call`, or sixteen if it left nothing.
+fn color_depth(call: &EvaluatedCall) -> Result<ColorDepth, LabeledError> {
+    Ok(match call.get_flag::<i64>(COLOR_DEPTH)? {
+        Some(colors) if colors > 256 => ColorDepth::TrueColor,
+        Some(256) => ColorDepth::Ansi256,
+        _ => ColorDepth::Ansi16,
+    })
+}
+
+/// The columns and lines of the terminal, as `with_terminal` left them
+/// in `call`.
+fn terminal(call: &EvaluatedCall) -> Result<Option<(u32, u32)>, LabeledError> {
+    Ok(call.get_flag::<Vec<i64>>(TERMINAL)?.and_then(|size| match size[..] {
+        [columns, lines] => Some((columns as u32, lines as u32)),
+        _ => None,
+    }))
+}
+
+/// The size given by the flag `flag` of `call`: a number, or a
+/// percentage such as `80%` of `whole`, the size of the terminal.
+fn size_flag(call: &EvaluatedCall, flag: &str, whole: Option<u32>) -> Result<Option<u32>, LabeledError> {
+    let span = call.get_flag_span(flag).unwrap_or(call.head);
+    match call.get_flag_value(flag) {
+        None => Ok(None),
+        Some(Value::String { val, .. }) => {
+            let percent = val.strip_suffix('%').and_then(|p| p.trim().parse::<f64>().ok()).filter(|p| *p > 0.0).ok_or_else(|| {
+                LabeledError::new(format!("Unknown size {}, use a number or a percentage such as 80%.", val)).with_label("Size error.", span)
+            })?;
+            match whole {
+                Some(whole) => Ok(Some((whole as f64 * percent / 100.0).round() as u32)),
+                None => Err(LabeledError::new(format!("Can't size the chart to {} of the terminal, which has no size.", val)).with_label("Size error.", span)),
+            }
+        }
+        Some(value) => match value.as_int()? {
+            size if size > 0 => Ok(Some(size.min(u32::MAX.into()) as u32)),
+            size => Err(LabeledError::new(format!("Can't size the chart to {}, use a positive number or a percentage such as 80%.", size)).with_label("Size error.", span)),
+        },
+    }
+}
+
+/// Whether to color a text chart, from `--color always|auto|never`,
+/// unless `--raw` asks for plain text.
+///
+/// Left to `auto`, charts are colored unless `NO_COLOR` is set,
+/// `CLICOLOR` is `0`, nushell has ANSI coloring turned off, or the
+/// chart is saved to a file or otherwise not going to a terminal.
+fn use_color(engine: &nu_plugin::EngineInterface, call: &EvaluatedCall) -> Result<bool, LabeledError> {
+    if call.has_flag("raw")? {
+        return Ok(false);
+    }
+    match call.get_flag::<String>("color")?.as_deref() {
+        Some("always") => return Ok(true),
+        Some("never") => return Ok(false),
+        None | Some("auto") => (),
+        Some(c) => return Err(LabeledError::new(format!("Unknown color mode {}, use always, auto or never.", c)).with_label("Color error.", call.head)),
+    }
+    if call.get_flag::<String>("output")?.is_some() {
+        return Ok(false);
+    }
+    let env = |name: &str| -> Result<Option<String>, LabeledError> {
+        Ok(engine.get_env_var(name)?.and_then(|v| v.coerce_into_string().ok()))
+    };
+    if env("NO_COLOR")?.is_some_and(|v| !v.is_empty()) || env("CLICOLOR")?.as_deref() == Some("0") {
+        return Ok(false);
+    }
+    if !engine.get_config()?.use_ansi_coloring {
+        return Ok(false);
+    }
+    // over stdio, the plugin's stdout is nushell's pipe, so ask of stderr instead
+    Ok(if engine.is_using_stdio() {
+        std::io::stderr().is_terminal()
+    } else {
+        std::io::stdout().is_terminal()
+    })
+}
+
+/// Draw `chart` with its annotations, then indent it and add the title
+/// above it and the `legend`, if it has any entries, where `pos` puts it.
+///
+/// SVG and PNG charts are files of their own, so they draw the title
+/// themselves, and always box the legend inside the plot. A PNG chart
+/// is returned as binary. A Vega-Lite spec calls the shapes of the
+/// chart `names`, and has a legend of its own if `legend` has entries,
+/// as does a text chart returned as a chart value, if `object` is set.
+#[allow(clippy::too_many_arguments)]
+fn finish_chart(
+    chart: &mut Chart,
+    format: Format,
+    names: &[String],
+    annotations: &[Annotation],
+    titles: &Titles,
+    legend: &[LegendEntry],
+    pos: LegendPos,
+    pad: &str,
+    object: bool,
+    span: Span,
+) -> Value {
+    if format == Format::Vega {
+        return vega::spec(chart, names, annotations, titles, !legend.is_empty(), span);
+    }
+    let chart = annotate(chart, annotations);
+    if format != Format::Text {
+        if let Some(t) = &titles.title {
+            chart.title(t);
+        }
+        let chart = chart.legend(legend);
+        return match format {
+            Format::Png => Value::binary(chart.to_bytes(), span),
+            _ => Value::string(chart.to_string(), span),
+        };
+    }
+    if pos == LegendPos::Inside {
+        chart.legend(legend);
+    }
+    let frame = chart.to_string();
+    finish_text(chart, frame, names, titles, legend, pos, pad, object, span)
+}
+
+/// Indent `frame`, the text of `chart` as drawn, and add the title above
+/// it and the `legend`, if it has any entries, where `pos` puts it, as
+/// `finish_chart` does for a text chart. Returned as a chart value of
+/// `chart` if `object` is set.
+#[allow(clippy::too_many_arguments)]
+fn finish_text(
+    chart: &Chart,
+    mut frame: String,
+    names: &[String],
+    titles: &Titles,
+    legend: &[LegendEntry],
+    pos: LegendPos,
+    pad: &str,
+    object: bool,
+    span: Span,
+) -> Value {
+    let inline = legend.iter().map(legend_text).collect::<Vec<_>>().join(" ");
+
+    if pos == LegendPos::Right && !legend.is_empty() {
+        let rows: Vec<&str> = frame.lines().collect();
+        let width = rows.iter().map(|row| visible_len(row)).max().unwrap_or(0);
+        frame = rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| match legend.get(i) {
+                Some(entry) => format!("{}{}  {}", row, " ".repeat(width - visible_len(row)), legend_text(entry)),
+                None => row.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+            + "\n";
+    }
+
+    // the titles line up across the chart as it's drawn, labels and all
+    let width = frame.lines().map(visible_len).max().unwrap_or(0);
+    let mut out = titles.head(width, pad);
+    if pos == LegendPos::Top && !legend.is_empty() {
+        out += &format!("{}{}\n", pad, inline);
+    }
+    out += pad;
+    out += &frame.replace('\n', &format!("\n{}", pad));
+    if pos == LegendPos::Bottom {
+        out += &inline;
+    }
+    if titles.caption.is_some() {
+        // under the legend, or in place of the blank line left for it
+        if pos == LegendPos::Bottom && !inline.is_empty() {
+            out.push('\n');
+        } else {
+            out.truncate(out.len() - pad.len());
+        }
+        out += &titles.foot(width, pad);
+    }
+    if object {
+        return Value::custom(Box::new(ChartValue::new(chart, out, names, titles.title.as_deref())), span);
+    }
+    Value::string(out, span)
+}
+
+/// The heights in dots of a chart `height` dots tall, with a panel under
+/// it, and of the panel: a quarter of the height, less a row for the
+/// line between them and one for the x labels of the panel.
+fn panel_heights(height: u32) -> (u32, u32) {
+    let panel = (height / 4).max(32);
+    (height.saturating_sub(panel + 2 * DOTS.1).max(32), panel)
+}
+
+/// Draw `chart` as `finish_chart` does, with `panel`, if there is one,
+/// as text under it, sharing its x axis. The panel is drawn as it is,
+/// and the annotations, title and legend go with the chart.
+#[allow(clippy::too_many_arguments)]
+fn finish_panels(
+    chart: &mut Chart,
+    panel: Option<&mut Chart>,
+    format: Format,
+    names: &[String],
+    annotations: &[Annotation],
+    titles: &Titles,
+    legend: &[LegendEntry],
+    pos: LegendPos,
+    pad: &str,
+    object: bool,
+    span: Span,
+) -> Value {
+    let Some(panel) = panel else {
+        return finish_chart(chart, format, names, annotations, titles, legend, pos, pad, object, span);
+    };
+    let chart = annotate(chart, annotations);
+    if pos == LegendPos::Inside {
+        chart.legend(legend);
+    }
+    let frame = layout::share_x(&chart.to_string(), &panel.to_string());
+    finish_text(chart, frame, names, titles, legend, pos, pad, object, span)
+}
+
+/// Draw one or more series, sharing the x range `(xmin, xmax)`, with
+/// the shape, title and legend from the command line. `legend_extra`
+/// entries follow those for the series in the legend. The x values are
+/// in `unit`, and the y values plain numbers. With `from_zero`, the y
+/// range always takes in 0, so a single bar still stands on the axis.
+///
+/// `hist` and `density` both draw through here, so they range and
+/// render their series the same way.
+fn render_series(
+    call: &EvaluatedCall,
+    opts: &CliOpts,
+    (xmin, xmax): (f64, f64),
+    series: Vec<Vec<(f64, f64)>>,
+    legend_extra: &[LegendEntry],
+    unit: Unit,
+    from_zero: bool,
+) -> Result<Value, LabeledError> {
+    let max_x = opts.width_op.unwrap_or(200);
+    let max_y = opts.height_op.unwrap_or(50);
+    let labels = label_formats(opts.label_format, (unit, Unit::Number));
+
+    if !series.iter().flatten().any(|(x, y)| x.is_finite() && y.is_finite()) {
+        return Err(no_finite_values(call));
+    }
+
+    let count = series.len();
+    let mut annotations = opts.annotations.clone();
+    for (i, data) in series.iter().enumerate() {
+        annotations.extend(mark_labels(&opts.marks, labels.1, data));
+        annotations.extend(value_labels(opts.labels, labels.1, data));
+        annotations.extend(opts.palette.marks(i, count, data, (xmin, xmax), max_x));
+    }
+
+    let names = series_names(call, opts.names.as_deref(), count)?;
+    let mut legend: Vec<LegendEntry> = names.iter().enumerate().map(|(i, name)| opts.palette.entry(name, i, count, None, labels.1)).collect();
+    legend.extend_from_slice(legend_extra);
+    if !opts.legend {
+        legend.clear();
+    }
+
+    if count > 1 {
+        check_gradient(&opts.palette, call)?;
+    }
+    let mut chart = new_chart(opts.format, opts.charset, labels, max_x, max_y, xmin, xmax);
+    if from_zero {
+        chart.widen_y_range(0.0, 0.0);
+    }
+    for (i, data) in series.into_iter().enumerate() {
+        let shape = chart_shape(nth_shape(opts.shapes.as_deref(), i), opts.steps, opts.step_mode, opts.bars, opts.points, opts.stem, call, data)?;
+        opts.palette.plot_nth(&mut chart, i, count, shape);
+    }
+    Ok(finish_chart(&mut chart, opts.format, &names, &annotations, &opts.titles, &legend, opts.legend_pos, &opts.pad, opts.object, call.head))
+}
+
+/// A chart of `values`, taken from nushell values to points as `plot`
+/// takes them, and set up `width` by `height` with a reference line and
+/// a shaded band, ready to draw.
+///
+/// The benchmarks in `benches/` time the plugin through this, without a
+/// running shell: converting the values, then drawing the chart.
+///
+/// ```
+/// use nu_plugin_plot::render_benchmark_fixture;
+/// use nu_protocol::{Span, Value};
+///
+/// let values = (0..100).map(|i| Value::float((i as f64).sin(), Span::unknown())).collect();
+/// let mut chart = render_benchmark_fixture(values, 80, 32).unwrap();
+/// assert!(chart.to_string().contains('⠉'));
+/// ```
+pub fn render_benchmark_fixture(values: Vec<Value>, width: u32, height: u32) -> Result<Chart, LabeledError> {
+    let call = EvaluatedCall::new(Span::unknown());
+    let (data, _) = collect_plot_data(&call, PipelineData::Value(Value::list(values, call.head), None), false)?;
+    let series: Vec<Vec<(f64, f64)>> = match data {
+        PlotData::Single(ys) => vec![SeriesBuilder::indexed(&ys, false).build().0],
+        PlotData::Nested(lists) => lists.iter().map(|ys| SeriesBuilder::indexed(ys, false).build().0).collect(),
+        PlotData::Pairs(groups) => groups.into_iter().map(|g| g.points).collect(),
+    };
+
+    let (xmin, xmax) = extent(series.iter().flatten().map(|(x, _)| *x)).ok_or_else(|| no_finite_values(&call))?;
+    let (ymin, ymax) = extent(series.iter().flatten().map(|(_, y)| *y)).ok_or_else(|| no_finite_values(&call))?;
+    let mut chart = new_chart(Format::Text, Charset::Braille, Default::default(), width, height, xmin, xmax.max(xmin + 1.0));
+    for (i, points) in series.into_iter().enumerate() {
+        chart.linecolorplot(Shape::Lines(points), COLORS[i % COLORS.len()]);
+    }
+    chart.annotate(Annotation::HLine((ymin + ymax) / 2.0, Some("mid".to_string())));
+    chart.annotate(Annotation::ShadeX(xmin + (xmax - xmin) / 3.0, xmin + 2.0 * (xmax - xmin) / 3.0));
+    Ok(chart)
+}
+
+/// The numeric data collected from the pipeline input.
+///
+/// Nulls that are kept as gaps, and values which are not finite,
+/// are stored as `NaN`.
+enum PlotData {
+    /// A single list of numbers.
+    Single(Vec<f64>),
+    /// A list of numeric lists, all of the same length unless the
+    /// command takes them ragged.
+    Nested(Vec<Vec<f64>>),
+    /// A list of `{x, y}` records, split into groups.
+    Pairs(Vec<Group>),
+}
+
+/// The points of a list of `{x, y}` records with the same `series`, or
+/// if there is none, the same `color`.
+struct Group {
+    /// The `series` or `color` the points share.
+    name: Option<String>,
+    /// The color the points share, if their `color` names one.
+    color: Option<PixelColor>,
+    points: Vec<(f64, f64)>,
+    /// The x value, and the low and high y values, of the error bar of
+    /// each point with one.
+    errors: Vec<(f64, f64, f64)>,
+}
+
+/// The fields of a record which give the error bar of its point.
+const ERROR_FIELDS: [&str; 3] = ["yerr", "ylow", "yhigh"];
+
+/// Convert a single numeric `Value` to `f64`.
+fn value_to_f64(e: &Value, call: &EvaluatedCall) -> Result<f64, LabeledError> {
+    match e {
+        Value::Int { val, .. } => Ok(*val as f64),
+        Value::Float { val, .. } => Ok(*val),
+        // in bytes and nanoseconds, their labels written as such
+        Value::Filesize { val, .. } | Value::Duration { val, .. } => Ok(*val as f64),
+        // by the clock of their own time zone, so they are labelled as
+        // they were written
+        Value::Date { val, .. } => Ok(val.naive_local().and_utc().timestamp_micros() as f64 * 1e3),
+        Value::Bool { val, .. } => Ok(*val as u8 as f64),
+        e => Err(LabeledError::new(format!("Got {}, need integer, float, filesize, duration, date or bool.", e.get_type())).with_label("Incorrect type supplied.", call.head)),
+    }
+}
+
+/// Collect the pipeline input into numeric lists. Binary data is a
+/// single list of its bytes.
+///
+/// Values are converted one at a time as they come off the stream,
+/// so a long `ListStream` is never held in memory as a `Value`, and
+/// nor is a range. The lists of a nested list must be of the same
+/// length, unless they are `ragged`.
+fn collect_plot_data(call: &EvaluatedCall, input: PipelineData, ragged: bool) -> Result<(PlotData, Meta), LabeledError> {
+    match input {
+        PipelineData::ListStream(..) | PipelineData::Value(Value::List { .. }, ..) => (),
+        PipelineData::Value(Value::Range { ref val, .. }, ..) => check_bounded(call, val)?,
+        PipelineData::Value(Value::Binary { val, .. }, ..) => return Ok(binary_data(val)),
+        PipelineData::ByteStream(stream, ..) => return Ok(binary_data(stream.into_bytes()?)),
+        PipelineData::Value(Value::Error { error, .. }, ..) => return Err((*error).into()),
+        e => return Err(LabeledError::new(format!("Input type should be a list, range or binary, got {}.", e.get_type())).with_label( "Incorrect input type.", call.head)),
+    }
+
+    let nulls = parse_nulls(call)?;
+    let mut meta = Meta::default();
+    let mut to_f64 = |e: &Value| {
+        meta.points += 1;
+        match e {
+            Value::Nothing { .. } if nulls != Nulls::Error => {
+                meta.nulls += 1;
+                Ok(f64::NAN)
+            }
+            e => value_to_f64(e, call).map(|y| {
+                if y.is_finite() {
+                    y
+                } else {
+                    meta.non_finite += 1;
+                    f64::NAN
+                }
+            }),
+        }
+    };
+
+    let mut units = vec![];
+    let list_unit = |vals: &[Value]| {
+        let mut seen = None;
+        vals.iter().for_each(|v| Unit::see(&mut seen, v));
+        seen.unwrap_or_default()
+    };
+    // where the nulls of each series were, as only they are filled
+    let mut null_at: Vec<Vec<bool>> = vec![];
+    let mut values = input.into_iter();
+    let mut data = match values.next() {
+        None => return Err(LabeledError::new("Can't plot a zero element list.").with_label( "No elements in the list.", call.head)),
+        Some(first @ (Value::List { .. } | Value::Range { .. })) => {
+            let vals = inner_list(call, first)?;
+            units.push(list_unit(&vals));
+            null_at.push(vals.iter().map(is_null).collect());
+            let first = vals.iter().map(&mut to_f64).collect::<Result<Vec<f64>, _>>()?;
+            let mut nested = vec![first];
+            // the lists of a parametric nested list only match in pairs
+            let parametric = call.has_flag("parametric")?;
+            for val in values {
+                let list = inner_list(call, val)?;
+                let like = match parametric {
+                    _ if ragged => None,
+                    true if nested.len().is_multiple_of(2) => None,
+                    true => nested.last(),
+                    false => nested.first(),
+                };
+                if like.is_some_and(|like| like.len() != list.len()) {
+                    return Err(LabeledError::new("Can't plot a list of differing length lists.").with_label("List length differences.", call.head));
+                }
+                units.push(list_unit(&list));
+                null_at.push(list.iter().map(is_null).collect());
+                nested.push(list.iter().map(&mut to_f64).collect::<Result<Vec<f64>, _>>()?);
+            }
+            PlotData::Nested(nested)
+        }
+        Some(first) if is_point(&first) => {
+            let mut groups: Vec<Group> = vec![];
+            let (mut x_unit, mut y_unit) = (None, None);
+            for val in std::iter::once(first).chain(values) {
+                let record = match &val {
+                    Value::Record { val, .. } => val,
+                    _ => return Err(LabeledError::new("Can't plot a list of multiple types.").with_label("Type differences.", call.head)),
+                };
+                let (Some(x), Some(y)) = (record.get("x"), record.get("y")) else {
+                    return Err(LabeledError::new("Records to plot need x and y fields.").with_label("Missing x or y.", val.span()));
+                };
+                Unit::see(&mut x_unit, x);
+                Unit::see(&mut y_unit, y);
+                let point = (to_f64(x)?, to_f64(y)?);
+                let error = error_bar(call, record, point)?;
+                let color = record.get("color");
+                let name = record.get("series").or(color).map(|v| v.to_abbreviated_string(&Config::default()));
+                match groups.iter().position(|g| g.name == name) {
+                    Some(i) => {
+                        groups[i].points.push(point);
+                        groups[i].errors.extend(error);
+                        null_at[i].push(is_null(y));
+                    }
+                    None => {
+                        groups.push(Group {
+                            name,
+                            color: color.and_then(|c| c.as_str().ok()).and_then(color_from_name),
+                            points: vec![point],
+                            errors: error.into_iter().collect(),
+                        });
+                        null_at.push(vec![is_null(y)]);
+                    }
+                }
+            }
+            units = vec![x_unit.unwrap_or_default(), y_unit.unwrap_or_default()];
+            PlotData::Pairs(groups)
+        }
+        Some(first) => {
+            let mut seen = None;
+            Unit::see(&mut seen, &first);
+            let mut single = vec![to_f64(&first)?];
+            let mut null = vec![is_null(&first)];
+            for val in values {
+                Unit::see(&mut seen, &val);
+                single.push(to_f64(&val)?);
+                null.push(is_null(&val));
+            }
+            null_at.push(null);
+            units.push(seen.unwrap_or_default());
+            PlotData::Single(single)
+        }
+    };
+    meta.units = units;
+
+    match &mut data {
+        PlotData::Single(series) => {
+            meta.series = 1;
+            fill_nulls(series, &null_at[0], nulls);
+        }
+        PlotData::Nested(nested) => {
+            meta.series = nested.len();
+            nested.iter_mut().zip(&null_at).for_each(|(series, null)| fill_nulls(series, null, nulls));
+        }
+        PlotData::Pairs(groups) => {
+            meta.series = groups.len();
+            // the ys are only copied out if there are nulls to fill
+            if !matches!(nulls, Nulls::Error | Nulls::Skip) {
+                for (group, null) in groups.iter_mut().zip(&null_at) {
+                    let mut ys: Vec<f64> = group.points.iter().map(|(_, y)| *y).collect();
+                    fill_nulls(&mut ys, null, nulls);
+                    group.points.iter_mut().zip(ys).for_each(|(point, y)| point.1 = y);
+                }
+            }
+        }
+    }
+
+    let finite = match &data {
+        PlotData::Single(series) => series.iter().any(|y| y.is_finite()),
+        PlotData::Nested(nested) => nested.iter().flatten().any(|y| y.is_finite()),
+        PlotData::Pairs(groups) => groups.iter().flat_map(|g| &g.points).any(|(x, y)| x.is_finite() && y.is_finite()),
+    };
+    if !finite {
+        return Err(no_finite_values(call));
+    }
+
+    Ok((data, meta))
+}
+
+/// The error bar of `point`, from the fields of its `record`: `yerr`
+/// either side of its y value, or from `ylow` to `yhigh`, either of which
+/// is its y value if left out. Nulls count as left out.
+fn error_bar(call: &EvaluatedCall, record: &Record, (x, y): (f64, f64)) -> Result<Option<(f64, f64, f64)>, LabeledError> {
+    let bound = |field: &str| match record.get(field) {
+        None | Some(Value::Nothing { .. }) => Ok(None),
+        Some(v) => value_to_f64(v, call).map(Some),
+    };
+    Ok(match (bound("yerr")?, bound("ylow")?, bound("yhigh")?) {
+        (None, None, None) => None,
+        (Some(err), None, None) => Some((x, y - err.abs(), y + err.abs())),
+        (None, low, high) => Some((x, low.unwrap_or(y), high.unwrap_or(y))),
+        (Some(_), ..) => {
+            return Err(LabeledError::new("Give the error of a point as yerr, or as ylow and yhigh, not both.").with_label("Error bar error.", call.head))
+        }
+    })
+}
+
+/// An error if `range` goes on for ever, as it can't be plotted.
+fn check_bounded(call: &EvaluatedCall, range: &Range) -> Result<(), LabeledError> {
+    let unbounded = match range {
+        Range::IntRange(range) => range.end() == Bound::Unbounded,
+        Range::FloatRange(range) => range.end() == Bound::Unbounded,
+    };
+    if unbounded {
+        return Err(LabeledError::new("Can't plot a range without an end.").with_label("Unbounded range.", call.head));
+    }
+    Ok(())
+}
+
+/// The values of one of the lists of a nested list, which may also be
+/// a range.
+fn inner_list(call: &EvaluatedCall, value: Value) -> Result<Vec<Value>, LabeledError> {
+    let span = value.span();
+    match value {
+        Value::List { vals, .. } => Ok(vals),
+        Value::Range { val, .. } => {
+            check_bounded(call, &val)?;
+            Ok(val.into_range_iter(span, Signals::empty()).collect())
+        }
+        _ => Err(LabeledError::new("Can't plot a list of multiple types.").with_label("Type differences.", call.head)),
+    }
+}
+
+/// The values of the bytes of binary data, as a single list.
+fn binary_data(bytes: Vec<u8>) -> (PlotData, Meta) {
+    let meta = Meta {
+        series: 1,
+        points: bytes.len(),
+        units: vec![Unit::Binary],
+        ..Meta::default()
+    };
+    (PlotData::Single(bytes.into_iter().map(f64::from).collect()), meta)
+}
+
+/// Whether `value` is a record with `x` and `y` fields, to plot as a
+/// point rather than as a row of a table.
+fn is_point(value: &Value) -> bool {
+    matches!(value, Value::Record { val, .. } if val.contains("x") && val.contains("y"))
+}
+
+/// The columns of a table of `rows` as lists, the first for x and the
+/// rest for y, with the names of the y columns. Cells a row is missing
+/// are null.
+fn table_columns(call: &EvaluatedCall, rows: Vec<Value>) -> Result<(Vec<Value>, Vec<String>), LabeledError> {
+    let columns: Vec<String> = rows[0].as_record()?.columns().cloned().collect();
+    if columns.len() < 2 {
+        return Err(LabeledError::new("An xyplot of a table needs a column of x values and at least one of y values.").with_label("Too few columns.", call.head));
+    }
+    let lists = columns
+        .iter()
+        .map(|column| {
+            let cells = rows
+                .iter()
+                .map(|row| Ok(row.as_record()?.get(column).cloned().unwrap_or_else(|| Value::nothing(row.span()))))
+                .collect::<Result<Vec<Value>, ShellError>>()?;
+            Ok(Value::list(cells, call.head))
+        })
+        .collect::<Result<Vec<Value>, LabeledError>>()?;
+    Ok((lists, columns[1..].to_vec()))
+}
+
+/// The rows of a table as `{x, y}` records, in a series for each value
+/// of `column`. The x and y values are the `x` and `y` columns, if there
+/// are both, or else the first two columns after leaving out `column`
+/// and those of error bars, which are kept.
+fn group_rows(call: &EvaluatedCall, column: &str, rows: Vec<Value>) -> Result<Vec<Value>, LabeledError> {
+    let span = call.get_flag_span("group-by").unwrap_or(call.head);
+    rows.iter()
+        .map(|row| {
+            let Value::Record { val, .. } = row else {
+                return Err(LabeledError::new(format!("Can't group {} by a column, --group-by needs a table.", row.get_type())).with_label("Not a table.", span));
+            };
+            let Some(group) = val.get(column) else {
+                return Err(LabeledError::new(format!("A row has no {} column to group by.", column)).with_label("Missing column.", row.span()));
+            };
+            let rest: Vec<&str> = val.columns().map(String::as_str).filter(|c| *c != column && !ERROR_FIELDS.contains(c)).collect();
+            let (x, y) = match &rest[..] {
+                _ if rest.contains(&"x") && rest.contains(&"y") => ("x", "y"),
+                [x, y, ..] => (*x, *y),
+                _ => return Err(LabeledError::new("A table grouped by a column needs a column of x values and one of y values besides it.").with_label("Too few columns.", row.span())),
+            };
+            let mut point = record! { "x" => val.get(x).cloned().unwrap_or_default(), "y" => val.get(y).cloned().unwrap_or_default(), "series" => group.clone() };
+            for field in ERROR_FIELDS {
+                if let Some(bound) = val.get(field) {
+                    point.push(field, bound.clone());
+                }
+            }
+            Ok(Value::record(point, row.span()))
+        })
+        .collect()
+}
+
+/// The values to plot for the rows of a list: with `--group-by`, a
+/// table as points split into series; any other table as its columns,
+/// with the names of the y columns; and anything else as it is.
+fn xy_values(call: &EvaluatedCall, rows: Vec<Value>) -> Result<(Vec<Value>, Option<Vec<String>>), LabeledError> {
+    if let Some(column) = call.get_flag::<String>("group-by")? {
+        return Ok((group_rows(call, &column, rows)?, None));
+    }
+    match rows.first() {
+        // a list of points with x and y fields is not a table
+        Some(first @ Value::Record { .. }) if !is_point(first) => {
+            let (columns, names) = table_columns(call, rows)?;
+            Ok((columns, Some(names)))
+        }
+        _ => Ok((rows, None)),
+    }
+}
+
+/// The rows of a table split by the values of `column`, in the order
+/// they first appear, with `column` left out of them.
+fn facet_rows(call: &EvaluatedCall, column: &str, rows: Vec<Value>) -> Result<Vec<(String, Vec<Value>)>, LabeledError> {
+    let span = call.get_flag_span("facet").unwrap_or(call.head);
+    let mut facets: Vec<(String, Vec<Value>)> = vec![];
+    for row in rows {
+        let row_span = row.span();
+        let Value::Record { val, .. } = row else {
+            return Err(LabeledError::new(format!("Can't facet {} by a column, --facet needs a table.", row.get_type())).with_label("Not a table.", span));
+        };
+        let mut record = val.into_owned();
+        let Some(facet) = record.remove(column) else {
+            return Err(LabeledError::new(format!("A row has no {} column to facet by.", column)).with_label("Missing column.", row_span));
+        };
+        let name = facet.to_abbreviated_string(&Config::default());
+        let row = Value::record(record, row_span);
+        match facets.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, rows)) => rows.push(row),
+            None => facets.push((name, vec![row])),
+        }
+    }
+    Ok(facets)
+}
+
+/// Chart width and height of each facet, unless given.
+const FACET_SIZE: (u32, u32) = (80, 32);
+
+/// Draw a small xyplot of each facet, all to the same scale, in a grid
+/// as wide as the terminal. The facets are the tables of a record, or
+/// the rows of a table split by `column`.
+fn facet_plot(
+    engine: &nu_plugin::EngineInterface,
+    call: &EvaluatedCall,
+    column: Option<&str>,
+    input: PipelineData,
+) -> Result<PipelineData, LabeledError> {
+    let call = &with_config(engine, call)?;
+    let facets = match (input, column) {
+        (PipelineData::Value(Value::Record { val, .. }, ..), _) => val
+            .into_owned()
+            .into_iter()
+            .map(|(name, table)| Ok((name, table.into_list()?)))
+            .collect::<Result<Vec<_>, ShellError>>()?,
+        (input @ (PipelineData::ListStream(..) | PipelineData::Value(Value::List { .. }, ..)), Some(column)) => {
+            facet_rows(call, column, input.into_iter().collect())?
+        }
+        (input, _) => return Err(LabeledError::new(format!("Input type should be a table or a record of tables, got {}.", input.get_type())).with_label("Incorrect input type.", call.head)),
+    };
+    if facets.is_empty() {
+        return Err(LabeledError::new("Can't plot a zero element list.").with_label("No elements in the list.", call.head));
+    }
+
+    let mut meta = Meta::default();
+    let mut charts = vec![];
+    for (name, rows) in facets {
+        let mut opts = parse_cli_opts(call)?;
+        if opts.format != Format::Text {
+            return Err(LabeledError::new("Facets can only be drawn as text.").with_label("Format error.", call.get_flag_span("format").unwrap_or(call.head)));
+        }
+        if opts.object {
+            return Err(LabeledError::new("Facets can't be returned as a chart value.").with_label("Format error.", call.get_flag_span("object").unwrap_or(call.head)));
+        }
+        let (values, columns) = xy_values(call, rows)?;
+        let (data, facet_meta) = collect_plot_data(call, PipelineData::Value(Value::list(values, call.head), None), false)?;
+        meta.add(&facet_meta);
+        // points in the colors they name keep them
+        let named = matches!(&data, PlotData::Pairs(groups) if groups.iter().all(|g| g.color.is_some()));
+        let (series, names) = match data {
+            PlotData::Nested(data) => nested_series(call, opts.names.as_deref().or(columns.as_deref()), data, opts.parametric)?,
+            PlotData::Pairs(data) => pair_series(call, &mut opts, data)?,
+            PlotData::Single(_) => return Err(LabeledError::new(format!("The facet {} has no x and y values to plot.", name)).with_label("Plot type error.", call.head)),
+        };
+        let series = if opts.polar { polar(series) } else { series };
+        if call.get_flag_span("width").is_none() {
+            opts.width_op = Some(FACET_SIZE.0);
+        }
+        if call.get_flag_span("height").is_none() {
+            opts.height_op = Some(FACET_SIZE.1);
+        }
+        // the subtitle and caption go with the grid, not each facet
+        opts.titles = Titles { title: Some(name), align: opts.titles.align, ..Default::default() };
+        charts.push((opts, series, names, named, facet_meta.xy_units()));
     }
 
-    let first_len_op = &len_ops[0];
-    let check_len_pass = len_ops.iter().all(|e| e == first_len_op);
-
-    if !check_len_pass {
-        return Err(LabeledError::new("Can't plot a list of differing length lists.").with_label("List length differences.", call.head));
+    // any other series has the same color in every facet, whichever
+    // facets it is in
+    let mut all_names: Vec<&String> = vec![];
+    for name in charts.iter().flat_map(|(_, _, names, _, _)| names) {
+        if !all_names.contains(&name) {
+            all_names.push(name);
+        }
     }
-
-    if let Some(_len) = first_len_op {
-        // *should* always index + unwrap without panicking...
-        let inner_type = l[0].as_list()?[0].get_type();
-        match inner_type {
-            Type::Float | Type::Int => (),
-            _ => {
-                return Err(LabeledError::new("Nested list elements not float or int.").with_label("Incorrect type.", call.head));
+    if all_names.len() > 1 {
+        let colors: Vec<Vec<PixelColor>> = charts
+            .iter()
+            .map(|(opts, _, names, _, _)| names.iter().map(|n| opts.palette.nth(all_names.iter().position(|a| *a == n).unwrap_or(0))).collect())
+            .collect();
+        for ((opts, _, _, named, _), colors) in charts.iter_mut().zip(colors) {
+            if !*named {
+                opts.palette.colors = Some(colors);
             }
         }
     }
 
-    Ok((first_type.clone(), *first_len_op))
+    // every facet is drawn over the x and y values of all of them
+    let points = || charts.iter().flat_map(|(_, series, _, _, _)| series.iter().flatten());
+    let (Some(x_range), Some(y_range)) = (extent(points().map(|(x, _)| *x)), extent(points().map(|(_, y)| *y))) else {
+        return Err(no_finite_values(call));
+    };
+    let ranges = (x_range, y_range);
+
+    let titles = Titles::from_call(call)?;
+    let frames = charts
+        .into_iter()
+        .map(|(opts, series, names, _, units)| Ok(xy_chart(call, opts, series, names, Some(ranges), units)?.into_string()?))
+        .collect::<Result<Vec<String>, LabeledError>>()?;
+    let grid = layout::grid(&frames, terminal(call)?.map_or(80, |(columns, _)| columns as usize));
+    let out = titles.around(&grid, &indent(call)?);
+    output_chart(engine, call, Value::string(out, call.head), meta)
 }
 
 pub struct PluginPlot;
@@ -210,168 +2826,478 @@ impl Plugin for PluginPlot {
     }
     fn commands(&self) -> Vec<Box<dyn nu_plugin::PluginCommand<Plugin = Self>>> {
         vec![
-            Box::new(CommandPlot), Box::new(CommandHist), Box::new(CommandXyplot)
+            Box::new(CommandPlot), Box::new(CommandHist), Box::new(CommandXyplot),
+            Box::new(CommandPlotConfig), Box::new(barchart::CommandBarchart),
+            Box::new(density::CommandDensity), Box::new(ecdf::CommandEcdf),
+            Box::new(compose::CommandCompose), Box::new(subplot::CommandSubplot),
+            Box::new(spec::CommandSpec), Box::new(function::CommandFunction),
+            Box::new(merge::CommandMerge), Box::new(overlay::CommandOverlay),
+            Box::new(candle::CommandCandle), Box::new(qqplot::CommandQqplot),
+            Box::new(acf::CommandAcf), Box::new(lagplot::CommandLagplot),
+            Box::new(spectrum::CommandSpectrum),
+            Box::new(hist2d::CommandHist2d),
+            Box::new(corrplot::CommandCorrplot),
+            Box::new(nullmap::CommandNullmap),
+            Box::new(gantt::CommandGantt),
+            Box::new(radar::CommandRadar),
+            Box::new(gauge::CommandGauge),
+            Box::new(ridgeline::CommandRidgeline),
         ]
     }
 }
 
+/// What the commands drawing lists of numbers take: a list of numbers,
+/// a list of lists, a list of filesizes, durations, bools or nulls, a
+/// range or bytes. Each returns a chart, a record with `--meta`, or
+/// nothing with `--output`.
+pub(crate) fn numeric_input_types() -> Vec<(Type, Type)> {
+    vec![
+        (Type::List(Box::new(Type::Number)), Type::Any),
+        (Type::List(Box::new(Type::List(Box::new(Type::Number)))), Type::Any),
+        (Type::List(Box::new(Type::Any)), Type::Any),
+        (Type::Range, Type::Any),
+        (Type::Binary, Type::Any),
+    ]
+}
+
+/// `signature` with the flags every chart command takes, which size,
+/// title, color and save the chart, as [`parse_cli_opts`] reads them.
+pub(crate) fn common_chart_flags(signature: Signature) -> Signature {
+    signature
+        .named(
+            "width",
+            SyntaxShape::OneOf(vec![SyntaxShape::Number, SyntaxShape::String]),
+            "The maximum width of the plot, in dots, or as a percentage of the terminal such as 80%.",
+            None,
+        )
+        .named(
+            "height",
+            SyntaxShape::OneOf(vec![SyntaxShape::Number, SyntaxShape::String]),
+            "The maximum height of the plot, in dots, or as a percentage of the terminal such as 80%.",
+            None,
+        )
+        .named(
+            "max-lines",
+            SyntaxShape::Int,
+            "The most lines of output, with the title, labels and legend, shrinking the chart to fit.",
+            None,
+        )
+        .named(
+            "title",
+            SyntaxShape::String,
+            "Provide a title to the plot.",
+            Some('t'),
+        )
+        .named("subtitle", SyntaxShape::String, "A line under the title.", None)
+        .named("caption", SyntaxShape::String, "A line under the chart, such as where its data is from.", None)
+        .named("title-align", SyntaxShape::String, "Line the title, subtitle and caption up across the chart: left (default), center or right.", None)
+        .named("pad", SyntaxShape::Int, "Indent each line of a text chart by this many spaces (default 4), or not at all with 0.", None)
+        .switch("legend", "Plot a tiny, maybe useful legend.", Some('l'))
+        .named(
+            "legend-pos",
+            SyntaxShape::String,
+            "Where to put the legend: top, bottom (default), right or inside.",
+            None,
+        )
+        .named(
+            "format",
+            SyntaxShape::String,
+            "Draw the chart as text (default), svg, png, or vega for a Vega-Lite spec.",
+            None,
+        )
+        .named(
+            "charset",
+            SyntaxShape::String,
+            "The characters to draw a text chart with: braille (default), ascii or blocks.",
+            None,
+        )
+        .named(
+            "palette",
+            SyntaxShape::OneOf(vec![SyntaxShape::List(Box::new(SyntaxShape::String)), SyntaxShape::String]),
+            "Colors for the series in turn: default, okabe-ito, viridis, mono or high-contrast, or a list such as [red bright-blue \"#ff8800\"].",
+            None,
+        )
+        .named(
+            "color",
+            SyntaxShape::String,
+            "Color a text chart always, never, or auto (default) when NO_COLOR and CLICOLOR=0 are unset and the output is a terminal.",
+            None,
+        )
+        .switch("raw", "Return plain text without color or any other escape codes, whatever --color says.", None)
+        .switch("print", "Print the chart as soon as it is drawn rather than returning it, so each chart of a loop or script is shown.", None)
+        .switch("object", "Return the chart as a chart value, shown as the chart, with its data, ranges and facts as cells such as ymax.", None)
+        .named(
+            "output",
+            SyntaxShape::Filepath,
+            "Save the chart to this file rather than returning it, as SVG, PNG or a Vega-Lite spec if it ends in .svg, .png or .json.",
+            Some('o'),
+        )
+}
+
 trait Plotter {
     fn plot(
         &self,
         call: &EvaluatedCall,
-        input: &Value,
+        input: Vec<f64>,
+        meta: &mut Meta,
     ) -> Result<Value, LabeledError>;
     fn plot_nested(
         &self,
         call: &EvaluatedCall,
-        input: &Value,
+        input: Vec<Vec<f64>>,
+        meta: &mut Meta,
     ) -> Result<Value, LabeledError>;
+    fn plot_pairs(
+        &self,
+        call: &EvaluatedCall,
+        _input: Vec<Group>,
+        _meta: &mut Meta,
+    ) -> Result<Value, LabeledError> {
+        Err(LabeledError::new("Only xyplot can plot records of x and y.").with_label("Plot type error.", call.head))
+    }
+
+    /// Whether the lists of a nested list may differ in length, as
+    /// samples compared only by their distributions may.
+    fn ragged(&self) -> bool {
+        false
+    }
+
+    /// Collect the input and dispatch on whether we got a single
+    /// list, a nested list or a list of records. With `--output`, the chart is saved to
+    /// a file instead of returned.
+    fn plot_input(
+        &self,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        let call = &with_config(engine, call)?;
+        let (data, mut meta) = collect_plot_data(call, input, self.ragged())?;
+        let chart = match data {
+            PlotData::Single(data) => self.plot(call, data, &mut meta)?,
+            PlotData::Nested(data) => self.plot_nested(call, data, &mut meta)?,
+            PlotData::Pairs(data) => self.plot_pairs(call, data, &mut meta)?,
+        };
+        output_chart(engine, call, chart, meta)
+    }
+}
+
+/// Return `chart`, without color if it shouldn't have any, or with
+/// `--output` save it to a file instead. With `--meta`, the chart comes
+/// in a record with facts about the input.
+fn output_chart(
+    engine: &nu_plugin::EngineInterface,
+    call: &EvaluatedCall,
+    mut chart: Value,
+    meta: Meta,
+) -> Result<PipelineData, LabeledError> {
+    if let Value::String { val, .. } = &chart {
+        chart = match use_color(engine, call)? {
+            true => Value::string(color_depth(call)?.downgrade(val), chart.span()),
+            false => Value::string(strip_escapes(val), chart.span()),
+        };
+    }
+    // a chart value has the facts --meta would give as cells of its own
+    let object = match &mut chart {
+        Value::Custom { val, .. } => val.as_mut_any().downcast_mut::<ChartValue>(),
+        _ => None,
+    };
+    if let Some(object) = object {
+        object.frame = match use_color(engine, call)? {
+            true => color_depth(call)?.downgrade(&object.frame),
+            false => strip_escapes(&object.frame),
+        };
+        object.facts = meta.facts(call.head);
+        let frame = Value::string(&object.frame, call.head);
+        if let Some(output) = call.get_flag::<String>("output")? {
+            write_chart(engine, call, &output, frame.as_str()?.as_bytes())?;
+        }
+        if call.has_flag("print")? {
+            print_chart(engine, call, &frame)?;
+        }
+        return Ok(PipelineData::Value(chart, None));
+    }
+    if let Some(output) = call.get_flag::<String>("output")? {
+        let bytes = match &chart {
+            Value::Record { .. } => vega::to_json(&chart).into_bytes(),
+            chart => chart.coerce_binary()?.to_vec(),
+        };
+        write_chart(engine, call, &output, &bytes)?;
+        if !call.has_flag("meta")? {
+            return Ok(PipelineData::Empty);
+        }
+    }
+    if call.has_flag("print")? {
+        print_chart(engine, call, &chart)?;
+        if !call.has_flag("meta")? {
+            return Ok(PipelineData::Empty);
+        }
+    }
+    if call.has_flag("meta")? {
+        chart = meta.into_value(chart, call.head);
+    }
+    Ok(PipelineData::Value(chart, None))
+}
+
+/// Save `bytes`, a chart, to the file `output`, relative to the current
+/// directory.
+fn write_chart(engine: &nu_plugin::EngineInterface, call: &EvaluatedCall, output: &str, bytes: &[u8]) -> Result<(), LabeledError> {
+    let path = Path::new(&engine.get_current_dir()?).join(output);
+    std::fs::write(&path, bytes).map_err(|e| {
+        LabeledError::new(format!("Couldn't write {}: {}", path.display(), e)).with_label("Output error.", call.head)
+    })
+}
+
+/// Have nushell `print` `chart` now, as it would a value at the end of a
+/// pipeline, since only the last value of a script or loop is shown.
+fn print_chart(engine: &nu_plugin::EngineInterface, call: &EvaluatedCall, chart: &Value) -> Result<(), LabeledError> {
+    let print = engine
+        .find_decl("print")?
+        .ok_or_else(|| LabeledError::new("Can't print the chart, as nushell has no print command.").with_label("Print error.", call.head))?;
+    engine.call_decl(print, EvaluatedCall::new(call.head), PipelineData::Value(chart.clone(), None), false, false)?;
+    Ok(())
 }
 
 impl Plotter for CommandPlot {
     fn plot(
         &self,
         call: &EvaluatedCall,
-        input: &Value,
+        mut input: Vec<f64>,
+        meta: &mut Meta,
     ) -> Result<Value, LabeledError> {
         let CliOpts {
             height_op,
             width_op,
             legend,
+            legend_stats,
+            legend_pos,
+            pad,
+            names,
+            format,
+            charset,
+            palette,
             steps,
+            step_mode,
             bars,
             points,
-            title,
-            bins: _,
+            stem,
+            shapes,
+            fill,
+            band,
+            titles,
+            decimate,
+            connect_gaps: connect,
+            jitter,
+            smooth,
+            trend,
+            mut annotations,
+            marks,
+            labels: show_labels,
+            label_format,
+            mut prepare,
+            object,
+            ..
         } = parse_cli_opts(call)?;
 
         let max_x = width_op.unwrap_or(200);
         let max_y = height_op.unwrap_or(50);
+        let labels = label_formats(label_format, (Unit::Number, meta.units_from(0)));
+        let labels = (labels.0, prepare.y_labels(labels.1));
+        // bools step between 0 and 1, unless drawn as another shape
+        let steps = steps || (meta.units_from(0) == Unit::Bool && !bars && !points);
+        if band.is_some() {
+            return Err(LabeledError::new("A band needs a nested list of a series and the low and high ends of the band.").with_label("Band error.", call.get_flag_span("band").unwrap_or(call.head)));
+        }
 
-        let values = input.as_list()?;
-
-        let v: Result<Vec<(f32, f32)>, LabeledError> = values
-            .iter()
-            .enumerate()
-            .map(|(i, e)| match e {
-                Value::Int { .. } => Ok((i as f32, e.as_int()? as f32)),
-                Value::Float { .. } => Ok((i as f32, e.as_float()? as f32)),
-                e => Err(LabeledError::new(format!("Got {}, need integer or float.", e.get_type())).with_label("Incorrect type supplied", call.head)),
-            })
-            .collect();
-
-        let min_max_x = {
-            let x: Vec<f32> = v.clone().unwrap().iter().map(|e| e.0).collect();
-            min_max(&x)
-        };
-
-        let chart_data = v;
-
-        let mut chart = Chart::new(max_x, max_y, min_max_x.0, min_max_x.1)
-            .lineplot(&chart_shape(steps, bars, points, call, &chart_data?)?)
-            .to_string();
+        prepare.apply(&mut input);
+        prepare.count(meta);
+        let (v, min_max_x) = SeriesBuilder::indexed(&input, connect).build();
+        let min_max_x = min_max_x.ok_or_else(|| no_finite_values(call))?;
+        // label the true extremes, which downsampling may drop
+        annotations.extend(mark_labels(&marks, labels.1, &v));
 
-        if let Some(t) = title {
-            chart = TAB.to_owned() + &t + "\n" + &chart;
+        // fit on every point, before downsampling
+        let fit = fit_trend(call, trend, &v, min_max_x, max_x)?;
+        let mut chart_data = decimate.apply(v, max_x as usize);
+        // after downsampling, which would otherwise pick among the jittered points
+        jitter_series(std::slice::from_mut(&mut chart_data), jitter);
+        let min_max_x = if jitter.is_some() { extent(chart_data.iter().map(|(x, _)| *x)).unwrap_or(min_max_x) } else { min_max_x };
+        annotations.extend(value_labels(show_labels, labels.1, &chart_data));
+        annotations.extend(palette.marks(0, 1, &chart_data, min_max_x, max_x));
+        if fill {
+            annotations.push(area(chart_data.iter().map(|&(x, y)| (x, 0.0, y)).collect(), nth_steps(shapes.as_deref(), 0, steps, step_mode), palette.single()));
         }
-        chart = TAB.to_owned() + &chart.replace('\n', &format!("\n{}", TAB));
 
+        let mut chart = new_chart(format, charset, labels, max_x, max_y, min_max_x.0, min_max_x.1);
+        chart.smooth(smooth);
+        palette.plot(&mut chart, chart_shape(nth_shape(shapes.as_deref(), 0), steps, step_mode, bars, points, stem, call, chart_data)?);
+        if let Some(fit) = &fit {
+            chart.linecolorplot(Shape::Lines(fit.curve.clone()), palette.nth(1));
+        }
+        let names = series_names(call, names.as_deref(), 1)?;
+        let mut entries = vec![];
         if legend {
-            chart += &format!("Line 1: {}", "---".white());
+            entries.push(palette.entry(&names[0], 0, 1, legend_stats.then_some(&input[..]), labels.1));
+            entries.extend(fit.as_ref().map(|fit| trend_legend(fit, palette.nth(1))));
+            entries.extend(prepare.legend());
         }
+        let names = shape_names(&names, fit.as_ref());
+        let chart = finish_chart(&mut chart, format, &names, &annotations, &titles, &entries, legend_pos, &pad, object, call.head);
 
-        Ok(Value::string(chart, call.head))
+        Ok(chart)
     }
 
-    fn plot_nested<'a>(
+    fn plot_nested(
         &self,
         call: &EvaluatedCall,
-        input: &Value,
+        mut input: Vec<Vec<f64>>,
+        meta: &mut Meta,
     ) -> Result<Value, LabeledError> {
         let CliOpts {
             height_op,
             width_op,
             legend,
+            legend_stats,
+            legend_pos,
+            pad,
+            names,
+            format,
+            charset,
+            mut palette,
             steps,
+            step_mode,
             bars,
             points,
-            title,
-            bins: _,
+            stem,
+            shapes,
+            bar_layout,
+            fill,
+            band,
+            titles,
+            decimate,
+            connect_gaps: connect,
+            jitter,
+            order,
+            highlight,
+            panel,
+            blend,
+            smooth,
+            trend,
+            mut annotations,
+            marks,
+            labels: show_labels,
+            label_format,
+            mut prepare,
+            object,
+            ..
         } = parse_cli_opts(call)?;
 
         let max_x = width_op.unwrap_or(200);
         let max_y = height_op.unwrap_or(50);
+        let labels = label_formats(label_format, (Unit::Number, meta.units_from(0)));
+        let labels = (labels.0, prepare.y_labels(labels.1));
+        // bools step between 0 and 1, unless drawn as another shape
+        let steps = steps || (meta.units_from(0) == Unit::Bool && !bars && !points);
 
-        let values = input.as_list()?;
-        if values.len() > 5 {
+        if input.len() > 5 {
             return Err(LabeledError::new("Nested list can't contain more than 5 inner lists.").with_label("Nested list error.", call.head));
         }
-
-        let mut data = vec![];
-
-        for val in values {
-            let list = val.as_list()?;
-
-            let v: Result<Vec<(f32, f32)>, LabeledError> = list
-                .iter()
-                .enumerate()
-                .map(|(i, e)| match e {
-                    Value::Int { .. } => Ok((i as f32, e.as_int()? as f32)),
-                    Value::Float { .. } => Ok((i as f32, e.as_float()? as f32)),
-                    e => Err(LabeledError::new(format!("Got {}, need integer or float.", e.get_type())).with_label("Incorrect type supplied.", call.head)),
-                })
-                .collect();
-
-            let min_max_x = {
-                let x: Vec<f32> = v.clone()?.iter().map(|e| e.0).collect();
-                let y: Option<Vec<f32>> = None;
-                (min_max(&x), y)
-            };
-
-            data.push((min_max_x, v?));
+        if trend.is_some() {
+            return Err(LabeledError::new("A trend can only be fitted to a single series.").with_label("Trend error.", call.head));
         }
+        if fill {
+            return Err(LabeledError::new("Only a single series can be filled.").with_label("Fill error.", call.get_flag_span("fill").unwrap_or(call.head)));
+        }
+        input.iter_mut().for_each(|list| prepare.apply(list));
+        prepare.count(meta);
+        let mut names = series_names(call, names.as_deref(), input.len())?;
+        let band = take_band(call, band.as_deref(), &mut input, &mut names)?;
+        check_gradient(&palette, call)?;
+        let highlight = highlight.map(|h| series_index(call, &h, &names, "highlight", "highlight")).transpose()?;
+        if let Some(i) = highlight {
+            palette.highlight(i, names.len());
+        }
+        let panel = panel_series(call, panel.as_ref(), &names)?;
+        let (max_y, panel_y) = if panel.is_empty() { (max_y, 0) } else { panel_heights(max_y) };
+        // the marks and labels of a series go on the chart it's drawn on
+        let mut panel_annotations = vec![];
 
-        let min_all: Vec<f32> = data.iter().map(|((e, _), _)| e.0).collect();
-        let max_all: Vec<f32> = data.iter().map(|((e, _), _)| e.1).collect();
-
-        let min = min_all.iter().fold(f32::INFINITY, |a, &b| a.min(b));
-        let max = *max_all.iter().max_by(|a, b| a.total_cmp(b)).unwrap();
-
-        // copying data structure again here but wanted to be explicit.
-        let chart_data: Vec<Vec<(f32, f32)>> = data.iter().map(|(_, e)| e.clone()).collect();
+        let mut chart_data = Vec::with_capacity(input.len());
+        let mut x_ranges = vec![];
 
-        // let shapes = chart_data.into_iter().map(|data| chart_shape(steps, bars, points, call, &data));
-        check_chart_shape(steps, bars, points, call)?;
-        let shapes: Vec<Shape> = (&chart_data)
-            .iter()
-            .map(|data| chart_shape(steps, bars, points, call, data).unwrap())
-            .collect();
-        let charts = (&shapes).iter()
-            .enumerate()
-            .fold(&mut Chart::new(max_x, max_y, min, max), |chart, (i, shape)| {
-                chart.linecolorplot(shape, COLORS[i])
-            })
-            .to_string();
+        if let Some((low, high)) = &band {
+            let ((low, x_range), (high, _)) = (SeriesBuilder::indexed(low, false).build(), SeriesBuilder::indexed(high, false).build());
+            let color = if input.len() == 1 { palette.single() } else { None };
+            annotations.push(area(band_points(&low, &high, connect), nth_steps(shapes.as_deref(), 0, steps, step_mode), color));
+            x_ranges.extend(x_range);
+        }
+        for (i, list) in input.iter().enumerate() {
+            let (v, x_range) = SeriesBuilder::indexed(list, connect).build();
+            let annotations = if panel.contains(&i) { &mut panel_annotations } else { &mut annotations };
+            annotations.extend(mark_labels(&marks, labels.1, &v));
+            let v = decimate.apply(v, max_x as usize);
+            annotations.extend(value_labels(show_labels, labels.1, &v));
 
-        let mut final_chart = TAB.to_owned() + &charts.replace('\n', &format!("\n{}", TAB));
+            x_ranges.extend(x_range);
+            chart_data.push(v);
+        }
+        let (mut min, mut max) = extent(x_ranges.iter().flat_map(|&(min, max)| [min, max])).ok_or_else(|| no_finite_values(call))?;
+        jitter_series(&mut chart_data, jitter);
+        if jitter.is_some() {
+            (min, max) = extent(chart_data.iter().flatten().map(|(x, _)| *x)).unwrap_or((min, max));
+        }
+        if let Some(layout) = bar_layout {
+            chart_data = arrange(&chart_data, layout);
+            (min, max) = extent(chart_data.iter().flatten().map(|(x, _)| *x)).unwrap_or((min, max));
+        }
 
-        if let Some(t) = title {
-            final_chart = TAB.to_owned() + &t + "\n" + &final_chart;
+        for (i, data) in chart_data.iter().enumerate() {
+            let annotations = if panel.contains(&i) { &mut panel_annotations } else { &mut annotations };
+            annotations.extend(palette.marks(i, chart_data.len(), data, (min, max), max_x));
         }
 
+        let mut chart = new_chart(format, charset, labels, max_x, max_y, min, max);
+        let mut below = (!panel.is_empty()).then(|| new_chart(format, charset, labels, max_x, panel_y, min, max));
+        for chart in std::iter::once(&mut chart).chain(&mut below) {
+            if bar_layout.is_some() {
+                // the bars rise from zero
+                chart.widen_y_range(0.0, 0.0);
+            }
+            chart.blend(blend).smooth(smooth);
+        }
+        let count = chart_data.len();
+        let order = draw_order(call, order.as_deref(), highlight, &names)?;
+        for &i in &order {
+            let data = std::mem::take(&mut chart_data[i]);
+            let shape = chart_shape(nth_shape(shapes.as_deref(), i), steps, step_mode, bars, points, stem, call, data)?;
+            let marks = palette.point_marks(i, count, &shape);
+            let chart = match &mut below {
+                Some(below) if panel.contains(&i) => below,
+                _ => &mut chart,
+            };
+            annotate(chart.linecolorplot(shape, palette.nth(i)).line_style(palette.style(i)).line_width(palette.width(i)), &marks);
+        }
+        if let Some(below) = &mut below {
+            annotate(below, &panel_annotations);
+        }
+        let mut entries = vec![];
         if legend {
-            for (l, (_, _)) in data.iter().enumerate() {
-                let col: PixelColor = COLORS[l];
-                final_chart += &format!("Line {}: {} ", l + 1, "---".color(col));
+            for (l, list) in input.iter().enumerate() {
+                entries.push(palette.entry(&names[l], l, input.len(), legend_stats.then_some(&list[..]), labels.1));
             }
+            entries.extend(prepare.legend());
         }
+        // the names go with the series of the chart in the order they were
+        // drawn
+        let names: Vec<String> = order.iter().filter(|i| !panel.contains(i)).map(|&i| names[i].clone()).collect();
+        let final_chart = finish_panels(&mut chart, below.as_mut(), format, &names, &annotations, &titles, &entries, legend_pos, &pad, object, call.head);
 
-        Ok(Value::string(final_chart, call.head))
+        Ok(final_chart)
     }
 }
 
 
-impl SimplePluginCommand for CommandPlot {
+impl PluginCommand for CommandPlot {
     type Plugin = PluginPlot;
 
     fn name(&self) -> &str {
@@ -379,30 +3305,173 @@ impl SimplePluginCommand for CommandPlot {
     }
 
     fn signature(&self) -> nu_protocol::Signature {
-        Signature::build("plot")
-            .description("Render an ASCII plot from a list of values.")
+        common_chart_flags(Signature::build("plot").description("Render an ASCII plot from a list of values."))
+            .switch("square", "Draw the chart as tall as it is wide, as many dots across as up.", None)
             .named(
-                "width",
-                SyntaxShape::Number,
-                "The maximum width of the plot.",
+                "names",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "Names for the series in the legend, one for each.",
                 None,
             )
             .named(
-                "height",
-                SyntaxShape::Number,
-                "The maximum height of the plot.",
+                "markers",
+                SyntaxShape::OneOf(vec![SyntaxShape::List(Box::new(SyntaxShape::String)), SyntaxShape::String]),
+                "Mark each series with its own glyph, so they can be told apart without color: default or ascii, or a list of glyphs such as [o x + *].",
+                None,
+            )
+            .named(
+                "styles",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "Line styles for the series in turn, such as [solid dashed dotted], so lines can be told apart without color.",
+                None,
+            )
+            .named(
+                "line-width",
+                SyntaxShape::OneOf(vec![SyntaxShape::Int, SyntaxShape::List(Box::new(SyntaxShape::Int))]),
+                "Draw lines this many dots across, from 1 to 4, or the series in turn as wide as a list says, such as [2 1] to pick out the first.",
+                None,
+            )
+            .switch("smooth", "Fill the corners of the diagonal steps of lines, so they run on unbroken.", None)
+            .named(
+                "gradient",
+                SyntaxShape::String,
+                "Color a single series by its values along a colormap: green-red, blue-red or viridis.",
                 None,
             )
             .named(
-                "title",
+                "label-format",
                 SyntaxShape::String,
-                "Provide a title to the plot.",
-                Some('t'),
+                "How to write the numbers of the axes and labels: %.3f, %.2e, scientific, si, percent, thousands, bytes, duration or date, all but date with :N decimal places.",
+                None,
             )
-            .switch("legend", "Plot a tiny, maybe useful legend.", Some('l'))
+            .switch("legend-stats", "Show the min, mean, max, last value and count of each series in the legend.", None)
             .switch("bars", "Change lines to bars.", Some('b'))
+            .switch("stacked", "Stack the bars of several series at each x, the first on top.", None)
+            .switch("dodge", "Draw the bars of several series at each x side by side.", None)
+            .switch("fill", "Shade the area between the line of a single series and zero.", None)
+            .named(
+                "band",
+                SyntaxShape::List(Box::new(SyntaxShape::Any)),
+                "Shade between two series, named or by index as [low high], such as the ends of a confidence interval, rather than draw them.",
+                None,
+            )
             .switch("steps", "Change lines to steps.", Some('s'))
+            .named(
+                "step-mode",
+                SyntaxShape::String,
+                "Where a step plot steps: pre (default), at each point to the next value; post, at the next point, holding each value until then as a counter does; or mid, halfway between.",
+                None,
+            )
             .switch("points", "Change lines to points.", Some('p'))
+            .switch("stem", "Change lines to stems, a line from zero up or down to each point with a dot on the end.", None)
+            .named(
+                "shapes",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "A shape for each series in turn, such as [points lines]: lines, points, steps, steps:pre, steps:post, steps:mid, bars or stems.",
+                None,
+            )
+            .named(
+                "jitter",
+                SyntaxShape::Number,
+                "Move each point by up to this much either way in x and y, at random, so points which share a value don't all land on one dot.",
+                None,
+            )
+            .named("seed", SyntaxShape::Int, "Seed the random offsets of --jitter, so a different seed jitters the points another way (default 0).", None)
+            .named("order", SyntaxShape::List(Box::new(SyntaxShape::Any)), "Draw these series, by name or index, over the rest, the last on top.", None)
+            .named("highlight", SyntaxShape::Any, "Draw this series, by name or index, in its color over the rest, which are grey.", None)
+            .named("panel", SyntaxShape::Any, "Draw this series, or list of series, by name or index, in a shorter panel under the chart, sharing its x axis.", None)
+            .named("blend", SyntaxShape::String, "Color where series cross: last (the last drawn wins), mix (the colors mixed) or highlight (bright white).", None)
+            .named(
+                "decimate",
+                SyntaxShape::String,
+                "How to downsample long series, either lttb (default) or minmax.",
+                None,
+            )
+            .switch("no-downsample", "Plot every point, even if there are more points than the plot is wide.", None)
+            .switch("live", "Keep reading the stream and redraw the plot in place.", None)
+            .named(
+                "window",
+                SyntaxShape::Int,
+                "The number of most recent points shown in live mode, default is 500.",
+                None,
+            )
+            .switch("skip-nulls", "Leave out null values, joining the points either side unless --break-gaps is given.", None)
+            .named(
+                "fill-nulls",
+                SyntaxShape::String,
+                "Replace null values using linear, previous or zero.",
+                None,
+            )
+            .switch("cumsum", "Plot the running sum of each series, turning rates into a total.", None)
+            .switch("diff", "Plot the change from each value to the next, turning a counter into rates.", None)
+            .named(
+                "normalize",
+                SyntaxShape::String,
+                "Rescale each series before plotting: minmax, zscore or index100.",
+                None,
+            )
+            .named(
+                "transform",
+                SyntaxShape::String,
+                "Put each value through log, log1p or sqrt before plotting, labelling the axis with the values themselves.",
+                None,
+            )
+            .named("offset", SyntaxShape::Number, "Add this to each value before --transform, such as 1 to take the log of zeros.", None)
+            .named(
+                "clip-quantile",
+                SyntaxShape::Number,
+                "Cap each series' values beyond this quantile, and the one as far from the other end, such as 0.99.",
+                None,
+            )
+            .switch("winsorize", "Cap each series' values beyond its 1st and 99th percentiles.", None)
+            .named(
+                "trend",
+                SyntaxShape::String,
+                "Overlay a fitted trend: linear, poly:N or loess.",
+                None,
+            )
+            .switch("grid", "Draw dim grid lines across and down the chart, behind the series.", None)
+            .named("bg", SyntaxShape::String, "The color behind a text chart, such as black, or bright-white on a dark terminal.", None)
+            .named(
+                "hline",
+                SyntaxShape::Any,
+                "Draw horizontal reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .named(
+                "vline",
+                SyntaxShape::Any,
+                "Draw vertical reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .named(
+                "shade-above",
+                SyntaxShape::Number,
+                "Shade the region above this y value.",
+                None,
+            )
+            .named(
+                "shade-below",
+                SyntaxShape::Number,
+                "Shade the region below this y value.",
+                None,
+            )
+            .named(
+                "shade-x",
+                SyntaxShape::List(Box::new(SyntaxShape::Number)),
+                "Shade the region between two x values, given as [from to].",
+                None,
+            )
+            .named(
+                "annotate",
+                SyntaxShape::String,
+                "Label points of each series with their value: any of min, max and last, separated by commas.",
+                None,
+            )
+            .switch("labels", "Label each point with its value, leaving out labels that would overlap.", None)
+            .switch("break-gaps", "Break the line at null, NaN and infinite values, rather than joining the points either side.", None)
+            .switch("meta", "Return a record of the chart and facts about the input.", None)
+            .input_output_types(numeric_input_types())
             .category(Category::Experimental)
     }
 
@@ -410,202 +3479,205 @@ impl SimplePluginCommand for CommandPlot {
         "Render an ASCII plot from a list of values."
     }
 
+    fn search_terms(&self) -> Vec<&str> {
+        vec!["chart", "graph", "line", "braille", "visualize"]
+    }
+
+    fn examples(&self) -> Vec<Example<'_>> {
+        vec![
+            Example {
+                example: "[1 5 3 8 2] | plot --width 64 --height 32",
+                description: "Plot a list of numbers as a line.",
+                result: Some(Value::test_string(
+                    [
+                        "    8.0 ⡁                     ⢀⠔⢣        ",
+                        "        ⠄                    ⢠⠊  ⠣⡀      ",
+                        "        ⠂                   ⡰⠁    ⠱⡀     ",
+                        "        ⡁      ⢀⠤⣀        ⢀⠎       ⠘⡄    ",
+                        "    4.5 ⠄    ⢀⠔⠁  ⠉⠒⢄⡀   ⡠⠃         ⠈⢆   ",
+                        "        ⠂   ⡔⠁       ⠈⠑⠢⡔⠁           ⠈⢢  ",
+                        "        ⡁ ⡠⠊                           ⠣⡀",
+                        "        ⡤⠊                               ",
+                        "    1.0 ⠁                                ",
+                        "        0.0                           4.0",
+                        "    ",
+                    ]
+                    .join("\n"),
+                )),
+            },
+            Example {
+                example: "[[1 5 3 8 2] [4 2 6 1 3]] | plot --width 64 --height 32 --steps",
+                description: "Plot two lists as steps, in two colors.",
+                result: Some(Value::test_string(
+                    [
+                        "    8.0 ⡁               ⡏⠉⠉⠉⠉⠉⠉⠉⡇        ",
+                        "        ⠄               ⡇       ⡇        ",
+                        "        ⠂       ⡖⠒⠒⠒⠒⠒⠒⠒⡇       ⡇        ",
+                        "        ⡥⠤⠤⠤⠤⠤⠤⠤⡇       ⡇       ⡇        ",
+                        "    4.5 ⡇       ⡇       ⡇       ⡇        ",
+                        "        ⡇       ⣇⣀⣀⣀⣀⣀⣀⣀⡇       ⣇⣀⣀⣀⣀⣀⣀⣀⡀",
+                        "        ⣇⣀⣀⣀⣀⣀⣀⣀⡇       ⡇       ⣇⣀⣀⣀⣀⣀⣀⣀⡀",
+                        "        ⡇               ⡇       ⡇        ",
+                        "    1.0 ⠁               ⠉⠉⠉⠉⠉⠉⠉⠉⠁        ",
+                        "        0.0                           4.0",
+                        "    ",
+                    ]
+                    .join("\n"),
+                )),
+            },
+            Example {
+                example: "[1 NaN 3 inf 5] | plot --width 64 --height 32",
+                description: "Plot past NaN and infinite values, joining the points either side of them.",
+                result: Some(Value::test_string(
+                    [
+                        "    5.0 ⡁                            ⣀⠤⠒⠁",
+                        "        ⠄                        ⣀⠤⠒⠉    ",
+                        "        ⠂                    ⣀⠤⠒⠉        ",
+                        "        ⡁                ⣀⠤⠒⠉            ",
+                        "    3.0 ⠄            ⣀⠤⠒⠉                ",
+                        "        ⠂        ⣀⠤⠒⠉                    ",
+                        "        ⡁    ⣀⠤⠒⠉                        ",
+                        "        ⠄⣀⠤⠒⠉                            ",
+                        "    1.0 ⠉                                ",
+                        "        0.0                           4.0",
+                        "    ",
+                    ]
+                    .join("\n"),
+                )),
+            },
+            Example {
+                example: "[1 NaN 3 inf 5] | plot --width 64 --height 32 --break-gaps",
+                description: "Break the line at NaN and infinite values, leaving the points between them as dots.",
+                result: Some(Value::test_string(
+                    [
+                        "    5.0 ⡁                               ⠁",
+                        "        ⠄                                ",
+                        "        ⠂                                ",
+                        "        ⡁                                ",
+                        "    3.0 ⠄               ⠁                ",
+                        "        ⠂                                ",
+                        "        ⡁                                ",
+                        "        ⠄                                ",
+                        "    1.0 ⠁                                ",
+                        "        0.0                           4.0",
+                        "    ",
+                    ]
+                    .join("\n"),
+                )),
+            },
+            Example {
+                example: "[NaN inf] | plot",
+                description: "Fail with an error, rather than draw an empty chart, when there are no finite values to plot.",
+                result: None,
+            },
+            Example {
+                example: "[1 2 null 4 5 null 7] | plot --skip-nulls --width 64 --height 32",
+                description: "Leave out nulls, joining the points either side of them.",
+                result: Some(Value::test_string(
+                    [
+                        "    7.0 ⡁                            ⢀⡠⠔⠁",
+                        "        ⠄                        ⢀⡠⠔⠊⠁   ",
+                        "        ⠂                    ⢀⡠⠔⠊⠁       ",
+                        "        ⡁                ⢀⡠⠔⠊⠁           ",
+                        "    4.0 ⠄            ⢀⡠⠔⠉⠁               ",
+                        "        ⠂        ⢀⡠⠔⠊⠁                   ",
+                        "        ⡁    ⢀⡠⠔⠊⠁                       ",
+                        "        ⠄⢀⡠⠔⠊⠁                           ",
+                        "    1.0 ⠉⠁                               ",
+                        "        0.0                           6.0",
+                        "    ",
+                    ]
+                    .join("\n"),
+                )),
+            },
+            Example {
+                example: "[1 null NaN 4 null 6] | plot --fill-nulls linear --break-gaps --width 64 --height 32",
+                description: "Fill nulls in between their neighbours, leaving the NaN a gap.",
+                result: Some(Value::test_string(
+                    [
+                        "    6.0 ⡁                            ⣀⠤⠒⠁",
+                        "        ⠄                        ⡠⠤⠒⠉    ",
+                        "        ⠂                    ⣀⠤⠒⠉        ",
+                        "        ⡁                  ⠒⠉            ",
+                        "    3.5 ⠄                                ",
+                        "        ⠂                                ",
+                        "        ⡁    ⢀⡠                          ",
+                        "        ⠄⢀⡠⠔⠊⠁                           ",
+                        "    1.0 ⠉⠁                               ",
+                        "        0.0                           5.0",
+                        "    ",
+                    ]
+                    .join("\n"),
+                )),
+            },
+            Example {
+                example: "ls | get size | plot --label-format bytes -t Sizes",
+                description: "Plot the sizes of the files here, labelled in bytes.",
+                result: None,
+            },
+        ]
+    }
+
     fn run(
         &self,
         _plugin: &Self::Plugin,
-        _engine: &nu_plugin::EngineInterface,
+        engine: &nu_plugin::EngineInterface,
         call: &EvaluatedCall,
-        input: &Value,
-    ) -> Result<Value, LabeledError> {
-        match input.as_list() {
-            Ok(list) => {
-                if list.is_empty() {
-                    return Err(LabeledError::new("Can't plot a zero element list.").with_label( "No elements in the list.", call.head));
-                }
-                let (value_type, list_len_op) = check_equality_of_list(list, call)?;
-
-                // if in fact we have a nested list
-                if let Some(_len) = list_len_op {
-                    // we haven't implemented this yet
-                    self.plot_nested(call, input)
-                } else {
-                    // we have a normal plot, single list of numbers
-                    match value_type {
-                        Type::Float | Type::Int => self.plot(call, input),
-                        e =>  Err(LabeledError::new(format!("List type is {}, but should be float or int.", e)).with_label("Incorrect List type.", call.head)),
-                    }
-                }
-            },
-            Err(e) => Err(LabeledError::new(format!("Input type should be a list: {}.", e)).with_label( "Incorrect input type.", call.head)),
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        if call.has_flag("live")? {
+            let call = &with_config(engine, call)?;
+            return live::live_plot(call, input, engine.signals().clone(), use_color(engine, call)?);
         }
+        self.plot_input(engine, call, input)
     }
 }
 
 impl Plotter for CommandHist {
     fn plot(
-        &self,
-        call: &EvaluatedCall,
-        input: &Value,
-    ) -> Result<Value, LabeledError> {
-        let CliOpts {
-            height_op,
-            width_op,
-            legend,
-            steps,
-            bars,
-            points,
-            title,
-            bins,
-        } = parse_cli_opts(call)?;
-
-        let max_x = width_op.unwrap_or(200);
-        let max_y = height_op.unwrap_or(50);
-
-        let values = input.as_list()?;
-
-        let v: Result<Vec<(f32, f32)>, LabeledError> = values
-            .iter()
-            .enumerate()
-            .map(|(i, e)| match e {
-                Value::Int { .. } => Ok((i as f32, e.as_int()? as f32)),
-                Value::Float { .. } => Ok((i as f32, e.as_float()? as f32)),
-                e => Err(LabeledError::new(format!("Got {}, need integer or float.", e.get_type())).with_label("Incorrect type supplied", call.head)),
-            })
-            .collect();
-
-        let (min, max) = min_max(
-            &v.clone()
-                .unwrap()
-                .iter()
-                .map(|(_, e)| *e)
-                .collect::<Vec<f32>>(),
-        );
-        let chart_data: Vec<(f32, f32)> = histogram(
-            &v.unwrap(),
-            min,
-            max,
-            bins.map(|e| e as usize).unwrap_or(20),
-        );
-        let min_max_x = (min, max);
-
-
-        let mut chart = Chart::new(max_x, max_y, min_max_x.0, min_max_x.1)
-            .lineplot(&chart_shape(steps, bars, points, call, &chart_data)?)
-            .to_string();
-
-        if let Some(t) = title {
-            chart = TAB.to_owned() + &t + "\n" + &chart;
-        }
-        chart = TAB.to_owned() + &chart.replace('\n', &format!("\n{}", TAB));
-
-        if legend {
-            chart += &format!("Line 1: {}", "---".white());
-        }
-
-        Ok(Value::string(chart, call.head))
-    }
-
-    fn plot_nested(
-        &self,
-        call: &EvaluatedCall,
-        input: &Value,
-    ) -> Result<Value, LabeledError> {
-        let CliOpts {
-            height_op,
-            width_op,
-            legend,
-            steps,
-            bars,
-            points,
-            title,
-            bins,
-        } = parse_cli_opts(call)?;
-
-        let max_x = width_op.unwrap_or(200);
-        let max_y = height_op.unwrap_or(50);
-
-        let values = input.as_list()?;
-        if values.len() > 5 {
-            return Err(LabeledError::new("Nested list can't contain more than 5 inner lists.").with_label("Nested list error.", call.head));
-        }
-
-        let mut data = vec![];
-
-        for val in values {
-            let list = val.as_list()?;
-
-            let v: Result<Vec<(f32, f32)>, LabeledError> = list
-                .iter()
-                .enumerate()
-                .map(|(i, e)| match e {
-                    Value::Int { .. } => Ok((i as f32, e.as_int()? as f32)),
-                    Value::Float { .. } => Ok((i as f32, e.as_float()? as f32)),
-                    e => Err(LabeledError::new(format!("Got {}, need integer or float.", e.get_type())).with_label("Incorrect type supplied.", call.head)),
-                })
-                .collect();
-
-            let x: Vec<f32> = v.clone()?.iter().map(|e| e.0).collect();
-            let y: Option<Vec<f32>> = None;
-            let min_max_x = (min_max(&x), y);
-
-            data.push((min_max_x, v?));
-        }
-
-        // copying data structure again here but wanted to be explicit.
-        let mut mins = 0.0;
-        let mut maxs = 0.0;
-
-        for (i, (_, el)) in data.iter().enumerate() {
-            let (min, max) = min_max(&el.iter().map(|(_, e)| *e).collect::<Vec<f32>>());
-            if i == 0 {
-                maxs = max;
-                mins = min;
-            } else {
-                if max > maxs {
-                    maxs = max;
-                }
-                if min < mins {
-                    mins = min;
-                }
-            }
-        }
-        let (min, max) = (mins, maxs);
-
-        let hist_data: Vec<Vec<(f32, f32)>> = data
-            .iter()
-            .map(|(_, e)| histogram(e, mins, maxs, bins.map(|e| e as usize).unwrap_or(20)))
-            .collect();
-
-        check_chart_shape(steps, bars, points, call)?;
-        let shapes: Vec<Shape> = (&hist_data)
-            .iter()
-            .map(|data| chart_shape(steps, bars, points, call, data).unwrap())
-            .collect();
-        let charts = (&shapes).iter()
-            .enumerate()
-            .fold(&mut Chart::new(max_x, max_y, min, max), |chart, (i, shape)| {
-                chart.linecolorplot(shape, COLORS[i])
-            })
-            .to_string();
+        &self,
+        call: &EvaluatedCall,
+        input: Vec<f64>,
+        meta: &mut Meta,
+    ) -> Result<Value, LabeledError> {
+        self.plot_nested(call, vec![input], meta)
+    }
 
-        let mut final_chart = TAB.to_owned() + &charts.replace('\n', &format!("\n{}", TAB));
+    fn plot_nested(
+        &self,
+        call: &EvaluatedCall,
+        input: Vec<Vec<f64>>,
+        meta: &mut Meta,
+    ) -> Result<Value, LabeledError> {
+        let opts = parse_cli_opts(call)?;
 
-        if let Some(t) = title {
-            final_chart = TAB.to_owned() + &t + "\n" + &final_chart;
+        if input.len() > 5 {
+            return Err(LabeledError::new("Nested list can't contain more than 5 inner lists.").with_label("Nested list error.", call.head));
         }
 
-        if legend {
-            for (l, (_, _)) in data.iter().enumerate() {
-                let col: PixelColor = COLORS[l];
-                final_chart += &format!("Line {}: {} ", l + 1, "---".color(col));
-            }
-        }
+        // all series share the same bins, chosen from the pooled values,
+        // and the bytes of binary data have a bin for each value
+        let pooled = pooled(&input);
+        let bins = match (&opts.bins, meta.units_from(0)) {
+            (None, Unit::Binary) => Some(Bins::Edges((0..=256).map(f64::from).collect())),
+            (bins, _) => bins.clone(),
+        };
+        let edges = hist_edges(bins, &pooled, min_max(&pooled).ok_or_else(|| no_finite_values(call))?, call, meta)?;
+        let hist_data: Vec<Vec<(f64, f64)>> = input
+            .iter()
+            .map(|list| {
+                let v: Vec<(f64, f64)> = list.iter().map(|e| (0.0, *e)).collect();
+                let mut hist = histogram_edges(&v, &edges);
+                normalize(&mut hist, &edges, opts.cumulative, opts.density);
+                hist
+            })
+            .collect();
 
-        Ok(Value::string(final_chart, call.head))
+        render_series(call, &opts, (edges[0], edges[edges.len() - 1]), hist_data, &bins_legend(meta), meta.units_from(0), true)
     }
 }
 
-impl SimplePluginCommand for CommandHist {
+impl PluginCommand for CommandHist {
     type Plugin = PluginPlot;
 
     fn name(&self) -> &str {
@@ -613,70 +3685,200 @@ impl SimplePluginCommand for CommandHist {
     }
 
     fn signature(&self) -> nu_protocol::Signature {
-        Signature::build("hist")
-            .description("Render an ASCII histogram from a list of values.")
+        common_chart_flags(Signature::build("hist").description("Render an ASCII histogram from a list of values, or a frequency bar chart from a list of strings."))
+            .switch("square", "Draw the chart as tall as it is wide, as many dots across as up.", None)
             .named(
-                "width",
-                SyntaxShape::Number,
-                "The maximum width of the plot.",
+                "bins",
+                SyntaxShape::OneOf(vec![SyntaxShape::Int, SyntaxShape::String]),
+                "The number of bins in the histogram, default is 20, or auto, sturges, fd or scott to choose from the data.",
                 None,
             )
             .named(
-                "height",
+                "bin-width",
                 SyntaxShape::Number,
-                "The maximum height of the plot.",
+                "The width of each bin, with edges at multiples of the width.",
+                None,
+            )
+            .named(
+                "edges",
+                SyntaxShape::List(Box::new(SyntaxShape::Number)),
+                "The edges of the bins, as a list of increasing numbers.",
+                None,
+            )
+            .named(
+                "top",
+                SyntaxShape::Int,
+                "For a list of strings, only show the N most common values.",
                 None,
             )
+            .switch("cumulative", "Accumulate the counts from left to right.", None)
+            .switch("density", "Scale each histogram so its area sums to 1.", None)
             .named(
-                "title",
+                "names",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "Names for the series in the legend, one for each.",
+                None,
+            )
+            .named(
+                "markers",
+                SyntaxShape::OneOf(vec![SyntaxShape::List(Box::new(SyntaxShape::String)), SyntaxShape::String]),
+                "Mark each series with its own glyph, so they can be told apart without color: default or ascii, or a list of glyphs such as [o x + *].",
+                None,
+            )
+            .named(
+                "gradient",
                 SyntaxShape::String,
-                "Provide a title to the plot.",
-                Some('t'),
+                "Color a single series by its values along a colormap: green-red, blue-red or viridis.",
+                None,
             )
             .named(
-                "bins",
-                SyntaxShape::Number,
-                "The number of bins in the histogram, default is 20.",
+                "label-format",
+                SyntaxShape::String,
+                "How to write the numbers of the axes and labels: %.3f, %.2e, scientific, si, percent, thousands, bytes, duration or date, all but date with :N decimal places.",
                 None,
             )
-            .switch("legend", "Plot a tiny, maybe useful legend.", Some('l'))
             .switch("bars", "Change lines to bars.", Some('b'))
             .switch("steps", "Change lines to steps.", Some('s'))
+            .named(
+                "step-mode",
+                SyntaxShape::String,
+                "Where a step plot steps: pre (default), at each point to the next value; post, at the next point, holding each value until then as a counter does; or mid, halfway between.",
+                None,
+            )
+            .switch("skip-nulls", "Leave out null values, joining the points either side unless --break-gaps is given.", None)
+            .named(
+                "fill-nulls",
+                SyntaxShape::String,
+                "Replace null values using linear, previous or zero.",
+                None,
+            )
+            .switch("grid", "Draw dim grid lines across and down the chart, behind the series.", None)
+            .named("bg", SyntaxShape::String, "The color behind a text chart, such as black, or bright-white on a dark terminal.", None)
+            .named(
+                "hline",
+                SyntaxShape::Any,
+                "Draw horizontal reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .named(
+                "vline",
+                SyntaxShape::Any,
+                "Draw vertical reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .named(
+                "shade-above",
+                SyntaxShape::Number,
+                "Shade the region above this y value.",
+                None,
+            )
+            .named(
+                "shade-below",
+                SyntaxShape::Number,
+                "Shade the region below this y value.",
+                None,
+            )
+            .named(
+                "shade-x",
+                SyntaxShape::List(Box::new(SyntaxShape::Number)),
+                "Shade the region between two x values, given as [from to].",
+                None,
+            )
+            .named(
+                "annotate",
+                SyntaxShape::String,
+                "Label points of each series with their value: any of min, max and last, separated by commas.",
+                None,
+            )
+            .switch("labels", "Label each point with its value, leaving out labels that would overlap.", None)
+            .switch("break-gaps", "Break the line at null, NaN and infinite values, rather than joining the points either side.", None)
+            .switch("meta", "Return a record of the chart and facts about the input.", None)
+            .input_output_types([numeric_input_types(), vec![(Type::List(Box::new(Type::String)), Type::Any)]].concat())
             .category(Category::Experimental)
     }
 
     fn description(&self) -> &str {
-        "Render an ASCII histogram from a list of values."
+        "Render an ASCII histogram from a list of values, or a frequency bar chart from a list of strings."
+    }
+
+    fn search_terms(&self) -> Vec<&str> {
+        vec!["chart", "histogram", "distribution", "frequency", "bins"]
+    }
+
+    fn examples(&self) -> Vec<Example<'_>> {
+        vec![
+            Example {
+                example: "[1 2 2 3 3 3 4 4 5] | hist --width 64 --height 32 --bins 5",
+                description: "Count numbers in five bins.",
+                result: Some(Value::test_string(
+                    [
+                        "    3.0            ⢀⠔⠉⠢⡀                 ",
+                        "                 ⡠⠔⠁   ⠈⠢⡀               ",
+                        "              ⢀⡠⠊        ⠈⠢⣀             ",
+                        "            ⣀⠔⠁             ⠑⠤⡀          ",
+                        "    1.5  ⢀⠤⠊                  ⠈⠒⢄        ",
+                        "        ⠒⠁                       ⠑       ",
+                        "                                         ",
+                        "                                         ",
+                        "    0.0 ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ",
+                        "        1.0                           5.0",
+                        "    ",
+                    ]
+                    .join("\n"),
+                )),
+            },
+            Example {
+                example: "[2 2 2] | hist --bin-width 1 --bars --width 64 --height 32",
+                description: "A single bin is a bar standing on 0.",
+                result: Some(Value::test_string(
+                    [
+                        "    3.0 ⡇                                ",
+                        "        ⡇                                ",
+                        "        ⡇                                ",
+                        "        ⡇                                ",
+                        "    1.5 ⡇                                ",
+                        "        ⡇                                ",
+                        "        ⡇                                ",
+                        "        ⡇                                ",
+                        "    0.0 ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ",
+                        "        2.0                           3.0",
+                        "    ",
+                    ]
+                    .join("\n"),
+                )),
+            },
+            Example {
+                example: "[a b a c a b] | hist",
+                description: "Count strings, drawn as a bar for each, most common first.",
+                result: None,
+            },
+            Example {
+                example: "open data.csv | get value | hist --density --bins 20",
+                description: "Draw the distribution of a column, scaled so its area is 1.",
+                result: None,
+            },
+        ]
     }
 
     fn run(
         &self,
         _plugin: &Self::Plugin,
-        _engine: &nu_plugin::EngineInterface,
+        engine: &nu_plugin::EngineInterface,
         call: &EvaluatedCall,
-        input: &Value,
-    ) -> Result<Value, LabeledError> {
-        match input.as_list() {
-            Ok(list) => {
-                if list.is_empty() {
-                    return Err(LabeledError::new("Can't plot a zero element list.").with_label( "No elements in the list.", call.head));
-                }
-                let (value_type, list_len_op) = check_equality_of_list(list, call)?;
-
-                // if in fact we have a nested list
-                if let Some(_len) = list_len_op {
-                    // we haven't implemented this yet
-                    self.plot_nested(call, input)
-                } else {
-                    // we have a normal plot, single list of numbers
-                    match value_type {
-                        Type::Float | Type::Int => self.plot(call, input),
-                        e =>  Err(LabeledError::new(format!("List type is {}, but should be float or int.", e)).with_label("Incorrect List type.", call.head)),
-                    }
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        // a list of strings is counted rather than binned
+        let input = match input {
+            PipelineData::ListStream(..) | PipelineData::Value(Value::List { .. }, ..) => {
+                let mut values = input.into_iter().peekable();
+                if let Some(Value::String { .. }) = values.peek() {
+                    return barchart::categorical_hist(engine, &with_config(engine, call)?, values);
                 }
-            },
-            Err(e) => Err(LabeledError::new(format!("Input type should be a list: {}.", e)).with_label( "Incorrect input type.", call.head)),
-        }
+                PipelineData::ListStream(ListStream::new(values, call.head, engine.signals().clone()), None)
+            }
+            input => input,
+        };
+        self.plot_input(engine, call, input)
     }
 }
 
@@ -684,7 +3886,8 @@ impl Plotter for CommandXyplot {
     fn plot(
         &self,
         call: &EvaluatedCall,
-        _input: &Value,
+        _input: Vec<f64>,
+        _meta: &mut Meta,
     ) -> Result<Value, LabeledError> {
         Err(LabeledError::new( "Doesn't make sense to plot an xyplot with a single list of values.").with_label("Plot type error.", call.head))
     }
@@ -692,90 +3895,296 @@ impl Plotter for CommandXyplot {
     fn plot_nested(
         &self,
         call: &EvaluatedCall,
-        input: &Value,
+        input: Vec<Vec<f64>>,
+        meta: &mut Meta,
     ) -> Result<Value, LabeledError> {
-        let CliOpts {
-            height_op,
-            width_op,
-            legend,
-            steps,
-            bars,
-            points,
-            title,
-            bins: _,
-        } = parse_cli_opts(call)?;
-
-        let max_x = width_op.unwrap_or(200);
-        let max_y = height_op.unwrap_or(50);
-
-        let values = input.as_list()?;
-        if values.len() > 5 {
-            return Err(LabeledError::new("Nested list can't contain more than 5 inner lists.").with_label("Nested list error.", call.head));
-        }
-
-        let mut data = vec![];
-
-        for val in values {
-            let list = val.as_list()?;
+        let mut opts = parse_cli_opts(call)?;
+        let (mut series, names) = nested_series(call, opts.names.as_deref(), input, opts.parametric)?;
+        opts.prepare.apply_points(&mut series);
+        opts.prepare.count(meta);
+        let series = if opts.polar { polar(series) } else { series };
+        xy_chart(call, opts, series, names, None, meta.xy_units())
+    }
 
-            let v: Result<Vec<(f32, f32)>, LabeledError> = list
-                .iter()
-                .enumerate()
-                .map(|(i, e)| match e {
-                    Value::Int { .. } => Ok((i as f32, e.as_int()? as f32)),
-                    Value::Float { .. } => Ok((i as f32, e.as_float()? as f32)),
-                    e => Err(LabeledError::new(format!("Got {}, need integer or float.", e.get_type())).with_label("Incorrect type supplied.", call.head)),
-                })
-                .collect();
+    fn plot_pairs(
+        &self,
+        call: &EvaluatedCall,
+        input: Vec<Group>,
+        meta: &mut Meta,
+    ) -> Result<Value, LabeledError> {
+        let mut opts = parse_cli_opts(call)?;
+        let (mut series, names) = pair_series(call, &mut opts, input)?;
+        opts.prepare.apply_points(&mut series);
+        opts.prepare.count(meta);
+        let series = if opts.polar { polar(series) } else { series };
+        xy_chart(call, opts, series, names, None, meta.xy_units())
+    }
+}
 
-            let min_max_x = {
-                let x: Vec<f32> = v.clone()?.iter().map(|e| e.0).collect();
-                let temp: Vec<f32> = v.clone()?.iter().map(|e| e.1).collect();
-                let y = Some(min_max(&temp));
-                (min_max(&x), y)
-            };
+/// Series of x and y points, with their names.
+type NamedSeries = (Vec<Vec<(f64, f64)>>, Vec<String>);
 
-            data.push((min_max_x, v?));
-        }
-        if data.len() != 2 {
-            return Err(LabeledError::new("xyplot requires a nested list of length 2.").with_label( "Wrong number of dimensions in xyplot.", call.head));
+/// The series of a nested list of x values and lists of y values, and
+/// their names. A `parametric` nested list is of pairs of lists instead,
+/// the x and the y values of each series, named after the second.
+fn nested_series(call: &EvaluatedCall, names: Option<&[String]>, input: Vec<Vec<f64>>, parametric: bool) -> Result<NamedSeries, LabeledError> {
+    if parametric {
+        if !matches!(input.len(), 2 | 4 | 6 | 8 | 10) {
+            return Err(LabeledError::new("A parametric xyplot requires a nested list of 1 to 5 pairs of lists of x and y values.").with_label("Wrong number of dimensions in xyplot.", call.head));
         }
+        let series: Vec<Vec<(f64, f64)>> = input.chunks(2).map(|xy| xy[0].iter().copied().zip(xy[1].iter().copied()).collect()).collect();
+        let y_names: Option<Vec<String>> = names.filter(|names| names.len() == 2 * series.len()).map(|names| names.iter().skip(1).step_by(2).cloned().collect());
+        let count = series.len();
+        return Ok((series, series_names(call, y_names.as_deref().or(names), count)?));
+    }
+    // the x values, then a list of y values for each series
+    let (xs, ys) = match &input[..] {
+        [xs, ys @ ..] if !ys.is_empty() && ys.len() <= 5 => (xs, ys),
+        _ => return Err(LabeledError::new("xyplot requires a nested list of x values and 1 to 5 lists of y values.").with_label("Wrong number of dimensions in xyplot.", call.head)),
+    };
+    let series: Vec<Vec<(f64, f64)>> = ys
+        .iter()
+        .map(|y| xs.iter().copied().zip(y.iter().copied()).collect())
+        .collect();
+
+    let names = match names {
+        // a name for the x values as well, as a single series once needed, is left out
+        Some([_, rest @ ..]) if rest.len() == ys.len() => Some(rest),
+        names => names,
+    };
+    Ok((series, series_names(call, names, ys.len())?))
+}
 
-        let (min, max) = {
-            // only interested in the first list
-            let (_, xy_x) = &data[0].0;
-            xy_x.unwrap()
+/// The series of groups of points, and their names. Groups are named
+/// after the field they were split by, and drawn in the colors they
+/// name, if they all name one. The error bars of their points are added
+/// to the annotations, each in a dimmer shade of its series' color.
+fn pair_series(call: &EvaluatedCall, opts: &mut CliOpts, input: Vec<Group>) -> Result<NamedSeries, LabeledError> {
+    let group_names: Option<Vec<String>> = input.iter().map(|g| g.name.clone()).collect();
+    let names = series_names(call, opts.names.as_deref().or(group_names.as_deref()), input.len())?;
+    if let Some(colors) = input.iter().map(|g| g.color).collect() {
+        opts.palette.colors = Some(colors);
+    }
+    if opts.polar && input.iter().any(|g| !g.errors.is_empty()) {
+        return Err(LabeledError::new("Error bars can't be drawn on a polar chart.").with_label("Error bar error.", call.head));
+    }
+    for (i, group) in input.iter().enumerate() {
+        let color = match (input.len(), opts.palette.gradient) {
+            (_, Some(_)) => None,
+            (1, None) => opts.palette.single(),
+            (_, None) => Some(opts.palette.nth(i)),
         };
+        let color = dimmer(color);
+        opts.annotations.extend(group.errors.iter().map(|&(x, low, high)| Annotation::ErrorBar(x, low, high, color)));
+    }
+    Ok((input.into_iter().map(|g| g.points).collect(), names))
+}
 
-        let y: Vec<f32> = data[1].1.iter().map(|e| e.1).collect();
-        let xy: Vec<(f32, f32)> = data[0].1.iter().map(|e| e.1).zip(y).collect();
-        let chart_data = vec![xy];
+/// `series` of points of an angle, in radians, and a radius, as points
+/// of x and y values.
+fn polar(series: Vec<Vec<(f64, f64)>>) -> Vec<Vec<(f64, f64)>> {
+    series
+        .into_iter()
+        .map(|points| points.into_iter().map(|(theta, r)| (r * theta.cos(), r * theta.sin())).collect())
+        .collect()
+}
+
+/// The size of a chart at most `width` by `height`, and x and y ranges
+/// about `x_range` and `y_range`, to draw a y unit `aspect` times as
+/// long as an x unit, so that at 1 a circle is round. The dots of every
+/// chart are square: a text chart has 2 by 4 of them in a character,
+/// which is twice as tall as it is wide.
+fn aspect_scale(x_range: (f64, f64), y_range: (f64, f64), aspect: f64, width: u32, height: u32) -> ((u32, u32), (f64, f64), (f64, f64)) {
+    let span = |(lo, hi): (f64, f64)| if hi > lo { hi - lo } else { 1.0 };
+    // the x units of a dot, and the y units
+    let unit = f64::max(span(x_range) / width as f64, span(y_range) * aspect / height as f64);
+    let units = (unit, unit / aspect);
+    // textplots can't draw a chart less than 32 dots either way
+    let dots = |range, unit: f64, most: u32| ((span(range) / unit).ceil() as u32).clamp(32.min(most), most);
+    let around = |(lo, hi): (f64, f64), unit: f64, dots: u32| {
+        let mid = if hi >= lo { (lo + hi) / 2.0 } else { 0.0 };
+        (mid - unit * dots as f64 / 2.0, mid + unit * dots as f64 / 2.0)
+    };
+    let (width, height) = (dots(x_range, units.0, width), dots(y_range, units.1, height));
+    ((width, height), around(x_range, units.0, width), around(y_range, units.1, height))
+}
 
-        let mut chart = Chart::new(max_x, max_y, min, max);
+/// An xyplot of `series`, points of x and y values, called `names`.
+/// Given `ranges`, the x and y ranges of other charts, it is drawn to
+/// the same scale as them. The x and y values are in `units`, or both
+/// in the unit of the radius of polar coordinates. Polar and parametric
+/// charts are drawn to the same scale on both axes, unless an aspect
+/// says otherwise.
+fn xy_chart(
+    call: &EvaluatedCall,
+    opts: CliOpts,
+    mut series: Vec<Vec<(f64, f64)>>,
+    mut names: Vec<String>,
+    ranges: Option<((f64, f64), (f64, f64))>,
+    units: (Unit, Unit),
+) -> Result<Value, LabeledError> {
+    let CliOpts {
+        height_op,
+        width_op,
+        legend,
+        legend_stats,
+        legend_pos,
+        pad,
+        format,
+        charset,
+        mut palette,
+        steps,
+        step_mode,
+        bars,
+        points,
+        stem,
+        shapes,
+        bar_layout,
+        fill,
+        band,
+        titles,
+        connect_gaps: connect,
+        jitter,
+        order,
+        highlight,
+        panel,
+        blend,
+        smooth,
+        trend,
+        mut annotations,
+        marks,
+        labels: show_labels,
+        label_format,
+        polar,
+        parametric,
+        sort_x,
+        aggregate,
+        every,
+        aspect,
+        prepare,
+        object,
+        ..
+    } = opts;
 
-        let charts = chart
-            .lineplot(&chart_shape(steps, bars, points, call, &chart_data[0])?)
-            .to_string();
+    let mut max_x = width_op.unwrap_or(200);
+    let mut max_y = height_op.unwrap_or(50);
+    let labels = label_formats(label_format, if polar { (units.1, units.1) } else { units });
+    // the axes of a polar chart are across and up, not along the radius
+    let labels = if polar { labels } else { (labels.0, prepare.y_labels(labels.1)) };
+    let every = bucket_width(call, every, units.0, &series)?;
+    sort_series(&mut series, sort_x, aggregate, every);
+    if !series.iter().flatten().any(|(x, y)| x.is_finite() && y.is_finite()) {
+        return Err(no_finite_values(call));
+    }
+    let band = take_band(call, band.as_deref(), &mut series, &mut names)?;
+    jitter_series(&mut series, jitter);
+    let highlight = highlight.map(|h| series_index(call, &h, &names, "highlight", "highlight")).transpose()?;
+    if let Some(i) = highlight {
+        palette.highlight(i, names.len());
+    }
+    let panel = panel_series(call, panel.as_ref(), &names)?;
+    let panel_y;
+    (max_y, panel_y) = if panel.is_empty() { (max_y, 0) } else { panel_heights(max_y) };
 
+    if series.len() > 1 && trend.is_some() {
+        return Err(LabeledError::new("A trend can only be fitted to a single series.").with_label("Trend error.", call.head));
+    }
+    if series.len() > 1 && fill {
+        return Err(LabeledError::new("Only a single series can be filled.").with_label("Fill error.", call.get_flag_span("fill").unwrap_or(call.head)));
+    }
+    if series.len() > 1 {
+        check_gradient(&palette, call)?;
+    }
+    // the ends of a band, as points to go with the series
+    let ends = band.iter().flat_map(|(low, high)| low.iter().chain(high));
+    let (mut min, mut max) = match ranges {
+        Some((x_range, _)) => x_range,
+        None => extent(series.iter().flatten().chain(ends.clone()).map(|(x, _)| *x)).ok_or_else(|| no_finite_values(call))?,
+    };
+    let mut y_range = ranges.map(|(_, y_range)| y_range);
+    let aspect = aspect.or((polar || parametric).then_some(1.0));
+    if let Some(aspect) = aspect {
+        let ys = match y_range {
+            Some(ys) => ys,
+            None => extent(series.iter().flatten().chain(ends).map(|(_, y)| *y)).ok_or_else(|| no_finite_values(call))?,
+        };
+        let (size, xs, ys) = aspect_scale((min, max), ys, aspect, max_x, max_y);
+        ((max_x, max_y), (min, max), y_range) = (size, xs, Some(ys));
+    }
 
-        let mut final_chart = TAB.to_owned() + &charts.replace('\n', &format!("\n{}", TAB));
+    let count = series.len();
+    // the marks and labels of a series go on the chart it's drawn on
+    let mut panel_annotations = vec![];
+    for (i, xy) in series.iter_mut().enumerate() {
+        connect_gaps(xy, connect);
+        let annotations = if panel.contains(&i) { &mut panel_annotations } else { &mut annotations };
+        annotations.extend(mark_labels(&marks, labels.1, xy));
+        annotations.extend(value_labels(show_labels, labels.1, xy));
+        annotations.extend(palette.marks(i, count, xy, (min, max), max_x));
+    }
+    if fill {
+        annotations.push(area(series[0].iter().map(|&(x, y)| (x, 0.0, y)).collect(), nth_steps(shapes.as_deref(), 0, steps, step_mode), palette.single()));
+    }
+    if let Some((low, high)) = &band {
+        let color = if count == 1 { palette.single() } else { None };
+        annotations.push(area(band_points(low, high, connect), nth_steps(shapes.as_deref(), 0, steps, step_mode), color));
+    }
+    let fit = fit_trend(call, trend, &series[0], (min, max), max_x)?;
 
-        if let Some(t) = title {
-            final_chart = TAB.to_owned() + &t + "\n" + &final_chart;
+    let mut entries = vec![];
+    if legend {
+        for (l, xy) in series.iter().enumerate() {
+            let ys: Vec<f64> = xy.iter().map(|(_, y)| *y).collect();
+            entries.push(palette.entry(&names[l], l, count, legend_stats.then_some(&ys[..]), labels.1));
         }
-
-        if legend {
-            for (l, (_, _)) in data.iter().enumerate() {
-                let col: PixelColor = COLORS[l];
-                final_chart += &format!("Line {}: {} ", l + 1, "---".color(col));
-            }
+        entries.extend(fit.as_ref().map(|fit| trend_legend(fit, palette.nth(1))));
+        entries.extend(prepare.legend());
+    }
+    if let Some(layout) = bar_layout {
+        series = arrange(&series, layout);
+        if ranges.is_none() {
+            (min, max) = extent(series.iter().flatten().map(|(x, _)| *x)).unwrap_or((min, max));
         }
+    }
 
-        Ok(Value::string(final_chart, call.head))
+    let mut chart = new_chart(format, charset, labels, max_x, max_y, min, max);
+    match y_range {
+        Some((ymin, ymax)) if aspect.is_some() => chart.fix_y_range(ymin, ymax),
+        Some((ymin, ymax)) => chart.widen_y_range(ymin, ymax),
+        None => &mut chart,
+    };
+    let mut below = (!panel.is_empty()).then(|| new_chart(format, charset, labels, max_x, panel_y, min, max));
+    for chart in std::iter::once(&mut chart).chain(&mut below) {
+        if bar_layout.is_some() {
+            // the bars rise from zero
+            chart.widen_y_range(0.0, 0.0);
+        }
+        chart.blend(blend).smooth(smooth);
+    }
+    let order = draw_order(call, order.as_deref(), highlight, &names)?;
+    for &i in &order {
+        let xy = std::mem::take(&mut series[i]);
+        let chart = match &mut below {
+            Some(below) if panel.contains(&i) => below,
+            _ => &mut chart,
+        };
+        palette.plot_nth(chart, i, count, chart_shape(nth_shape(shapes.as_deref(), i), steps, step_mode, bars, points, stem, call, xy)?);
+    }
+    if let Some(below) = &mut below {
+        annotate(below, &panel_annotations);
     }
+    let names: Vec<String> = order.iter().filter(|i| !panel.contains(i)).map(|&i| names[i].clone()).collect();
+    if let Some(fit) = &fit {
+        chart.linecolorplot(Shape::Lines(fit.curve.clone()), palette.nth(1));
+    }
+    let names = match fit {
+        Some(_) => shape_names(&names, fit.as_ref()),
+        None => names,
+    };
+    Ok(finish_panels(&mut chart, below.as_mut(), format, &names, &annotations, &titles, &entries, legend_pos, &pad, object, call.head))
 }
 
-impl SimplePluginCommand for CommandXyplot {
+impl PluginCommand for CommandXyplot {
     type Plugin = PluginPlot;
 
     fn name(&self) -> &str {
@@ -783,65 +4192,305 @@ impl SimplePluginCommand for CommandXyplot {
     }
 
     fn signature(&self) -> nu_protocol::Signature {
-        Signature::build("xyplot")
-            .description("Render an ASCII xy plot from a list of values.")
+        common_chart_flags(Signature::build("xyplot").description("Render an ASCII xy plot from a list of values."))
+            .switch("square", "Draw the chart as tall as it is wide, as many dots across as up.", None)
             .named(
-                "width",
+                "aspect",
                 SyntaxShape::Number,
-                "The maximum width of the plot.",
+                "Draw a y unit this many times as long as an x unit, so 1 keeps distances true, shrinking the chart to fit.",
                 None,
             )
             .named(
-                "height",
-                SyntaxShape::Number,
-                "The maximum height of the plot.",
+                "names",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "Names for the series in the legend, one for each.",
+                None,
+            )
+            .named(
+                "markers",
+                SyntaxShape::OneOf(vec![SyntaxShape::List(Box::new(SyntaxShape::String)), SyntaxShape::String]),
+                "Mark each series with its own glyph, so they can be told apart without color: default or ascii, or a list of glyphs such as [o x + *].",
+                None,
+            )
+            .named(
+                "styles",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "Line styles for the series in turn, such as [solid dashed dotted], so lines can be told apart without color.",
+                None,
+            )
+            .named(
+                "line-width",
+                SyntaxShape::OneOf(vec![SyntaxShape::Int, SyntaxShape::List(Box::new(SyntaxShape::Int))]),
+                "Draw lines this many dots across, from 1 to 4, or the series in turn as wide as a list says, such as [2 1] to pick out the first.",
+                None,
+            )
+            .switch("smooth", "Fill the corners of the diagonal steps of lines, so they run on unbroken.", None)
+            .named(
+                "gradient",
+                SyntaxShape::String,
+                "Color a single series by its values along a colormap: green-red, blue-red or viridis.",
                 None,
             )
             .named(
-                "title",
+                "label-format",
                 SyntaxShape::String,
-                "Provide a title to the plot.",
-                Some('t'),
+                "How to write the numbers of the axes and labels: %.3f, %.2e, scientific, si, percent, thousands, bytes, duration or date, all but date with :N decimal places.",
+                None,
             )
-            .switch("legend", "Plot a tiny, maybe useful legend.", Some('l'))
+            .switch("legend-stats", "Show the min, mean, max, last value and count of each series in the legend.", None)
             .switch("bars", "Change lines to bars.", Some('b'))
+            .switch("stacked", "Stack the bars of several series at each x, the first on top.", None)
+            .switch("dodge", "Draw the bars of several series at each x side by side.", None)
+            .switch("fill", "Shade the area between the line of a single series and zero.", None)
+            .named(
+                "band",
+                SyntaxShape::List(Box::new(SyntaxShape::Any)),
+                "Shade between two series, named or by index as [low high], such as the ends of a confidence interval, rather than draw them.",
+                None,
+            )
             .switch("steps", "Change lines to steps.", Some('s'))
+            .named(
+                "step-mode",
+                SyntaxShape::String,
+                "Where a step plot steps: pre (default), at each point to the next value; post, at the next point, holding each value until then as a counter does; or mid, halfway between.",
+                None,
+            )
             .switch("points", "Change lines to points.", Some('p'))
+            .switch("stem", "Change lines to stems, a line from zero up or down to each point with a dot on the end.", None)
+            .named(
+                "shapes",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "A shape for each series in turn, such as [points lines]: lines, points, steps, steps:pre, steps:post, steps:mid, bars or stems.",
+                None,
+            )
+            .named(
+                "jitter",
+                SyntaxShape::Number,
+                "Move each point by up to this much either way in x and y, at random, so points which share a value don't all land on one dot.",
+                None,
+            )
+            .named("seed", SyntaxShape::Int, "Seed the random offsets of --jitter, so a different seed jitters the points another way (default 0).", None)
+            .named("order", SyntaxShape::List(Box::new(SyntaxShape::Any)), "Draw these series, by name or index, over the rest, the last on top.", None)
+            .named("highlight", SyntaxShape::Any, "Draw this series, by name or index, in its color over the rest, which are grey.", None)
+            .named("panel", SyntaxShape::Any, "Draw this series, or list of series, by name or index, in a shorter panel under the chart, sharing its x axis.", None)
+            .named("blend", SyntaxShape::String, "Color where series cross: last (the last drawn wins), mix (the colors mixed) or highlight (bright white).", None)
+            .switch("skip-nulls", "Leave out null values, joining the points either side unless --break-gaps is given.", None)
+            .named(
+                "fill-nulls",
+                SyntaxShape::String,
+                "Replace null values using linear, previous or zero.",
+                None,
+            )
+            .switch("cumsum", "Plot the running sum of each series, turning rates into a total.", None)
+            .switch("diff", "Plot the change from each value to the next, turning a counter into rates.", None)
+            .named(
+                "normalize",
+                SyntaxShape::String,
+                "Rescale each series before plotting: minmax, zscore or index100.",
+                None,
+            )
+            .named(
+                "transform",
+                SyntaxShape::String,
+                "Put each value through log, log1p or sqrt before plotting, labelling the axis with the values themselves.",
+                None,
+            )
+            .named("offset", SyntaxShape::Number, "Add this to each value before --transform, such as 1 to take the log of zeros.", None)
+            .named(
+                "clip-quantile",
+                SyntaxShape::Number,
+                "Cap each series' values beyond this quantile, and the one as far from the other end, such as 0.99.",
+                None,
+            )
+            .switch("winsorize", "Cap each series' values beyond its 1st and 99th percentiles.", None)
+            .named(
+                "trend",
+                SyntaxShape::String,
+                "Overlay a fitted trend: linear, poly:N or loess.",
+                None,
+            )
+            .named(
+                "group-by",
+                SyntaxShape::String,
+                "Split a table into a series for each value of this column, plotting the next two columns as x and y.",
+                Some('g'),
+            )
+            .named(
+                "facet",
+                SyntaxShape::String,
+                "Draw a small chart for each value of this column, all to the same scale, in a grid as wide as the terminal.",
+                None,
+            )
+            .switch("grid", "Draw dim grid lines across and down the chart, behind the series.", None)
+            .named("bg", SyntaxShape::String, "The color behind a text chart, such as black, or bright-white on a dark terminal.", None)
+            .named(
+                "hline",
+                SyntaxShape::Any,
+                "Draw horizontal reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .named(
+                "vline",
+                SyntaxShape::Any,
+                "Draw vertical reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .named(
+                "shade-above",
+                SyntaxShape::Number,
+                "Shade the region above this y value.",
+                None,
+            )
+            .named(
+                "shade-below",
+                SyntaxShape::Number,
+                "Shade the region below this y value.",
+                None,
+            )
+            .named(
+                "shade-x",
+                SyntaxShape::List(Box::new(SyntaxShape::Number)),
+                "Shade the region between two x values, given as [from to].",
+                None,
+            )
+            .named(
+                "annotate",
+                SyntaxShape::String,
+                "Label points of each series with their value: any of min, max and last, separated by commas.",
+                None,
+            )
+            .switch("labels", "Label each point with its value, leaving out labels that would overlap.", None)
+            .switch("break-gaps", "Break the line at null, NaN and infinite values, rather than joining the points either side.", None)
+            .switch("polar", "Take the x values as angles in radians and the y values as radii, drawn to the same scale across and up.", None)
+            .switch("parametric", "Take a nested list as pairs of lists, the x and y values of each curve, drawn in order and to the same scale across and up.", None)
+            .switch("sort-x", "Sort the points of each series by x, so a line through x values given out of order doesn't zig-zag.", None)
+            .named("agg", SyntaxShape::String, "Combine the points of each series which share an x value, or an --every bucket, into their mean, sum or max, sorting them by x.", None)
+            .named(
+                "every",
+                SyntaxShape::OneOf(vec![SyntaxShape::Duration, SyntaxShape::Number]),
+                "Combine the points of each series in buckets of x this wide, such as 5min for dates, into their mean or --agg.",
+                None,
+            )
+            .switch("meta", "Return a record of the chart and facts about the input.", None)
+            .input_output_types(vec![
+                (Type::List(Box::new(Type::List(Box::new(Type::Number)))), Type::Any),
+                (Type::table(), Type::Any),
+                (Type::List(Box::new(Type::Any)), Type::Any),
+                (Type::record(), Type::Any),
+            ])
             .category(Category::Experimental)
     }
 
     fn description(&self) -> &str {
-        "Render an ASCII xy plot from a list of values."
+        "Render an ASCII xy plot of lists of y values against a list of x values, of the columns of a table against its first, or a grid of them for a record of tables."
+    }
+
+    fn search_terms(&self) -> Vec<&str> {
+        vec!["chart", "scatter", "graph", "xy", "visualize"]
+    }
+
+    fn examples(&self) -> Vec<Example<'_>> {
+        vec![
+            Example {
+                example: "[[0 1 2 3 4] [0 1 4 9 16]] | xyplot --width 64 --height 32",
+                description: "Plot a list of y values against a list of x values.",
+                result: Some(Value::test_string(
+                    [
+                        "    16.0 ⡁                             ⢀⠔⠁",
+                        "         ⠄                           ⣀⠔⠁  ",
+                        "         ⠂                         ⡠⠊     ",
+                        "         ⡁                      ⢀⠤⠊       ",
+                        "     8.0 ⠄                   ⣀⠤⠊⠁         ",
+                        "         ⠂                ⣀⠔⠊             ",
+                        "         ⡁           ⣀⡠⠤⠒⠉                ",
+                        "         ⠄   ⣀⣀⣀⣀⠤⠔⠒⠉                     ",
+                        "     0.0 ⠉⠉⠉⠉⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ⠁⠈ ",
+                        "         0.0                           4.0",
+                        "    ",
+                    ]
+                    .join("\n"),
+                )),
+            },
+            Example {
+                example: "[[x y]; [0 3] [1 1] [2 4] [4 2]] | xyplot --width 64 --height 32 --points",
+                description: "Plot the points of a table with x and y columns.",
+                result: Some(Value::test_string(
+                    [
+                        "    4.0 ⡁               ⠁                ",
+                        "        ⠄                                ",
+                        "        ⡂                                ",
+                        "        ⡁                                ",
+                        "    2.5 ⠄                                ",
+                        "        ⠂                               ⠂",
+                        "        ⡁                                ",
+                        "        ⠄                                ",
+                        "    1.0         ⠁                        ",
+                        "        0.0                           4.0",
+                        "    ",
+                    ]
+                    .join("\n"),
+                )),
+            },
+            Example {
+                example: "[[0 12 31 47] [1 2 3 4]] | xyplot --every 5 --width 64 --height 32",
+                description: "Resample irregular points into buckets 5 wide, joining the line across the empty ones.",
+                result: Some(Value::test_string(
+                    [
+                        "    4.0 ⡁                            ⢀⡠⠔⠁",
+                        "        ⠄                        ⢀⡠⠔⠊⠁   ",
+                        "        ⠂                    ⢀⡠⠔⠊⠁       ",
+                        "        ⡁               ⣀⡠⠤⠒⠊⠁           ",
+                        "    2.5 ⠄         ⣀⡠⠤⠒⠊⠉                 ",
+                        "        ⠂     ⡠⠒⠊⠉                       ",
+                        "        ⡁  ⢀⠔⠊                           ",
+                        "        ⠄⡠⠒⠁                             ",
+                        "    1.0 ⠉                                ",
+                        "        0.0                          45.0",
+                        "    ",
+                    ]
+                    .join("\n"),
+                )),
+            },
+            Example {
+                example: "open metrics.csv | select time value host | xyplot -g host -l",
+                description: "Plot a series for each host, named in the legend.",
+                result: None,
+            },
+            Example {
+                example: "open results.csv | select x y yerr | xyplot",
+                description: "Plot each point with an error bar of yerr either side of it.",
+                result: None,
+            },
+        ]
     }
 
     fn run(
         &self,
         _plugin: &Self::Plugin,
-        _engine: &nu_plugin::EngineInterface,
+        engine: &nu_plugin::EngineInterface,
         call: &EvaluatedCall,
-        input: &Value,
-    ) -> Result<Value, LabeledError> {
-        match input.as_list() {
-            Ok(list) => {
-                if list.is_empty() {
-                    return Err(LabeledError::new("Can't plot a zero element list.").with_label( "No elements in the list.", call.head));
-                }
-                let (value_type, list_len_op) = check_equality_of_list(list, call)?;
-
-                // if in fact we have a nested list
-                if let Some(_len) = list_len_op {
-                    // we haven't implemented this yet
-                    self.plot_nested(call, input)
-                } else {
-                    // we have a normal plot, single list of numbers
-                    match value_type {
-                        Type::Float | Type::Int => self.plot(call, input),
-                        e =>  Err(LabeledError::new(format!("List type is {}, but should be float or int.", e)).with_label("Incorrect List type.", call.head)),
-                    }
-                }
-            },
-            Err(e) => Err(LabeledError::new(format!("Input type should be a list: {}.", e)).with_label( "Incorrect input type.", call.head)),
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        let facet: Option<String> = call.get_flag("facet")?;
+        if facet.is_some() || matches!(input, PipelineData::Value(Value::Record { .. }, ..)) {
+            return facet_plot(engine, call, facet.as_deref(), input);
         }
+        let input = match input {
+            PipelineData::ListStream(..) | PipelineData::Value(Value::List { .. }, ..) => {
+                let (values, columns) = xy_values(call, input.into_iter().collect())?;
+                let values = PipelineData::Value(Value::list(values, call.head), None);
+                // the series of a table are named after its columns
+                if let Some(columns) = columns.filter(|_| call.get_flag_span("names").is_none()) {
+                    let mut call = call.clone();
+                    let names = columns.into_iter().map(|n| Value::string(n, call.head)).collect();
+                    call.named.push((Spanned { item: "names".to_string(), span: call.head }, Some(Value::list(names, call.head))));
+                    return self.plot_input(engine, &call, values);
+                }
+                values
+            }
+            input => input,
+        };
+        self.plot_input(engine, call, input)
     }
 }
 
@@ -855,9 +4504,8 @@ impl SimplePluginCommand for CommandPlotConfig {
     fn signature(&self) -> Signature {
         Signature::build("plot-config")
             .description("Show plugin configuration")
-            .extra_description("The configuration is set under $env.config.plugins.plot")
+            .extra_description("The configuration is set under $env.config.plugins.plot, and gives defaults for the palette, markers, charset, legend, width, height and pad flags.")
             .category(Category::Experimental)
-            .search_terms(vec!["plot".into(), "configuration".into()])
             .input_output_type(Type::Nothing, Type::table())
     }
 
@@ -865,6 +4513,10 @@ impl SimplePluginCommand for CommandPlotConfig {
         "Show plugin configuration"
     }
 
+    fn search_terms(&self) -> Vec<&str> {
+        vec!["plot", "configuration"]
+    }
+
     fn run(
         &self,
         _plugin: &Self::Plugin,
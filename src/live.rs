@@ -0,0 +1,202 @@
+//! Live rendering of streamed data.
+//!
+//! `plot --live` keeps the last `--window` points in a ring buffer and
+//! redraws the chart in place as values arrive, moving the cursor back
+//! up over the previous frame with ANSI escape codes. With `--raw` the
+//! frames are plain text, one after another. Frames are emitted as a
+//! string `ByteStream`, which nushell prints as it is produced.
+//!
+//! Nulls are skipped or filled as `--skip-nulls` and `--fill-nulls`
+//! say, within the window, so a null at the newest end takes the last
+//! value until the next one arrives.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use nu_plugin::EvaluatedCall;
+use nu_protocol::{
+    ByteStream, ByteStreamType, LabeledError, PipelineData, PipelineIterator, ShellError, Signals, Value,
+};
+
+use crate::{
+    annotate, chart_shape, check_chart_shape, connect_gaps, fill_nulls, label_formats, new_chart, parse_cli_opts, parse_nulls,
+    strip_escapes, value_to_f64, CliOpts, Format, Nulls,
+};
+
+/// Number of points kept if `--window` is not given.
+const DEFAULT_WINDOW: usize = 500;
+
+/// Minimum time between two redraws.
+const FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A chart over a sliding window of the most recent points.
+struct LiveChart {
+    /// The points in the window, oldest first, with nulls as `None`
+    /// and non-finite values as `NaN`.
+    buffer: VecDeque<(f64, Option<f64>)>,
+    /// Maximum number of points in the window.
+    window: usize,
+    /// Number of points seen so far, used as the x value.
+    seen: usize,
+    /// Number of lines in the last frame, so it can be drawn over.
+    last_lines: usize,
+    /// What to do with null values.
+    nulls: Nulls,
+    /// Whether frames are drawn in color.
+    color: bool,
+    /// Whether each frame is drawn over the last, rather than after it
+    /// as plain text for `--raw`.
+    redraw: bool,
+}
+
+impl LiveChart {
+    fn new(window: usize, nulls: Nulls, color: bool, redraw: bool) -> Self {
+        Self {
+            buffer: VecDeque::new(),
+            window,
+            seen: 0,
+            last_lines: 0,
+            nulls,
+            color,
+            redraw,
+        }
+    }
+
+    /// Add a point, dropping the oldest if the window is full.
+    fn push(&mut self, y: Option<f64>) {
+        if self.buffer.len() == self.window {
+            self.buffer.pop_front();
+        }
+        self.buffer.push_back((self.seen as f64, y));
+        self.seen += 1;
+    }
+
+    /// Render the window, prefixed with the escape codes needed to
+    /// overwrite the previous frame.
+    fn frame(
+        &mut self,
+        call: &EvaluatedCall,
+        opts: &CliOpts,
+    ) -> Result<String, LabeledError> {
+        let max_x = opts.width_op.unwrap_or(200);
+        let max_y = opts.height_op.unwrap_or(50);
+
+        let mut ys: Vec<f64> = self.buffer.iter().map(|(_, y)| y.unwrap_or(f64::NAN)).collect();
+        let null: Vec<bool> = self.buffer.iter().map(|(_, y)| y.is_none()).collect();
+        fill_nulls(&mut ys, &null, self.nulls);
+        let mut data: Vec<(f64, f64)> = self.buffer.iter().zip(ys).map(|((x, _), y)| (*x, y)).collect();
+        connect_gaps(&mut data, opts.connect_gaps);
+        let data = opts.decimate.apply(data, max_x as usize);
+        let xmin = data.first().map(|e| e.0).unwrap_or(0.0);
+        // a single point still needs a non-empty x range
+        let xmax = data.last().map(|e| e.0).unwrap_or(0.0).max(xmin + 1.0);
+
+        let mut chart = new_chart(Format::Text, opts.charset, label_formats(opts.label_format, Default::default()), max_x, max_y, xmin, xmax);
+        let mut annotations = opts.annotations.clone();
+        annotations.extend(opts.palette.marks(0, 1, &data, (xmin, xmax), max_x));
+        let shape = chart_shape(None, opts.steps, opts.step_mode, opts.bars, opts.points, opts.stem, call, data)?;
+        let mut chart = annotate(opts.palette.plot(&mut chart, shape), &annotations).to_string();
+        chart = match self.color {
+            true => opts.depth.downgrade(&chart),
+            false => strip_escapes(&chart),
+        };
+
+        chart = opts.pad.clone() + &chart.trim_end().replace('\n', &format!("\n{}", opts.pad)) + "\n";
+        chart = opts.titles.around(&chart, &opts.pad);
+
+        let mut frame = String::new();
+        if self.redraw && self.last_lines > 0 {
+            // cursor up over the last frame, then clear to the end of the screen
+            frame += &format!("\x1b[{}A\r\x1b[J", self.last_lines);
+        }
+        self.last_lines = chart.matches('\n').count();
+        frame += &chart;
+
+        Ok(frame)
+    }
+}
+
+/// Pulls values off the input stream and yields a new frame at most
+/// every `FRAME_INTERVAL`, plus a final frame when the stream ends.
+struct LiveFrames {
+    values: PipelineIterator,
+    chart: LiveChart,
+    call: EvaluatedCall,
+    opts: CliOpts,
+    last_draw: Option<Instant>,
+    done: bool,
+}
+
+impl Iterator for LiveFrames {
+    type Item = Result<String, ShellError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        for val in self.values.by_ref() {
+            let y = match val {
+                Value::Nothing { .. } if self.chart.nulls != Nulls::Error => Ok(None),
+                val => value_to_f64(&val, &self.call).map(|y| Some(if y.is_finite() { y } else { f64::NAN })),
+            };
+            if let Err(e) = y.map(|y| self.chart.push(y)) {
+                self.done = true;
+                return Some(Err(e.into()));
+            }
+            let due = self
+                .last_draw
+                .is_none_or(|t| t.elapsed() >= FRAME_INTERVAL);
+            if due {
+                self.last_draw = Some(Instant::now());
+                return Some(self.chart.frame(&self.call, &self.opts).map_err(Into::into));
+            }
+        }
+        self.done = true;
+        if self.chart.seen == 0 {
+            return Some(Err(LabeledError::new("Can't plot a zero element list.")
+                .with_label("No elements in the list.", self.call.head)
+                .into()));
+        }
+        Some(self.chart.frame(&self.call, &self.opts).map_err(Into::into))
+    }
+}
+
+/// Turn the input into a stream of redrawn frames, in color if `color`
+/// is set.
+pub fn live_plot(
+    call: &EvaluatedCall,
+    input: PipelineData,
+    signals: Signals,
+    color: bool,
+) -> Result<PipelineData, LabeledError> {
+    let opts = parse_cli_opts(call)?;
+    check_chart_shape(opts.steps, opts.bars, opts.points, opts.stem, call)?;
+    if opts.legend {
+        let span = ["legend", "legend-stats", "legend-pos"].iter().find_map(|f| call.get_flag_span(f)).unwrap_or(call.head);
+        return Err(LabeledError::new("A live plot has no legend, as it draws a single series.")
+            .with_label("Legend error.", span));
+    }
+
+    let window = match call.get_flag::<i64>("window")? {
+        None => DEFAULT_WINDOW,
+        Some(w) if w >= 2 => w as usize,
+        Some(_) => {
+            return Err(LabeledError::new("The live window must hold at least 2 points.")
+                .with_label("Window too small.", call.get_flag_span("window").unwrap_or(call.head)))
+        }
+    };
+
+    let frames = LiveFrames {
+        values: input.into_iter(),
+        chart: LiveChart::new(window, parse_nulls(call)?, color, !call.has_flag("raw")?),
+        call: call.clone(),
+        opts,
+        last_draw: None,
+        done: false,
+    };
+
+    Ok(PipelineData::ByteStream(
+        ByteStream::from_result_iter(frames, call.head, signals, ByteStreamType::String),
+        None,
+    ))
+}
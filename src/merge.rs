@@ -0,0 +1,123 @@
+//! The `plot merge` command.
+//!
+//! Draws the series of several charts, returned as chart values with
+//! `--object`, together on one xy chart, ranged to all of them. Each
+//! series keeps its name, told apart by the title of its chart if
+//! another has it, and takes its turn of the palette.
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::{Category, LabeledError, PipelineData, Signature, SyntaxShape, Type, Value};
+
+use crate::chart_value::{distinct_name, ChartValue};
+use crate::{common_chart_flags, output_chart, parse_cli_opts, series_names, with_config, xy_chart, Meta, PluginPlot, Unit};
+
+pub struct CommandMerge;
+
+/// The charts of `input`, a chart value or a list of them.
+fn charts(call: &EvaluatedCall, input: PipelineData) -> Result<Vec<ChartValue>, LabeledError> {
+    let not_charts = |span| {
+        LabeledError::new("plot merge needs a list of charts returned with --object.").with_label("Incorrect input type.", span)
+    };
+    match input {
+        PipelineData::Value(Value::Custom { val, internal_span }, ..) => {
+            Ok(vec![val.as_any().downcast_ref::<ChartValue>().cloned().ok_or_else(|| not_charts(internal_span))?])
+        }
+        PipelineData::ListStream(..) | PipelineData::Value(Value::List { .. }, ..) => input
+            .into_iter()
+            .map(|chart| match &chart {
+                Value::Custom { val, .. } => val.as_any().downcast_ref::<ChartValue>().cloned().ok_or_else(|| not_charts(chart.span())),
+                _ => Err(not_charts(chart.span())),
+            })
+            .collect(),
+        _ => Err(not_charts(call.head)),
+    }
+}
+
+impl PluginCommand for CommandMerge {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "plot merge"
+    }
+
+    fn signature(&self) -> Signature {
+        common_chart_flags(Signature::build("plot merge").description("Render the series of a list of charts together on one chart."))
+            .switch("square", "Draw the chart as tall as it is wide, as many dots across as up.", None)
+            .named(
+                "names",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "Names for the series in the legend, one for each, rather than the names they had.",
+                None,
+            )
+            .named(
+                "markers",
+                SyntaxShape::OneOf(vec![SyntaxShape::List(Box::new(SyntaxShape::String)), SyntaxShape::String]),
+                "Mark each series with its own glyph, so they can be told apart without color: default or ascii, or a list of glyphs such as [o x + *].",
+                None,
+            )
+            .named(
+                "styles",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "Line styles for the series in turn, such as [solid dashed dotted], so lines can be told apart without color.",
+                None,
+            )
+            .named(
+                "label-format",
+                SyntaxShape::String,
+                "How to write the numbers of the axes and labels: %.3f, %.2e, scientific, si, percent, thousands, bytes, duration or date, all but date with :N decimal places.",
+                None,
+            )
+            .switch("steps", "Change lines to steps.", Some('s'))
+            .named(
+                "step-mode",
+                SyntaxShape::String,
+                "Where a step plot steps: pre (default), at each point to the next value; post, at the next point, holding each value until then as a counter does; or mid, halfway between.",
+                None,
+            )
+            .switch("points", "Change lines to points.", Some('p'))
+            .input_output_types(vec![
+                (Type::List(Box::new(Type::Custom("chart".into()))), Type::Any),
+                (Type::Custom("chart".into()), Type::Any),
+            ])
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render the series of a list of charts, returned with --object, together on one chart, ranged to all of them."
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        let call = &with_config(engine, call)?;
+        let opts = parse_cli_opts(call)?;
+        let charts = charts(call, input)?;
+
+        let mut names: Vec<String> = vec![];
+        let mut series = vec![];
+        for (i, chart) in charts.into_iter().enumerate() {
+            for s in chart.series {
+                names.push(distinct_name(s.name, &names, i, chart.title.as_deref()));
+                series.push(s.points);
+            }
+        }
+        if series.is_empty() {
+            return Err(LabeledError::new("Can't merge a zero element list.").with_label("No charts in the list.", call.head));
+        }
+        let names = match opts.names.as_deref() {
+            Some(_) => series_names(call, opts.names.as_deref(), series.len())?,
+            None => names,
+        };
+        let meta = Meta {
+            series: series.len(),
+            points: series.iter().map(Vec::len).sum(),
+            ..Meta::default()
+        };
+        let chart = xy_chart(call, opts, series, names, None, (Unit::Number, Unit::Number))?;
+        output_chart(engine, call, chart, meta)
+    }
+}
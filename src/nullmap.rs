@@ -0,0 +1,254 @@
+//! The `nullmap` command.
+//!
+//! Draws a table as a grid, a column of cells for each of its columns,
+//! named along the top, and a row for each of its rows, numbered down
+//! the left, with each null cell colored and each present one left
+//! empty, so the gaps in the data show before it is analysed. A table
+//! too long for a row of dots each has several rows to a dot, shaded
+//! as densely as they are null.
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::{Category, Example, LabeledError, PipelineData, Signature, SyntaxShape, Type, Value};
+
+use crate::color_plot::textplots::label::LabelFormat;
+use crate::color_plot::textplots::{Annotation, Heatmap, Shape};
+use crate::{finish_chart, new_chart, output_chart, parse_cli_opts, truncate, with_config, Meta, PluginPlot, MAX_NAME};
+
+pub struct CommandNullmap;
+
+/// Whether each cell of a table is null, a list for each column, with
+/// the names of the columns in the order they first appear. A cell a
+/// row is missing is null.
+fn null_columns(call: &EvaluatedCall, input: PipelineData) -> Result<(Vec<Vec<bool>>, Vec<String>), LabeledError> {
+    let not_table = |span| LabeledError::new("nullmap needs a table.").with_label("Incorrect input type.", span);
+    let rows: Vec<Value> = match input {
+        PipelineData::ListStream(..) | PipelineData::Value(Value::List { .. }, ..) => input.into_iter().collect(),
+        _ => return Err(not_table(call.head)),
+    };
+    if rows.is_empty() {
+        return Err(LabeledError::new("Can't map the nulls of a zero row table.").with_label("No rows in the table.", call.head));
+    }
+    let records = rows.iter().map(|row| row.as_record().map_err(|_| not_table(row.span()))).collect::<Result<Vec<_>, _>>()?;
+
+    let mut names: Vec<String> = vec![];
+    for record in &records {
+        for name in record.columns() {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+    }
+    if names.is_empty() {
+        return Err(LabeledError::new("Can't map the nulls of a table without columns.").with_label("No columns.", call.head));
+    }
+    let columns = names
+        .iter()
+        .map(|name| records.iter().map(|record| matches!(record.get(name), None | Some(Value::Nothing { .. }))).collect())
+        .collect();
+    Ok((columns, names))
+}
+
+impl PluginCommand for CommandNullmap {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "nullmap"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("nullmap")
+            .description("Render an ASCII map of the null cells of a table, its rows down and its columns across.")
+            .named(
+                "width",
+                SyntaxShape::OneOf(vec![SyntaxShape::Number, SyntaxShape::String]),
+                "The maximum width of the plot, in dots, or as a percentage of the terminal such as 80%.",
+                None,
+            )
+            .named(
+                "height",
+                SyntaxShape::OneOf(vec![SyntaxShape::Number, SyntaxShape::String]),
+                "The maximum height of the plot, in dots, or as a percentage of the terminal such as 80%.",
+                None,
+            )
+            .named(
+                "max-lines",
+                SyntaxShape::Int,
+                "The most lines of output, with the title, labels and legend, shrinking the chart to fit.",
+                None,
+            )
+            .named(
+                "title",
+                SyntaxShape::String,
+                "Provide a title to the plot.",
+                Some('t'),
+            )
+            .named("subtitle", SyntaxShape::String, "A line under the title.", None)
+            .named("caption", SyntaxShape::String, "A line under the chart, such as where its data is from.", None)
+            .named("title-align", SyntaxShape::String, "Line the title, subtitle and caption up across the chart: left (default), center or right.", None)
+            .named("pad", SyntaxShape::Int, "Indent each line of a text chart by this many spaces (default 4), or not at all with 0.", None)
+            .switch("legend", "Plot a tiny, maybe useful legend.", Some('l'))
+            .named(
+                "legend-pos",
+                SyntaxShape::String,
+                "Where to put the legend: top, bottom (default), right or inside.",
+                None,
+            )
+            .named(
+                "format",
+                SyntaxShape::String,
+                "Draw the chart as text (default), svg, png, or vega for a Vega-Lite spec.",
+                None,
+            )
+            .named(
+                "charset",
+                SyntaxShape::String,
+                "The characters to draw a text chart with: braille (default), ascii or blocks.",
+                None,
+            )
+            .named(
+                "palette",
+                SyntaxShape::OneOf(vec![SyntaxShape::List(Box::new(SyntaxShape::String)), SyntaxShape::String]),
+                "The color of the null cells, the first of a palette: default, okabe-ito, viridis, mono or high-contrast, or a list such as [red].",
+                None,
+            )
+            .named(
+                "gradient",
+                SyntaxShape::String,
+                "Color each cell by the share of its rows which are null along a colormap: green-red, blue-red or viridis.",
+                None,
+            )
+            .named(
+                "color",
+                SyntaxShape::String,
+                "Color a text chart always, never, or auto (default) when NO_COLOR and CLICOLOR=0 are unset and the output is a terminal.",
+                None,
+            )
+            .switch("raw", "Return plain text without color or any other escape codes, whatever --color says.", None)
+            .switch("print", "Print the chart as soon as it is drawn rather than returning it, so each chart of a loop or script is shown.", None)
+            .switch("object", "Return the chart as a chart value, shown as the chart, with its data, ranges and facts as cells such as ymax.", None)
+            .named(
+                "output",
+                SyntaxShape::Filepath,
+                "Save the chart to this file rather than returning it, as SVG, PNG or a Vega-Lite spec if it ends in .svg, .png or .json.",
+                Some('o'),
+            )
+            .switch("meta", "Return a record of the chart and facts about the input.", None)
+            .input_output_type(Type::table(), Type::Any)
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render an ASCII map of the missing data of a table: a column of cells for each of its columns, named along the top, and a row for each of its rows, numbered down the left, each null or missing cell colored and each present one left empty. A table too long for a row of dots each has several rows to a dot, shaded as densely as they are null."
+    }
+
+    fn search_terms(&self) -> Vec<&str> {
+        vec!["null", "missing", "nan", "data quality", "heatmap"]
+    }
+
+    fn examples(&self) -> Vec<Example<'_>> {
+        vec![
+            Example {
+                example: "open survey.csv | nullmap -l",
+                description: "See which answers of a survey are missing, and how many.",
+                result: None,
+            },
+            Example {
+                example: "open sensors.csv | nullmap --gradient viridis --height 100%",
+                description: "Map the gaps of a long table, coloring each line of dots by the share of its rows which are null.",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        let call = &with_config(engine, call)?;
+        let opts = parse_cli_opts(call)?;
+        let (columns, names) = null_columns(call, input)?;
+        let (n, k) = (columns[0].len(), names.len());
+
+        // laid out in characters: the row numbers down the left, and the
+        // names along the top, then a cell of some columns of characters
+        // for each column of the table
+        let gutter = (n - 1).to_string().len() + 1;
+        let widest = names.iter().map(|n| n.chars().count()).max().unwrap_or(0).min(MAX_NAME) + 1;
+        let max_x = opts.width_op.unwrap_or(200) as usize;
+        let max_y = opts.height_op.unwrap_or(50) as usize;
+        let cell_cols = (max_x / 2).saturating_sub(gutter).checked_div(k).unwrap_or(0).clamp(1, widest.max(2));
+
+        // and down, in dots: up to a line of characters a row, or as
+        // many rows as need be to a dot
+        let room = 4 * (max_y / 4).saturating_sub(1).max(1);
+        let (dots, per_dot) = match n <= room {
+            true => ((room / n).min(4), 1),
+            false => (1, n.div_ceil(room)),
+        };
+        let bands = n.div_ceil(per_dot);
+        let lines = (bands * dots).div_ceil(4);
+        let width = (2 * (gutter + k * cell_cols)).max(32);
+        let height = (4 * (1 + lines)).max(32);
+
+        // a unit is a point, from the left and, with `y`, down from the
+        // top; they count from 1, so 0 is off the chart and no axis is
+        // drawn across it
+        let (x, y) = (|col: usize| (2 * col + 1) as f64, |dot: usize| (height - dot + 1) as f64);
+        let x_edges: Vec<f64> = (0..=k).map(|j| x(gutter + j * cell_cols)).collect();
+        let mut y_edges = vec![];
+        if 4 + bands * dots < height {
+            y_edges.push(y(height));
+        }
+        y_edges.extend((0..=bands).rev().map(|b| y(4 + b * dots)));
+        y_edges.push(y(0));
+
+        // the share of each band's rows which are null, from the bottom,
+        // with empty rows below and above
+        let mut values = vec![f64::NAN; k * (y_edges.len() - 2 - bands)];
+        for b in (0..bands).rev() {
+            let rows = b * per_dot..((b + 1) * per_dot).min(n);
+            let count = rows.len() as f64;
+            values.extend(columns.iter().map(|column| column[rows.clone()].iter().filter(|null| **null).count() as f64 / count));
+        }
+        values.extend(vec![f64::NAN; k]);
+
+        let mut annotations = vec![];
+        for (j, name) in names.iter().enumerate() {
+            annotations.push(Annotation::Text(x(gutter + j * cell_cols), y(0), truncate(name, cell_cols.saturating_sub(1).max(1))));
+        }
+        // a number every line, or every fifth of many
+        let step = if lines > 10 { 5 } else { 1 };
+        for line in (0..lines).step_by(step) {
+            let band = 4 * line / dots;
+            if band < bands {
+                annotations.push(Annotation::Text(x(0), y(4 + 4 * line), (band * per_dot).to_string()));
+            }
+        }
+
+        let nulls: usize = columns.iter().map(|column| column.iter().filter(|null| **null).count()).sum();
+        let mut entries = vec![];
+        if opts.legend {
+            entries.push(opts.palette.entry("Null", 0, 1, None, LabelFormat::Hidden));
+            let mut facts = format!("{} of {} cells, {:.1}%", nulls, n * k, 100.0 * nulls as f64 / (n * k) as f64);
+            if per_dot > 1 {
+                facts.push_str(&format!(", {} rows a dot", per_dot));
+            }
+            entries.push(vec![(facts, None)]);
+        }
+
+        let mut chart = new_chart(opts.format, opts.charset, (LabelFormat::Hidden, LabelFormat::Hidden), width as u32, height as u32, x(0), x(width / 2));
+        opts.palette.plot(&mut chart, Shape::Heatmap(Heatmap { x: x_edges, y: y_edges, values, range: (0.0, 1.0), midpoint: None }));
+
+        let meta = Meta {
+            series: k,
+            points: n * k,
+            nulls,
+            ..Meta::default()
+        };
+        let chart = finish_chart(&mut chart, opts.format, &["Null".to_string()], &annotations, &opts.titles, &entries, opts.legend_pos, &opts.pad, opts.object, call.head);
+        output_chart(engine, call, chart, meta)
+    }
+}
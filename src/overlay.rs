@@ -0,0 +1,127 @@
+//! The `plot overlay` command.
+//!
+//! Composites charts, returned as chart values with `--object`, and
+//! datasets onto one chart with a shared range, each series in the
+//! shape it was drawn in, so that a histogram and the density fitted to
+//! it, drawn in separate stages of a pipeline, can be read together.
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::{record, Category, LabeledError, PipelineData, Signature, SyntaxShape, Type, Value};
+
+use crate::chart_value::{distinct_name, ChartValue};
+use crate::spec::{draw, range, series, Series};
+use crate::{common_chart_flags, parse_cli_opts, with_config, PluginPlot};
+
+pub struct CommandOverlay;
+
+/// The series of `input`, a list of chart values and datasets: lists of
+/// y values, or records of a series as `plot spec` takes them. A series
+/// is told apart from any before it of the same name by its chart.
+fn layers(call: &EvaluatedCall, input: PipelineData) -> Result<Vec<Series>, LabeledError> {
+    let not_layers = |span| {
+        LabeledError::new("plot overlay needs a list of charts returned with --object, lists of numbers or records of a series.")
+            .with_label("Incorrect input type.", span)
+    };
+    let items: Vec<Value> = match input {
+        PipelineData::ListStream(..) | PipelineData::Value(Value::List { .. }, ..) => input.into_iter().collect(),
+        _ => return Err(not_layers(call.head)),
+    };
+    let mut layers: Vec<Series> = vec![];
+    for (i, item) in items.into_iter().enumerate() {
+        let (title, new) = match &item {
+            Value::Custom { val, .. } => {
+                let chart = val.as_any().downcast_ref::<ChartValue>().ok_or_else(|| not_layers(item.span()))?;
+                let new = chart
+                    .series
+                    .iter()
+                    .map(|s| Series { name: s.name.clone(), points: s.points.clone(), shape: Some(s.shape.clone()), color: None })
+                    .collect();
+                (chart.title.clone(), new)
+            }
+            Value::Record { .. } => (None, vec![series(call, layers.len(), &item)?]),
+            Value::List { .. } => (None, vec![series(call, layers.len(), &Value::record(record! { "y" => item.clone() }, item.span()))?]),
+            _ => return Err(not_layers(item.span())),
+        };
+        for mut s in new {
+            let names: Vec<String> = layers.iter().map(|s| s.name.clone()).collect();
+            s.name = distinct_name(s.name, &names, i, title.as_deref());
+            layers.push(s);
+        }
+    }
+    Ok(layers)
+}
+
+impl PluginCommand for CommandOverlay {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "plot overlay"
+    }
+
+    fn signature(&self) -> Signature {
+        common_chart_flags(Signature::build("plot overlay").description("Render charts and datasets together on one chart, each series in its own shape."))
+            .named(
+                "x-range",
+                SyntaxShape::List(Box::new(SyntaxShape::Number)),
+                "Fix the range of x, as [from to], rather than fitting it to all the series.",
+                None,
+            )
+            .named(
+                "y-range",
+                SyntaxShape::List(Box::new(SyntaxShape::Number)),
+                "Fix the range of y, as [from to], rather than fitting it to all the series.",
+                None,
+            )
+            .switch("square", "Draw the chart as tall as it is wide, as many dots across as up.", None)
+            .named(
+                "markers",
+                SyntaxShape::OneOf(vec![SyntaxShape::List(Box::new(SyntaxShape::String)), SyntaxShape::String]),
+                "Mark each series with its own glyph, so they can be told apart without color: default or ascii, or a list of glyphs such as [o x + *].",
+                None,
+            )
+            .named(
+                "styles",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "Line styles for the series in turn, such as [solid dashed dotted], so lines can be told apart without color.",
+                None,
+            )
+            .named(
+                "label-format",
+                SyntaxShape::String,
+                "How to write the numbers of the axes and labels: %.3f, %.2e, scientific, si, percent, thousands, bytes, duration or date, all but date with :N decimal places.",
+                None,
+            )
+            .switch("steps", "Change lines of datasets without a shape to steps.", Some('s'))
+            .named(
+                "step-mode",
+                SyntaxShape::String,
+                "Where a step plot steps: pre (default), at each point to the next value; post, at the next point, holding each value until then as a counter does; or mid, halfway between.",
+                None,
+            )
+            .switch("points", "Change lines of datasets without a shape to points.", Some('p'))
+            .input_output_type(Type::List(Box::new(Type::Any)), Type::Any)
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render charts returned with --object, and lists of numbers or records of a series as plot spec takes them, together on one chart with a shared range, each series in the shape it was drawn in."
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        let call = &with_config(engine, call)?;
+        let opts = parse_cli_opts(call)?;
+        let layers = layers(call, input)?;
+        if layers.is_empty() {
+            return Err(LabeledError::new("Can't overlay a zero element list.").with_label("No charts in the list.", call.head));
+        }
+        let x_range = call.get_flag_value("x-range").map(|value| range(call, &value)).transpose()?;
+        let y_range = call.get_flag_value("y-range").map(|value| range(call, &value)).transpose()?;
+        draw(engine, call, opts, layers, x_range, y_range)
+    }
+}
@@ -0,0 +1,157 @@
+//! The `qqplot` command.
+//!
+//! Draws a quantile-quantile plot: the quantiles of a numeric list
+//! against those of a normal distribution with its mean and standard
+//! deviation, or of the first list of a nested list against the second,
+//! as points, with the line x = y where they would lie if the two
+//! distributions were the same.
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::{Category, LabeledError, PipelineData, Signature, SyntaxShape, Value};
+
+use crate::color_plot::drawille::PixelColor;
+use crate::color_plot::textplots::{ColorPlot, Shape};
+use crate::stats::{qq_normal, qq_samples};
+use crate::{common_chart_flags, extent, finish_chart, label_formats, legend_entry, new_chart, numeric_input_types, parse_cli_opts, series_names, Meta, Plotter, PluginPlot, Unit};
+
+/// The color of the line x = y.
+const REFERENCE: PixelColor = PixelColor::BrightBlack;
+
+pub struct CommandQqplot;
+
+impl CommandQqplot {
+    /// The chart of `points`, quantiles in `units`, with the line x = y
+    /// across them.
+    fn qq_chart(&self, call: &EvaluatedCall, points: Vec<(f64, f64)>, units: (Unit, Unit)) -> Result<Value, LabeledError> {
+        let opts = parse_cli_opts(call)?;
+        let Some((min, max)) = extent(points.iter().map(|(x, _)| *x)) else {
+            return Err(LabeledError::new("Can't compare the quantiles of a list with no finite values.").with_label("No values.", call.head));
+        };
+
+        let max_x = opts.width_op.unwrap_or(200);
+        let max_y = opts.height_op.unwrap_or(50);
+        let labels = label_formats(opts.label_format, units);
+        // a single distinct quantile still needs a non-empty x range
+        let (min, max) = if max > min { (min, max) } else { (min - 0.5, max + 0.5) };
+
+        let mut annotations = opts.annotations.clone();
+        annotations.extend(opts.palette.marks(0, 1, &points, (min, max), max_x));
+        let names = match &opts.names {
+            Some(_) => series_names(call, opts.names.as_deref(), 1)?,
+            None => vec!["Quantiles".to_string()],
+        };
+        let mut entries = vec![];
+        if opts.legend {
+            entries.push(opts.palette.entry(&names[0], 0, 1, None, labels.1));
+            entries.push(legend_entry("x = y", REFERENCE, None, None, labels.1));
+        }
+
+        let mut chart = new_chart(opts.format, opts.charset, labels, max_x, max_y, min, max);
+        opts.palette.plot(&mut chart, Shape::Points(points));
+        chart.linecolorplot(Shape::Lines(vec![(min, min), (max, max)]), REFERENCE);
+        let names = vec![names[0].clone(), "x = y".to_string()];
+        Ok(finish_chart(&mut chart, opts.format, &names, &annotations, &opts.titles, &entries, opts.legend_pos, &opts.pad, opts.object, call.head))
+    }
+}
+
+impl Plotter for CommandQqplot {
+    fn plot(
+        &self,
+        call: &EvaluatedCall,
+        input: Vec<f64>,
+        meta: &mut Meta,
+    ) -> Result<Value, LabeledError> {
+        // the normal quantiles are in the units of the list
+        let unit = meta.units_from(0);
+        self.qq_chart(call, qq_normal(&input), (unit, unit))
+    }
+
+    fn plot_nested(
+        &self,
+        call: &EvaluatedCall,
+        input: Vec<Vec<f64>>,
+        meta: &mut Meta,
+    ) -> Result<Value, LabeledError> {
+        let [x, y] = &input[..] else {
+            return Err(LabeledError::new("qqplot compares a list with a normal distribution, or a nested list of two lists with each other.").with_label("Nested list error.", call.head));
+        };
+        self.qq_chart(call, qq_samples(x, y), (meta.units_from(0), meta.units_from(1)))
+    }
+
+    fn ragged(&self) -> bool {
+        true
+    }
+}
+
+impl PluginCommand for CommandQqplot {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "qqplot"
+    }
+
+    fn signature(&self) -> Signature {
+        common_chart_flags(Signature::build("qqplot").description("Render an ASCII quantile-quantile plot of a list against a normal distribution, or of two lists."))
+            .switch("square", "Draw the chart as tall as it is wide, as many dots across as up.", None)
+            .named(
+                "names",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "A name for the points in the legend, rather than Quantiles.",
+                None,
+            )
+            .named(
+                "markers",
+                SyntaxShape::OneOf(vec![SyntaxShape::List(Box::new(SyntaxShape::String)), SyntaxShape::String]),
+                "Mark each series with its own glyph, so they can be told apart without color: default or ascii, or a list of glyphs such as [o x + *].",
+                None,
+            )
+            .named(
+                "label-format",
+                SyntaxShape::String,
+                "How to write the numbers of the axes and labels: %.3f, %.2e, scientific, si, percent, thousands, bytes, duration or date, all but date with :N decimal places.",
+                None,
+            )
+            .switch("skip-nulls", "Leave out null values.", None)
+            .named(
+                "fill-nulls",
+                SyntaxShape::String,
+                "Replace null values using linear, previous or zero.",
+                None,
+            )
+            .switch("grid", "Draw dim grid lines across and down the chart, behind the series.", None)
+            .named("bg", SyntaxShape::String, "The color behind a text chart, such as black, or bright-white on a dark terminal.", None)
+            .named(
+                "hline",
+                SyntaxShape::Any,
+                "Draw horizontal reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .named(
+                "vline",
+                SyntaxShape::Any,
+                "Draw vertical reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .switch("meta", "Return a record of the chart and facts about the input.", None)
+            .input_output_types(numeric_input_types())
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render an ASCII quantile-quantile plot: the sorted values of a list against the quantiles of a normal distribution with its mean and standard deviation, or the quantiles of the first of two lists against the second, with the line x = y they follow if the distributions match."
+    }
+
+    fn search_terms(&self) -> Vec<&str> {
+        vec!["quantile", "normal", "distribution", "qq"]
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        self.plot_input(engine, call, input)
+    }
+}
@@ -0,0 +1,245 @@
+//! The `radar` command.
+//!
+//! Draws values of a few categories as a radar, or spider, chart: a
+//! spoke out from the middle for each category, evenly round from the
+//! top, and a polygon for each series through its value on each spoke,
+//! over a dashed grid of rings. A record is a single series, and each
+//! row of a table another.
+
+use std::f64::consts::{FRAC_PI_2, TAU};
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::{Category, Example, LabeledError, PipelineData, Record, Signature, SyntaxShape, Type, Value};
+
+use crate::color_plot::textplots::label::LabelFormat;
+use crate::color_plot::textplots::{Annotation, Shape};
+use crate::{common_chart_flags, finish_chart, new_chart, output_chart, parse_cli_opts, series_names, truncate, value_to_f64, with_config, Meta, PluginPlot, MAX_NAME};
+
+/// The rings of the grid, as shares of the way out to the end of each
+/// spoke; those labelled with their values are every other one.
+const RINGS: [f64; 4] = [0.25, 0.5, 0.75, 1.0];
+
+pub struct CommandRadar;
+
+/// The values of each series, in the order of `categories`, with nulls
+/// and missing cells as NaN, and the name of each, if it has one.
+struct Series {
+    categories: Vec<String>,
+    values: Vec<Vec<f64>>,
+    names: Vec<Option<String>>,
+}
+
+/// A row of the input: its numeric cells, by name, and its name, if it
+/// has one.
+type Row = (Vec<(String, f64)>, Option<String>);
+
+/// The numeric cells of `record`, by name, with a null as NaN, and the
+/// first of its text cells, if it has one.
+fn numeric_cells(call: &EvaluatedCall, record: &Record) -> Result<Row, LabeledError> {
+    let (mut cells, mut name) = (vec![], None);
+    for (column, value) in record.iter() {
+        match value {
+            Value::String { val, .. } => {
+                name.get_or_insert_with(|| val.clone());
+            }
+            Value::Nothing { .. } => cells.push((column.clone(), f64::NAN)),
+            value => cells.push((column.clone(), value_to_f64(value, call)?)),
+        }
+    }
+    Ok((cells, name))
+}
+
+/// The series of a record, or of each row of a table, whose columns are
+/// the categories, in the order they first appear. The text cell of a
+/// row, if each has one, names its series.
+fn radar_series(call: &EvaluatedCall, input: PipelineData) -> Result<Series, LabeledError> {
+    let not_record = |span| LabeledError::new("radar needs a record of values, or a table with a row of them for each series.").with_label("Incorrect input type.", span);
+    let rows: Vec<Value> = match input {
+        PipelineData::Value(record @ Value::Record { .. }, ..) => vec![record],
+        PipelineData::ListStream(..) | PipelineData::Value(Value::List { .. }, ..) => input.into_iter().collect(),
+        _ => return Err(not_record(call.head)),
+    };
+    let rows = rows
+        .iter()
+        .map(|row| numeric_cells(call, row.as_record().map_err(|_| not_record(row.span()))?))
+        .collect::<Result<Vec<Row>, LabeledError>>()?;
+
+    let mut categories: Vec<String> = vec![];
+    for (cells, _) in &rows {
+        for (column, _) in cells {
+            if !categories.contains(column) {
+                categories.push(column.clone());
+            }
+        }
+    }
+    let values = rows
+        .iter()
+        .map(|(cells, _)| categories.iter().map(|c| cells.iter().find(|(column, _)| column == c).map_or(f64::NAN, |(_, v)| *v)).collect())
+        .collect();
+    let names = rows.into_iter().map(|(_, name)| name).collect();
+    Ok(Series { categories, values, names })
+}
+
+impl PluginCommand for CommandRadar {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "radar"
+    }
+
+    fn signature(&self) -> Signature {
+        common_chart_flags(Signature::build("radar").description("Render an ASCII radar chart, a polygon for each series on a spoke for each category."))
+            .switch("normalize", "Scale each spoke to its own largest value, for categories of different units or sizes.", None)
+            .named(
+                "names",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "Names for the series in the legend, one for each, rather than the text cell of each row.",
+                None,
+            )
+            .named(
+                "label-format",
+                SyntaxShape::String,
+                "How to write the numbers of the rings and labels: %.3f, %.2e, scientific, si, percent, thousands, bytes, duration or date, all but date with :N decimal places.",
+                None,
+            )
+            .switch("meta", "Return a record of the chart and facts about the input.", None)
+            .input_output_types(vec![(Type::record(), Type::Any), (Type::table(), Type::Any)])
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render an ASCII radar, or spider, chart: a spoke for each category, named at its end, evenly round from the top, and a polygon through the values of each series on them, over a dashed grid of rings. A record is one series, whose fields are the categories, and each row of a table another, named by its text cell. Every spoke runs from the least of 0 and the values to the largest, or to its own largest with --normalize."
+    }
+
+    fn search_terms(&self) -> Vec<&str> {
+        vec!["radar", "spider", "star", "polar", "web", "profile"]
+    }
+
+    fn examples(&self) -> Vec<Example<'_>> {
+        vec![
+            Example {
+                example: "{speed: 7 power: 5 range: 9 armour: 3 cost: 6} | radar",
+                description: "Draw the stats of one thing on a spoke each.",
+                result: None,
+            },
+            Example {
+                example: "open cars.csv | select name mpg hp weight accel | first 3 | radar --normalize -l",
+                description: "Compare three cars, each spoke scaled to its own largest value.",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        let call = &with_config(engine, call)?;
+        let opts = parse_cli_opts(call)?;
+        let Series { categories, values, names } = radar_series(call, input)?;
+        let (k, n) = (categories.len(), values.len());
+        if k < 3 {
+            return Err(LabeledError::new("A radar chart needs at least three categories, numeric fields or columns.").with_label("Too few categories.", call.head));
+        }
+        if values.iter().flatten().all(|v| !v.is_finite()) {
+            return Err(LabeledError::new("A radar chart needs at least one finite value.").with_label("No values.", call.head));
+        }
+        let names = match (&opts.names, names.into_iter().collect::<Option<Vec<String>>>()) {
+            (None, Some(names)) => names,
+            _ => series_names(call, opts.names.as_deref(), n)?,
+        };
+        let format = opts.label_format.unwrap_or_default();
+
+        // each spoke from the least of 0 and its values to the largest,
+        // those of every spoke unless they're normalized
+        let normalize = call.has_flag("normalize")?;
+        let extent = |j: Option<usize>| {
+            let on = |row: &Vec<f64>| match j {
+                Some(j) => vec![row[j]],
+                None => row.clone(),
+            };
+            let finite: Vec<f64> = values.iter().flat_map(on).filter(|v| v.is_finite()).collect();
+            let low = finite.iter().copied().fold(0.0, f64::min);
+            let high = finite.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            (low, if high > low { high } else { low + 1.0 })
+        };
+        let extents: Vec<(f64, f64)> = (0..k).map(|j| extent(normalize.then_some(j))).collect();
+        let labels: Vec<String> = categories
+            .iter()
+            .zip(&extents)
+            .map(|(c, (_, high))| match normalize {
+                true => format!("{} ({})", truncate(c, MAX_NAME), format.value(*high)),
+                false => truncate(c, MAX_NAME),
+            })
+            .collect();
+
+        // a round chart, with room for the labels either side and a line
+        // of them above and below
+        let widest = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        let max_x = opts.width_op.unwrap_or(200) as usize;
+        let max_y = opts.height_op.unwrap_or(50) as usize;
+        let radius = ((max_x.saturating_sub(4 * widest + 4) / 2).min(max_y.saturating_sub(16) / 2)).max(8);
+        let width = (2 * radius + 4 * widest + 4).max(32);
+        let height = (2 * radius + 16).max(32);
+
+        // a unit is a point, from 1, so 0 is off the chart and no axis
+        // is drawn across it, with the spokes from the middle
+        let (cx, cy) = ((width / 2 + 1) as f64, (height / 2 + 1) as f64);
+        let angle = |j: usize| FRAC_PI_2 - TAU * j as f64 / k as f64;
+        let at = |j: usize, r: f64| (cx + r * radius as f64 * angle(j).cos(), cy + r * radius as f64 * angle(j).sin());
+
+        let mut annotations = vec![];
+        for ring in RINGS {
+            annotations.push(Annotation::Path((0..=k).map(|j| at(j % k, ring)).collect()));
+        }
+        for (j, label) in labels.iter().enumerate() {
+            annotations.push(Annotation::Path(vec![(cx, cy), at(j, 1.0)]));
+            // beyond the end of the spoke, away from the middle
+            let (x, y) = at(j, 1.0 + 3.0 / radius as f64);
+            let (cos, sin) = (angle(j).cos(), angle(j).sin());
+            let len = 2.0 * label.chars().count() as f64;
+            let left = match cos {
+                cos if cos > 0.1 => x,
+                cos if cos < -0.1 => x - len,
+                _ => x - len / 2.0,
+            };
+            annotations.push(Annotation::Text(left, y + 2.0 + 2.0 * sin, label.clone()));
+        }
+        if !normalize {
+            let (low, high) = extents[0];
+            for ring in [RINGS[1], RINGS[3]] {
+                let (x, y) = at(0, ring);
+                annotations.push(Annotation::Text(x + 2.0, y, format.value(low + ring * (high - low))));
+            }
+        }
+
+        let mut chart = new_chart(opts.format, opts.charset, (LabelFormat::Hidden, LabelFormat::Hidden), width as u32, height as u32, 1.0, (width + 1) as f64);
+        chart.fix_y_range(1.0, (height + 1) as f64);
+        let mut entries = vec![];
+        for (i, row) in values.iter().enumerate() {
+            // closed back to the first category
+            let points = (0..=k)
+                .map(|j| {
+                    let (low, high) = extents[j % k];
+                    at(j % k, (row[j % k] - low) / (high - low))
+                })
+                .collect();
+            opts.palette.plot_nth(&mut chart, i, n, Shape::Lines(points));
+            if opts.legend {
+                entries.push(opts.palette.entry(&names[i], i, n, None, format));
+            }
+        }
+
+        let meta = Meta {
+            series: n,
+            points: n * k,
+            nulls: values.iter().flatten().filter(|v| v.is_nan()).count(),
+            ..Meta::default()
+        };
+        let chart = finish_chart(&mut chart, opts.format, &names, &annotations, &opts.titles, &entries, opts.legend_pos, &opts.pad, opts.object, call.head);
+        output_chart(engine, call, chart, meta)
+    }
+}
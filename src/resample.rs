@@ -0,0 +1,459 @@
+//! Preparing points before they are plotted: summing, differencing,
+//! rescaling, clipping or normalizing their y values, sorting them by
+//! x, combining the points which share an x value, and reducing their
+//! number.
+//!
+//! There is no visual benefit in drawing more points than the canvas
+//! has horizontal pixels, so long series are downsampled first. All of
+//! these expect the data to be sorted by x, as [`sort_x`] leaves it.
+
+use crate::bins::quantile;
+
+/// How to reduce a long series before plotting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decimation {
+    /// Largest-Triangle-Three-Buckets, see [`lttb`].
+    Lttb,
+    /// The smallest and largest point per column, see [`min_max`].
+    MinMax,
+    /// Keep every point.
+    Off,
+}
+
+impl Decimation {
+    /// Reduce `data` to fit into `columns` horizontal pixels.
+    ///
+    /// Points that are not finite are gaps in the series. Each run
+    /// between two gaps is reduced on its own, taking its share of the
+    /// columns, and the gaps between runs are kept.
+    pub fn apply(self, data: Vec<(f64, f64)>, columns: usize) -> Vec<(f64, f64)> {
+        let finite = |p: &(f64, f64)| p.0.is_finite() && p.1.is_finite();
+        if self == Decimation::Off || data.len() <= columns {
+            return data;
+        }
+        if data.iter().all(finite) {
+            return self.apply_run(&data, columns);
+        }
+
+        let runs: Vec<&[(f64, f64)]> = data
+            .split(|p| !finite(p))
+            .filter(|run| !run.is_empty())
+            .collect();
+        let (Some(first), Some(last)) = (runs.first(), runs.last()) else {
+            return data;
+        };
+        let span = last[last.len() - 1].0 - first[0].0;
+
+        let mut sampled = Vec::with_capacity(columns + runs.len());
+        for run in runs {
+            if !sampled.is_empty() {
+                sampled.push((f64::NAN, f64::NAN));
+            }
+            let share = if span > 0.0 {
+                (run[run.len() - 1].0 - run[0].0) / span
+            } else {
+                1.0
+            };
+            let run_columns = ((share * columns as f64).ceil() as usize).max(1);
+            sampled.extend(self.apply_run(run, run_columns));
+        }
+        sampled
+    }
+
+    /// Reduce a run of finite points.
+    fn apply_run(self, data: &[(f64, f64)], columns: usize) -> Vec<(f64, f64)> {
+        match self {
+            Decimation::Lttb => lttb(data, columns),
+            Decimation::MinMax => min_max(data, columns),
+            Decimation::Off => data.to_vec(),
+        }
+    }
+}
+
+/// Largest-Triangle-Three-Buckets downsampling.
+///
+/// Reduces `data` to `threshold` points while keeping its visual
+/// shape. The first and last points are always kept. `data` must be
+/// sorted by x. If `threshold` is less than 3, or not less than the
+/// length of the data, the data is returned as is.
+///
+/// ```
+/// # use nu_plugin_plot::resample::lttb;
+/// let data: Vec<(f64, f64)> = (0..100).map(|i| (i as f64, (i % 10) as f64)).collect();
+/// let sampled = lttb(&data, 20);
+/// assert_eq!(sampled.len(), 20);
+/// assert_eq!(sampled[0], data[0]);
+/// assert_eq!(sampled[19], data[99]);
+/// ```
+pub fn lttb(data: &[(f64, f64)], threshold: usize) -> Vec<(f64, f64)> {
+    let len = data.len();
+    if threshold < 3 || threshold >= len {
+        return data.to_vec();
+    }
+
+    // the first and last points sit in buckets of their own
+    let every = (len - 2) as f64 / (threshold - 2) as f64;
+    let bucket_start = |i: usize| ((i as f64 * every) as usize + 1).min(len - 1);
+
+    let mut sampled = Vec::with_capacity(threshold);
+    sampled.push(data[0]);
+
+    let mut a = 0;
+    for i in 0..threshold - 2 {
+        // the average point of the next bucket
+        let next = &data[bucket_start(i + 1)..bucket_start(i + 2).max(bucket_start(i + 1) + 1)];
+        let (sum_x, sum_y) = next
+            .iter()
+            .fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x, sy + y));
+        let (avg_x, avg_y) = (sum_x / next.len() as f64, sum_y / next.len() as f64);
+
+        // the point in this bucket making the largest triangle
+        let (ax, ay) = data[a];
+        let (start, end) = (bucket_start(i), bucket_start(i + 1));
+        let mut max_area = -1.0;
+        for (j, (x, y)) in data.iter().enumerate().take(end).skip(start) {
+            let area = ((ax - avg_x) * (y - ay) - (ax - x) * (avg_y - ay)).abs();
+            if area > max_area {
+                max_area = area;
+                a = j;
+            }
+        }
+        sampled.push(data[a]);
+    }
+
+    sampled.push(data[len - 1]);
+    sampled
+}
+
+/// Min/max decimation.
+///
+/// Splits the x range into `columns` equal buckets and keeps the
+/// lowest and highest point in each, in x order, so that spikes are
+/// never lost. If the data would not shrink, it is returned as is.
+///
+/// ```
+/// # use nu_plugin_plot::resample::min_max;
+/// let mut data: Vec<(f64, f64)> = (0..1000).map(|i| (i as f64, 0.0)).collect();
+/// data[501].1 = 100.0;
+/// let sampled = min_max(&data, 10);
+/// assert!(sampled.len() <= 20);
+/// assert!(sampled.contains(&(501.0, 100.0)));
+/// ```
+pub fn min_max(data: &[(f64, f64)], columns: usize) -> Vec<(f64, f64)> {
+    if columns == 0 || data.len() <= 2 * columns {
+        return data.to_vec();
+    }
+
+    let xmin = data[0].0;
+    let step = (data[data.len() - 1].0 - xmin) / columns as f64;
+    let column = |x: f64| {
+        if step > 0.0 {
+            (((x - xmin) / step) as usize).min(columns - 1)
+        } else {
+            0
+        }
+    };
+
+    let mut sampled = Vec::with_capacity(2 * columns);
+    let mut push = |lo: (f64, f64), hi: (f64, f64)| {
+        if lo == hi {
+            sampled.push(lo);
+        } else if lo.0 <= hi.0 {
+            sampled.extend([lo, hi]);
+        } else {
+            sampled.extend([hi, lo]);
+        }
+    };
+
+    // (column, lowest point, highest point) of the current bucket
+    let mut bucket = (column(data[0].0), data[0], data[0]);
+    for &p in &data[1..] {
+        let col = column(p.0);
+        if col == bucket.0 {
+            if p.1 < bucket.1 .1 {
+                bucket.1 = p;
+            }
+            if p.1 > bucket.2 .1 {
+                bucket.2 = p;
+            }
+        } else {
+            push(bucket.1, bucket.2);
+            bucket = (col, p, p);
+        }
+    }
+    push(bucket.1, bucket.2);
+
+    sampled
+}
+
+/// How to combine the y values of points which share an x value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aggregate {
+    /// Their mean.
+    Mean,
+    /// Their sum.
+    Sum,
+    /// The largest of them.
+    Max,
+}
+
+impl Aggregate {
+    /// The aggregate called `name`: `mean`, `sum` or `max`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "mean" => Some(Aggregate::Mean),
+            "sum" => Some(Aggregate::Sum),
+            "max" => Some(Aggregate::Max),
+            _ => None,
+        }
+    }
+
+    /// The y values `ys` combined, leaving out those which are not
+    /// finite, or `NaN`, a gap, if none are.
+    fn apply(self, ys: impl Iterator<Item = f64>) -> f64 {
+        let ys: Vec<f64> = ys.filter(|y| y.is_finite()).collect();
+        if ys.is_empty() {
+            return f64::NAN;
+        }
+        match self {
+            Aggregate::Mean => ys.iter().sum::<f64>() / ys.len() as f64,
+            Aggregate::Sum => ys.iter().sum(),
+            Aggregate::Max => ys.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+/// `data` sorted by x, so a line through it runs left to right rather
+/// than back and forth. Points of the same x keep their order, and a
+/// point of a y value which isn't finite stays a gap at its x, but
+/// points whose x isn't finite have nowhere to go, so are left out.
+///
+/// ```
+/// # use nu_plugin_plot::resample::sort_x;
+/// let data = [(3.0, 1.0), (1.0, 2.0), (f64::NAN, 5.0), (2.0, 3.0), (1.0, 4.0)];
+/// assert_eq!(sort_x(&data), vec![(1.0, 2.0), (1.0, 4.0), (2.0, 3.0), (3.0, 1.0)]);
+/// ```
+pub fn sort_x(data: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut sorted: Vec<(f64, f64)> = data.iter().copied().filter(|(x, _)| x.is_finite()).collect();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+    sorted
+}
+
+/// `data` sorted by x, as [`sort_x`] sorts it, with the points which
+/// share an x value combined into one as `how` says.
+///
+/// ```
+/// # use nu_plugin_plot::resample::{aggregate, Aggregate};
+/// let data = [(2.0, 1.0), (1.0, 2.0), (2.0, 5.0), (1.0, f64::NAN)];
+/// assert_eq!(aggregate(&data, Aggregate::Mean), vec![(1.0, 2.0), (2.0, 3.0)]);
+/// assert_eq!(aggregate(&data, Aggregate::Sum), vec![(1.0, 2.0), (2.0, 6.0)]);
+/// assert_eq!(aggregate(&data, Aggregate::Max), vec![(1.0, 2.0), (2.0, 5.0)]);
+/// ```
+pub fn aggregate(data: &[(f64, f64)], how: Aggregate) -> Vec<(f64, f64)> {
+    sort_x(data)
+        .chunk_by(|a, b| a.0 == b.0)
+        .map(|points| (points[0].0, how.apply(points.iter().map(|(_, y)| *y))))
+        .collect()
+}
+
+/// A running transform of the y values of a series, taken in the order
+/// they come.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Running {
+    /// Each value replaced by the sum of it and every value before it,
+    /// turning rates into a counter.
+    Cumsum,
+    /// Each value replaced by how much it changed from the one before,
+    /// turning a counter into rates.
+    Diff,
+}
+
+impl Running {
+    /// Transform `ys` in place. Values which aren't finite stay gaps:
+    /// a cumulative sum carries on past them, but a difference either
+    /// side of one is a gap too, as is the first, which has nothing to
+    /// change from, so each value keeps its x.
+    ///
+    /// ```
+    /// # use nu_plugin_plot::resample::Running;
+    /// let mut ys = [1.0, 2.0, f64::NAN, 3.0];
+    /// Running::Cumsum.apply(&mut ys);
+    /// assert_eq!(ys[..2], [1.0, 3.0]);
+    /// assert!(ys[2].is_nan());
+    /// assert_eq!(ys[3], 6.0);
+    ///
+    /// let mut ys = [1.0, 4.0, 9.0, f64::NAN, 25.0];
+    /// Running::Diff.apply(&mut ys);
+    /// assert_eq!(ys[1..3], [3.0, 5.0]);
+    /// assert!(ys[0].is_nan() && ys[3].is_nan() && ys[4].is_nan());
+    /// ```
+    pub fn apply(self, ys: &mut [f64]) {
+        match self {
+            Running::Cumsum => {
+                let mut sum = 0.0;
+                for y in ys.iter_mut().filter(|y| y.is_finite()) {
+                    sum += *y;
+                    *y = sum;
+                }
+            }
+            Running::Diff => {
+                let mut previous = f64::NAN;
+                for y in ys.iter_mut() {
+                    (previous, *y) = (*y, *y - previous);
+                }
+            }
+        }
+    }
+}
+
+/// A function the y values of each series are put through before they
+/// are plotted, spreading out the values bunched near zero, as those of
+/// latencies with a long tail are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rescale {
+    /// The logarithm, to base 10.
+    Log,
+    /// The natural logarithm of one more than the value, which keeps
+    /// zero at zero.
+    Log1p,
+    /// The square root.
+    Sqrt,
+}
+
+impl Rescale {
+    /// The function called `name`: `log`, `log1p` or `sqrt`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "log" => Some(Rescale::Log),
+            "log1p" => Some(Rescale::Log1p),
+            "sqrt" => Some(Rescale::Sqrt),
+            _ => None,
+        }
+    }
+
+    /// `y` rescaled, or `NaN`, a gap, where the function isn't defined,
+    /// such as the log of 0 or less.
+    ///
+    /// ```
+    /// # use nu_plugin_plot::resample::Rescale;
+    /// assert_eq!(Rescale::Log.apply(1000.0), 3.0);
+    /// assert_eq!(Rescale::Log1p.apply(0.0), 0.0);
+    /// assert_eq!(Rescale::Sqrt.apply(16.0), 4.0);
+    /// assert!(Rescale::Log.apply(0.0).is_nan() && Rescale::Sqrt.apply(-1.0).is_nan());
+    /// for rescale in [Rescale::Log, Rescale::Log1p, Rescale::Sqrt] {
+    ///     assert!((rescale.invert(rescale.apply(42.0)) - 42.0).abs() < 1e-9);
+    /// }
+    /// ```
+    pub fn apply(self, y: f64) -> f64 {
+        match self {
+            Rescale::Log if y > 0.0 => y.log10(),
+            Rescale::Log1p if y > -1.0 => y.ln_1p(),
+            Rescale::Sqrt if y >= 0.0 => y.sqrt(),
+            _ => f64::NAN,
+        }
+    }
+
+    /// The value `y` was rescaled from.
+    pub fn invert(self, y: f64) -> f64 {
+        match self {
+            Rescale::Log => 10f64.powf(y),
+            Rescale::Log1p => y.exp_m1(),
+            Rescale::Sqrt => y * y,
+        }
+    }
+}
+
+/// Cap the values of `ys` beyond its `q`th quantile, and those beyond
+/// the `1 - q`th at the other end, at those quantiles, so a spike
+/// doesn't flatten the rest of a chart: winsorizing them. Values which
+/// aren't finite are left as gaps. Returns how many were capped.
+///
+/// ```
+/// # use nu_plugin_plot::resample::winsorize;
+/// let mut ys = [1.0, 2.0, 3.0, 4.0, 100.0];
+/// assert_eq!(winsorize(&mut ys, 0.75), 2);
+/// assert_eq!(ys, [2.0, 2.0, 3.0, 4.0, 4.0]);
+/// ```
+pub fn winsorize(ys: &mut [f64], q: f64) -> usize {
+    let mut sorted: Vec<f64> = ys.iter().copied().filter(|y| y.is_finite()).collect();
+    sorted.sort_by(f64::total_cmp);
+    let (low, high) = (quantile(&sorted, q.min(1.0 - q)), quantile(&sorted, q.max(1.0 - q)));
+    let mut capped = 0;
+    for y in ys.iter_mut().filter(|y| **y < low || **y > high) {
+        *y = y.clamp(low, high);
+        capped += 1;
+    }
+    capped
+}
+
+/// How to rescale the y values of each series, so series of different
+/// sizes can be compared on one axis.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Normalize {
+    /// From 0 at the smallest value to 1 at the largest.
+    MinMax,
+    /// The number of standard deviations from the mean.
+    ZScore,
+    /// As a percentage of the first value, which becomes 100.
+    Index100,
+}
+
+impl Normalize {
+    /// The normalization called `name`: `minmax`, `zscore` or
+    /// `index100`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "minmax" => Some(Normalize::MinMax),
+            "zscore" => Some(Normalize::ZScore),
+            "index100" => Some(Normalize::Index100),
+            _ => None,
+        }
+    }
+
+    /// Rescale `ys` in place, leaving values which aren't finite as
+    /// gaps. A series of one value all through, which has no spread to
+    /// scale by, is 0 throughout, and a series indexed to a first value
+    /// of 0 is all gaps.
+    ///
+    /// ```
+    /// # use nu_plugin_plot::resample::Normalize;
+    /// let mut ys = [2.0, 4.0, f64::NAN, 6.0];
+    /// Normalize::MinMax.apply(&mut ys);
+    /// assert_eq!([ys[0], ys[1], ys[3]], [0.0, 0.5, 1.0]);
+    ///
+    /// let mut ys = [2.0, 4.0, 6.0];
+    /// Normalize::ZScore.apply(&mut ys);
+    /// assert_eq!(ys[1], 0.0);
+    /// assert!((ys[2] - 1.5f64.sqrt()).abs() < 1e-12);
+    ///
+    /// let mut ys = [f64::NAN, 50.0, 75.0];
+    /// Normalize::Index100.apply(&mut ys);
+    /// assert_eq!(ys[1..], [100.0, 150.0]);
+    /// ```
+    pub fn apply(self, ys: &mut [f64]) {
+        let finite: Vec<f64> = ys.iter().copied().filter(|y| y.is_finite()).collect();
+        let Some(&first) = finite.first() else {
+            return;
+        };
+        let (offset, scale) = match self {
+            Normalize::MinMax => {
+                let min = finite.iter().copied().fold(f64::INFINITY, f64::min);
+                let max = finite.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                (min, max - min)
+            }
+            Normalize::ZScore => {
+                let mean = finite.iter().sum::<f64>() / finite.len() as f64;
+                let variance = finite.iter().map(|y| (y - mean).powi(2)).sum::<f64>() / finite.len() as f64;
+                (mean, variance.sqrt())
+            }
+            Normalize::Index100 => (0.0, first / 100.0),
+        };
+        for y in ys.iter_mut().filter(|y| y.is_finite()) {
+            *y = match (self, scale == 0.0) {
+                (Normalize::Index100, true) => f64::NAN,
+                (_, true) => 0.0,
+                (_, false) => (*y - offset) / scale,
+            };
+        }
+    }
+}
@@ -0,0 +1,368 @@
+//! The `ridgeline` command.
+//!
+//! Draws the kernel density estimates of several lists one above the
+//! other, each on its own baseline and reaching a little way into the
+//! one above, with their names in a gutter to the left, so many
+//! distributions can be compared in little room. The lists may be a
+//! nested list, a record of lists, the columns of a table, or the values
+//! of a table split by a column.
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::{Category, Config, Example, LabeledError, PipelineData, Signature, SyntaxShape, Type, Value};
+
+use crate::color_plot::textplots::label::LabelFormat;
+use crate::color_plot::textplots::{Annotation, Shape};
+use crate::density::{bandwidth, kernel};
+use crate::kde::kde;
+use crate::{extent, finish_chart, label_formats, new_chart, no_finite_values, output_chart, parse_cli_opts, series_names, truncate, value_to_f64, with_config, Meta, PluginPlot, Unit, MAX_NAME};
+
+pub struct CommandRidgeline;
+
+/// How far each curve reaches into the band of the one above, as a share
+/// of a band, if `--overlap` is not given.
+const DEFAULT_OVERLAP: f64 = 0.5;
+
+/// The lists to draw, in order, with their names if the input gave them.
+struct Distributions {
+    values: Vec<Vec<f64>>,
+    names: Option<Vec<String>>,
+    unit: Unit,
+    nulls: usize,
+}
+
+/// The lists of a nested list, a record of lists or a table, its columns
+/// or, with `--group-by`, the first column besides the one named split by
+/// its values, in the order they first appear. Nulls are left out.
+fn distributions(call: &EvaluatedCall, input: PipelineData) -> Result<Distributions, LabeledError> {
+    let wrong = |span| LabeledError::new("ridgeline needs a list of lists, a record of lists or a table.").with_label("Incorrect input type.", span);
+    let (mut unit, mut nulls) = (None, 0);
+    let mut numbers = |cells: &[Value]| -> Result<Vec<f64>, LabeledError> {
+        let mut values = vec![];
+        for cell in cells {
+            if let Value::Nothing { .. } = cell {
+                nulls += 1;
+                continue;
+            }
+            Unit::see(&mut unit, cell);
+            values.push(value_to_f64(cell, call)?);
+        }
+        Ok(values)
+    };
+
+    let mut lists: Vec<(Option<String>, Vec<f64>)> = vec![];
+    match input {
+        PipelineData::Value(Value::Record { val, .. }, ..) => {
+            for (name, list) in val.iter() {
+                let cells = list.as_list().map_err(|_| wrong(list.span()))?;
+                lists.push((Some(name.clone()), numbers(cells)?));
+            }
+        }
+        PipelineData::ListStream(..) | PipelineData::Value(Value::List { .. }, ..) => {
+            let rows: Vec<Value> = input.into_iter().collect();
+            match rows.first() {
+                Some(Value::Record { .. }) => {
+                    let records = rows.iter().map(|row| row.as_record().map_err(|_| wrong(row.span()))).collect::<Result<Vec<_>, _>>()?;
+                    let mut columns: Vec<String> = vec![];
+                    for name in records.iter().flat_map(|record| record.columns()) {
+                        if !columns.contains(name) {
+                            columns.push(name.clone());
+                        }
+                    }
+                    let cells = |column: &str| records.iter().map(|record| record.get(column).cloned().unwrap_or_default()).collect::<Vec<Value>>();
+                    match call.get_flag::<String>("group-by")? {
+                        Some(group) => {
+                            let span = call.get_flag_span("group-by").unwrap_or(call.head);
+                            if !columns.contains(&group) {
+                                return Err(LabeledError::new(format!("The table has no {} column to group by.", group)).with_label("Missing column.", span));
+                            }
+                            let Some(column) = columns.iter().find(|c| **c != group) else {
+                                return Err(LabeledError::new("A table grouped by a column needs a column of values besides it.").with_label("Too few columns.", span));
+                            };
+                            let mut groups: Vec<(String, Vec<Value>)> = vec![];
+                            for (key, cell) in cells(&group).into_iter().zip(cells(column)) {
+                                let key = key.to_abbreviated_string(&Config::default());
+                                match groups.iter_mut().find(|(k, _)| *k == key) {
+                                    Some((_, cells)) => cells.push(cell),
+                                    None => groups.push((key, vec![cell])),
+                                }
+                            }
+                            for (key, cells) in groups {
+                                lists.push((Some(key), numbers(&cells)?));
+                            }
+                        }
+                        None => {
+                            for column in columns {
+                                let values = numbers(&cells(&column))?;
+                                lists.push((Some(column), values));
+                            }
+                        }
+                    }
+                }
+                Some(Value::List { .. }) => {
+                    for row in &rows {
+                        let cells = row.as_list().map_err(|_| wrong(row.span()))?;
+                        lists.push((None, numbers(cells)?));
+                    }
+                }
+                _ => lists.push((None, numbers(&rows)?)),
+            }
+        }
+        _ => return Err(wrong(call.head)),
+    }
+
+    if lists.iter().all(|(_, values)| values.is_empty()) {
+        return Err(LabeledError::new("Can't draw the distributions of no values.").with_label("No values.", call.head));
+    }
+    let names = lists.iter().map(|(name, _)| name.clone()).collect::<Option<Vec<String>>>();
+    Ok(Distributions {
+        values: lists.into_iter().map(|(_, values)| values).collect(),
+        names,
+        unit: unit.unwrap_or_default(),
+        nulls,
+    })
+}
+
+impl PluginCommand for CommandRidgeline {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "ridgeline"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("ridgeline")
+            .description("Render ASCII density curves of several lists, one above the other, each reaching a little into the one above.")
+            .named(
+                "width",
+                SyntaxShape::OneOf(vec![SyntaxShape::Number, SyntaxShape::String]),
+                "The maximum width of the plot, in dots, or as a percentage of the terminal such as 80%.",
+                None,
+            )
+            .named(
+                "height",
+                SyntaxShape::OneOf(vec![SyntaxShape::Number, SyntaxShape::String]),
+                "The maximum height of the plot, in dots, or as a percentage of the terminal such as 80%.",
+                None,
+            )
+            .named(
+                "max-lines",
+                SyntaxShape::Int,
+                "The most lines of output, with the title, labels and legend, shrinking the chart to fit.",
+                None,
+            )
+            .named(
+                "title",
+                SyntaxShape::String,
+                "Provide a title to the plot.",
+                Some('t'),
+            )
+            .named("subtitle", SyntaxShape::String, "A line under the title.", None)
+            .named("caption", SyntaxShape::String, "A line under the chart, such as where its data is from.", None)
+            .named("title-align", SyntaxShape::String, "Line the title, subtitle and caption up across the chart: left (default), center or right.", None)
+            .named("pad", SyntaxShape::Int, "Indent each line of a text chart by this many spaces (default 4), or not at all with 0.", None)
+            .named(
+                "group-by",
+                SyntaxShape::String,
+                "Split a table into a curve for each value of this column, of the first column besides it.",
+                Some('g'),
+            )
+            .named(
+                "overlap",
+                SyntaxShape::Number,
+                "How far the tallest curve reaches into the band above, as a share of a band, 0.5 by default.",
+                None,
+            )
+            .named(
+                "bandwidth",
+                SyntaxShape::Number,
+                "The width of the kernel, default is Silverman's rule of thumb.",
+                None,
+            )
+            .named(
+                "kernel",
+                SyntaxShape::String,
+                "The kernel: gaussian (default), epanechnikov, triangular or uniform.",
+                Some('k'),
+            )
+            .switch("fill", "Shade under each curve, down to its baseline.", None)
+            .switch("legend", "Plot a tiny, maybe useful legend.", Some('l'))
+            .named(
+                "legend-pos",
+                SyntaxShape::String,
+                "Where to put the legend: top, bottom (default), right or inside.",
+                None,
+            )
+            .named(
+                "names",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "Names for the curves, one for each.",
+                None,
+            )
+            .named(
+                "format",
+                SyntaxShape::String,
+                "Draw the chart as text (default), svg, png, or vega for a Vega-Lite spec.",
+                None,
+            )
+            .named(
+                "charset",
+                SyntaxShape::String,
+                "The characters to draw a text chart with: braille (default), ascii or blocks.",
+                None,
+            )
+            .named(
+                "palette",
+                SyntaxShape::OneOf(vec![SyntaxShape::List(Box::new(SyntaxShape::String)), SyntaxShape::String]),
+                "Colors for the curves in turn: default, okabe-ito, viridis, mono or high-contrast, or a list such as [red bright-blue \"#ff8800\"].",
+                None,
+            )
+            .named(
+                "label-format",
+                SyntaxShape::String,
+                "How to write the numbers of the axis: %.3f, %.2e, scientific, si, percent, thousands, bytes, duration or date, all but date with :N decimal places.",
+                None,
+            )
+            .named(
+                "color",
+                SyntaxShape::String,
+                "Color a text chart always, never, or auto (default) when NO_COLOR and CLICOLOR=0 are unset and the output is a terminal.",
+                None,
+            )
+            .switch("raw", "Return plain text without color or any other escape codes, whatever --color says.", None)
+            .switch("print", "Print the chart as soon as it is drawn rather than returning it, so each chart of a loop or script is shown.", None)
+            .switch("object", "Return the chart as a chart value, shown as the chart, with its data, ranges and facts as cells such as ymax.", None)
+            .named(
+                "output",
+                SyntaxShape::Filepath,
+                "Save the chart to this file rather than returning it, as SVG, PNG or a Vega-Lite spec if it ends in .svg, .png or .json.",
+                Some('o'),
+            )
+            .switch("meta", "Return a record of the chart and facts about the input.", None)
+            .input_output_type(Type::List(Box::new(Type::Any)), Type::Any)
+            .input_output_type(Type::record(), Type::Any)
+            .input_output_type(Type::table(), Type::Any)
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render ASCII kernel density estimates of several lists one above the other, each on its own baseline and reaching a little way into the one above, named in a gutter on the left, to compare many distributions in little room. Give it a nested list, a record of lists, a table, a curve for each column, or a table with --group-by, a curve of its values for each group. The curves share a bandwidth and a scale."
+    }
+
+    fn search_terms(&self) -> Vec<&str> {
+        vec!["ridgeline", "joyplot", "density", "distribution", "kde"]
+    }
+
+    fn examples(&self) -> Vec<Example<'_>> {
+        vec![
+            Example {
+                example: "{before: [3 4 4 5 5 5 6 6 7] after: [5 6 6 7 7 7 8 8 9]} | ridgeline --fill",
+                description: "Compare two distributions, shaded.",
+                result: None,
+            },
+            Example {
+                example: "open weather.csv | select month temp | ridgeline --group-by month --overlap 1 -t Temperatures",
+                description: "Draw the temperatures of each month, overlapping a whole band.",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        let call = &with_config(engine, call)?;
+        let opts = parse_cli_opts(call)?;
+        let Distributions { values, names, unit, nulls } = distributions(call, input)?;
+        let k = values.len();
+        let names = match names {
+            Some(names) if opts.names.is_none() => names,
+            _ => series_names(call, opts.names.as_deref(), k)?,
+        };
+        let (format, _) = label_formats(opts.label_format, (unit, Unit::Number));
+        let overlap = match call.get_flag::<f64>("overlap")? {
+            None => DEFAULT_OVERLAP,
+            Some(o) if o.is_finite() && o >= 0.0 => o,
+            Some(_) => return Err(LabeledError::new("The overlap must be a number, 0 or more.").with_label("Overlap error.", call.get_flag_span("overlap").unwrap_or(call.head))),
+        };
+
+        // the curves share a kernel, a bandwidth and a range, with room
+        // for their tails
+        let kernel = kernel(call)?;
+        let pooled = values.concat();
+        let bandwidth = bandwidth(call, &pooled)?;
+        let (min, max) = extent(pooled.iter().copied()).ok_or_else(|| no_finite_values(call))?;
+        let range = (min - kernel.reach() * bandwidth, max + kernel.reach() * bandwidth);
+
+        // laid out in points: the names in a gutter of characters on the
+        // left, and the curves across the rest; a band of whole lines of
+        // characters for each curve, its baseline on the bottom dots of
+        // the last, with room above the first for it to reach up, and a
+        // line under them all for the ends of the axis
+        let gutter = names.iter().map(|n| n.chars().count()).max().unwrap_or(0).min(MAX_NAME) + 1;
+        let width = (opts.width_op.unwrap_or(200) as usize).max(2 * gutter + 32);
+        let max_y = opts.height_op.unwrap_or(8 * k as u32 + 16) as f64;
+        let band = 4 * (((max_y - 4.0) / (k as f64 + overlap) / 4.0) as usize).max(1);
+        let reach = (band as f64 * (1.0 + overlap)).round() as usize;
+        let first = reach.saturating_sub(3).div_ceil(4);
+        let baseline = |i: usize| 4 * first + i * band + 3;
+        let bottom = baseline(k - 1) + 1;
+        let height = (bottom + 4).max(32);
+
+        // a unit is a point, from the left and down from the top, with
+        // the values along after the gutter; they count from 1, so 0 is
+        // off the chart and no axis is drawn across it
+        let x = |col: usize| (2 * col + 1) as f64;
+        let y = |dot: f64| height as f64 - dot + 1.0;
+        let columns = width / 2 - gutter;
+        let curves: Vec<Vec<(f64, f64)>> = values.iter().map(|list| kde(list, bandwidth, kernel, range, 2 * columns)).collect();
+        let tallest = curves.iter().flatten().map(|(_, d)| *d).fold(0.0, f64::max);
+        let scale = if tallest > 0.0 { reach as f64 / tallest } else { 0.0 };
+        let along = |v: f64| x(gutter) + (v - range.0) / (range.1 - range.0) * (x(width / 2 - 1) - x(gutter));
+
+        let mut chart = new_chart(opts.format, opts.charset, (LabelFormat::Hidden, LabelFormat::Hidden), width as u32, height as u32, x(0), x(width / 2));
+        chart.fix_y_range(y(height as f64), y(0.0));
+        let mut annotations = vec![];
+        // from the back, so each curve is drawn over the one above it
+        for (i, curve) in curves.iter().enumerate() {
+            let base = y(baseline(i) as f64);
+            let points: Vec<(f64, f64)> = curve.iter().filter(|(_, d)| d.is_finite()).map(|&(v, d)| (along(v), base + d * scale)).collect();
+            if opts.fill {
+                let color = if k == 1 { opts.palette.single() } else { Some(opts.palette.nth(i)) };
+                annotations.push(Annotation::Area(points.iter().map(|&(x, y)| (x, base, y)).collect(), color));
+            }
+            opts.palette.plot_nth(&mut chart, i, k, Shape::Lines(points));
+            annotations.push(Annotation::Text(x(0), y((baseline(i) - 3) as f64), truncate(&names[i], gutter - 1)));
+        }
+
+        // the ends of the axis, on the line under the curves, as finely
+        // as a tenth of it needs
+        let span = range.1 - range.0;
+        let (low, high) = (format.axis(range.0, span / 10.0), format.axis(range.1, span / 10.0));
+        let (low_len, high_len) = (low.chars().count(), high.chars().count());
+        annotations.push(Annotation::Text(x(gutter), y(bottom as f64), low));
+        if gutter + low_len < width / 2 - high_len {
+            annotations.push(Annotation::Text(x(width / 2 - high_len), y(bottom as f64), high));
+        }
+
+        let mut entries = vec![];
+        if opts.legend {
+            entries.push(vec![(format!("Bandwidth: {:.3} ({})", bandwidth, kernel.name()), None)]);
+        }
+
+        let meta = Meta {
+            series: k,
+            points: pooled.len(),
+            nulls,
+            units: vec![unit],
+            bandwidth: Some(bandwidth),
+            kernel: Some(kernel),
+            ..Meta::default()
+        };
+        let chart = finish_chart(&mut chart, opts.format, &names, &annotations, &opts.titles, &entries, opts.legend_pos, &opts.pad, opts.object, call.head);
+        output_chart(engine, call, chart, meta)
+    }
+}
@@ -0,0 +1,223 @@
+//! The `plot spec` command.
+//!
+//! Draws a chart described by a single record, so that a chart can be
+//! built up in nushell and kept as nuon. The record gives a list of
+//! `series`, each with its own values, name, shape and color, the `x`
+//! and `y` ranges, and any other option of `plot` by name, such as a
+//! `title`, `hline` or `legend`.
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::{Category, LabeledError, PipelineData, Record, Signature, Spanned, Type, Value};
+
+use crate::color_plot::drawille::PixelColor;
+use crate::color_plot::textplots::StepMode;
+use crate::{
+    chart_shape, check_chart_shape, check_gradient, color_from_name, connect_gaps, extent, finish_chart, label_formats, mark_labels, new_chart, no_finite_values, output_chart,
+    parse_cli_opts, value_labels, value_to_f64, with_config, CliOpts, CommandPlot, Meta, PluginPlot, SeriesShape,
+};
+
+/// Options of `plot` which don't describe a single chart, or which a
+/// spec gives for each series instead.
+const NOT_SPEC: [&str; 21] = ["names", "shapes", "decimate", "no-downsample", "live", "window", "skip-nulls", "fill-nulls", "cumsum", "diff", "normalize", "transform", "offset", "clip-quantile", "winsorize", "trend", "jitter", "seed", "order", "highlight", "panel"];
+
+pub struct CommandSpec;
+
+/// A series of a spec.
+pub(crate) struct Series {
+    pub(crate) name: String,
+    pub(crate) points: Vec<(f64, f64)>,
+    /// `lines`, `points`, `steps`, `bars` or `stems`, if not the chart's
+    /// shape, with steps as `steps:post` or `steps:mid` if they step
+    /// other than at the first point.
+    pub(crate) shape: Option<String>,
+    pub(crate) color: Option<PixelColor>,
+}
+
+/// The numbers in the list `value`, with nulls as gaps.
+fn numbers(call: &EvaluatedCall, value: &Value) -> Result<Vec<f64>, LabeledError> {
+    value
+        .as_list()?
+        .iter()
+        .map(|v| match v {
+            Value::Nothing { .. } => Ok(f64::NAN),
+            v => value_to_f64(v, call),
+        })
+        .collect()
+}
+
+/// The `i`th series of a spec, from its record.
+pub(crate) fn series(call: &EvaluatedCall, i: usize, value: &Value) -> Result<Series, LabeledError> {
+    let record = value.as_record()?;
+    let Some(y) = record.get("y") else {
+        return Err(LabeledError::new("A series needs a list of y values.").with_label("Missing y.", value.span()));
+    };
+    let ys = numbers(call, y)?;
+    let xs = match record.get("x") {
+        Some(x) => numbers(call, x)?,
+        None => (0..ys.len()).map(|x| x as f64).collect(),
+    };
+    if xs.len() != ys.len() {
+        return Err(LabeledError::new("A series needs as many x values as y values.").with_label("List length differences.", value.span()));
+    }
+    let shape = match record.get("shape") {
+        Some(shape) => match shape.as_str()? {
+            s if SeriesShape::from_name(s, StepMode::default()).is_some() => Some(s.to_string()),
+            s => return Err(LabeledError::new(format!("Unknown shape {}, use lines, points, steps, steps:pre, steps:post, steps:mid, bars or stems.", s)).with_label("Shape error.", shape.span())),
+        },
+        None => None,
+    };
+    let color = match record.get("color") {
+        Some(color) => Some(color_from_name(color.as_str()?).ok_or_else(|| {
+            LabeledError::new(format!("Unknown color {}, use black, red, green, yellow, blue, magenta, cyan or white, bright-red and so on, or #rrggbb.", color.as_str().unwrap_or_default()))
+                .with_label("Color error.", color.span())
+        })?),
+        None => None,
+    };
+    let name = match record.get("name") {
+        Some(name) => name.coerce_string()?,
+        None => format!("Line {}", i + 1),
+    };
+    Ok(Series { name, points: xs.into_iter().zip(ys).collect(), shape, color })
+}
+
+/// A range given as a list of two numbers, from low to high.
+pub(crate) fn range(call: &EvaluatedCall, value: &Value) -> Result<(f64, f64), LabeledError> {
+    match numbers(call, value)?[..] {
+        [lo, hi] if lo < hi => Ok((lo, hi)),
+        _ => Err(LabeledError::new("A range needs two numbers, from low to high.").with_label("Range error.", value.span())),
+    }
+}
+
+/// The call `plot` would be given for the options in `spec`, leaving
+/// out the series and ranges. Options may be written with `_` for `-`.
+fn spec_call(call: &EvaluatedCall, spec: &Record) -> Result<EvaluatedCall, LabeledError> {
+    let signature = CommandPlot.signature();
+    let mut spec_call = EvaluatedCall::new(call.head);
+    for (name, value) in spec.iter() {
+        let name = name.replace('_', "-");
+        if ["series", "x-range", "y-range"].contains(&name.as_str()) {
+            continue;
+        }
+        if NOT_SPEC.contains(&name.as_str()) || !signature.named.iter().any(|flag| flag.long == name) {
+            return Err(LabeledError::new(format!("A chart spec has no option {}.", name)).with_label("Option error.", value.span()));
+        }
+        spec_call.add_named(Spanned { item: name, span: value.span() }, value.clone());
+    }
+    Ok(spec_call)
+}
+
+impl PluginCommand for CommandSpec {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "plot spec"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("plot spec")
+            .description("Render a chart described by a record of its series and options.")
+            .input_output_type(Type::record(), Type::Any)
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render a chart described by a record: a list of series, each a record of y values and optionally x values, a name, a shape (lines, points, steps or bars) and a color, an x_range and y_range of two numbers, and any option of plot by name."
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        let spec = match input.into_value(call.head)? {
+            Value::Record { val, .. } => val.into_owned(),
+            v => return Err(LabeledError::new(format!("Input type should be a record describing the chart, got {}.", v.get_type())).with_label("Incorrect input type.", call.head)),
+        };
+        let call = &with_config(engine, &spec_call(call, &spec)?)?;
+        let opts = parse_cli_opts(call)?;
+
+        let series = match spec.get("series") {
+            Some(list) => list
+                .as_list()?
+                .iter()
+                .enumerate()
+                .map(|(i, value)| series(call, i, value))
+                .collect::<Result<Vec<Series>, LabeledError>>()?,
+            None => vec![],
+        };
+        if series.is_empty() {
+            return Err(LabeledError::new("A chart spec needs a list of series to plot.").with_label("No series.", call.head));
+        }
+        let x_range = spec.get("x_range").or(spec.get("x-range")).map(|value| range(call, value)).transpose()?;
+        let y_range = spec.get("y_range").or(spec.get("y-range")).map(|value| range(call, value)).transpose()?;
+        draw(engine, call, opts, series, x_range, y_range)
+    }
+}
+
+/// Draw `series`, each in its own shape and color or else the chart's,
+/// across `x_range`, or all their x values, and `y_range` if given.
+pub(crate) fn draw(
+    engine: &nu_plugin::EngineInterface,
+    call: &EvaluatedCall,
+    mut opts: CliOpts,
+    mut series: Vec<Series>,
+    x_range: Option<(f64, f64)>,
+    y_range: Option<(f64, f64)>,
+) -> Result<PipelineData, LabeledError> {
+    check_chart_shape(opts.steps, opts.bars, opts.points, opts.stem, call)?;
+    if series.len() > 1 {
+        check_gradient(&opts.palette, call)?;
+    }
+    // a series keeps the color it gives, and takes its turn of the palette otherwise
+    if series.iter().any(|s| s.color.is_some()) {
+        let colors = series.iter().enumerate().map(|(i, s)| s.color.unwrap_or_else(|| opts.palette.nth(i))).collect();
+        opts.palette.colors = Some(colors);
+    }
+    let palette = &opts.palette;
+
+    let (xmin, xmax) = match x_range {
+        Some(x_range) => x_range,
+        None => extent(series.iter().flat_map(|s| s.points.iter().map(|(x, _)| *x))).ok_or_else(|| no_finite_values(call))?,
+    };
+    let max_x = opts.width_op.unwrap_or(200);
+    let max_y = opts.height_op.unwrap_or(50);
+
+    let count = series.len();
+    let labels = label_formats(opts.label_format, Default::default());
+    let mut annotations = opts.annotations.clone();
+    for (i, s) in series.iter_mut().enumerate() {
+        connect_gaps(&mut s.points, opts.connect_gaps);
+        annotations.extend(mark_labels(&opts.marks, labels.1, &s.points));
+        annotations.extend(value_labels(opts.labels, labels.1, &s.points));
+        annotations.extend(palette.marks(i, count, &s.points, (xmin, xmax), max_x));
+    }
+    let mut entries = vec![];
+    if opts.legend {
+        for (i, s) in series.iter().enumerate() {
+            let ys: Vec<f64> = s.points.iter().map(|(_, y)| *y).collect();
+            entries.push(palette.entry(&s.name, i, count, opts.legend_stats.then_some(&ys[..]), labels.1));
+        }
+    }
+    let names: Vec<String> = series.iter().map(|s| s.name.clone()).collect();
+    let meta = Meta {
+        series: count,
+        points: series.iter().map(|s| s.points.len()).sum(),
+        ..Meta::default()
+    };
+
+    let mut chart = new_chart(opts.format, opts.charset, labels, max_x, max_y, xmin, xmax);
+    if let Some((ymin, ymax)) = y_range {
+        chart.fix_y_range(ymin, ymax);
+    }
+    chart.blend(opts.blend).smooth(opts.smooth);
+    for (i, s) in series.into_iter().enumerate() {
+        // a shape drawn another way, such as candles, is drawn as lines
+        let shape = s.shape.as_deref().map(|name| SeriesShape::from_name(name, opts.step_mode).unwrap_or(SeriesShape::Lines));
+        let shape = chart_shape(shape, opts.steps, opts.step_mode, opts.bars, opts.points, opts.stem, call, s.points)?;
+        palette.plot_nth(&mut chart, i, count, shape);
+    }
+    let chart = finish_chart(&mut chart, opts.format, &names, &annotations, &opts.titles, &entries, opts.legend_pos, &opts.pad, opts.object, call.head);
+    output_chart(engine, call, chart, meta)
+}
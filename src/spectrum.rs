@@ -0,0 +1,205 @@
+//! The `spectrum` command.
+//!
+//! Draws the amplitude spectrum of a numeric list, or of each list in a
+//! nested list, sampled at even intervals: the amplitude of the sine
+//! wave at each frequency, from 0 to half the sample rate, found by a
+//! fast Fourier transform.
+
+use nu_plugin::{EvaluatedCall, PluginCommand};
+use nu_protocol::{Category, LabeledError, PipelineData, Signature, SyntaxShape, Value};
+
+use crate::dsp::{decibels, spectrum, Window};
+use crate::{common_chart_flags, numeric_input_types, parse_cli_opts, render_series, Meta, Plotter, PluginPlot, Unit};
+
+pub struct CommandSpectrum;
+
+impl Plotter for CommandSpectrum {
+    fn plot(
+        &self,
+        call: &EvaluatedCall,
+        input: Vec<f64>,
+        meta: &mut Meta,
+    ) -> Result<Value, LabeledError> {
+        self.plot_nested(call, vec![input], meta)
+    }
+
+    fn plot_nested(
+        &self,
+        call: &EvaluatedCall,
+        input: Vec<Vec<f64>>,
+        _meta: &mut Meta,
+    ) -> Result<Value, LabeledError> {
+        let opts = parse_cli_opts(call)?;
+
+        if input.len() > 5 {
+            return Err(LabeledError::new("Nested list can't contain more than 5 inner lists.").with_label("Nested list error.", call.head));
+        }
+        if input.iter().any(|list| list.len() < 2) {
+            return Err(LabeledError::new("A spectrum needs at least two values in each list.").with_label("Too few values.", call.head));
+        }
+        // the transform needs every sample, evenly spaced
+        if input.iter().flatten().any(|v| !v.is_finite()) {
+            return Err(LabeledError::new("A spectrum needs a value for every sample, fill any gaps with --fill-nulls.").with_label("Gaps in the values.", call.head));
+        }
+
+        let window = match call.get_flag::<String>("window")? {
+            None => Window::Hann,
+            Some(w) => Window::from_name(&w).ok_or_else(|| {
+                LabeledError::new(format!("Unknown window {}, use hann, hamming, blackman or rectangular.", w))
+                    .with_label("Window error.", call.get_flag_span("window").unwrap_or(call.head))
+            })?,
+        };
+        let rate = match call.get_flag::<f64>("sample-rate")? {
+            None => 1.0,
+            Some(rate) if rate.is_finite() && rate > 0.0 => rate,
+            Some(_) => {
+                return Err(LabeledError::new("The sample rate must be a positive number.")
+                    .with_label("Sample rate error.", call.get_flag_span("sample-rate").unwrap_or(call.head)))
+            }
+        };
+
+        let mut spectra: Vec<Vec<(f64, f64)>> = input.iter().map(|list| spectrum(list, window, rate)).collect();
+        if call.has_flag("db")? {
+            spectra.iter_mut().for_each(|points| decibels(points));
+        }
+
+        let legend = vec![vec![(format!("Window: {}", window.name()), None)]];
+        render_series(call, &opts, (0.0, rate / 2.0), spectra, &legend, Unit::Number, false)
+    }
+
+    /// Each list is transformed on its own.
+    fn ragged(&self) -> bool {
+        true
+    }
+}
+
+impl PluginCommand for CommandSpectrum {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "spectrum"
+    }
+
+    fn signature(&self) -> Signature {
+        common_chart_flags(Signature::build("spectrum").description("Render an ASCII frequency spectrum of a list of evenly sampled values."))
+            .switch("square", "Draw the chart as tall as it is wide, as many dots across as up.", None)
+            .named(
+                "sample-rate",
+                SyntaxShape::Number,
+                "How many values were sampled a second, or any unit of time, to label the frequencies in; by default they are in cycles per value.",
+                Some('r'),
+            )
+            .named(
+                "window",
+                SyntaxShape::String,
+                "The window the values are weighted by: hann (default), hamming, blackman or rectangular.",
+                Some('w'),
+            )
+            .switch("db", "Draw the amplitudes in decibels, 20 log10 of each.", None)
+            .named(
+                "names",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "Names for the series in the legend, one for each.",
+                None,
+            )
+            .named(
+                "markers",
+                SyntaxShape::OneOf(vec![SyntaxShape::List(Box::new(SyntaxShape::String)), SyntaxShape::String]),
+                "Mark each series with its own glyph, so they can be told apart without color: default or ascii, or a list of glyphs such as [o x + *].",
+                None,
+            )
+            .named(
+                "styles",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "Line styles for the series in turn, such as [solid dashed dotted], so lines can be told apart without color.",
+                None,
+            )
+            .named(
+                "gradient",
+                SyntaxShape::String,
+                "Color a single series by its values along a colormap: green-red, blue-red or viridis.",
+                None,
+            )
+            .named(
+                "label-format",
+                SyntaxShape::String,
+                "How to write the numbers of the axes and labels: %.3f, %.2e, scientific, si, percent, thousands, bytes, duration or date, all but date with :N decimal places.",
+                None,
+            )
+            .switch("bars", "Change lines to bars.", Some('b'))
+            .switch("steps", "Change lines to steps.", Some('s'))
+            .named(
+                "step-mode",
+                SyntaxShape::String,
+                "Where a step plot steps: pre (default), at each point to the next value; post, at the next point, holding each value until then as a counter does; or mid, halfway between.",
+                None,
+            )
+            .switch("points", "Change lines to points.", Some('p'))
+            .switch("skip-nulls", "Leave out null values.", None)
+            .named(
+                "fill-nulls",
+                SyntaxShape::String,
+                "Replace null values using linear, previous or zero.",
+                None,
+            )
+            .switch("grid", "Draw dim grid lines across and down the chart, behind the series.", None)
+            .named("bg", SyntaxShape::String, "The color behind a text chart, such as black, or bright-white on a dark terminal.", None)
+            .named(
+                "hline",
+                SyntaxShape::Any,
+                "Draw horizontal reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .named(
+                "vline",
+                SyntaxShape::Any,
+                "Draw vertical reference lines at a value or list of values, each optionally \"value:label\".",
+                None,
+            )
+            .named(
+                "shade-above",
+                SyntaxShape::Number,
+                "Shade the region above this y value.",
+                None,
+            )
+            .named(
+                "shade-below",
+                SyntaxShape::Number,
+                "Shade the region below this y value.",
+                None,
+            )
+            .named(
+                "shade-x",
+                SyntaxShape::List(Box::new(SyntaxShape::Number)),
+                "Shade the region between two x values, given as [from to].",
+                None,
+            )
+            .named(
+                "annotate",
+                SyntaxShape::String,
+                "Label points of each series with their value: any of min, max and last, separated by commas.",
+                None,
+            )
+            .switch("meta", "Return a record of the chart and facts about the input.", None)
+            .input_output_types(numeric_input_types())
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render an ASCII amplitude spectrum of a list of evenly sampled values, by a fast Fourier transform, with the frequencies in cycles per value or, given --sample-rate, per unit of time."
+    }
+
+    fn search_terms(&self) -> Vec<&str> {
+        vec!["fft", "fourier", "frequency", "periodogram", "signal"]
+    }
+
+    fn run(
+        &self,
+        _plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        self.plot_input(engine, call, input)
+    }
+}
@@ -0,0 +1,214 @@
+//! Statistics of samples: quantiles, of samples and of the normal
+//! distribution, autocorrelation, correlation and rolling means.
+//!
+//! A QQ plot pairs the quantiles of one distribution with those of
+//! another at the same probabilities. If the two have the same shape,
+//! the points lie along a straight line.
+
+use crate::bins::quantile;
+
+/// The quantile of the standard normal distribution at probability `p`,
+/// by Acklam's rational approximation, good to a relative error of about
+/// 1e-9. It is infinite at 0 and 1, and NaN outside them.
+///
+/// ```
+/// # use nu_plugin_plot::stats::normal_quantile;
+/// assert_eq!(normal_quantile(0.5), 0.0);
+/// assert!((normal_quantile(0.975) - 1.959964).abs() < 1e-6);
+/// assert!((normal_quantile(0.01) + 2.326348).abs() < 1e-6);
+/// ```
+pub fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [-3.969683028665376e1, 2.209460984245205e2, -2.759285104469687e2, 1.38357751867269e2, -3.066479806614716e1, 2.506628277459239];
+    const B: [f64; 5] = [-5.447609879822406e1, 1.615858368580409e2, -1.556989798598866e2, 6.680131188771972e1, -1.328068155288572e1];
+    const C: [f64; 6] = [-7.784894002430293e-3, -3.223964580411365e-1, -2.400758277161838, -2.549732539343734, 4.374664141464968, 2.938163982698783];
+    const D: [f64; 4] = [7.784695709041462e-3, 3.224671290700398e-1, 2.445134137142996, 3.754408661907416];
+    // where the tails take over from the central approximation
+    const TAIL: f64 = 0.02425;
+
+    let poly = |coefficients: &[f64], x: f64| coefficients.iter().fold(0.0, |sum, c| sum * x + c);
+    let tail = |p: f64| {
+        let q = (-2.0 * p.ln()).sqrt();
+        poly(&C, q) / (poly(&D, q) * q + 1.0)
+    };
+    match p {
+        _ if !(0.0..=1.0).contains(&p) => f64::NAN,
+        0.0 => f64::NEG_INFINITY,
+        1.0 => f64::INFINITY,
+        _ if p < TAIL => tail(p),
+        _ if p > 1.0 - TAIL => -tail(1.0 - p),
+        _ => {
+            let q = p - 0.5;
+            let r = q * q;
+            poly(&A, r) * q / (poly(&B, r) * r + 1.0)
+        }
+    }
+}
+
+/// The points of a normal QQ plot of `data`: the normal quantiles, for
+/// a normal distribution with the mean and standard deviation of `data`,
+/// against its sorted values. Normally distributed data lies along x = y.
+///
+/// The probability of the `i`th of `n` values is `(i - a) / (n + 1 - 2a)`,
+/// counting from 1, with `a` 3/8 for ten values or fewer and 1/2 for
+/// more. Values which are not finite are ignored.
+///
+/// ```
+/// # use nu_plugin_plot::stats::qq_normal;
+/// let points = qq_normal(&[3.0, 1.0, 2.0]);
+/// assert_eq!(points[1], (2.0, 2.0));
+/// assert!(points[0].0 < 1.5 && points[0].1 == 1.0);
+/// ```
+pub fn qq_normal(data: &[f64]) -> Vec<(f64, f64)> {
+    let mut sorted: Vec<f64> = data.iter().copied().filter(|e| e.is_finite()).collect();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let n = sorted.len() as f64;
+    let mean = sorted.iter().sum::<f64>() / n;
+    let sd = (sorted.iter().map(|e| (e - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+    // a single value has no spread to speak of
+    let sd = if sd.is_finite() { sd } else { 0.0 };
+    let a = if sorted.len() <= 10 { 3.0 / 8.0 } else { 0.5 };
+    sorted
+        .iter()
+        .enumerate()
+        .map(|(i, y)| (mean + sd * normal_quantile((i as f64 + 1.0 - a) / (n + 1.0 - 2.0 * a)), *y))
+        .collect()
+}
+
+/// The points of a QQ plot of the sample `x` against the sample `y`:
+/// the quantiles of each at as many probabilities, evenly spaced from 0
+/// to 1, as the smaller sample has values. So the smaller sample's
+/// sorted values are paired with the larger one's, interpolated. Values
+/// which are not finite are ignored.
+///
+/// ```
+/// # use nu_plugin_plot::stats::qq_samples;
+/// assert_eq!(qq_samples(&[2.0, 1.0], &[10.0, 30.0, 20.0]), vec![(1.0, 10.0), (2.0, 30.0)]);
+/// ```
+pub fn qq_samples(x: &[f64], y: &[f64]) -> Vec<(f64, f64)> {
+    let sorted = |data: &[f64]| {
+        let mut sorted: Vec<f64> = data.iter().copied().filter(|e| e.is_finite()).collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        sorted
+    };
+    let (x, y) = (sorted(x), sorted(y));
+    let n = x.len().min(y.len());
+    (0..n)
+        .map(|i| {
+            let p = if n > 1 { i as f64 / (n - 1) as f64 } else { 0.5 };
+            (quantile(&x, p), quantile(&y, p))
+        })
+        .collect()
+}
+
+/// The autocorrelation of `data` at lags 0 to `lags`: how closely each
+/// value follows the one `lag` before it, from -1 to 1, and 1 at lag 0.
+///
+/// Values which are not finite are left out of the sums, as are the
+/// pairs they are in, but keep their place in the series. If the data
+/// has no spread, every lag is NaN.
+///
+/// ```
+/// # use nu_plugin_plot::stats::acf;
+/// assert_eq!(acf(&[1.0, 2.0, 3.0, 4.0], 2), vec![1.0, 0.25, -0.3]);
+/// ```
+pub fn acf(data: &[f64], lags: usize) -> Vec<f64> {
+    let finite = data.iter().filter(|e| e.is_finite());
+    let n = finite.clone().count() as f64;
+    let mean = finite.sum::<f64>() / n;
+    let covariance = |lag: usize| {
+        data.iter()
+            .zip(&data[lag.min(data.len())..])
+            .map(|(a, b)| (a - mean) * (b - mean))
+            .filter(|product| product.is_finite())
+            .sum::<f64>()
+            / n
+    };
+    let variance = covariance(0);
+    (0..=lags).map(|lag| covariance(lag) / variance).collect()
+}
+
+/// The Pearson correlation of `x` and `y`, from -1 to 1: how nearly the
+/// pairs of their values lie along a straight line, rising or falling.
+/// Pairs with a value which is not finite are left out. With fewer than
+/// two pairs, or no spread in either, it is NaN.
+///
+/// ```
+/// # use nu_plugin_plot::stats::pearson;
+/// assert_eq!(pearson(&[1.0, 2.0, 3.0], &[2.0, 4.0, 6.0]), 1.0);
+/// assert_eq!(pearson(&[1.0, 2.0, 3.0, f64::NAN], &[3.0, 1.0, 2.0, 0.0]), -0.5);
+/// ```
+pub fn pearson(x: &[f64], y: &[f64]) -> f64 {
+    let pairs: Vec<(f64, f64)> = x.iter().copied().zip(y.iter().copied()).filter(|(a, b)| a.is_finite() && b.is_finite()).collect();
+    let n = pairs.len() as f64;
+    let (mean_x, mean_y) = (pairs.iter().map(|(a, _)| a).sum::<f64>() / n, pairs.iter().map(|(_, b)| b).sum::<f64>() / n);
+    let (mut sxy, mut sxx, mut syy) = (0.0, 0.0, 0.0);
+    for (a, b) in &pairs {
+        sxy += (a - mean_x) * (b - mean_y);
+        sxx += (a - mean_x).powi(2);
+        syy += (b - mean_y).powi(2);
+    }
+    if pairs.len() < 2 || sxx == 0.0 || syy == 0.0 {
+        return f64::NAN;
+    }
+    (sxy / (sxx * syy).sqrt()).clamp(-1.0, 1.0)
+}
+
+/// The rank of each of `data`, from 1 for the smallest, with tied
+/// values sharing the mean of their ranks.
+///
+/// ```
+/// # use nu_plugin_plot::stats::ranks;
+/// assert_eq!(ranks(&[30.0, 10.0, 20.0, 10.0]), vec![4.0, 1.5, 3.0, 1.5]);
+/// ```
+pub fn ranks(data: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..data.len()).collect();
+    order.sort_by(|&a, &b| data[a].total_cmp(&data[b]));
+    let mut ranks = vec![0.0; data.len()];
+    let mut start = 0;
+    while start < order.len() {
+        // NaN, equal to nothing, even itself, is ranked on its own
+        let end = start + order[start..].iter().take_while(|&&i| data[i] == data[order[start]]).count().max(1);
+        // ranks start + 1 to end, counting from 1, share their mean
+        let rank = (start + 1 + end) as f64 / 2.0;
+        order[start..end].iter().for_each(|&i| ranks[i] = rank);
+        start = end;
+    }
+    ranks
+}
+
+/// The Spearman correlation of `x` and `y`, from -1 to 1: the Pearson
+/// correlation of their ranks, so how nearly one rises, or falls, as the
+/// other rises, along any curve. Pairs with a value which is not finite
+/// are left out before ranking.
+///
+/// ```
+/// # use nu_plugin_plot::stats::spearman;
+/// assert_eq!(spearman(&[1.0, 2.0, 3.0, 4.0], &[1.0, 8.0, 27.0, 64.0]), 1.0);
+/// ```
+pub fn spearman(x: &[f64], y: &[f64]) -> f64 {
+    let (x, y): (Vec<f64>, Vec<f64>) = x.iter().copied().zip(y.iter().copied()).filter(|(a, b)| a.is_finite() && b.is_finite()).unzip();
+    pearson(&ranks(&x), &ranks(&y))
+}
+
+/// The mean and standard deviation of each run of `window` values of
+/// `data` in turn, the first of those ending at `data[window - 1]`: as
+/// a moving average and how far about it the values stray. The
+/// deviation is of the window as a population, as Bollinger bands take
+/// it. There are none if `data` is shorter than `window`.
+///
+/// ```
+/// # use nu_plugin_plot::stats::rolling;
+/// assert_eq!(rolling(&[1.0, 3.0, 5.0, 5.0], 2), vec![(2.0, 1.0), (4.0, 1.0), (5.0, 0.0)]);
+/// assert!(rolling(&[1.0], 2).is_empty());
+/// ```
+pub fn rolling(data: &[f64], window: usize) -> Vec<(f64, f64)> {
+    data.windows(window.max(1))
+        .map(|run| {
+            let n = run.len() as f64;
+            let mean = run.iter().sum::<f64>() / n;
+            let variance = run.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+            (mean, variance.sqrt())
+        })
+        .collect()
+}
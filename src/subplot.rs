@@ -0,0 +1,153 @@
+//! The `subplot` command.
+//!
+//! Draws a grid of charts of any kind from a list of records, one for
+//! each cell, giving the kind of chart, its data and its options. Each
+//! chart is drawn by its own command, as though it had been called with
+//! the options as flags, and the charts are laid out as `plot compose
+//! --layout grid` would.
+
+use nu_plugin::{EvaluatedCall, Plugin, PluginCommand};
+use nu_protocol::{Category, LabeledError, PipelineData, Record, Signature, Span, Spanned, SyntaxShape, Type, Value};
+
+use crate::{indent, layout, terminal_size, PluginPlot, Titles, FACET_SIZE};
+
+/// The kinds of chart a cell can be.
+const TYPES: [&str; 6] = ["plot", "hist", "xyplot", "density", "ecdf", "barchart"];
+
+/// Options which draw something other than a chart as text.
+const NOT_TEXT: [&str; 4] = ["format", "output", "live", "meta"];
+
+/// Options of the `subplot` call which every cell takes, unless it
+/// gives its own.
+const SHARED: [&str; 4] = ["width", "height", "color", "raw"];
+
+pub struct CommandSubplot;
+
+/// The command to draw `cell` with, and the call to draw it as: the
+/// options of the cell as flags, after those shared from `call`.
+fn cell_call(
+    plugin: &PluginPlot,
+    call: &EvaluatedCall,
+    cell: &Record,
+    span: Span,
+) -> Result<(Box<dyn PluginCommand<Plugin = PluginPlot>>, EvaluatedCall), LabeledError> {
+    let kind = match cell.get("type") {
+        Some(kind) => kind.as_str()?,
+        None => return Err(LabeledError::new("A subplot cell needs a type of chart.").with_label("Missing type.", span)),
+    };
+    let command = plugin
+        .commands()
+        .into_iter()
+        .find(|command| TYPES.contains(&kind) && command.name() == kind)
+        .ok_or_else(|| {
+            LabeledError::new(format!("Unknown chart type {}, use plot, hist, xyplot, density, ecdf or barchart.", kind))
+                .with_label("Type error.", cell.get("type").map_or(span, Value::span))
+        })?;
+    let signature = command.signature();
+    let takes = |name: &str| signature.named.iter().any(|flag| flag.long == name);
+
+    let mut cell_call = EvaluatedCall::new(call.head);
+    for (name, value) in cell.iter().filter(|(name, _)| *name != "type" && *name != "data") {
+        if NOT_TEXT.contains(&name.as_str()) {
+            return Err(LabeledError::new(format!("A subplot cell can only be drawn as text, so can't take {}.", name)).with_label("Option error.", value.span()));
+        }
+        if !takes(name) {
+            return Err(LabeledError::new(format!("{} has no option {}.", kind, name)).with_label("Option error.", value.span()));
+        }
+        cell_call.add_named(Spanned { item: name.clone(), span: value.span() }, value.clone());
+    }
+    for name in SHARED.into_iter().filter(|name| takes(name) && !cell.contains(*name)) {
+        let value = match (call.get_flag_value(name), name) {
+            (Some(value), _) => value,
+            // a cell is drawn small, rather than as wide as the terminal
+            (None, "width") => Value::int(FACET_SIZE.0 as i64, call.head),
+            (None, "height") => Value::int(FACET_SIZE.1 as i64, call.head),
+            (None, _) => continue,
+        };
+        cell_call.add_named(Spanned { item: name.to_string(), span: call.head }, value);
+    }
+    Ok((command, cell_call))
+}
+
+impl PluginCommand for CommandSubplot {
+    type Plugin = PluginPlot;
+
+    fn name(&self) -> &str {
+        "subplot"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("subplot")
+            .description("Render a grid of charts from a list of {type, data} records.")
+            .named(
+                "width",
+                SyntaxShape::OneOf(vec![SyntaxShape::Number, SyntaxShape::String]),
+                "The width of each chart, unless its cell gives one, or as a percentage of the terminal such as 50%.",
+                None,
+            )
+            .named(
+                "height",
+                SyntaxShape::OneOf(vec![SyntaxShape::Number, SyntaxShape::String]),
+                "The height of each chart, unless its cell gives one, or as a percentage of the terminal such as 50%.",
+                None,
+            )
+            .named(
+                "title",
+                SyntaxShape::String,
+                "Provide a title to the plot.",
+                Some('t'),
+            )
+            .named("subtitle", SyntaxShape::String, "A line under the title.", None)
+            .named("caption", SyntaxShape::String, "A line under the chart, such as where its data is from.", None)
+            .named("title-align", SyntaxShape::String, "Line the title, subtitle and caption up across the chart: left (default), center or right.", None)
+            .named("pad", SyntaxShape::Int, "Indent each line of a text chart by this many spaces (default 4), or not at all with 0.", None)
+            .named(
+                "color",
+                SyntaxShape::String,
+                "Color the charts always, never, or auto (default) when NO_COLOR and CLICOLOR=0 are unset and the output is a terminal.",
+                None,
+            )
+            .switch("raw", "Return plain text without color or any other escape codes, whatever --color says.", None)
+            .input_output_type(Type::table(), Type::String)
+            .category(Category::Experimental)
+    }
+
+    fn description(&self) -> &str {
+        "Render a grid of charts from a list of records, each giving the type of chart (plot, hist, xyplot, density, ecdf or barchart), its data, and any options of that command, such as a title."
+    }
+
+    fn run(
+        &self,
+        plugin: &Self::Plugin,
+        engine: &nu_plugin::EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        let cells = match input {
+            PipelineData::ListStream(..) | PipelineData::Value(Value::List { .. }, ..) => input.into_iter().collect::<Vec<Value>>(),
+            input => return Err(LabeledError::new(format!("Input type should be a list of records, got {}.", input.get_type())).with_label("Incorrect input type.", call.head)),
+        };
+        if cells.is_empty() {
+            return Err(LabeledError::new("Can't plot a zero element list.").with_label("No elements in the list.", call.head));
+        }
+
+        let mut frames = vec![];
+        for cell in &cells {
+            let record = cell.as_record().map_err(|_| {
+                LabeledError::new("subplot needs a list of {type, data} records.").with_label("Incorrect input type.", cell.span())
+            })?;
+            let Some(data) = record.get("data") else {
+                return Err(LabeledError::new("A subplot cell needs data to plot.").with_label("Missing data.", cell.span()));
+            };
+            let (command, cell_call) = cell_call(plugin, call, record, cell.span())?;
+            let chart = command
+                .run(plugin, engine, &cell_call, PipelineData::Value(data.clone(), None))?
+                .into_value(call.head)?;
+            frames.push(chart.into_string()?);
+        }
+
+        let grid = layout::grid(&frames, terminal_size(engine)?.map_or(80, |(columns, _)| columns as usize));
+        let out = Titles::from_call(call)?.around(&grid, &indent(call)?);
+        Ok(PipelineData::Value(Value::string(out, call.head), None))
+    }
+}
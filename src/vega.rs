@@ -0,0 +1,362 @@
+//! Vega-Lite specs, so a chart can be opened in a browser.
+//!
+//! A spec describes a chart's data and encodings rather than a drawing
+//! of it. Each series is a layer of `x`, `y` and `series` values, drawn
+//! with the mark it has in the terminal and in the colors of an SVG
+//! chart, with reference lines, shading and labels layered on top.
+
+use nu_protocol::{record, Record, Span, Value};
+
+use crate::color_plot::drawille::PixelColor;
+use crate::color_plot::svg::css;
+use crate::color_plot::textplots::{Annotation, Chart, Shape, StepMode};
+use crate::{TitleAlign, Titles};
+
+const SCHEMA: &str = "https://vega.github.io/schema/vega-lite/v5.json";
+
+/// Pixels per point, as in an SVG chart.
+const SCALE: u32 = 4;
+
+/// The Vega-Lite spec for `chart` as a record, with the series, and any
+/// shapes after them, called `names`. The legend is left out unless
+/// `legend` is set. A spec has no caption, so `titles` gives only the
+/// title and subtitle.
+pub(crate) fn spec(
+    chart: &Chart,
+    names: &[String],
+    annotations: &[Annotation],
+    titles: &Titles,
+    legend: bool,
+    span: Span,
+) -> Value {
+    let num = |v: f64| if v.is_finite() { Value::float(v, span) } else { Value::nothing(span) };
+    let text = |s: &str| Value::string(s, span);
+    let list = |vals: Vec<Value>| Value::list(vals, span);
+    let obj = |record: Record| Value::record(record, span);
+
+    let (width, height) = chart.size();
+    let (xmin, xmax) = chart.x_range();
+    let names: Vec<String> = (0..chart.shapes().len())
+        .map(|i| names.get(i).cloned().unwrap_or_else(|| format!("Series {}", i + 1)))
+        .collect();
+    let colors: Vec<Value> = chart.shapes().iter().map(|(_, color)| text(&css(*color))).collect();
+    let grey = &*css(Some(PixelColor::BrightBlack));
+
+    let mut layers = vec![];
+    for ((((shape, shape_color), style), &line_width), name) in chart.shapes().iter().zip(chart.line_styles()).zip(chart.line_widths()).zip(&names) {
+        let values: Vec<Value> = shape
+            .points((xmin, xmax), width)
+            .iter()
+            .map(|(x, y)| obj(record! { "x" => num(*x), "y" => num(*y), "series" => text(name) }))
+            .collect();
+        let mut mark = match shape {
+            Shape::Continuous(_) | Shape::Lines(_) => record! { "type" => text("line") },
+            Shape::Points(_) | Shape::Stems(_) => record! { "type" => text("point"), "filled" => Value::bool(true, span) },
+            Shape::Steps(_, mode) => {
+                let interpolate = match mode {
+                    StepMode::Pre => "step-before",
+                    StepMode::Post => "step-after",
+                    StepMode::Mid => "step",
+                };
+                record! { "type" => text("line"), "interpolate" => text(interpolate) }
+            }
+            Shape::Bars(_) => record! {
+                "type" => text("area"),
+                "interpolate" => text("step-after"),
+                "opacity" => num(0.5),
+            },
+            // a wick of a rule from the low to the high, under a bar from the open to the close
+            Shape::Candles(candles) => {
+                let values: Vec<Value> = candles
+                    .iter()
+                    .map(|c| obj(record! { "x" => num(c.x), "open" => num(c.open), "high" => num(c.high), "low" => num(c.low), "close" => num(c.close), "series" => text(name) }))
+                    .collect();
+                let field = |name: &str| obj(record! { "field" => text(name), "type" => text("quantitative") });
+                let scale = obj(record! { "domain" => list(vec![num(xmin), num(xmax)]) });
+                let color = text(&css(*shape_color));
+                layers.push(obj(record! {
+                    "data" => obj(record! { "values" => list(values) }),
+                    "encoding" => obj(record! {
+                        "x" => obj(record! { "field" => text("x"), "type" => text("quantitative"), "scale" => scale }),
+                        "color" => obj(record! { "value" => color }),
+                    }),
+                    "layer" => list(vec![
+                        obj(record! { "mark" => obj(record! { "type" => text("rule") }), "encoding" => obj(record! { "y" => field("low"), "y2" => field("high") }) }),
+                        obj(record! { "mark" => obj(record! { "type" => text("bar") }), "encoding" => obj(record! { "y" => field("open"), "y2" => field("close") }) }),
+                    ]),
+                }));
+                continue;
+            }
+            // a rect for each cell with a value, colored along the colormap,
+            // or in its color as opaque as it is dense
+            Shape::Heatmap(heatmap) => {
+                let values: Vec<Value> = heatmap
+                    .cells()
+                    .filter(|(_, _, value)| value.is_finite())
+                    .map(|((x1, x2), (y1, y2), value)| obj(record! { "x" => num(x1), "x2" => num(x2), "y" => num(y1), "y2" => num(y2), "value" => num(value), "series" => text(name) }))
+                    .collect();
+                let field = |name: &str| obj(record! { "field" => text(name), "type" => text("quantitative") });
+                let domain = || list(vec![num(heatmap.range.0), num(heatmap.range.1)]);
+                let mut encoding = record! {
+                    "x" => obj(record! { "field" => text("x"), "type" => text("quantitative"), "scale" => obj(record! { "domain" => list(vec![num(xmin), num(xmax)]) }) }),
+                    "x2" => obj(record! { "field" => text("x2") }),
+                    "y" => field("y"),
+                    "y2" => obj(record! { "field" => text("y2") }),
+                };
+                match chart.colormap().filter(|_| shape_color.is_none()) {
+                    Some(colormap) => {
+                        let mut scale = record! {
+                            "domain" => domain(),
+                            "range" => list(colormap.swatches().iter().map(|c| text(&css(Some(*c)))).collect()),
+                        };
+                        // a diverging colormap is centred on the midpoint
+                        if let Some(mid) = heatmap.midpoint {
+                            scale.push("domainMid", num(mid));
+                        }
+                        encoding.push("color", obj(record! { "field" => text("value"), "type" => text("quantitative"), "scale" => obj(scale) }));
+                    }
+                    None => {
+                        encoding.push("color", obj(record! { "value" => text(&css(*shape_color)) }));
+                        encoding.push("opacity", obj(record! { "field" => text("value"), "type" => text("quantitative"), "scale" => obj(record! { "domain" => domain() }) }));
+                    }
+                }
+                layers.push(obj(record! {
+                    "data" => obj(record! { "values" => list(values) }),
+                    "mark" => obj(record! { "type" => text("rect") }),
+                    "encoding" => obj(encoding),
+                }));
+                continue;
+            }
+        };
+        // a dashed or dotted line in dashes as long as in the terminal
+        if let (Shape::Continuous(_) | Shape::Lines(_) | Shape::Steps(..), Some((on, off))) = (shape, style.dash()) {
+            mark.push("strokeDash", list(vec![num((on * SCALE) as f64), num((off * SCALE) as f64)]));
+        }
+        // a wider line as many times as wide as Vega-Lite's of 2 pixels
+        if let (Shape::Continuous(_) | Shape::Lines(_) | Shape::Steps(..), 2..) = (shape, line_width) {
+            mark.push("strokeWidth", num(2.0 * line_width as f64));
+        }
+        // bars and stems rise from zero, everything else is ranged to the data as in the terminal
+        let zero = matches!(shape, Shape::Bars(_) | Shape::Stems(_));
+        let mut color = record! {
+            "field" => text("series"),
+            "type" => text("nominal"),
+            "scale" => obj(record! {
+                "domain" => list(names.iter().map(|n| text(n)).collect()),
+                "range" => list(colors.clone()),
+            }),
+        };
+        if !legend {
+            color.push("legend", Value::nothing(span));
+        }
+        // a line can't change color along its length, so a gradient
+        // is drawn as points over it, colored by their values
+        let gradient = chart.colormap().filter(|_| shape_color.is_none()).map(|colormap| {
+            obj(record! {
+                "data" => obj(record! { "values" => list(values.clone()) }),
+                "mark" => obj(record! { "type" => text("point"), "filled" => Value::bool(true, span) }),
+                "encoding" => obj(record! {
+                    "x" => obj(record! { "field" => text("x"), "type" => text("quantitative") }),
+                    "y" => obj(record! { "field" => text("y"), "type" => text("quantitative") }),
+                    "color" => obj(record! {
+                        "field" => text("y"),
+                        "type" => text("quantitative"),
+                        "scale" => obj(record! {
+                            "range" => list(colormap.swatches().iter().map(|c| text(&css(Some(*c)))).collect()),
+                        }),
+                        "legend" => Value::nothing(span),
+                    }),
+                }),
+            })
+        });
+        let mut encoding = record! {
+            "x" => obj(record! {
+                "field" => text("x"),
+                "type" => text("quantitative"),
+                "scale" => obj(record! { "domain" => list(vec![num(xmin), num(xmax)]) }),
+            }),
+            "y" => obj(record! {
+                "field" => text("y"),
+                "type" => text("quantitative"),
+                "scale" => obj(record! { "zero" => Value::bool(zero, span) }),
+            }),
+            "color" => obj(color),
+        };
+        // a line which doubles back, such as a polar one, is drawn in the
+        // order of its points rather than along x
+        if let Shape::Lines(points) = shape {
+            if points.windows(2).any(|pair| pair[1].0 < pair[0].0) {
+                encoding.push("order", obj(record! { "value" => Value::nothing(span) }));
+            }
+        }
+        // the stems, a rule from zero to each point, under the points
+        if let Shape::Stems(_) = shape {
+            let mut stems = encoding.clone();
+            stems.push("y2", obj(record! { "datum" => num(0.0) }));
+            layers.push(obj(record! {
+                "data" => obj(record! { "values" => list(values.clone()) }),
+                "mark" => obj(record! { "type" => text("rule") }),
+                "encoding" => obj(stems),
+            }));
+        }
+        layers.push(obj(record! {
+            "data" => obj(record! { "values" => list(values) }),
+            "mark" => obj(mark),
+            "encoding" => obj(encoding),
+        }));
+        layers.extend(gradient);
+    }
+
+    let layer = |mark: Record, encoding: Record| obj(record! { "mark" => obj(mark), "encoding" => obj(encoding) });
+    let rule = || record! { "type" => text("rule"), "color" => text(grey), "strokeDash" => list(vec![num(4.0), num(4.0)]) };
+    let shade = || record! { "type" => text("rect"), "color" => text(grey), "opacity" => num(0.2) };
+    let label = |dx: f64, dy: f64, color: &str| {
+        record! { "type" => text("text"), "align" => text("left"), "dx" => num(dx), "dy" => num(dy), "color" => text(color) }
+    };
+    let datum = |v: f64| obj(record! { "datum" => num(v) });
+    let value = |v: Value| obj(record! { "value" => v });
+    for annotation in annotations {
+        match annotation {
+            Annotation::HLine(y, name) => {
+                layers.push(layer(rule(), record! { "y" => datum(*y) }));
+                if let Some(name) = name {
+                    let encoding = record! { "x" => value(num(0.0)), "y" => datum(*y), "text" => value(text(name)) };
+                    layers.push(layer(label(4.0, -6.0, grey), encoding));
+                }
+            }
+            Annotation::VLine(x, name) => {
+                layers.push(layer(rule(), record! { "x" => datum(*x) }));
+                if let Some(name) = name {
+                    let encoding = record! { "x" => datum(*x), "y" => value(num(0.0)), "text" => value(text(name)) };
+                    layers.push(layer(label(4.0, 8.0, grey), encoding));
+                }
+            }
+            Annotation::ShadeAbove(y) => layers.push(layer(
+                shade(),
+                record! { "x" => value(num(0.0)), "x2" => value(text("width")), "y" => datum(*y), "y2" => value(num(0.0)) },
+            )),
+            Annotation::ShadeBelow(y) => layers.push(layer(
+                shade(),
+                record! { "x" => value(num(0.0)), "x2" => value(text("width")), "y" => datum(*y), "y2" => value(text("height")) },
+            )),
+            Annotation::ShadeX(from, to) => layers.push(layer(
+                shade(),
+                record! { "x" => datum(*from), "x2" => datum(*to), "y" => value(num(0.0)), "y2" => value(text("height")) },
+            )),
+            Annotation::Area(points, color) => {
+                // under the series, as it's shaded behind them in the terminal
+                let values = points.iter().map(|(x, low, high)| obj(record! { "x" => num(*x), "low" => num(*low), "high" => num(*high) })).collect();
+                let mark = record! { "type" => text("area"), "color" => text(&color.map_or(grey.into(), |c| css(Some(c)))), "opacity" => num(0.2) };
+                let encoding = record! {
+                    "x" => obj(record! { "field" => text("x"), "type" => text("quantitative") }),
+                    "y" => obj(record! { "field" => text("low"), "type" => text("quantitative") }),
+                    "y2" => obj(record! { "field" => text("high") }),
+                };
+                layers.insert(0, obj(record! { "data" => obj(record! { "values" => list(values) }), "mark" => obj(mark), "encoding" => obj(encoding) }));
+            }
+            Annotation::Path(points) => {
+                let values = points.iter().map(|(x, y)| obj(record! { "x" => num(*x), "y" => num(*y) })).collect();
+                let mut mark = rule();
+                mark.insert("type", text("line"));
+                let encoding = record! {
+                    "x" => obj(record! { "field" => text("x"), "type" => text("quantitative") }),
+                    "y" => obj(record! { "field" => text("y"), "type" => text("quantitative") }),
+                    // in the order of the points, not of x
+                    "order" => value(Value::nothing(span)),
+                };
+                layers.insert(0, obj(record! { "data" => obj(record! { "values" => list(values) }), "mark" => obj(mark), "encoding" => obj(encoding) }));
+            }
+            Annotation::ErrorBar(x, low, high, color) => layers.push(layer(
+                record! { "type" => text("rule"), "color" => text(&css(Some(*color))) },
+                record! { "x" => datum(*x), "y" => datum(*low), "y2" => datum(*high) },
+            )),
+            Annotation::Label(x, y, s) => layers.push(layer(
+                label(4.0, 0.0, grey),
+                record! { "x" => datum(*x), "y" => datum(*y), "text" => value(text(s)) },
+            )),
+            Annotation::Text(x, y, s) => {
+                let mut mark = label(0.0, 0.0, &css(None));
+                mark.insert("baseline", text("top"));
+                layers.push(layer(mark, record! { "x" => datum(*x), "y" => datum(*y), "text" => value(text(s)) }));
+            }
+            Annotation::Value(x, y, s) => {
+                let mut mark = label(0.0, -8.0, &css(None));
+                mark.insert("align", text("center"));
+                layers.push(layer(mark, record! { "x" => datum(*x), "y" => datum(*y), "text" => value(text(s)) }));
+            }
+            Annotation::Marker(x, y, marker, color) => {
+                let mut mark = label(0.0, 0.0, &css(*color));
+                mark.insert("align", text("center"));
+                mark.insert("baseline", text("middle"));
+                let encoding = record! { "x" => datum(*x), "y" => datum(*y), "text" => value(text(&marker.to_string())) };
+                layers.push(layer(mark, encoding));
+            }
+            // Vega-Lite draws grid lines of its own
+            Annotation::Grid => {}
+            // a page of its own, on white like the images
+            Annotation::Background(..) => {}
+        }
+    }
+
+    let mut spec = record! { "$schema" => text(SCHEMA) };
+    // a plain title is a string, anything more a record
+    match (&titles.title, &titles.subtitle, titles.align) {
+        (None, None, _) => {}
+        (Some(title), None, TitleAlign::Left) => spec.push("title", text(title)),
+        (title, subtitle, align) => {
+            let anchor = match align {
+                TitleAlign::Left => "start",
+                TitleAlign::Center => "middle",
+                TitleAlign::Right => "end",
+            };
+            let mut record = record! { "text" => text(title.as_deref().unwrap_or("")), "anchor" => text(anchor) };
+            if let Some(subtitle) = subtitle {
+                record.push("subtitle", text(subtitle));
+            }
+            spec.push("title", obj(record));
+        }
+    }
+    spec.push("width", Value::int((width * SCALE) as i64, span));
+    spec.push("height", Value::int((height * SCALE) as i64, span));
+    spec.push("layer", list(layers));
+    obj(spec)
+}
+
+/// `value` as JSON, for saving a spec to a file.
+///
+/// Only the values a spec is made of are written: records, lists,
+/// strings, numbers and booleans. Anything else, and numbers which are
+/// not finite, are written as `null`.
+pub(crate) fn to_json(value: &Value) -> String {
+    match value {
+        Value::Record { val, .. } => {
+            let fields: Vec<String> = val.iter().map(|(k, v)| format!("{}:{}", json_string(k), to_json(v))).collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::List { vals, .. } => {
+            let items: Vec<String> = vals.iter().map(to_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        Value::String { val, .. } => json_string(val),
+        Value::Float { val, .. } if val.is_finite() => val.to_string(),
+        Value::Int { val, .. } => val.to_string(),
+        Value::Bool { val, .. } => val.to_string(),
+        _ => "null".to_string(),
+    }
+}
+
+/// `s` as a JSON string, quoted and escaped.
+fn json_string(s: &str) -> String {
+    let mut out = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => out += "\\\"",
+            '\\' => out += "\\\\",
+            '\n' => out += "\\n",
+            c if (c as u32) < 0x20 => out += &format!("\\u{:04x}", c as u32),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
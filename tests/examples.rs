@@ -0,0 +1,191 @@
+//! The examples of every command, run through nushell, each checked
+//! against its result where it gives one, and the errors of those which
+//! fail.
+//!
+//! Run with `cargo test --test examples`.
+
+use std::sync::Arc;
+
+use nu_plugin::Plugin;
+use nu_plugin_plot::PluginPlot;
+use nu_plugin_test_support::PluginTest;
+use nu_protocol::{ShellError, Span};
+
+/// Run the examples of the command called `name`. Charts are drawn
+/// without color, as they are in the results.
+fn test_examples(name: &str) -> Result<(), ShellError> {
+    let plugin = Arc::new(PluginPlot);
+    let command = plugin.commands().into_iter().find(|c| c.name() == name).unwrap_or_else(|| panic!("no command {}", name));
+    let mut test = PluginTest::new("plot", plugin)?;
+    let mut config = test.engine_state().get_config().as_ref().clone();
+    config.use_ansi_coloring = false;
+    test.engine_state_mut().set_config(config);
+    test.test_examples(&command.examples())
+}
+
+/// The message of the error `source` fails with.
+fn error_of(source: &str) -> Result<String, ShellError> {
+    let mut test = PluginTest::new("plot", Arc::new(PluginPlot))?;
+    match test.eval(source).and_then(|data| data.into_value(Span::test_data())) {
+        Ok(value) => panic!("{} gave {:?}", source, value),
+        Err(ShellError::LabeledError(error)) => Ok(error.msg),
+        Err(error) => Err(error),
+    }
+}
+
+#[test]
+fn plot() -> Result<(), ShellError> {
+    test_examples("plot")
+}
+
+#[test]
+fn plot_no_finite_values() -> Result<(), ShellError> {
+    assert_eq!(error_of("[NaN inf] | plot")?, "There are no finite values to plot.");
+    Ok(())
+}
+
+#[test]
+fn plot_only_nulls() -> Result<(), ShellError> {
+    assert_eq!(error_of("[null null] | plot --skip-nulls")?, "There are no finite values to plot.");
+    assert_eq!(error_of("[null null] | plot --fill-nulls linear")?, "There are no finite values to plot.");
+    Ok(())
+}
+
+#[test]
+fn hist() -> Result<(), ShellError> {
+    test_examples("hist")
+}
+
+#[test]
+fn xyplot() -> Result<(), ShellError> {
+    test_examples("xyplot")
+}
+
+#[test]
+fn plot_config() -> Result<(), ShellError> {
+    test_examples("plot-config")
+}
+
+#[test]
+fn plot_compose() -> Result<(), ShellError> {
+    test_examples("plot compose")
+}
+
+#[test]
+fn plot_fn() -> Result<(), ShellError> {
+    test_examples("plot fn")
+}
+
+#[test]
+fn plot_fn_no_values() -> Result<(), ShellError> {
+    assert_eq!(error_of("plot fn {|x| null}")?, "The function has no finite values in the x range.");
+    Ok(())
+}
+
+#[test]
+fn plot_merge() -> Result<(), ShellError> {
+    test_examples("plot merge")
+}
+
+#[test]
+fn plot_overlay() -> Result<(), ShellError> {
+    test_examples("plot overlay")
+}
+
+#[test]
+fn plot_spec() -> Result<(), ShellError> {
+    test_examples("plot spec")
+}
+
+#[test]
+fn barchart() -> Result<(), ShellError> {
+    test_examples("barchart")
+}
+
+#[test]
+fn density() -> Result<(), ShellError> {
+    test_examples("density")
+}
+
+#[test]
+fn ecdf() -> Result<(), ShellError> {
+    test_examples("ecdf")
+}
+
+#[test]
+fn subplot() -> Result<(), ShellError> {
+    test_examples("subplot")
+}
+
+#[test]
+fn candle() -> Result<(), ShellError> {
+    test_examples("candle")
+}
+
+#[test]
+fn qqplot() -> Result<(), ShellError> {
+    test_examples("qqplot")
+}
+
+#[test]
+fn acf() -> Result<(), ShellError> {
+    test_examples("acf")
+}
+
+#[test]
+fn lagplot() -> Result<(), ShellError> {
+    test_examples("lagplot")
+}
+
+#[test]
+fn spectrum() -> Result<(), ShellError> {
+    test_examples("spectrum")
+}
+
+#[test]
+fn spectrum_one_sample() -> Result<(), ShellError> {
+    assert_eq!(error_of("[1] | spectrum")?, "A spectrum needs at least two values in each list.");
+    assert_eq!(error_of("[[1 2 3 4] [1]] | spectrum")?, "A spectrum needs at least two values in each list.");
+    Ok(())
+}
+
+#[test]
+fn hist2d() -> Result<(), ShellError> {
+    test_examples("hist2d")
+}
+
+#[test]
+fn corrplot() -> Result<(), ShellError> {
+    test_examples("corrplot")
+}
+
+#[test]
+fn corrplot_one_row() -> Result<(), ShellError> {
+    assert_eq!(error_of("[{a: 1, b: 2}] | corrplot")?, "A correlation plot needs a table with at least two rows.");
+    Ok(())
+}
+
+#[test]
+fn nullmap() -> Result<(), ShellError> {
+    test_examples("nullmap")
+}
+
+#[test]
+fn gantt() -> Result<(), ShellError> {
+    test_examples("gantt")
+}
+
+#[test]
+fn radar() -> Result<(), ShellError> {
+    test_examples("radar")
+}
+
+#[test]
+fn gauge() -> Result<(), ShellError> {
+    test_examples("gauge")
+}
+
+#[test]
+fn ridgeline() -> Result<(), ShellError> {
+    test_examples("ridgeline")
+}